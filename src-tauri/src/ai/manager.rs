@@ -57,6 +57,12 @@ fn is_claude_anthropic_request(channel: &AiChannel, model: &AiModel) -> bool {
             .unwrap_or(false)
 }
 
+/// The channel's API key (or Copilot OAuth token), revealed if the vault sealed it.
+fn channel_api_key(channel: &AiChannel) -> Result<String, String> {
+    crate::config::vault::touch();
+    crate::config::vault::reveal(channel.api_key.as_deref().unwrap_or_default())
+}
+
 fn is_provider(channel: &AiChannel, provider: &str) -> bool {
     channel.provider.eq_ignore_ascii_case(provider)
 }
@@ -142,7 +148,8 @@ impl AiManager {
             } else {
                 "http"
             };
-            let auth_part = if let (Some(u), Some(pass)) = (&p.username, &p.password) {
+            let password = crate::config::vault::reveal_option(&p.password)?;
+            let auth_part = if let (Some(u), Some(pass)) = (&p.username, &password) {
                 format!("{}:{}@", u, pass)
             } else {
                 "".to_string()
@@ -238,8 +245,8 @@ impl AiManager {
         let http_client = self.build_http_client(proxy, Some(&channel.provider))?;
 
         let (endpoint, auth, adapter_kind) = if is_provider(channel, "copilot") {
-            let oauth_token = channel.api_key.as_deref().unwrap_or_default();
-            let session_token = self.get_copilot_token(oauth_token, &http_client).await?;
+            let oauth_token = channel_api_key(channel)?;
+            let session_token = self.get_copilot_token(&oauth_token, &http_client).await?;
 
             let auth = AuthData::Key(session_token);
             let endpoint = Endpoint::from_owned("https://api.githubcopilot.com/");
@@ -248,8 +255,8 @@ impl AiManager {
             // Using Anthropic adapter here sends Anthropic-style routes and can 404.
             (endpoint, auth, AdapterKind::OpenAI)
         } else {
-            let api_key = channel.api_key.as_deref().unwrap_or_default();
-            let auth = AuthData::Key(api_key.to_string());
+            let api_key = channel_api_key(channel)?;
+            let auth = AuthData::Key(api_key);
             let endpoint_url = endpoint_for_channel(channel);
 
             let endpoint_url = if endpoint_url.ends_with('/') {
@@ -350,8 +357,8 @@ impl AiManager {
         let http_client = self.build_http_client(proxy, Some(&channel.provider))?;
 
         if is_provider(channel, "copilot") {
-            let oauth_token = channel.api_key.as_deref().unwrap_or_default();
-            let session_token = self.get_copilot_token(oauth_token, &http_client).await?;
+            let oauth_token = channel_api_key(channel)?;
+            let session_token = self.get_copilot_token(&oauth_token, &http_client).await?;
 
             let res = http_client
                 .get("https://api.githubcopilot.com/models")
@@ -384,7 +391,7 @@ impl AiManager {
             ids.sort();
             Ok(ids)
         } else {
-            let api_key = channel.api_key.as_deref().unwrap_or_default();
+            let api_key = channel_api_key(channel)?;
             let endpoint = endpoint_for_channel(channel);
 
             let url = if endpoint.ends_with("/") {
@@ -9,6 +9,19 @@ pub fn resolve_app_data_dir_from_default(default_app_data_dir: &Path) -> PathBuf
         .unwrap_or_else(|| default_app_data_dir.join(APP_DATA_DIR_NAME))
 }
 
+/// The current user's home directory (`%USERPROFILE%` on Windows, `$HOME` elsewhere).
+pub fn user_home_dir() -> Option<PathBuf> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    std::env::var_os(var)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The OpenSSH per-user config directory (`~/.ssh`).
+pub fn user_ssh_dir() -> Option<PathBuf> {
+    user_home_dir().map(|home| home.join(".ssh"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
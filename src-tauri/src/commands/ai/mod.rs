@@ -1772,7 +1772,7 @@ pub async fn run_in_terminal(
     let timeout = timeout_seconds.unwrap_or(30);
     let timeout_ms = timeout * 1000;
 
-    SSHClient::start_command_recording(&session_id).await?;
+    SSHClient::start_command_recording(&session_id, &command).await?;
 
     let input_payload =
         build_recording_input_payload(&session_id, &command, "run_in_terminal").await;
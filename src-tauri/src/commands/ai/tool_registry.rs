@@ -219,6 +219,32 @@ fn tool_catalog() -> Vec<ToolDefinition> {
             serde_json::json!({"type": "object", "properties": {}, "required": []}),
             read_only_parallel(),
         ),
+        tool(
+            "search_command_history",
+            "Search commands previously run on this server, with their working directory, exit code and time. Without a query it returns the most recent commands. Useful to see how something was done before or what changed recently.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "Words to look for in the command line or working directory, matched as prefixes (example: nginx reload). Leave empty for the most recent commands."},
+                    "limit": {"type": "integer", "description": "Maximum number of commands to return (default: 20, max: 100)"}
+                },
+                "required": []
+            }),
+            read_only_parallel(),
+        ),
+        tool(
+            "get_session_commands",
+            "List the commands run in the linked terminal since it connected, each with an id, working directory, exit code (or running) and start time. Pass command_id to get the output of one of them instead. Needs shell integration in the remote shell.",
+            serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "command_id": {"type": "integer", "description": "Id of a listed command whose output to return. Omit to list commands."},
+                    "limit": {"type": "integer", "description": "Maximum number of most recent commands to list (default: 20, max: 100)"}
+                },
+                "required": []
+            }),
+            read_only_parallel(),
+        ),
         tool(
             "read_file",
             "Read file content directly from the remote server over SFTP without using terminal commands. Useful for analyzing config/code/log files.",
@@ -36,8 +36,13 @@ use super::types::{
 };
 use super::{AI_CANCELLED, AI_STREAM_IDLE_TIMEOUT_SECS};
 use crate::commands::AppState;
+use crate::ssh_manager::command_history::{self, HistoryScope};
+use crate::ssh_manager::shell_integration;
 use crate::ssh_manager::ssh::SSHClient;
 
+const COMMAND_HISTORY_TOOL_LIMIT: u32 = 20;
+const COMMAND_HISTORY_TOOL_MAX_LIMIT: u32 = 100;
+
 /// Race a future against cancellation. Used for tool awaits that do not natively
 /// observe the token (SFTP read, terminal buffer fetch, etc.).
 async fn await_or_cancel<T, F>(token: &CancellationToken, fut: F) -> Result<T, String>
@@ -150,6 +155,39 @@ async fn bind_background_task(
         .await
 }
 
+/// The linked terminal's server, or the server the chat was opened for. A chat with neither has
+/// no history to search; an empty scope would cover every server.
+async fn command_history_scope(
+    state: &Arc<AppState>,
+    session_id: &str,
+    ssh_session_id: Option<&str>,
+) -> Result<HistoryScope, String> {
+    if let Some(ssh_id) = ssh_session_id {
+        if let Ok(scope) = SSHClient::history_scope(ssh_id).await {
+            return Ok(scope);
+        }
+    }
+    let session_id = session_id.to_string();
+    let server_id = state
+        .db_manager
+        .run_blocking(move |conn| {
+            conn.query_row(
+                "SELECT server_id FROM ai_sessions WHERE id = ?1",
+                params![session_id],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())
+        })
+        .await?
+        .filter(|id| !id.is_empty())
+        .ok_or_else(|| "No server is linked to this chat".to_string())?;
+    Ok(HistoryScope {
+        server_id: Some(server_id),
+        host: None,
+    })
+}
+
 async fn execute_tools(
     app_handle: tauri::AppHandle,
     state: &Arc<AppState>,
@@ -184,6 +222,75 @@ async fn execute_tools(
                     "Error: No active terminal session linked to this chat.".to_string()
                 }
             }
+            "search_command_history" => {
+                let args = serde_json::from_str::<serde_json::Value>(&call.function.arguments)
+                    .unwrap_or_default();
+                let query = args
+                    .get("query")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                let limit = args
+                    .get("limit")
+                    .and_then(|v| v.as_u64())
+                    .map_or(COMMAND_HISTORY_TOOL_LIMIT, |n| {
+                        n.clamp(1, COMMAND_HISTORY_TOOL_MAX_LIMIT as u64) as u32
+                    });
+                match command_history_scope(state, session_id, ssh_session_id).await {
+                    Err(e) => format!("Error: {}", e),
+                    Ok(scope) => match await_or_cancel(
+                        &cancellation_token,
+                        state.db_manager.run_blocking(move |conn| {
+                            if query.is_empty() {
+                                command_history::recent(conn, &scope, limit)
+                            } else {
+                                command_history::search(conn, &query, &scope, limit)
+                            }
+                        }),
+                    )
+                    .await
+                    {
+                        Err(e) if e == AI_CANCELLED => return Err(AI_CANCELLED.to_string()),
+                        Ok(Ok(entries)) => command_history::format_for_ai(&entries),
+                        Ok(Err(e)) => format!("Error: {}", e),
+                        Err(e) => format!("Error: {}", e),
+                    },
+                }
+            }
+            "get_session_commands" => {
+                if let Some(ssh_id) = ssh_session_id {
+                    let args = serde_json::from_str::<serde_json::Value>(&call.function.arguments)
+                        .unwrap_or_default();
+                    let command_id = args.get("command_id").and_then(|v| v.as_u64());
+                    let limit = args
+                        .get("limit")
+                        .and_then(|v| v.as_u64())
+                        .map_or(COMMAND_HISTORY_TOOL_LIMIT, |n| {
+                            n.clamp(1, COMMAND_HISTORY_TOOL_MAX_LIMIT as u64) as u32
+                        });
+                    let ssh_id = ssh_id.to_string();
+                    match await_or_cancel(&cancellation_token, async move {
+                        match command_id {
+                            Some(id) => SSHClient::get_command_output(&ssh_id, id).await,
+                            None => SSHClient::get_command_history(&ssh_id)
+                                .await
+                                .map(|records| {
+                                    shell_integration::format_for_ai(&records, limit as usize)
+                                }),
+                        }
+                    })
+                    .await
+                    {
+                        Err(e) if e == AI_CANCELLED => return Err(AI_CANCELLED.to_string()),
+                        Ok(Ok(text)) => text,
+                        Ok(Err(e)) => format!("Error: {}", e),
+                        Err(e) => format!("Error: {}", e),
+                    }
+                } else {
+                    "Error: No active terminal session linked to this chat.".to_string()
+                }
+            }
             "get_selected_terminal_output" => {
                 if let Some(ssh_id) = ssh_session_id {
                     let ssh_id = ssh_id.to_string();
@@ -312,9 +419,13 @@ async fn execute_tools(
                                         }
                                     } else {
                                         let ssh_id_for_start = ssh_id.to_string();
+                                        let cmd_for_start = cmd.to_string();
                                         match await_or_cancel(&cancellation_token, async move {
-                                            SSHClient::start_command_recording(&ssh_id_for_start)
-                                                .await
+                                            SSHClient::start_command_recording(
+                                                &ssh_id_for_start,
+                                                &cmd_for_start,
+                                            )
+                                            .await
                                         })
                                         .await
                                         {
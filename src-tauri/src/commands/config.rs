@@ -2,16 +2,29 @@
 
 use crate::ai::manager::AiManager;
 use crate::app_paths::{resolve_app_data_dir_from_default, APP_DATA_DIR_NAME};
-use crate::config::{Config, ConfigManager, SyncManager};
+use crate::config::import::{self, ImportReport};
+use crate::config::shared_collections::check_read_only_edits;
+use crate::config::sync_history::{RevisionEntityDiff, SyncHistoryStore, SyncRevision};
+use crate::config::sync_protocol::EntityKey;
+use crate::config::sync_scheduler::{
+    backoff_delay, is_transient, pull_interval, synced_entities_changed, SchedulerState,
+    SyncScheduler, SyncSchedulerStatus, DRAIN_RECHECK, PUSH_DEBOUNCE,
+};
+use crate::config::types::WebDAVSettings;
+use crate::config::vault::{self, VaultStatus};
+use crate::config::{
+    mobaxterm, putty, ssh_config, termius, Config, ConfigManager, ConfigSync, SyncManager,
+};
 use crate::db::DatabaseManager;
 use crate::model_catalog::ModelCatalog;
 use crate::sftp_manager::edit::SftpEditManager;
+use crate::ssh_manager::certificate::{self, CertificateInfo};
 use serde::Serialize;
 use std::sync::{
     atomic::{AtomicU64, Ordering},
     Arc,
 };
-use tauri::{AppHandle, Emitter, Manager, State, Window};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::sync::Mutex;
 
 use dashmap::DashMap;
@@ -156,6 +169,8 @@ pub struct AppState {
     pub sftp_edit_manager: SftpEditManager,
     /// Tracks config/sync/SFTP write work for safe update restart draining.
     pub operation_coordinator: std::sync::Arc<crate::updater::OperationCoordinator>,
+    /// Periodic pulls and debounced pushes; see `spawn_sync_scheduler`.
+    pub sync_scheduler: SyncScheduler,
 }
 
 impl AppState {
@@ -263,7 +278,6 @@ pub async fn get_config(app: AppHandle) -> Result<Config, String> {
 pub async fn save_config(
     mut config: Config,
     state: State<'_, Arc<AppState>>,
-    window: Window,
 ) -> Result<(), String> {
     use crate::updater::OperationCategory;
 
@@ -289,6 +303,17 @@ pub async fn save_config(
     // GET→merge→conditional-PUT sequence.
     let _sync_gate = state.config_sync_gate.lock().await;
 
+    // Entities of read-only shared collections only change through their own sync.
+    let synced_changed = {
+        let current = state.config.lock().await;
+        let protected = read_only_entities(&current, &state.config_manager);
+        check_read_only_edits(&current, &config, &protected)?;
+        synced_entities_changed(&current, &config)
+    };
+
+    // Keep memory and disk identical: both hold the sealed form when the vault is enabled.
+    state.config_manager.seal_secrets(&mut config)?;
+
     let mut current_config = state.config.lock().await;
 
     *current_config = config.clone();
@@ -306,126 +331,9 @@ pub async fn save_config(
     // Update log level
     crate::logger::set_log_level(config.general.debug_enabled);
 
-    // If sync is enabled, trigger async sync in background without blocking
-    // Local save is already complete, sync failures should not block the operation
-    // Acquire webdavSync permit BEFORE spawn so restart never sees an idle gap.
-    if config.general.webdav.enabled && !config.general.webdav.url.is_empty() {
-        let proxy = config
-            .general
-            .webdav
-            .proxy_id
-            .as_ref()
-            .and_then(|id| config.proxies.iter().find(|p| &p.id == id).cloned());
-
-        let sync_manager = SyncManager::new(
-            config.general.webdav.url.clone(),
-            config.general.webdav.username.clone(),
-            config.general.webdav.password.clone(),
-            proxy,
-        )
-        .with_state_store(state.config_manager.app_data_dir().to_path_buf());
-
-        let app_state = state.inner().clone();
-        let sync_config = config.clone();
-        // A background sync must never overwrite an edit saved while its network request was in
-        // flight. The byte snapshot is intentionally exact: any persisted local change wins and
-        // its own save schedules the next sync.
-        let sync_snapshot = serde_json::to_vec(&config)
-            .map_err(|e| format!("Failed to snapshot config for sync: {}", e))?;
-        let sync_path = local_path.clone();
-
-        let window = window.clone();
-        let sync_permit = state
-            .operation_coordinator
-            .try_acquire(OperationCategory::WebdavSync)
-            .await?;
-        tokio::spawn(async move {
-            // Serialize app-originated syncs. If a newer save was queued first, this stale
-            // snapshot must not even issue its PUT; the newer generation owns the remote write.
-            let _sync_gate = app_state.config_sync_gate.lock().await;
-            if !app_state.is_current_config_sync_generation(sync_generation) {
-                tracing::info!(
-                    "Skipped stale background sync before remote write; a newer config save is queued"
-                );
-                sync_permit.release().await;
-                return;
-            }
-
-            let mut local_copy = sync_config;
-            match sync_manager.sync(&mut local_copy, vec![]).await {
-                Ok(outcome) => match outcome {
-                    crate::config::SyncOutcome::Applied { .. } => {
-                        let mut in_memory = app_state.config.lock().await;
-                        if app_state.is_current_config_sync_generation(sync_generation)
-                            && config_matches_snapshot(&in_memory, &sync_snapshot)
-                        {
-                            if let Err(e) = app_state
-                                .config_manager
-                                .save_config(&local_copy, &sync_path)
-                            {
-                                tracing::error!("Failed to save merged config after sync: {}", e);
-                            } else {
-                                *in_memory = local_copy.clone();
-                                let _ = window.emit("config-updated", local_copy);
-                            }
-                        } else {
-                            tracing::info!(
-                                "Discarded stale background sync projection; a newer local config is already saved"
-                            );
-                        }
-                    }
-                    crate::config::SyncOutcome::Conflicts {
-                        conflicts,
-                        attempt_token,
-                    } => {
-                        // A background conflict is only actionable for the exact local snapshot
-                        // that produced it. Do not replace a newer pending attempt or publish an
-                        // inevitably stale token after a local save.
-                        let is_current_attempt =
-                            app_state.is_current_config_sync_generation(sync_generation) && {
-                                let in_memory = app_state.config.lock().await;
-                                config_matches_snapshot(&in_memory, &sync_snapshot)
-                            };
-                        if is_current_attempt {
-                            tracing::info!(
-                                "Background sync has {} conflict(s); waiting for user resolution",
-                                conflicts.len()
-                            );
-                            *app_state.pending_sync_conflict_attempt.lock().await =
-                                Some(PendingSyncConflictAttempt {
-                                    token: attempt_token.clone(),
-                                    config_generation: sync_generation,
-                                });
-                            let _ = window.emit(
-                                "sync-conflicts",
-                                crate::config::SyncOutcome::Conflicts {
-                                    conflicts,
-                                    attempt_token,
-                                },
-                            );
-                        } else {
-                            tracing::info!(
-                                "Discarded stale background sync conflicts; a newer local config is already saved"
-                            );
-                        }
-                    }
-                    crate::config::SyncOutcome::ConcurrentRemoteChange { message } => {
-                        tracing::warn!("Background sync concurrent remote change: {}", message);
-                        let _ = window.emit("sync-failed", message);
-                    }
-                    crate::config::SyncOutcome::Failed { error } => {
-                        tracing::warn!("Background sync failed: {}", error.message);
-                        let _ = window.emit("sync-failed", error.message);
-                    }
-                },
-                Err(e) => {
-                    tracing::warn!("Background sync failed: {}", e);
-                    let _ = window.emit("sync-failed", e);
-                }
-            }
-            sync_permit.release().await;
-        });
-    }
+    // Edits to synced entities are pushed by the scheduler once they settle; any save may also
+    // have changed the sync settings it runs with.
+    state.sync_scheduler.config_saved(synced_changed);
 
     write_permit.release().await;
     Ok(())
@@ -482,6 +390,44 @@ pub async fn record_server_connection(
 pub async fn trigger_sync(
     app: AppHandle,
     state: State<'_, Arc<AppState>>,
+) -> Result<TriggerSyncResult, String> {
+    run_sync(&app, &state, false).await
+}
+
+/// Syncs once while accepting a remote that is still plain JSON, so it is re-uploaded encrypted
+/// with the configured passphrase. Called after the user confirms a `plaintextRemote` failure.
+#[tauri::command]
+pub async fn migrate_sync_encryption(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<TriggerSyncResult, String> {
+    run_sync(&app, &state, true).await
+}
+
+async fn run_sync(
+    app: &AppHandle,
+    state: &AppState,
+    allow_plaintext_remote: bool,
+) -> Result<TriggerSyncResult, String> {
+    let result = sync_now(state, allow_plaintext_remote).await?;
+    emit_sync_outcome(app, &result.outcome);
+    if result.outcome.is_applied() {
+        // A manual sync that got through ends any scheduled backoff.
+        let status = state.sync_scheduler.update_status(|status| {
+            status.consecutive_failures = 0;
+            status.last_error = None;
+            status.last_success_at = Some(chrono::Utc::now().to_rfc3339());
+        });
+        emit_scheduler_status(app, status);
+        state.sync_scheduler.reschedule();
+    }
+    Ok(result)
+}
+
+/// One full sync of the in-memory config. Events are left to the caller.
+async fn sync_now(
+    state: &AppState,
+    allow_plaintext_remote: bool,
 ) -> Result<TriggerSyncResult, String> {
     use crate::config::sync_protocol::{SyncError, SyncErrorKind, SyncOutcome};
     use crate::updater::OperationCategory;
@@ -507,24 +453,16 @@ pub async fn trigger_sync(
                 return Err("WebDAV sync is not enabled or configured".to_string());
             }
 
-            let proxy = config
-                .general
-                .webdav
-                .proxy_id
-                .as_ref()
-                .and_then(|id| config.proxies.iter().find(|p| &p.id == id).cloned());
-            let sync_manager = SyncManager::new(
-                config.general.webdav.url.clone(),
-                config.general.webdav.username.clone(),
-                config.general.webdav.password.clone(),
-                proxy,
-            )
-            .with_state_store(state.config_manager.app_data_dir().to_path_buf());
+            let candidate = with_revealed_secrets(&config)?;
+            let mut sync_manager = config_sync_manager(&candidate, &state.config_manager)?;
+            if allow_plaintext_remote {
+                sync_manager = sync_manager.allow_plaintext_remotes();
+            }
             let snapshot = serde_json::to_vec(&*config)
                 .map_err(|error| format!("Failed to snapshot config for sync: {error}"))?;
             (
                 sync_manager,
-                config.clone(),
+                candidate,
                 snapshot,
                 state.config_manager.local_config_path(),
             )
@@ -556,6 +494,7 @@ pub async fn trigger_sync(
                         },
                     )
                 } else {
+                    state.config_manager.seal_secrets(&mut sync_candidate)?;
                     state.config_manager.save_config(&sync_candidate, &local_path)?;
                     *in_memory = sync_candidate.clone();
                     (Some(sync_candidate), outcome)
@@ -572,7 +511,6 @@ pub async fn trigger_sync(
                 });
         }
 
-        emit_sync_outcome(&app, &outcome);
         Ok(TriggerSyncResult { config, outcome })
     }
     .await;
@@ -581,6 +519,293 @@ pub async fn trigger_sync(
     result
 }
 
+#[tauri::command]
+pub async fn get_sync_scheduler_status(
+    state: State<'_, Arc<AppState>>,
+) -> Result<SyncSchedulerStatus, String> {
+    Ok(state.sync_scheduler.status())
+}
+
+/// Runs the background sync scheduler for the app's lifetime: periodic pulls, debounced pushes
+/// after `save_config`, and backoff while the sync location is unreachable. Every status change
+/// is emitted as `sync-scheduler-status`.
+pub fn spawn_sync_scheduler(app: AppHandle) {
+    tokio::spawn(async move {
+        let state = app.state::<Arc<AppState>>().inner().clone();
+        let scheduler = &state.sync_scheduler;
+        // A wake-up that leaves the plan unchanged keeps its deadline, so unrelated saves and
+        // reschedules do not push the next pull or retry further out.
+        let mut current: Option<(SyncPlan, Option<tokio::time::Instant>)> = None;
+        loop {
+            let (enabled, interval) = {
+                let config = state.config.lock().await;
+                let webdav = &config.general.webdav;
+                (
+                    webdav.enabled && !webdav.url.is_empty(),
+                    pull_interval(webdav),
+                )
+            };
+            let failures = scheduler.status().consecutive_failures;
+            let (planned, wait) = if !enabled {
+                (SchedulerState::Disabled, None)
+            } else if state.operation_coordinator.is_draining() {
+                (SchedulerState::Paused, Some(DRAIN_RECHECK))
+            } else if failures > 0 {
+                (SchedulerState::Backoff, Some(backoff_delay(failures)))
+            } else if scheduler.push_pending() {
+                (SchedulerState::PushPending, Some(PUSH_DEBOUNCE))
+            } else {
+                (SchedulerState::Idle, interval)
+            };
+            let plan = SyncPlan {
+                state: planned,
+                wait,
+                push_generation: (planned == SchedulerState::PushPending)
+                    .then(|| scheduler.push_generation()),
+            };
+            let deadline = match current.take() {
+                Some((previous, deadline)) if previous == plan => deadline,
+                _ => wait.map(|wait| tokio::time::Instant::now() + wait),
+            };
+            current = Some((plan, deadline));
+            let next_sync_at = deadline
+                .map(|deadline| deadline.saturating_duration_since(tokio::time::Instant::now()))
+                .and_then(|wait| chrono::Duration::from_std(wait).ok())
+                .map(|wait| (chrono::Utc::now() + wait).to_rfc3339());
+            emit_scheduler_status(
+                &app,
+                scheduler.update_status(|status| {
+                    status.state = planned;
+                    status.next_sync_at = next_sync_at;
+                }),
+            );
+
+            // A wake-up re-plans, which applies changed settings and restarts the debounce after
+            // a new synced edit.
+            let elapsed = match deadline {
+                Some(deadline) => tokio::select! {
+                    _ = tokio::time::sleep_until(deadline) => true,
+                    _ = scheduler.woken() => false,
+                },
+                None => {
+                    scheduler.woken().await;
+                    false
+                }
+            };
+            if !elapsed {
+                continue;
+            }
+            current = None;
+            if planned == SchedulerState::Paused {
+                continue;
+            }
+
+            scheduler.take_push();
+            emit_scheduler_status(
+                &app,
+                scheduler.update_status(|status| {
+                    status.state = SchedulerState::Syncing;
+                    status.next_sync_at = None;
+                }),
+            );
+            let result = sync_now(&state, false).await;
+            record_scheduled_sync(&app, scheduler, result);
+        }
+    });
+}
+
+/// What the scheduler waits for; the deadline is recomputed only when this changes.
+#[derive(Debug, PartialEq, Eq)]
+struct SyncPlan {
+    state: SchedulerState,
+    wait: Option<std::time::Duration>,
+    /// Set while a push waits out its debounce, so a newer synced edit restarts it.
+    push_generation: Option<u64>,
+}
+
+/// Fold a scheduled sync into the scheduler status and tell the frontend. Repeated failures only
+/// raise `sync-failed` once per streak so an offline laptop is not flooded with toasts.
+fn record_scheduled_sync(
+    app: &AppHandle,
+    scheduler: &SyncScheduler,
+    result: Result<TriggerSyncResult, String>,
+) {
+    use crate::config::sync_protocol::{SyncErrorKind, SyncOutcome};
+
+    let (transient, error) = match result {
+        Ok(TriggerSyncResult { config, outcome }) => {
+            if let Some(config) = config {
+                let _ = app.emit("config-updated", config);
+            }
+            match outcome {
+                // The merged documents are saved; the skipped collections are retried next cycle.
+                SyncOutcome::Applied {
+                    collection_failures,
+                    ..
+                } if !collection_failures.is_empty() => {
+                    let message = collection_failures
+                        .iter()
+                        .map(|failure| format!("{}: {}", failure.collection, failure.message))
+                        .collect::<Vec<_>>()
+                        .join("; ");
+                    (false, Some(message))
+                }
+                SyncOutcome::Applied { .. } => (false, None),
+                outcome @ SyncOutcome::Conflicts { .. } => {
+                    emit_sync_outcome(app, &outcome);
+                    (false, None)
+                }
+                SyncOutcome::ConcurrentRemoteChange { message } => (true, Some(message)),
+                // The newer save queued its own push.
+                SyncOutcome::Failed { error }
+                    if error.kind == SyncErrorKind::ConcurrentLocalChange =>
+                {
+                    return;
+                }
+                SyncOutcome::Failed { error } => (is_transient(&error.kind), Some(error.message)),
+            }
+        }
+        // Draining started after the check; the next cycle reports the pause.
+        Err(e) if e == crate::updater::RESTART_PREPARING_ERROR => return,
+        Err(e) => (false, Some(e)),
+    };
+
+    let previous = scheduler.status();
+    let status = scheduler.update_status(|status| {
+        status.consecutive_failures = if transient {
+            status.consecutive_failures.saturating_add(1)
+        } else {
+            0
+        };
+        if error.is_none() {
+            status.last_success_at = Some(chrono::Utc::now().to_rfc3339());
+        }
+        status.last_error = error.clone();
+    });
+    if let Some(message) = &error {
+        let repeated = if transient {
+            previous.consecutive_failures > 0
+        } else {
+            previous.last_error.as_ref() == Some(message)
+        };
+        if repeated {
+            tracing::debug!("Scheduled sync failed again: {}", message);
+        } else {
+            tracing::warn!("Scheduled sync failed: {}", message);
+            let _ = app.emit("sync-failed", message);
+        }
+    }
+    emit_scheduler_status(app, status);
+}
+
+fn emit_scheduler_status(app: &AppHandle, status: SyncSchedulerStatus) {
+    let _ = app.emit("sync-scheduler-status", status);
+}
+
+/// Revisions recorded for the configured sync location, newest first.
+#[tauri::command]
+pub async fn list_sync_revisions(
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<SyncRevision>, String> {
+    let account_key = sync_account_key(&state).await?;
+    sync_history_store(&state).list(&account_key)
+}
+
+#[tauri::command]
+pub async fn diff_sync_revisions(
+    from_id: String,
+    to_id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<RevisionEntityDiff>, String> {
+    let account_key = sync_account_key(&state).await?;
+    sync_history_store(&state).diff(&account_key, &from_id, &to_id)
+}
+
+/// Rolls the synced entities back to a recorded revision and syncs the result. The rollback is
+/// an ordinary local edit, so it reaches other devices through the same conditional write and
+/// conflict checks as any other change.
+#[tauri::command]
+pub async fn restore_sync_revision(
+    app: AppHandle,
+    revision_id: String,
+    state: State<'_, Arc<AppState>>,
+) -> Result<TriggerSyncResult, String> {
+    use crate::updater::OperationCategory;
+
+    let account_key = sync_account_key(&state).await?;
+    let revision = sync_history_store(&state).load(&account_key, &revision_id)?;
+
+    let write_permit = state
+        .operation_coordinator
+        .try_acquire(OperationCategory::ConfigWrite)
+        .await?;
+    let result = async {
+        let _sync_gate = state.config_sync_gate.lock().await;
+        let mut config = state.config.lock().await;
+        let mut restored = with_revealed_secrets(&config)?;
+        config_sync_manager(&restored, &state.config_manager)?
+            .apply_personal_revision(&mut restored, &revision)?;
+        state.config_manager.seal_secrets(&mut restored)?;
+        state
+            .config_manager
+            .save_config(&restored, &state.config_manager.local_config_path())?;
+        *config = restored;
+        state.next_config_sync_generation();
+        *state.pending_sync_conflict_attempt.lock().await = None;
+        let _ = app.emit("config-updated", config.clone());
+        Ok::<(), String>(())
+    }
+    .await;
+    write_permit.release().await;
+    result?;
+
+    tracing::info!("Restored sync revision {}; synchronizing", revision_id);
+    run_sync(&app, &state, false).await
+}
+
+/// Moves synced entities into a writable shared collection and syncs, which removes them from
+/// the personal document and uploads them to the collection.
+#[tauri::command]
+pub async fn share_with_collection(
+    app: AppHandle,
+    collection_id: String,
+    entities: Vec<EntityKey>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<TriggerSyncResult, String> {
+    {
+        // Claims are written to sync-state.json, which a running sync also updates.
+        let _sync_gate = state.config_sync_gate.lock().await;
+        let config = state.config.lock().await;
+        if !config.general.webdav.enabled || config.general.webdav.url.is_empty() {
+            return Err("WebDAV sync is not enabled or configured".to_string());
+        }
+        let revealed = with_revealed_secrets(&config)?;
+        config_sync_manager(&revealed, &state.config_manager)?.claim(&collection_id, &entities)?;
+    }
+    tracing::info!(
+        "Moved {} entities into shared collection {}; synchronizing",
+        entities.len(),
+        collection_id
+    );
+    run_sync(&app, &state, false).await
+}
+
+fn sync_history_store(state: &AppState) -> SyncHistoryStore {
+    SyncHistoryStore::new(state.config_manager.app_data_dir())
+}
+
+/// History is kept per remote location, keyed like the sync baseline.
+async fn sync_account_key(state: &AppState) -> Result<String, String> {
+    let config = state.config.lock().await;
+    if !config.general.webdav.enabled || config.general.webdav.url.is_empty() {
+        return Err("WebDAV sync is not enabled or configured".to_string());
+    }
+    let revealed = with_revealed_secrets(&config)?;
+    Ok(config_sync_manager(&revealed, &state.config_manager)?
+        .account_key()
+        .to_string())
+}
+
 #[tauri::command]
 pub async fn resolve_sync_conflicts(
     app: AppHandle,
@@ -631,24 +856,16 @@ pub async fn resolve_sync_conflicts(
             if !config.general.webdav.enabled || config.general.webdav.url.is_empty() {
                 return Err("WebDAV sync is not enabled or configured".to_string());
             }
-            let proxy = config
-                .general
-                .webdav
-                .proxy_id
-                .as_ref()
-                .and_then(|id| config.proxies.iter().find(|p| &p.id == id).cloned());
-            let sync_manager = SyncManager::new(
-                config.general.webdav.url.clone(),
-                config.general.webdav.username.clone(),
-                config.general.webdav.password.clone(),
-                proxy,
-            )
-            .with_state_store(state.config_manager.app_data_dir().to_path_buf());
+            let candidate = with_revealed_secrets(&config)?;
+            let mut sync_manager = config_sync_manager(&candidate, &state.config_manager)?;
+            if allow_plaintext_remote {
+                sync_manager = sync_manager.allow_plaintext_remotes();
+            }
             let snapshot = serde_json::to_vec(&*config)
                 .map_err(|error| format!("Failed to snapshot config for sync resolution: {error}"))?;
             (
                 sync_manager,
-                config.clone(),
+                candidate,
                 snapshot,
                 state.config_manager.local_config_path(),
             )
@@ -693,6 +910,7 @@ pub async fn resolve_sync_conflicts(
                         },
                     )
                 } else {
+                    state.config_manager.seal_secrets(&mut sync_candidate)?;
                     state.config_manager.save_config(&sync_candidate, &local_path)?;
                     *in_memory = sync_candidate.clone();
                     (Some(sync_candidate), outcome)
@@ -736,6 +954,73 @@ fn emit_sync_outcome(app: &AppHandle, outcome: &crate::config::sync_protocol::Sy
     }
 }
 
+/// Copy of `config` with vault secrets revealed. Sync works on this so entity hashes stay stable
+/// across re-sealing and the remote document stays readable from other devices.
+fn with_revealed_secrets(config: &Config) -> Result<Config, String> {
+    let mut revealed = config.clone();
+    vault::reveal_secrets(&mut revealed)?;
+    Ok(revealed)
+}
+
+/// The personal document plus every enabled shared collection.
+fn config_sync_manager(
+    config: &Config,
+    config_manager: &ConfigManager,
+) -> Result<ConfigSync, String> {
+    let personal = source_sync_manager(&config.general.webdav, config, config_manager)?;
+    let mut sync =
+        ConfigSync::new(personal).with_state_store(config_manager.app_data_dir().to_path_buf());
+    for collection in config
+        .general
+        .shared_collections
+        .iter()
+        .filter(|collection| collection.sync.enabled)
+    {
+        let manager = source_sync_manager(&collection.sync, config, config_manager)?;
+        sync = sync.with_shared_collection(collection, manager);
+    }
+    Ok(sync)
+}
+
+fn source_sync_manager(
+    settings: &WebDAVSettings,
+    config: &Config,
+    config_manager: &ConfigManager,
+) -> Result<SyncManager, String> {
+    let proxy = settings
+        .proxy_id
+        .as_ref()
+        .and_then(|id| config.proxies.iter().find(|p| &p.id == id).cloned());
+    let backend =
+        crate::sync_backend::from_settings(settings, proxy, config_manager.app_data_dir())?;
+    Ok(SyncManager::with_backend(backend)
+        .with_state_store(config_manager.app_data_dir().to_path_buf())
+        .with_encryption_passphrase(&settings.encryption_passphrase))
+}
+
+/// Entities owned by read-only shared collections. Account keys do not depend on secrets, so
+/// the sealed config is enough. A misconfigured collection must not block saving the fix, so
+/// lookup failures only disable the check.
+fn read_only_entities(
+    config: &Config,
+    config_manager: &ConfigManager,
+) -> std::collections::BTreeSet<EntityKey> {
+    let has_read_only = config
+        .general
+        .shared_collections
+        .iter()
+        .any(|collection| collection.read_only && collection.sync.enabled);
+    if !has_read_only || !config.general.webdav.enabled || config.general.webdav.url.is_empty() {
+        return Default::default();
+    }
+    config_sync_manager(config, config_manager)
+        .and_then(|sync| sync.read_only_entities())
+        .unwrap_or_else(|error| {
+            tracing::warn!("Could not check read-only shared collections: {}", error);
+            Default::default()
+        })
+}
+
 fn config_matches_snapshot(config: &Config, snapshot: &[u8]) -> bool {
     serde_json::to_vec(config)
         .map(|current| current == snapshot)
@@ -759,6 +1044,249 @@ pub async fn get_app_data_dir(app: AppHandle) -> Result<String, String> {
     Ok(resolve_app_data_dir(&app)?.to_string_lossy().to_string())
 }
 
+/// Validity window, principals and CA of an OpenSSH certificate, for the auth editor.
+#[tauri::command]
+pub async fn inspect_ssh_certificate(certificate: String) -> Result<CertificateInfo, String> {
+    let cert = certificate::parse_certificate(&certificate)?;
+    Ok(certificate::certificate_info(
+        &cert,
+        certificate::unix_now(),
+    ))
+}
+
+/// Imports hosts from an OpenSSH client config (default `~/.ssh/config`). With `dry_run`
+/// only the planned servers, keys, duplicates and warnings are returned for review.
+#[tauri::command]
+pub async fn import_ssh_config(
+    path: Option<String>,
+    dry_run: bool,
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ImportReport, String> {
+    let ssh_dir = crate::app_paths::user_ssh_dir()
+        .ok_or_else(|| "Could not determine the home directory".to_string())?;
+    let path = path
+        .filter(|path| !path.trim().is_empty())
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| ssh_dir.join("config"));
+    let parsed = ssh_config::parse_ssh_config(&path, &ssh_dir)?;
+    run_import(path.display().to_string(), parsed, dry_run, &app, &state).await
+}
+
+/// Imports saved sessions exported from another client. `format` is "putty" (`.reg`),
+/// "mobaxterm" (`.mxtsessions`) or "termius" (JSON).
+#[tauri::command]
+pub async fn import_sessions(
+    format: String,
+    path: String,
+    dry_run: bool,
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ImportReport, String> {
+    let file = std::path::Path::new(&path);
+    let content = import::read_text(file)?;
+    let parsed = match format.as_str() {
+        "putty" => putty::parse_putty_reg(&content),
+        "mobaxterm" => mobaxterm::parse_mxtsessions(&content),
+        "termius" => termius::parse_termius_json(&content)?,
+        other => return Err(format!("Unsupported import format: {}", other)),
+    };
+    run_import(path, parsed, dry_run, &app, &state).await
+}
+
+/// Writes `server_ids` (plus the jump hosts they need) as an OpenSSH config fragment to
+/// `path`, or only returns it when `path` is empty. Keys are written to `key_dir` on request.
+#[tauri::command]
+pub async fn export_ssh_config(
+    server_ids: Vec<String>,
+    path: Option<String>,
+    key_dir: Option<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<ssh_config::SshConfigExport, String> {
+    let key_dir = key_dir
+        .filter(|dir| !dir.trim().is_empty())
+        .map(std::path::PathBuf::from);
+    let export = {
+        let config = state.config.lock().await;
+        if key_dir.is_some() {
+            // Key files are written in plaintext, as OpenSSH expects them.
+            let config = with_revealed_secrets(&config)?;
+            ssh_config::export_servers(&config, &server_ids, key_dir.as_deref())?
+        } else {
+            ssh_config::export_servers(&config, &server_ids, key_dir.as_deref())?
+        }
+    };
+
+    ssh_config::write_key_files(&export)?;
+    if let Some(path) = path.filter(|path| !path.trim().is_empty()) {
+        ssh_config::write_config_file(std::path::Path::new(&path), &export.content)?;
+        tracing::info!("Exported {} server(s) to {}", server_ids.len(), path);
+    }
+    Ok(export)
+}
+
+/// How often the auto-lock timeout is checked.
+const VAULT_AUTO_LOCK_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Locks the vault once it has been idle for its auto-lock timeout and tells the frontend.
+pub fn spawn_vault_auto_lock(app: AppHandle) {
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(VAULT_AUTO_LOCK_CHECK_INTERVAL).await;
+            if vault::lock_if_idle() {
+                tracing::info!("Vault locked after inactivity");
+                let _ = app.emit("vault-locked", ());
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_vault_status(state: State<'_, Arc<AppState>>) -> Result<VaultStatus, String> {
+    vault::status(state.config_manager.app_data_dir())
+}
+
+/// Sets a master password and seals every stored secret with it.
+#[tauri::command]
+pub async fn enable_vault(
+    password: String,
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<VaultStatus, String> {
+    // Key derivation is deliberately slow; keep it off the async workers and out of the locks.
+    let key = tokio::task::spawn_blocking(move || vault::derive_new_key(&password))
+        .await
+        .map_err(|e| e.to_string())??;
+    rewrite_vault_config(&app, &state, |manager, config| {
+        manager.enable_vault(key, config)
+    })
+    .await
+}
+
+/// Checks the master password, stores every secret in plaintext again and removes the vault.
+#[tauri::command]
+pub async fn disable_vault(
+    password: String,
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<VaultStatus, String> {
+    let app_data_dir = state.config_manager.app_data_dir().to_path_buf();
+    let key =
+        tokio::task::spawn_blocking(move || vault::derive_existing_key(&app_data_dir, &password))
+            .await
+            .map_err(|e| e.to_string())??;
+    rewrite_vault_config(&app, &state, |manager, config| {
+        manager.disable_vault(key, config)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn unlock_vault(
+    password: String,
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<VaultStatus, String> {
+    let app_data_dir = state.config_manager.app_data_dir().to_path_buf();
+    // Key derivation is deliberately slow; keep it off the async workers.
+    tokio::task::spawn_blocking(move || vault::unlock(&app_data_dir, &password))
+        .await
+        .map_err(|e| e.to_string())??;
+    let _ = app.emit("vault-unlocked", ());
+    vault::status(state.config_manager.app_data_dir())
+}
+
+#[tauri::command]
+pub async fn lock_vault(
+    app: AppHandle,
+    state: State<'_, Arc<AppState>>,
+) -> Result<VaultStatus, String> {
+    vault::lock();
+    let _ = app.emit("vault-locked", ());
+    vault::status(state.config_manager.app_data_dir())
+}
+
+/// Minutes of inactivity before the vault locks itself; `0` disables auto-lock.
+#[tauri::command]
+pub async fn set_vault_auto_lock(
+    minutes: u32,
+    state: State<'_, Arc<AppState>>,
+) -> Result<VaultStatus, String> {
+    vault::set_auto_lock(state.config_manager.app_data_dir(), minutes)?;
+    vault::status(state.config_manager.app_data_dir())
+}
+
+/// Re-encodes the local config and the sync history when the vault is turned on or off (see
+/// `ConfigManager::enable_vault`). Only the representation of secrets changes, so no sync is
+/// scheduled.
+async fn rewrite_vault_config(
+    app: &AppHandle,
+    state: &AppState,
+    rewrite: impl FnOnce(&ConfigManager, &Config) -> Result<Config, String>,
+) -> Result<VaultStatus, String> {
+    use crate::updater::OperationCategory;
+
+    let write_permit = state
+        .operation_coordinator
+        .try_acquire(OperationCategory::ConfigWrite)
+        .await?;
+    let _sync_gate = state.config_sync_gate.lock().await;
+    let mut config = state.config.lock().await;
+    let updated = rewrite(&state.config_manager, &config)?;
+    *config = updated;
+    let _ = app.emit("config-updated", config.clone());
+    drop(config);
+    drop(_sync_gate);
+    write_permit.release().await;
+    vault::status(state.config_manager.app_data_dir())
+}
+
+/// Imported keys are matched against existing ones by content, which needs plaintext. A locked
+/// vault only costs deduplication, so the sealed config is used as is then.
+fn revealed_for_matching(config: &Config) -> Config {
+    with_revealed_secrets(config).unwrap_or_else(|_| config.clone())
+}
+
+async fn run_import(
+    source: String,
+    parsed: import::ParsedImport,
+    dry_run: bool,
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<ImportReport, String> {
+    use crate::updater::OperationCategory;
+
+    if dry_run {
+        let config = state.config.lock().await;
+        let existing = revealed_for_matching(&config);
+        return Ok(import::plan_import(source, parsed, &existing));
+    }
+
+    let write_permit = state
+        .operation_coordinator
+        .try_acquire(OperationCategory::ConfigWrite)
+        .await?;
+    let mut config = state.config.lock().await;
+    let mut report = import::plan_import(source, parsed, &revealed_for_matching(&config));
+    report.dry_run = false;
+    let mut updated = config.clone();
+    report.apply_to(&mut updated);
+    state.config_manager.seal_secrets(&mut updated)?;
+    state.config_manager.save_local_config(&updated)?;
+    *config = updated;
+    let _ = app.emit("config-updated", config.clone());
+    drop(config);
+    write_permit.release().await;
+    tracing::info!(
+        "Imported {} server(s), {} key(s) and {} proxy(ies) from {}",
+        report.servers.len(),
+        report.authentications.len(),
+        report.proxies.len(),
+        report.source
+    );
+    Ok(report)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -787,6 +1315,8 @@ mod tests {
             proxy_id: None,
             jumphost_id: None,
             port_forwards: vec![],
+            dynamic_forward: None,
+            forward_agent: false,
             keep_alive: 0,
             auto_exec_commands: vec![],
             snippets: vec![],
@@ -794,6 +1324,7 @@ mod tests {
             sftp_custom_commands: vec![],
             sftp_favorite_paths: vec![],
             additional_prompt: None,
+            recording_policy: None,
             synced: true,
             created_at: None,
             updated_at: "2026-01-01T00:00:00Z".to_string(),
@@ -1,38 +1,44 @@
-use crate::ssh_manager::ssh::{ConnectParams, SSHClient};
-use dashmap::DashMap;
-use lazy_static::lazy_static;
+use crate::config::types::{Authentication, Server};
+use crate::config::{vault, Config};
+use crate::ssh_manager::command_history::{self, CommandHistoryEntry, HistoryScope, HistorySink};
+use crate::ssh_manager::forwarding::{PortForwardManager, PortForwardStatus};
+use crate::ssh_manager::keyboard_interactive;
+use crate::ssh_manager::known_hosts::{KnownHostsImportReport, KnownHostsStore};
+use crate::ssh_manager::recording::{self, RecordingFormat, RecordingOptions};
+use crate::ssh_manager::recording_policy;
+use crate::ssh_manager::shell_integration::{CommandPhase, CommandRecord};
+use crate::ssh_manager::socks::{DynamicForwardStatus, SocksForwardManager};
+use crate::ssh_manager::ssh::{ConnectParams, JumphostConfig, SSHClient};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::sync::Arc;
 use tauri::{Emitter, State, Window};
-use tokio::fs::File;
-use tokio::io::{AsyncWriteExt, BufWriter};
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::mpsc;
 use tokio::time::{Duration, MissedTickBehavior};
 
 use super::AppState;
 
-#[derive(Clone)]
-struct RecordingHandle {
-    writer: Arc<Mutex<BufWriter<File>>>,
-    mode: String,
-}
-
-lazy_static! {
-    // 用 DashMap 替代 Mutex<HashMap>，避免 forward task 每帧锁全局表导致多终端互相阻塞。
-    static ref RECORDING_SESSIONS: DashMap<String, RecordingHandle> = DashMap::new();
-}
-
-async fn flush_terminal_output(window: &Window, session_id: &str, pending_output: &mut String) {
+async fn flush_terminal_output(
+    window: &Window,
+    session_id: &str,
+    pending_output: &mut String,
+    history: &HistorySink,
+) {
     if pending_output.is_empty() {
         return;
     }
 
     let text = std::mem::take(pending_output);
     let mut text_for_emit = text.clone();
+    let mut shell_events = Vec::new();
 
     match SSHClient::update_terminal_buffer(session_id, &text).await {
-        Ok(filtered) => {
-            text_for_emit = filtered;
+        Ok(update) => {
+            text_for_emit = update.display;
+            shell_events = update.events;
+            if let Some(record) = update.recorded_command {
+                history.record(session_id, record);
+            }
         }
         Err(e) => {
             if e == "Session not found" {
@@ -46,11 +52,32 @@ async fn flush_terminal_output(window: &Window, session_id: &str, pending_output
         }
     }
 
-    if text_for_emit.is_empty() {
+    // Interleave command events with the output around them so the frontend places them on
+    // the right lines.
+    let mut emitted = 0;
+    for event in shell_events {
+        emit_terminal_output(window, session_id, &text_for_emit[emitted..event.at]);
+        emitted = event.at;
+        if event.phase == CommandPhase::End {
+            history.record(session_id, event.command.clone());
+        }
+        if let Err(e) = window.emit(&format!("terminal-shell-command:{}", session_id), event) {
+            tracing::debug!(
+                "Failed to emit shell command event for {}: {}",
+                session_id,
+                e
+            );
+        }
+    }
+    emit_terminal_output(window, session_id, &text_for_emit[emitted..]);
+}
+
+fn emit_terminal_output(window: &Window, session_id: &str, text: &str) {
+    if text.is_empty() {
         return;
     }
 
-    if let Err(e) = window.emit(&format!("terminal-output:{}", session_id), text_for_emit) {
+    if let Err(e) = window.emit(&format!("terminal-output:{}", session_id), text) {
         tracing::debug!("Failed to emit terminal event for {}: {}", session_id, e);
     }
 }
@@ -78,12 +105,183 @@ pub struct ResizeParams {
     pub rows: u32,
 }
 
+/// Upper bound for `jumphost_id` chains; real setups use two or three hops.
+const MAX_JUMP_HOPS: usize = 8;
+
+fn find_authentication<'a>(
+    config: &'a Config,
+    auth_id: Option<&str>,
+) -> Option<&'a Authentication> {
+    auth_id.and_then(|id| config.authentications.iter().find(|a| a.id == id))
+}
+
+fn is_agent_auth(config: &Config, auth_id: Option<&str>) -> bool {
+    find_authentication(config, auth_id)
+        .map(|auth| auth.auth_type == "agent")
+        .unwrap_or(false)
+}
+
+fn totp_secret(config: &Config, auth_id: Option<&str>) -> Option<String> {
+    find_authentication(config, auth_id).and_then(|auth| auth.totp_secret.clone())
+}
+
+fn certificate(config: &Config, auth_id: Option<&str>) -> Option<String> {
+    find_authentication(config, auth_id)
+        .filter(|auth| auth.auth_type == "key")
+        .and_then(|auth| auth.certificate.clone())
+}
+
+/// Fills in per-server options that the connect form does not carry, looked up via
+/// `params.server_id`. Values sent explicitly by the caller win.
+fn apply_server_options(params: &mut ConnectParams, config: &Config) -> Result<(), String> {
+    let Some(server) = params
+        .server_id
+        .as_deref()
+        .and_then(|id| config.servers.iter().find(|s| s.id == id))
+    else {
+        return Ok(());
+    };
+
+    if params.port_forwards.is_empty() {
+        params.port_forwards = server.port_forwards.clone();
+    }
+    if params.dynamic_forward.is_none() {
+        params.dynamic_forward = server.dynamic_forward.clone();
+    }
+    if server.forward_agent {
+        params.forward_agent = true;
+    }
+    if is_agent_auth(config, server.auth_id.as_deref()) {
+        params.use_agent = true;
+    }
+    if params.totp_secret.is_none() {
+        params.totp_secret = totp_secret(config, server.auth_id.as_deref());
+    }
+    if params.certificate.is_none() {
+        params.certificate = certificate(config, server.auth_id.as_deref());
+    }
+
+    let chain = resolve_jump_chain(config, server)?;
+    if params.jump_chain.is_empty() && !chain.is_empty() {
+        params.jump_chain = chain
+            .iter()
+            .map(|hop| jumphost_config(config, hop))
+            .collect();
+        // The outermost hop's own proxy is only used to reach that hop; the server's
+        // proxy stays on `params.proxy` as the fallback for it.
+        params.jump_chain[0].proxy = chain[0]
+            .proxy_id
+            .as_deref()
+            .and_then(|id| config.proxies.iter().find(|p| p.id == id))
+            .cloned();
+    }
+    Ok(())
+}
+
+/// Follows `jumphost_id` links from `server` and returns the hops outermost first, so
+/// `target -> gateway -> bastion` becomes `[bastion, gateway]`.
+fn resolve_jump_chain<'a>(config: &'a Config, server: &Server) -> Result<Vec<&'a Server>, String> {
+    let mut chain: Vec<&Server> = Vec::new();
+    let mut seen = HashSet::from([server.id.as_str()]);
+    let mut next = server.jumphost_id.as_deref();
+    while let Some(id) = next {
+        let hop = config.servers.iter().find(|s| s.id == id).ok_or_else(|| {
+            format!(
+                "Server '{}' references missing jumphost '{}'",
+                server.name, id
+            )
+        })?;
+        if !seen.insert(hop.id.as_str()) {
+            return Err(format!(
+                "Jumphost chain of '{}' loops back to '{}'",
+                server.name, hop.name
+            ));
+        }
+        if chain.len() == MAX_JUMP_HOPS {
+            return Err(format!(
+                "Jumphost chain of '{}' is longer than {} hops",
+                server.name, MAX_JUMP_HOPS
+            ));
+        }
+        chain.push(hop);
+        next = hop.jumphost_id.as_deref();
+    }
+    chain.reverse();
+    Ok(chain)
+}
+
+fn jumphost_config(config: &Config, hop: &Server) -> JumphostConfig {
+    let auth = find_authentication(config, hop.auth_id.as_deref());
+    let auth_type = auth.map(|a| a.auth_type.as_str()).unwrap_or_default();
+    JumphostConfig {
+        host: hop.host.clone(),
+        port: hop.port,
+        username: hop.username.clone(),
+        password: auth
+            .filter(|_| auth_type == "password")
+            .and_then(|a| a.password.clone()),
+        private_key: auth
+            .filter(|_| auth_type == "key")
+            .and_then(|a| a.key_content.clone()),
+        passphrase: auth
+            .filter(|_| auth_type == "key")
+            .and_then(|a| a.passphrase.clone()),
+        use_agent: auth_type == "agent",
+        totp_secret: totp_secret(config, hop.auth_id.as_deref()),
+        certificate: certificate(config, hop.auth_id.as_deref()),
+        proxy: None,
+    }
+}
+
+/// Replaces vault-sealed credentials with their plaintext. The frontend passes the config's
+/// sealed values through unchanged, and the server options above add more of them.
+fn reveal_credentials(params: &mut ConnectParams) -> Result<(), String> {
+    // Opening a connection is a deliberate use of the vault.
+    vault::touch();
+    params.password = vault::reveal_option(&params.password)?;
+    params.private_key = vault::reveal_option(&params.private_key)?;
+    params.passphrase = vault::reveal_option(&params.passphrase)?;
+    params.totp_secret = vault::reveal_option(&params.totp_secret)?;
+    if let Some(proxy) = params.proxy.as_mut() {
+        proxy.password = vault::reveal_option(&proxy.password)?;
+    }
+    for hop in params
+        .jumphost
+        .iter_mut()
+        .chain(params.jump_chain.iter_mut())
+    {
+        if let Some(proxy) = hop.proxy.as_mut() {
+            proxy.password = vault::reveal_option(&proxy.password)?;
+        }
+        hop.password = vault::reveal_option(&hop.password)?;
+        hop.private_key = vault::reveal_option(&hop.private_key)?;
+        hop.passphrase = vault::reveal_option(&hop.passphrase)?;
+        hop.totp_secret = vault::reveal_option(&hop.totp_secret)?;
+    }
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn connect_to_server(
     window: Window,
     params: ConnectParams,
     state: State<'_, Arc<AppState>>,
 ) -> Result<ConnectResponse, String> {
+    let mut params = params;
+    let mut policy_recording = {
+        let config = state.config.lock().await;
+        apply_server_options(&mut params, &config)?;
+        params.server_id.as_deref().and_then(|id| {
+            recording_policy::resolve(&config, id, state.config_manager.app_data_dir())
+        })
+    };
+    reveal_credentials(&mut params)?;
+    let history = HistorySink::new(
+        state.db_manager.clone(),
+        params.server_id.clone(),
+        params.host.clone(),
+    );
+
     // Create channel for receiving SSH data
     let (tx, mut rx) = mpsc::unbounded_channel::<(String, Vec<u8>)>();
 
@@ -107,42 +305,47 @@ pub async fn connect_to_server(
                         current_session_id = Some(session_id.clone());
                     }
 
-                    // 录制热路径：DashMap 分片读，命中后立刻释放分片锁再异步写文件
-                    let recording_target = RECORDING_SESSIONS
-                        .get(&session_id)
-                        .map(|entry| entry.clone());
-
-                    if let Some(RecordingHandle { writer, mode }) = recording_target {
-                        let mut writer = writer.lock().await;
-
-                        let data_to_write = if mode == "text" {
-                            strip_ansi_escapes::strip(&data)
-                        } else {
-                            data.clone()
-                        };
-
-                        if let Err(e) = writer.write_all(&data_to_write).await {
-                            tracing::error!("Failed to write to recording file for session {}: {}", session_id, e);
+                    // Policy recordings start before the first output so the login banner is kept.
+                    // A session the policy requires to be recorded never runs unrecorded: the
+                    // reason is shown in its terminal and the connection is closed.
+                    if let Some(target) = policy_recording.take() {
+                        if let Err(e) = recording_policy::start(&session_id, target).await {
+                            tracing::error!(
+                                "Failed to start policy recording for session {}: {}",
+                                session_id,
+                                e
+                            );
+                            pending_output.push_str(&format!(
+                                "\r\n[Resh] Recording required by policy could not start: {}. Disconnecting.\r\n",
+                                e
+                            ));
+                            flush_terminal_output(&window_clone, &session_id, &mut pending_output, &history).await;
+                            if let Err(e) = SSHClient::disconnect(&session_id).await {
+                                tracing::debug!("Failed to disconnect session {}: {}", session_id, e);
+                            }
+                            break;
                         }
                     }
 
+                    recording::record_output(&session_id, &data).await;
+
                     let text = String::from_utf8_lossy(&data);
                     pending_output.push_str(&text);
 
                     if pending_output.len() >= 8192 {
-                        flush_terminal_output(&window_clone, &session_id, &mut pending_output).await;
+                        flush_terminal_output(&window_clone, &session_id, &mut pending_output, &history).await;
                     }
                 }
                 _ = flush_interval.tick() => {
                     if let Some(session_id) = current_session_id.as_deref() {
-                        flush_terminal_output(&window_clone, session_id, &mut pending_output).await;
+                        flush_terminal_output(&window_clone, session_id, &mut pending_output, &history).await;
                     }
                 }
             }
         }
 
         if let Some(session_id) = current_session_id.as_deref() {
-            flush_terminal_output(&window_clone, session_id, &mut pending_output).await;
+            flush_terminal_output(&window_clone, session_id, &mut pending_output, &history).await;
         }
 
         // Notify frontend that connection is closed
@@ -153,7 +356,14 @@ pub async fn connect_to_server(
             state_clone.sftp_edit_manager.cleanup_session(&session_id);
 
             // Ensure recording is stopped
-            RECORDING_SESSIONS.remove(&session_id);
+            if let Err(e) = recording::stop(&session_id).await {
+                tracing::error!(
+                    "Failed to finish recording for session {}: {}",
+                    session_id,
+                    e
+                );
+            }
+            recording_policy::finish(&session_id).await;
 
             if let Err(e) = window_clone.emit(&format!("connection-closed:{}", session_id), ()) {
                 tracing::debug!("Failed to emit connection-closed event: {}", e);
@@ -166,7 +376,7 @@ pub async fn connect_to_server(
 
     let session_id_clone = session_id.clone();
     tokio::spawn(async move {
-        match SSHClient::gather_system_info(params).await {
+        match SSHClient::gather_system_info(&session_id_clone, &params).await {
             Ok(info) => {
                 if let Err(e) = SSHClient::update_system_info(&session_id_clone, info).await {
                     tracing::error!(
@@ -189,43 +399,103 @@ pub async fn connect_to_server(
     Ok(ConnectResponse { session_id })
 }
 
+/// `mode` is "raw", "text" or "asciicast"; `record_input` only applies to asciicast.
 #[tauri::command]
 pub async fn start_recording(
     session_id: String,
     file_path: String,
     mode: String,
+    record_input: Option<bool>,
 ) -> Result<(), String> {
-    let file = File::create(&file_path)
-        .await
-        .map_err(|e| format!("Failed to create file: {}", e))?;
-    let writer = BufWriter::new(file);
-
-    RECORDING_SESSIONS.insert(
-        session_id,
-        RecordingHandle {
-            writer: Arc::new(Mutex::new(writer)),
-            mode,
+    ensure_not_enforced(&session_id)?;
+    let (cols, rows) = SSHClient::terminal_size(&session_id).await?;
+    recording::start(
+        &session_id,
+        &file_path,
+        RecordingOptions {
+            format: RecordingFormat::from_mode(&mode),
+            cols,
+            rows,
+            record_input: record_input.unwrap_or(false),
         },
-    );
-
-    Ok(())
+    )
+    .await
 }
 
 #[tauri::command]
 pub async fn stop_recording(session_id: String) -> Result<(), String> {
-    // 先把 entry 从 dashmap 中取出，避免持有分片锁的同时跨 .await 等待文件 flush
-    let removed = RECORDING_SESSIONS.remove(&session_id).map(|(_, v)| v);
-    if let Some(handle) = removed {
-        let mut writer = handle.writer.lock().await;
-        writer
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush file: {}", e))?;
-        // File closes when dropped
+    ensure_not_enforced(&session_id)?;
+    recording::stop(&session_id).await
+}
+
+fn ensure_not_enforced(session_id: &str) -> Result<(), String> {
+    if recording_policy::is_enforced(session_id) {
+        return Err("This session is recorded by policy".to_string());
     }
     Ok(())
 }
 
+/// Commands reported by shell integration (OSC 133), oldest first.
+#[tauri::command]
+pub async fn get_command_history(session_id: String) -> Result<Vec<CommandRecord>, String> {
+    SSHClient::get_command_history(&session_id).await
+}
+
+/// Plain-text output of a command from `get_command_history`.
+#[tauri::command]
+pub async fn get_command_output(session_id: String, command_id: u64) -> Result<String, String> {
+    SSHClient::get_command_output(&session_id, command_id).await
+}
+
+/// Full-text search over stored commands of a saved server, or of a host when `server_id` is
+/// not given; neither searches every server.
+#[tauri::command]
+pub async fn search_command_history(
+    query: String,
+    server_id: Option<String>,
+    host: Option<String>,
+    limit: Option<u32>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<CommandHistoryEntry>, String> {
+    let scope = HistoryScope { server_id, host };
+    let limit = limit.unwrap_or(command_history::DEFAULT_LIMIT);
+    state
+        .db_manager
+        .run_blocking(move |conn| command_history::search(conn, &query, &scope, limit))
+        .await
+}
+
+/// Most recent stored commands on a saved server or host, newest first.
+#[tauri::command]
+pub async fn get_recent_commands(
+    server_id: Option<String>,
+    host: Option<String>,
+    limit: Option<u32>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<Vec<CommandHistoryEntry>, String> {
+    let scope = HistoryScope { server_id, host };
+    let limit = limit.unwrap_or(command_history::DEFAULT_LIMIT);
+    state
+        .db_manager
+        .run_blocking(move |conn| command_history::recent(conn, &scope, limit))
+        .await
+}
+
+/// Deletes the stored commands of a saved server or host, or of every server when neither is
+/// given. Returns how many were deleted.
+#[tauri::command]
+pub async fn clear_command_history(
+    server_id: Option<String>,
+    host: Option<String>,
+    state: State<'_, Arc<AppState>>,
+) -> Result<usize, String> {
+    let scope = HistoryScope { server_id, host };
+    state
+        .db_manager
+        .run_blocking(move |conn| command_history::clear(conn, &scope))
+        .await
+}
+
 #[tauri::command]
 pub async fn send_command(
     params: CommandParams,
@@ -260,6 +530,19 @@ pub async fn close_session(
     Ok(())
 }
 
+#[tauri::command]
+pub async fn get_port_forwards(session_id: String) -> Result<Vec<PortForwardStatus>, String> {
+    Ok(PortForwardManager::session_status(&session_id))
+}
+
+/// SOCKS5 (-D) listener state for a session, including live connection counts.
+#[tauri::command]
+pub async fn get_dynamic_forward_status(
+    session_id: String,
+) -> Result<Option<DynamicForwardStatus>, String> {
+    Ok(SocksForwardManager::session_status(&session_id))
+}
+
 #[tauri::command]
 pub async fn reconnect_session(
     session_id: String,
@@ -318,3 +601,200 @@ pub async fn update_terminal_selection(
 ) -> Result<(), String> {
     SSHClient::update_terminal_selection(&session_id, selection).await
 }
+
+fn known_hosts_store() -> Result<&'static KnownHostsStore, String> {
+    KnownHostsStore::global().ok_or_else(|| "known_hosts store not initialized".to_string())
+}
+
+/// Imports an OpenSSH `known_hosts` file; defaults to `~/.ssh/known_hosts`.
+#[tauri::command]
+pub async fn import_known_hosts(path: Option<String>) -> Result<KnownHostsImportReport, String> {
+    let source = match path.filter(|p| !p.trim().is_empty()) {
+        Some(p) => std::path::PathBuf::from(p),
+        None => crate::app_paths::user_ssh_dir()
+            .map(|dir| dir.join("known_hosts"))
+            .ok_or_else(|| "Unable to locate the user's home directory".to_string())?,
+    };
+    let store = known_hosts_store()?;
+    tokio::task::spawn_blocking(move || store.import_openssh(&source))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Answers a `keyboard-interactive-prompt` event; `answers: None` cancels the login.
+#[tauri::command]
+pub async fn answer_keyboard_interactive(
+    request_id: String,
+    answers: Option<Vec<String>>,
+) -> Result<(), String> {
+    keyboard_interactive::submit_answers(&request_id, answers)
+}
+
+/// Removes the recorded key(s) for a host so the next connection trusts the new key.
+#[tauri::command]
+pub async fn forget_host_key(host: String, port: u16) -> Result<usize, String> {
+    let store = known_hosts_store()?;
+    tokio::task::spawn_blocking(move || store.forget(&host, port))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::Proxy;
+
+    fn server(id: &str, jumphost_id: Option<&str>) -> Server {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": id,
+            "host": format!("{}.example", id),
+            "port": 22,
+            "username": "deploy",
+            "authId": null,
+            "proxyId": null,
+            "jumphostId": jumphost_id,
+        }))
+        .expect("server fixture should deserialize")
+    }
+
+    fn authentication(id: &str, auth_type: &str) -> Authentication {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": id,
+            "type": auth_type,
+            "keyContent": null,
+            "passphrase": null,
+            "password": null,
+        }))
+        .expect("authentication fixture should deserialize")
+    }
+
+    fn connect_params(server_id: &str) -> ConnectParams {
+        serde_json::from_value(serde_json::json!({
+            "host": "target.example",
+            "port": 22,
+            "username": "deploy",
+            "password": null,
+            "private_key": null,
+            "passphrase": null,
+            "proxy": null,
+            "jumphost": null,
+            "server_id": server_id,
+        }))
+        .expect("connect params fixture should deserialize")
+    }
+
+    #[test]
+    fn jump_chain_is_ordered_outermost_first() {
+        let mut config = Config::empty();
+        config.servers = vec![
+            server("target", Some("gateway")),
+            server("gateway", Some("bastion")),
+            server("bastion", None),
+        ];
+
+        let chain = resolve_jump_chain(&config, &config.servers[0]).unwrap();
+        let ids: Vec<&str> = chain.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["bastion", "gateway"]);
+        assert!(resolve_jump_chain(&config, &config.servers[2])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn jump_chain_rejects_loops_and_missing_hops() {
+        let mut config = Config::empty();
+        config.servers = vec![
+            server("target", Some("a")),
+            server("a", Some("b")),
+            server("b", Some("a")),
+            server("orphan", Some("missing")),
+        ];
+
+        assert!(resolve_jump_chain(&config, &config.servers[0])
+            .unwrap_err()
+            .contains("loops back"));
+        assert!(resolve_jump_chain(&config, &config.servers[3])
+            .unwrap_err()
+            .contains("missing jumphost"));
+    }
+
+    #[test]
+    fn agent_auth_is_detected_by_authentication_type() {
+        let mut config = Config::empty();
+        config.authentications = vec![
+            authentication("agent", "agent"),
+            authentication("key", "key"),
+        ];
+
+        assert!(is_agent_auth(&config, Some("agent")));
+        assert!(!is_agent_auth(&config, Some("key")));
+        assert!(!is_agent_auth(&config, Some("missing")));
+        assert!(!is_agent_auth(&config, None));
+    }
+
+    #[test]
+    fn server_options_enable_agent_auth_for_target_and_hops() {
+        let mut config = Config::empty();
+        config.authentications = vec![
+            authentication("agent", "agent"),
+            authentication("key", "key"),
+        ];
+        let mut target = server("target", Some("bastion"));
+        target.auth_id = Some("agent".into());
+        let mut bastion = server("bastion", None);
+        bastion.auth_id = Some("agent".into());
+        let mut plain = server("plain", None);
+        plain.auth_id = Some("key".into());
+        config.servers = vec![target, bastion, plain];
+
+        let mut params = connect_params("target");
+        apply_server_options(&mut params, &config).unwrap();
+        assert!(params.use_agent);
+        assert_eq!(params.jump_chain.len(), 1);
+        assert!(params.jump_chain[0].use_agent);
+        assert!(params.jump_chain[0].private_key.is_none());
+
+        let mut params = connect_params("plain");
+        apply_server_options(&mut params, &config).unwrap();
+        assert!(!params.use_agent);
+    }
+
+    #[test]
+    fn jump_chain_keeps_the_server_proxy_and_gives_the_first_hop_its_own() {
+        let proxy = |id: &str| -> Proxy {
+            serde_json::from_value(serde_json::json!({
+                "id": id,
+                "name": id,
+                "type": "socks5",
+                "host": format!("{}.example", id),
+                "port": 1080,
+                "username": null,
+                "password": null,
+            }))
+            .expect("proxy fixture should deserialize")
+        };
+        let mut config = Config::empty();
+        config.proxies = vec![proxy("office"), proxy("edge")];
+        let mut target = server("target", Some("gateway"));
+        target.proxy_id = Some("office".into());
+        let mut bastion = server("bastion", None);
+        bastion.proxy_id = Some("edge".into());
+        let mut gateway = server("gateway", Some("bastion"));
+        gateway.proxy_id = Some("office".into());
+        config.servers = vec![target, gateway, bastion];
+
+        let mut params = connect_params("target");
+        params.proxy = Some(proxy("office"));
+        apply_server_options(&mut params, &config).unwrap();
+
+        assert_eq!(params.proxy.as_ref().map(|p| p.id.as_str()), Some("office"));
+        let hop_proxies: Vec<Option<&str>> = params
+            .jump_chain
+            .iter()
+            .map(|hop| hop.proxy.as_ref().map(|p| p.id.as_str()))
+            .collect();
+        assert_eq!(hop_proxies, vec![Some("edge"), None]);
+    }
+}
@@ -2,6 +2,7 @@ pub mod ai;
 pub mod config;
 pub mod connection;
 pub mod model_catalog;
+pub mod playback;
 pub mod sftp;
 pub mod sftp_edit;
 pub mod updater;
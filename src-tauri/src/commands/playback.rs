@@ -0,0 +1,259 @@
+use crate::ssh_manager::playback::{
+    load_cast, search_index, Cast, CastEventKind, PlaybackClock, TextIndexEntry,
+};
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::{Emitter, Window};
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+
+/// Events due within this window are emitted together, like the 16ms terminal output flush.
+const FRAME_BATCH: f64 = 0.016;
+/// How often the position is published while playing.
+const STATUS_INTERVAL: Duration = Duration::from_millis(250);
+
+enum PlaybackControl {
+    Play,
+    Pause,
+    Seek(f64),
+    Speed(f64),
+    Close,
+}
+
+struct PlaybackHandle {
+    control: mpsc::UnboundedSender<PlaybackControl>,
+    index: Arc<Vec<TextIndexEntry>>,
+}
+
+lazy_static! {
+    static ref PLAYBACKS: DashMap<String, PlaybackHandle> = DashMap::new();
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackInfo {
+    pub playback_id: String,
+    pub cols: u32,
+    pub rows: u32,
+    pub duration: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackStatus {
+    pub position: f64,
+    pub duration: f64,
+    pub playing: bool,
+    pub speed: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybackSize {
+    pub cols: u32,
+    pub rows: u32,
+}
+
+/// Load an asciicast recording paused at the start. Frames arrive as `playback-output:<id>`,
+/// size changes as `playback-resize:<id>`, `playback-reset:<id>` asks for a cleared screen
+/// before a seek redraw, and `playback-state:<id>` reports the position.
+#[tauri::command]
+pub async fn open_playback(
+    window: Window,
+    file_path: String,
+    idle_time_limit: Option<f64>,
+) -> Result<PlaybackInfo, String> {
+    let cast = load_cast(&file_path, idle_time_limit).await?;
+    let playback_id = uuid::Uuid::new_v4().to_string();
+    let info = PlaybackInfo {
+        playback_id: playback_id.clone(),
+        cols: cast.cols,
+        rows: cast.rows,
+        duration: cast.duration,
+    };
+    let (control, rx) = mpsc::unbounded_channel();
+    PLAYBACKS.insert(
+        playback_id.clone(),
+        PlaybackHandle {
+            control,
+            index: Arc::new(cast.text_index()),
+        },
+    );
+    tokio::spawn(run_playback(window, playback_id, cast, rx));
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn play_playback(playback_id: String) -> Result<(), String> {
+    send_control(&playback_id, PlaybackControl::Play)
+}
+
+#[tauri::command]
+pub async fn pause_playback(playback_id: String) -> Result<(), String> {
+    send_control(&playback_id, PlaybackControl::Pause)
+}
+
+/// `position` is in seconds on the idle-capped clock.
+#[tauri::command]
+pub async fn seek_playback(playback_id: String, position: f64) -> Result<(), String> {
+    send_control(&playback_id, PlaybackControl::Seek(position))
+}
+
+/// `speed` is a multiplier, clamped to 0.1–16.
+#[tauri::command]
+pub async fn set_playback_speed(playback_id: String, speed: f64) -> Result<(), String> {
+    send_control(&playback_id, PlaybackControl::Speed(speed))
+}
+
+#[tauri::command]
+pub async fn close_playback(playback_id: String) -> Result<(), String> {
+    if let Some((_, handle)) = PLAYBACKS.remove(&playback_id) {
+        let _ = handle.control.send(PlaybackControl::Close);
+    }
+    Ok(())
+}
+
+/// Output lines containing `query`, with the position to seek to.
+#[tauri::command]
+pub async fn search_playback(
+    playback_id: String,
+    query: String,
+) -> Result<Vec<TextIndexEntry>, String> {
+    let index = PLAYBACKS
+        .get(&playback_id)
+        .map(|entry| Arc::clone(&entry.index))
+        .ok_or("Playback not found")?;
+    Ok(search_index(&index, &query))
+}
+
+fn send_control(playback_id: &str, control: PlaybackControl) -> Result<(), String> {
+    PLAYBACKS
+        .get(playback_id)
+        .ok_or("Playback not found")?
+        .control
+        .send(control)
+        .map_err(|_| "Playback has ended".to_string())
+}
+
+async fn run_playback(
+    window: Window,
+    playback_id: String,
+    cast: Cast,
+    mut control: mpsc::UnboundedReceiver<PlaybackControl>,
+) {
+    let mut clock = PlaybackClock::default();
+    let mut next_event = 0usize;
+    let mut last_status = Instant::now();
+
+    loop {
+        if clock.is_playing() && next_event >= cast.events.len() {
+            clock.pause_at(cast.duration);
+            emit_status(&window, &playback_id, &clock, &cast);
+        }
+        let wait = cast
+            .events
+            .get(next_event)
+            .filter(|_| clock.is_playing())
+            .map(|event| clock.until(event.time));
+
+        tokio::select! {
+            msg = control.recv() => {
+                match msg {
+                    None | Some(PlaybackControl::Close) => break,
+                    Some(PlaybackControl::Play) => {
+                        if next_event >= cast.events.len() {
+                            // Play at the end starts over.
+                            next_event = seek(&window, &playback_id, &cast, &mut clock, 0.0);
+                        }
+                        clock.play();
+                    }
+                    Some(PlaybackControl::Pause) => clock.pause(),
+                    Some(PlaybackControl::Seek(position)) => {
+                        let position = position.clamp(0.0, cast.duration);
+                        next_event = seek(&window, &playback_id, &cast, &mut clock, position);
+                    }
+                    Some(PlaybackControl::Speed(speed)) => clock.set_speed(speed),
+                }
+                emit_status(&window, &playback_id, &clock, &cast);
+                last_status = Instant::now();
+            }
+            _ = tokio::time::sleep(wait.unwrap_or_default()), if wait.is_some() => {
+                let due = clock.position() + FRAME_BATCH * clock.speed();
+                let mut output = String::new();
+                while let Some(event) = cast.events.get(next_event).filter(|e| e.time <= due) {
+                    match &event.kind {
+                        CastEventKind::Output(data) => output.push_str(data),
+                        CastEventKind::Resize { cols, rows } => {
+                            emit_output(&window, &playback_id, &mut output);
+                            emit_resize(&window, &playback_id, *cols, *rows);
+                        }
+                        CastEventKind::Input(_) | CastEventKind::Marker(_) => {}
+                    }
+                    next_event += 1;
+                }
+                emit_output(&window, &playback_id, &mut output);
+                if last_status.elapsed() >= STATUS_INTERVAL {
+                    emit_status(&window, &playback_id, &clock, &cast);
+                    last_status = Instant::now();
+                }
+            }
+        }
+    }
+
+    PLAYBACKS.remove(&playback_id);
+}
+
+/// Redraw the screen as of `position` and return the next event to play.
+fn seek(
+    window: &Window,
+    playback_id: &str,
+    cast: &Cast,
+    clock: &mut PlaybackClock,
+    position: f64,
+) -> usize {
+    let mut frame = cast.frame_at(position);
+    emit(window, &format!("playback-reset:{}", playback_id), ());
+    let (cols, rows) = frame.size.unwrap_or((cast.cols, cast.rows));
+    emit_resize(window, playback_id, cols, rows);
+    emit_output(window, playback_id, &mut frame.output);
+    clock.seek(position);
+    frame.next_event
+}
+
+fn emit_output(window: &Window, playback_id: &str, output: &mut String) {
+    if !output.is_empty() {
+        emit(
+            window,
+            &format!("playback-output:{}", playback_id),
+            std::mem::take(output),
+        );
+    }
+}
+
+fn emit_resize(window: &Window, playback_id: &str, cols: u32, rows: u32) {
+    emit(
+        window,
+        &format!("playback-resize:{}", playback_id),
+        PlaybackSize { cols, rows },
+    );
+}
+
+fn emit_status(window: &Window, playback_id: &str, clock: &PlaybackClock, cast: &Cast) {
+    emit(
+        window,
+        &format!("playback-state:{}", playback_id),
+        PlaybackStatus {
+            position: clock.position().min(cast.duration),
+            duration: cast.duration,
+            playing: clock.is_playing(),
+            speed: clock.speed(),
+        },
+    );
+}
+
+fn emit<S: Serialize + Clone>(window: &Window, event: &str, payload: S) {
+    if let Err(e) = window.emit(event, payload) {
+        tracing::debug!("Failed to emit {}: {}", event, e);
+    }
+}
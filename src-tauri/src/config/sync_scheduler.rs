@@ -0,0 +1,206 @@
+//! Background sync cadence.
+//!
+//! The scheduler task in `commands::config` pulls every `pullIntervalMinutes`, pushes once local
+//! edits to synced entities have settled for `PUSH_DEBOUNCE`, and backs off exponentially while
+//! the sync location is unreachable. This module holds the shared handle and the timing rules;
+//! the status it keeps is what the `sync-scheduler-status` event carries to the frontend.
+
+use crate::config::sync_protocol::{local_synced_hashes, SyncErrorKind};
+use crate::config::types::{Config, WebDAVSettings};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+pub const DEFAULT_PULL_INTERVAL_MINUTES: u32 = 15;
+/// Quiet period after the last synced edit before it is pushed.
+pub const PUSH_DEBOUNCE: Duration = Duration::from_secs(5);
+/// While a restart drain blocks sync permits, check this often whether it was cancelled.
+pub const DRAIN_RECHECK: Duration = Duration::from_secs(30);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_BACKOFF: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SchedulerState {
+    /// Sync is off or has no location.
+    Disabled,
+    /// Waiting for the next periodic pull.
+    Idle,
+    /// A local edit is waiting out the debounce.
+    PushPending,
+    Syncing,
+    /// The last attempt could not reach the sync location; retrying later.
+    Backoff,
+    /// Update restart preparation is draining write operations.
+    Paused,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSchedulerStatus {
+    pub state: SchedulerState,
+    /// RFC3339 time of the next planned sync, if one is planned.
+    pub next_sync_at: Option<String>,
+    pub last_success_at: Option<String>,
+    /// Message of the most recent failed attempt; cleared by a successful sync.
+    pub last_error: Option<String>,
+    /// Unreachable attempts in a row; drives the backoff delay.
+    pub consecutive_failures: u32,
+}
+
+/// Shared between `save_config`, which requests pushes, and the scheduler task.
+pub struct SyncScheduler {
+    wake: Notify,
+    push_pending: AtomicBool,
+    /// Bumped by every save that queues a push; a new value restarts the debounce.
+    push_generation: AtomicU64,
+    status: Mutex<SyncSchedulerStatus>,
+}
+
+impl Default for SyncScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyncScheduler {
+    pub fn new() -> Self {
+        Self {
+            wake: Notify::new(),
+            push_pending: AtomicBool::new(false),
+            push_generation: AtomicU64::new(0),
+            status: Mutex::new(SyncSchedulerStatus {
+                state: SchedulerState::Disabled,
+                next_sync_at: None,
+                last_success_at: None,
+                last_error: None,
+                consecutive_failures: 0,
+            }),
+        }
+    }
+
+    /// Called after every local save. A push is queued only when synced entities changed, and
+    /// each such call restarts the debounce; the wake-up also lets the task pick up changed sync
+    /// settings.
+    pub fn config_saved(&self, synced_entities_changed: bool) {
+        if synced_entities_changed {
+            self.push_generation.fetch_add(1, Ordering::AcqRel);
+            self.push_pending.store(true, Ordering::Release);
+        }
+        self.reschedule();
+    }
+
+    /// Make the task re-plan its next sync, e.g. after a manual sync cleared a backoff.
+    pub fn reschedule(&self) {
+        self.wake.notify_one();
+    }
+
+    pub fn push_pending(&self) -> bool {
+        self.push_pending.load(Ordering::Acquire)
+    }
+
+    pub fn push_generation(&self) -> u64 {
+        self.push_generation.load(Ordering::Acquire)
+    }
+
+    /// Clears the queued push right before a sync starts; edits saved during the sync queue a
+    /// new one.
+    pub fn take_push(&self) {
+        self.push_pending.store(false, Ordering::Release);
+    }
+
+    /// Resolves on the next `reschedule`. A call made while nobody waits is kept, so a save
+    /// during a running sync is not lost.
+    pub async fn woken(&self) {
+        self.wake.notified().await
+    }
+
+    pub fn status(&self) -> SyncSchedulerStatus {
+        self.lock_status().clone()
+    }
+
+    /// Apply `update` and return the new status for publishing.
+    pub fn update_status(
+        &self,
+        update: impl FnOnce(&mut SyncSchedulerStatus),
+    ) -> SyncSchedulerStatus {
+        let mut status = self.lock_status();
+        update(&mut status);
+        status.clone()
+    }
+
+    fn lock_status(&self) -> std::sync::MutexGuard<'_, SyncSchedulerStatus> {
+        self.status.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Whether two configs differ in anything the sync document carries.
+pub fn synced_entities_changed(before: &Config, after: &Config) -> bool {
+    local_synced_hashes(before) != local_synced_hashes(after)
+}
+
+/// Periodic pull cadence; `None` when periodic pulls are turned off.
+pub fn pull_interval(settings: &WebDAVSettings) -> Option<Duration> {
+    (settings.pull_interval_minutes > 0)
+        .then(|| Duration::from_secs(u64::from(settings.pull_interval_minutes) * 60))
+}
+
+/// Delay before retry number `consecutive_failures` (1-based): 30s doubling up to 30 minutes.
+pub fn backoff_delay(consecutive_failures: u32) -> Duration {
+    let exponent = consecutive_failures.saturating_sub(1).min(16);
+    INITIAL_BACKOFF
+        .saturating_mul(1 << exponent)
+        .min(MAX_BACKOFF)
+}
+
+/// Failures worth retrying with backoff: the location could not be reached or another device
+/// kept winning the conditional write. Everything else needs the user to act first.
+pub fn is_transient(kind: &SyncErrorKind) -> bool {
+    matches!(
+        kind,
+        SyncErrorKind::Network | SyncErrorKind::ConcurrentRemoteChange
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        assert_eq!(backoff_delay(1), Duration::from_secs(30));
+        assert_eq!(backoff_delay(2), Duration::from_secs(60));
+        assert_eq!(backoff_delay(4), Duration::from_secs(240));
+        assert_eq!(backoff_delay(7), MAX_BACKOFF);
+        assert_eq!(backoff_delay(u32::MAX), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn only_synced_edits_queue_a_push() {
+        let before = Config::empty();
+        let mut after = before.clone();
+        after.general.theme = "light".into();
+        assert!(!synced_entities_changed(&before, &after));
+        after.additional_prompt = Some("Be brief".into());
+        assert!(synced_entities_changed(&before, &after));
+
+        let scheduler = SyncScheduler::new();
+        scheduler.config_saved(false);
+        assert!(!scheduler.push_pending());
+        scheduler.config_saved(true);
+        assert!(scheduler.push_pending());
+        scheduler.take_push();
+        assert!(!scheduler.push_pending());
+    }
+
+    #[tokio::test]
+    async fn a_save_while_nobody_waits_still_wakes_the_task() {
+        let scheduler = SyncScheduler::new();
+        scheduler.config_saved(true);
+        tokio::time::timeout(Duration::from_secs(1), scheduler.woken())
+            .await
+            .expect("stored wake-up");
+    }
+}
@@ -0,0 +1,201 @@
+//! Opt-in settings groups in the sync document.
+//!
+//! `GeneralSettings` is device-local. Groups listed in `general.syncedSettings` are synced as
+//! singleton entities whose id is the type name. The merge treats a group that is not opted in
+//! like an entity with its sync toggle off: the remote value is kept but never applied. The SFTP
+//! download path is device-specific and never leaves the device.
+
+use crate::config::sync_protocol::{
+    hash_editor_rules, hash_sftp_settings, hash_terminal_settings, hash_theme,
+    summary_editor_rules, summary_sftp_settings, summary_terminal_settings, summary_theme,
+    EntitySummary, SyncEntityType,
+};
+use crate::config::types::{Config, GeneralSettings, SftpSettings, SyncConfig};
+
+pub const SETTINGS_GROUPS: [SyncEntityType; 4] = [
+    SyncEntityType::Theme,
+    SyncEntityType::TerminalSettings,
+    SyncEntityType::SftpSettings,
+    SyncEntityType::EditorRules,
+];
+
+pub fn is_opted_in(general: &GeneralSettings, entity_type: SyncEntityType) -> bool {
+    general
+        .synced_settings
+        .iter()
+        .any(|name| name == entity_type.as_str())
+}
+
+pub fn opt_out(general: &mut GeneralSettings, entity_type: SyncEntityType) {
+    general
+        .synced_settings
+        .retain(|name| name != entity_type.as_str());
+}
+
+/// Hash of the local group; `None` unless it is opted in.
+pub fn local_group_hash(config: &Config, entity_type: SyncEntityType) -> Option<String> {
+    if !is_opted_in(&config.general, entity_type) {
+        return None;
+    }
+    let general = &config.general;
+    match entity_type {
+        SyncEntityType::Theme => Some(hash_theme(&general.theme)),
+        SyncEntityType::TerminalSettings => Some(hash_terminal_settings(&general.terminal)),
+        SyncEntityType::SftpSettings => Some(hash_sftp_settings(&general.sftp)),
+        SyncEntityType::EditorRules => Some(hash_editor_rules(&general.sftp.editors)),
+        _ => None,
+    }
+}
+
+pub fn remote_group_hash(remote: &SyncConfig, entity_type: SyncEntityType) -> Option<String> {
+    let settings = &remote.settings;
+    match entity_type {
+        SyncEntityType::Theme => settings.theme.as_deref().map(hash_theme),
+        SyncEntityType::TerminalSettings => settings.terminal.as_ref().map(hash_terminal_settings),
+        SyncEntityType::SftpSettings => settings.sftp.as_ref().map(hash_sftp_settings),
+        SyncEntityType::EditorRules => settings.editor_rules.as_deref().map(hash_editor_rules),
+        _ => None,
+    }
+}
+
+/// Summary of the local group; `None` unless it is opted in.
+pub fn local_group_summary(config: &Config, entity_type: SyncEntityType) -> Option<EntitySummary> {
+    if !is_opted_in(&config.general, entity_type) {
+        return None;
+    }
+    let general = &config.general;
+    match entity_type {
+        SyncEntityType::Theme => Some(summary_theme(&general.theme)),
+        SyncEntityType::TerminalSettings => Some(summary_terminal_settings(&general.terminal)),
+        SyncEntityType::SftpSettings => Some(summary_sftp_settings(&general.sftp)),
+        SyncEntityType::EditorRules => Some(summary_editor_rules(&general.sftp.editors)),
+        _ => None,
+    }
+}
+
+pub fn remote_group_summary(
+    remote: &SyncConfig,
+    entity_type: SyncEntityType,
+) -> Option<EntitySummary> {
+    let settings = &remote.settings;
+    match entity_type {
+        SyncEntityType::Theme => settings.theme.as_deref().map(summary_theme),
+        SyncEntityType::TerminalSettings => {
+            settings.terminal.as_ref().map(summary_terminal_settings)
+        }
+        SyncEntityType::SftpSettings => settings.sftp.as_ref().map(summary_sftp_settings),
+        SyncEntityType::EditorRules => settings.editor_rules.as_deref().map(summary_editor_rules),
+        _ => None,
+    }
+}
+
+/// Display name used when neither side has the group.
+pub fn group_display_name(entity_type: SyncEntityType) -> &'static str {
+    match entity_type {
+        SyncEntityType::Theme => "Theme",
+        SyncEntityType::TerminalSettings => "Terminal settings",
+        SyncEntityType::SftpSettings => "SFTP transfer settings",
+        SyncEntityType::EditorRules => "Editor rules",
+        _ => entity_type.as_str(),
+    }
+}
+
+/// Write the local value of a group into the outgoing document.
+pub fn upload_group(local: &Config, remote: &mut SyncConfig, entity_type: SyncEntityType) {
+    let general = &local.general;
+    let settings = &mut remote.settings;
+    match entity_type {
+        SyncEntityType::Theme => settings.theme = Some(general.theme.clone()),
+        SyncEntityType::TerminalSettings => settings.terminal = Some(general.terminal.clone()),
+        SyncEntityType::SftpSettings => {
+            settings.sftp = Some(SftpSettings {
+                default_download_path: String::new(),
+                editors: vec![],
+                ..general.sftp.clone()
+            })
+        }
+        SyncEntityType::EditorRules => settings.editor_rules = Some(general.sftp.editors.clone()),
+        _ => {}
+    }
+}
+
+/// Carry a group from one document into another unchanged.
+pub fn keep_remote_group(from: &SyncConfig, to: &mut SyncConfig, entity_type: SyncEntityType) {
+    let (from, to) = (&from.settings, &mut to.settings);
+    match entity_type {
+        SyncEntityType::Theme => to.theme = from.theme.clone(),
+        SyncEntityType::TerminalSettings => to.terminal = from.terminal.clone(),
+        SyncEntityType::SftpSettings => to.sftp = from.sftp.clone(),
+        SyncEntityType::EditorRules => to.editor_rules = from.editor_rules.clone(),
+        _ => {}
+    }
+}
+
+/// Apply the remote value of a group to the local settings, keeping the local download path.
+pub fn apply_remote_group(remote: &SyncConfig, local: &mut Config, entity_type: SyncEntityType) {
+    let settings = &remote.settings;
+    let general = &mut local.general;
+    match entity_type {
+        SyncEntityType::Theme => {
+            if let Some(theme) = &settings.theme {
+                general.theme = theme.clone();
+            }
+        }
+        SyncEntityType::TerminalSettings => {
+            if let Some(terminal) = &settings.terminal {
+                general.terminal = terminal.clone();
+            }
+        }
+        SyncEntityType::SftpSettings => {
+            if let Some(sftp) = &settings.sftp {
+                general.sftp = SftpSettings {
+                    default_download_path: std::mem::take(&mut general.sftp.default_download_path),
+                    editors: std::mem::take(&mut general.sftp.editors),
+                    ..sftp.clone()
+                };
+            }
+        }
+        SyncEntityType::EditorRules => {
+            if let Some(rules) = &settings.editor_rules {
+                general.sftp.editors = rules.clone();
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::EditorRule;
+
+    #[test]
+    fn sftp_group_never_carries_device_fields() {
+        let mut laptop = Config::empty();
+        laptop.general.synced_settings = vec!["sftpSettings".into()];
+        laptop.general.sftp.default_download_path = "/home/me/Downloads".into();
+        laptop.general.sftp.transfer_profile = "fast".into();
+        laptop.general.sftp.editors = vec![EditorRule {
+            id: "r1".into(),
+            pattern: "*.md".into(),
+            editor: "code".into(),
+        }];
+
+        let mut remote = SyncConfig::empty("1.0");
+        upload_group(&laptop, &mut remote, SyncEntityType::SftpSettings);
+        let uploaded = remote.settings.sftp.as_ref().unwrap();
+        assert!(uploaded.default_download_path.is_empty());
+        assert!(uploaded.editors.is_empty());
+        assert_eq!(
+            remote_group_hash(&remote, SyncEntityType::SftpSettings),
+            local_group_hash(&laptop, SyncEntityType::SftpSettings)
+        );
+
+        let mut desktop = Config::empty();
+        desktop.general.sftp.default_download_path = "D:\\Downloads".into();
+        apply_remote_group(&remote, &mut desktop, SyncEntityType::SftpSettings);
+        assert_eq!(desktop.general.sftp.transfer_profile, "fast");
+        assert_eq!(desktop.general.sftp.default_download_path, "D:\\Downloads");
+        assert!(desktop.general.sftp.editors.is_empty());
+    }
+}
@@ -7,7 +7,7 @@ use crate::config::sync_protocol::{
     EntityKey, SyncEntityType, ADDITIONAL_PROMPT_ENTITY_ID, SYNC_SCHEMA_VERSION,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -36,6 +36,10 @@ pub struct AccountSyncBaseline {
     /// never contains user credentials or entity content.
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub resolution_secret: String,
+    /// `entityType:id` of local entities moved into this shared collection that have not been
+    /// uploaded yet. Together with `entity_hashes` this is the collection's ownership record.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub claimed_entities: Vec<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -126,6 +130,25 @@ impl AccountSyncBaseline {
         }
     }
 
+    /// Entities owned by this account's source: the last synced document plus pending claims.
+    /// Singletons (the additional prompt and settings groups) always stay with the personal
+    /// document.
+    pub fn owned_entities(&self) -> BTreeSet<EntityKey> {
+        self.entity_hashes
+            .keys()
+            .chain(&self.claimed_entities)
+            .filter_map(|key| EntityKey::parse_storage_key(key))
+            .filter(|key| !key.entity_type.is_singleton())
+            .collect()
+    }
+
+    pub fn claim(&mut self, key: &EntityKey) {
+        let key = key.storage_key();
+        if !self.claimed_entities.contains(&key) {
+            self.claimed_entities.push(key);
+        }
+    }
+
     pub fn clear_tombstone(&mut self, key: &EntityKey) {
         self.tombstones
             .retain(|t| !(t.entity_type == key.entity_type && t.id == key.id));
@@ -147,6 +170,8 @@ impl AccountSyncBaseline {
             self.entity_hashes.insert(k.storage_key(), h.clone());
         }
         self.tombstones = tombstones.iter().map(BaselineTombstone::from_key).collect();
+        // Claimed entities were part of the synced view, so they are in `hashes` now.
+        self.claimed_entities.clear();
         self.remote_etag = remote_etag;
         self.remote_revision = remote_revision;
         self.sync_schema = SYNC_SCHEMA_VERSION;
@@ -29,6 +29,15 @@ pub struct Server {
     pub jumphost_id: Option<String>,
     #[serde(default)]
     pub port_forwards: Vec<PortForward>,
+    /// Local SOCKS5 server (-D) started with each session.
+    #[serde(default)]
+    #[serde(alias = "dynamic_forward")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dynamic_forward: Option<DynamicForward>,
+    /// Forward the local SSH agent to the shell session (`ssh -A`).
+    #[serde(default)]
+    #[serde(alias = "forward_agent")]
+    pub forward_agent: bool,
     #[serde(default)]
     pub keep_alive: u32,
     #[serde(default)]
@@ -49,6 +58,10 @@ pub struct Server {
     #[serde(alias = "additionalPrompt", alias = "additional_prompt")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub additional_prompt: Option<String>,
+    /// Record every session to this server; overrides a policy for its group.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recording_policy: Option<RecordingPolicy>,
     #[serde(default = "default_true")]
     pub synced: bool,
     #[serde(default)]
@@ -65,6 +78,91 @@ pub struct Server {
 pub struct PortForward {
     pub local: u16,
     pub remote: u16,
+    /// "local" (-L): listen on `local` here and tunnel to `remote` on the server.
+    /// "remote" (-R): the server listens on `remote` and tunnels back to `local` here.
+    #[serde(default = "default_port_forward_direction")]
+    pub direction: String,
+    /// Listen address; local interface for -L, server-side address for -R.
+    #[serde(default)]
+    #[serde(alias = "bind_host")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bind_host: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DynamicForward {
+    /// Defaults to 127.0.0.1 so the proxy is not exposed to the network.
+    #[serde(default)]
+    #[serde(alias = "bind_host")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bind_host: Option<String>,
+    pub port: u16,
+}
+
+/// Recording started automatically for every session the policy applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingPolicy {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// "raw", "text" or "asciicast", as in `recordingMode`.
+    #[serde(default = "default_policy_recording_format")]
+    pub format: String,
+    /// Also record keystrokes; asciicast only.
+    #[serde(default)]
+    pub record_input: bool,
+    /// Folder for the recordings; empty means `recordings` in the app data folder.
+    #[serde(default)]
+    pub directory: String,
+    /// File name without extension. `{server}`, `{host}`, `{user}`, `{group}`, `{date}`,
+    /// `{time}` and `{session}` are filled in.
+    #[serde(default = "default_recording_file_name")]
+    pub file_name: String,
+    /// Keep at most this many finished recordings of the policy; 0 keeps all.
+    #[serde(default)]
+    pub keep_count: u32,
+    /// Delete recordings of the policy older than this many days; 0 keeps all.
+    #[serde(default)]
+    pub keep_days: u32,
+    /// Gzip each recording when its session ends.
+    #[serde(default)]
+    pub compress: bool,
+}
+
+/// Recording policy for every server in `group`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupRecordingPolicy {
+    pub group: String,
+    #[serde(flatten)]
+    pub policy: RecordingPolicy,
+}
+
+fn default_policy_recording_format() -> String {
+    "asciicast".to_string()
+}
+
+pub fn default_recording_file_name() -> String {
+    "{server}-{date}-{time}".to_string()
+}
+
+fn default_port_forward_direction() -> String {
+    "local".to_string()
+}
+
+impl PortForward {
+    pub fn is_remote(&self) -> bool {
+        self.direction.eq_ignore_ascii_case("remote")
+    }
+
+    /// The configured bind host, ignoring blank values.
+    pub fn bind_host(&self) -> Option<&str> {
+        self.bind_host
+            .as_deref()
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,10 +171,20 @@ pub struct Authentication {
     pub id: String,
     pub name: String,
     #[serde(rename = "type")]
-    pub auth_type: String, // "key" or "password"
+    pub auth_type: String, // "key", "password" or "agent"
     pub key_content: Option<String>,
     pub passphrase: Option<String>,
     pub password: Option<String>,
+    /// Base32 TOTP secret for servers that ask for a one-time code during
+    /// keyboard-interactive login.
+    #[serde(default)]
+    #[serde(alias = "totp_secret")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub totp_secret: Option<String>,
+    /// OpenSSH user certificate (`id_*-cert.pub` content) presented with `key_content`.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<String>,
     #[serde(default = "default_true")]
     pub synced: bool,
     #[serde(default = "default_updated_at")]
@@ -90,13 +198,21 @@ pub struct Proxy {
     pub id: String,
     pub name: String,
     #[serde(rename = "type")]
-    pub proxy_type: String, // "http" or "socks5"
+    pub proxy_type: String, // "http", "socks5" or "command"
+    #[serde(default)]
     pub host: String,
+    #[serde(default)]
     pub port: u16,
     pub username: Option<String>,
     pub password: Option<String>,
     #[serde(default)]
     pub ignore_ssl_errors: bool, // 忽略 SSL 证书校验（用于公司代理 MITM 场景）
+    /// Local command for type "command" (OpenSSH `ProxyCommand`); `%h`, `%p` and `%r`
+    /// expand to the SSH host, port and user. Only used for SSH connections. Never synced:
+    /// each device keeps its own command for a synced proxy.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
     #[serde(default = "default_true")]
     pub synced: bool,
     #[serde(default = "default_updated_at")]
@@ -195,12 +311,53 @@ fn default_terminal_renderer() -> String {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WebDAVSettings {
+    /// Storage backend: "webdav", "s3", "folder" or "git". `url` is the WebDAV URL, the
+    /// path-style bucket URL, the folder path or the repository URL respectively.
+    #[serde(default = "default_sync_backend")]
+    pub backend: String,
     pub url: String,
+    /// WebDAV user or S3 access key id.
     pub username: String,
+    /// WebDAV password or S3 secret access key.
     pub password: String,
     #[serde(default)]
     pub enabled: bool,
     pub proxy_id: Option<String>,
+    /// Passphrase for end-to-end encrypting `sync.json`; empty uploads plain JSON.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub encryption_passphrase: String,
+    /// S3 signing region; empty means us-east-1.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub region: String,
+    /// Git branch holding the sync documents; empty means main.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub branch: String,
+    /// Minutes between background pulls of the personal sync location; 0 turns them off.
+    #[serde(default = "default_pull_interval_minutes")]
+    pub pull_interval_minutes: u32,
+}
+
+fn default_sync_backend() -> String {
+    "webdav".to_string()
+}
+
+fn default_pull_interval_minutes() -> u32 {
+    crate::config::sync_scheduler::DEFAULT_PULL_INTERVAL_MINUTES
+}
+
+/// A team sync source synced next to the personal document. Which entities belong to it is
+/// recorded in that source's `sync-state.json` baseline rather than on the entities.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedCollection {
+    pub id: String,
+    pub name: String,
+    /// Mirror the remote collection without ever uploading; its entities cannot be edited
+    /// locally.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Location and credentials of the collection's own `sync.json`.
+    pub sync: WebDAVSettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -332,6 +489,13 @@ pub struct GeneralSettings {
     pub language: String,
     pub terminal: TerminalSettings,
     pub webdav: WebDAVSettings,
+    /// Team collections synced alongside `webdav`; local-only like the rest of the settings.
+    #[serde(default)]
+    pub shared_collections: Vec<SharedCollection>,
+    /// Settings groups opted into the personal sync document, by `SyncEntityType` name
+    /// (`theme`, `terminalSettings`, `sftpSettings`, `editorRules`). The list itself stays local.
+    #[serde(default)]
+    pub synced_settings: Vec<String>,
     pub confirm_close_tab: bool,
     pub confirm_exit_app: bool,
     #[serde(default)]
@@ -371,6 +535,12 @@ pub struct GeneralSettings {
     #[serde(default = "default_recording_mode")]
     #[serde(alias = "recordingMode", alias = "recording_mode")]
     pub recording_mode: String,
+    /// Also record keystrokes as input events; asciicast recordings only.
+    #[serde(default)]
+    pub recording_input: bool,
+    /// Automatic recording by server group; a server's own policy takes precedence.
+    #[serde(default)]
+    pub group_recording_policies: Vec<GroupRecordingPolicy>,
     #[serde(default = "default_tab_width_mode")]
     #[serde(alias = "tabWidthMode", alias = "tab_width_mode")]
     pub tab_width_mode: String,
@@ -570,6 +740,8 @@ pub struct SyncConfig {
         alias = "additional_prompt_updated_at"
     )]
     pub additional_prompt_updated_at: Option<String>,
+    #[serde(default, skip_serializing_if = "SyncedSettings::is_empty")]
+    pub settings: SyncedSettings,
     /// Typed deletion tombstones (preferred).
     #[serde(default)]
     pub tombstones: Vec<crate::config::sync_protocol::DeletionTombstone>,
@@ -578,6 +750,31 @@ pub struct SyncConfig {
     pub removed_ids: Vec<String>,
 }
 
+/// Settings groups carried by the sync document. A group stays `None` until a device that opted
+/// into it uploads its value; device-specific fields are never included.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncedSettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<TerminalSettings>,
+    /// Transfer tuning only; `defaultDownloadPath` and `editors` are always left empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sftp: Option<SftpSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor_rules: Option<Vec<EditorRule>>,
+}
+
+impl SyncedSettings {
+    pub fn is_empty(&self) -> bool {
+        self.theme.is_none()
+            && self.terminal.is_none()
+            && self.sftp.is_none()
+            && self.editor_rules.is_none()
+    }
+}
+
 impl SyncConfig {
     pub fn empty(version: impl Into<String>) -> Self {
         Self {
@@ -593,6 +790,7 @@ impl SyncConfig {
             sftp_custom_commands: vec![],
             additional_prompt: None,
             additional_prompt_updated_at: None,
+            settings: SyncedSettings::default(),
             tombstones: vec![],
             removed_ids: vec![],
         }
@@ -628,12 +826,19 @@ impl Config {
                     renderer: default_terminal_renderer(),
                 },
                 webdav: WebDAVSettings {
+                    backend: default_sync_backend(),
                     url: String::new(),
                     username: String::new(),
                     password: String::new(),
                     enabled: false,
                     proxy_id: None,
+                    encryption_passphrase: String::new(),
+                    region: String::new(),
+                    branch: String::new(),
+                    pull_interval_minutes: default_pull_interval_minutes(),
                 },
+                shared_collections: vec![],
+                synced_settings: vec![],
                 confirm_close_tab: true,
                 confirm_exit_app: true,
                 debug_enabled: false,
@@ -662,6 +867,8 @@ impl Config {
                 recent_server_ids: vec![],
                 window_state: default_window_state(),
                 recording_mode: default_recording_mode(),
+                recording_input: false,
+                group_recording_policies: vec![],
                 tab_width_mode: default_tab_width_mode(),
                 tab_fixed_width: default_tab_fixed_width(),
                 terminal_right_click_mode: default_terminal_right_click_mode(),
@@ -206,6 +206,8 @@ impl DatabaseManager {
             [],
         );
 
+        crate::ssh_manager::command_history::create_tables(&conn)?;
+
         Ok(())
     }
 
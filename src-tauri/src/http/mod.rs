@@ -70,6 +70,9 @@ pub fn build_reqwest_proxy(proxy: &Proxy) -> Result<ReqwestProxy, String> {
     let scheme = match proxy.proxy_type.as_str() {
         "socks5" => "socks5h",
         "http" => "http",
+        "command" => {
+            return Err("Command proxies can only be used for SSH connections.".to_string());
+        }
         other => {
             return Err(format!(
                 "Unsupported proxy type '{}'. Expected 'http' or 'socks5'.",
@@ -91,8 +94,8 @@ pub fn build_reqwest_proxy(proxy: &Proxy) -> Result<ReqwestProxy, String> {
 
     if let Some(ref user) = proxy.username {
         if !user.is_empty() {
-            let pass = proxy.password.as_deref().unwrap_or("");
-            p = p.basic_auth(user, pass);
+            let pass = crate::config::vault::reveal(proxy.password.as_deref().unwrap_or(""))?;
+            p = p.basic_auth(user, &pass);
         }
     }
 
@@ -137,6 +140,7 @@ mod tests {
             username: Some("user".to_string()),
             password: Some("pass".to_string()),
             ignore_ssl_errors: false,
+            command: None,
             synced: true,
             updated_at: "2026-01-01T00:00:00Z".to_string(),
         }
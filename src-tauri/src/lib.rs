@@ -8,6 +8,7 @@ pub mod logger;
 pub mod model_catalog;
 pub mod sftp_manager;
 pub mod ssh_manager;
+pub mod sync_backend;
 pub mod updater;
 pub mod webdav;
 pub mod window_state;
@@ -8,6 +8,7 @@ use resh::config::ConfigManager;
 use resh::db::DatabaseManager;
 use resh::logger;
 use resh::sftp_manager::edit::SftpEditManager;
+use resh::ssh_manager::known_hosts::KnownHostsStore;
 use resh::ssh_manager::ssh::SSHClient;
 use std::sync::Arc;
 use std::sync::OnceLock;
@@ -157,6 +158,7 @@ async fn main() {
             logger::init_logging(app_data_dir.clone(), debug_enabled);
             tracing::info!("Logging initialized. Debug mode: {}", debug_enabled);
             SSHClient::set_app_handle(app.handle().clone());
+            KnownHostsStore::init_global(&app_data_dir);
 
             let state = Arc::new(commands::AppState {
                 config_manager: config_manager.clone(),
@@ -172,6 +174,7 @@ async fn main() {
                 operation_coordinator: std::sync::Arc::new(
                     resh::updater::OperationCoordinator::new(),
                 ),
+                sync_scheduler: resh::config::sync_scheduler::SyncScheduler::new(),
             });
             app.manage(state.clone());
 
@@ -180,6 +183,12 @@ async fn main() {
             // when it goes stale. Failures keep the last good cache.
             commands::model_catalog::spawn_scheduled_refresh(state.model_catalog.clone());
 
+            // Drops the vault key once it has been idle for the configured timeout.
+            commands::config::spawn_vault_auto_lock(app.handle().clone());
+
+            // Background pulls and debounced pushes of the sync document.
+            commands::config::spawn_sync_scheduler(app.handle().clone());
+
             // Capture optional post-update restore token (validated later when loading snapshot).
             {
                 let args: Vec<String> = std::env::args().collect();
@@ -284,19 +293,52 @@ async fn main() {
             commands::config::save_config,
             commands::config::record_server_connection,
             commands::config::trigger_sync,
+            commands::config::migrate_sync_encryption,
             commands::config::resolve_sync_conflicts,
+            commands::config::list_sync_revisions,
+            commands::config::diff_sync_revisions,
+            commands::config::restore_sync_revision,
+            commands::config::share_with_collection,
+            commands::config::get_sync_scheduler_status,
             commands::config::get_app_data_dir,
+            commands::config::inspect_ssh_certificate,
+            commands::config::import_ssh_config,
+            commands::config::import_sessions,
+            commands::config::export_ssh_config,
+            commands::config::get_vault_status,
+            commands::config::enable_vault,
+            commands::config::disable_vault,
+            commands::config::unlock_vault,
+            commands::config::lock_vault,
+            commands::config::set_vault_auto_lock,
             commands::config::log_event,
             commands::connection::connect_to_server,
             commands::connection::start_recording,
             commands::connection::stop_recording,
+            commands::connection::get_command_history,
+            commands::connection::get_command_output,
+            commands::connection::search_command_history,
+            commands::connection::get_recent_commands,
+            commands::connection::clear_command_history,
+            commands::playback::open_playback,
+            commands::playback::play_playback,
+            commands::playback::pause_playback,
+            commands::playback::seek_playback,
+            commands::playback::set_playback_speed,
+            commands::playback::close_playback,
+            commands::playback::search_playback,
             commands::connection::send_command,
             commands::connection::resize_terminal,
             commands::connection::close_session,
             commands::connection::reconnect_session,
+            commands::connection::get_port_forwards,
+            commands::connection::get_dynamic_forward_status,
             commands::connection::export_terminal_log,
             commands::connection::select_save_path,
             commands::connection::update_terminal_selection,
+            commands::connection::import_known_hosts,
+            commands::connection::forget_host_key,
+            commands::connection::answer_keyboard_interactive,
             commands::ai::create_ai_session,
             commands::ai::get_ai_sessions,
             commands::ai::get_ai_messages,
@@ -0,0 +1,239 @@
+use crate::ssh_manager::ssh::SSHClient;
+use russh::keys::agent::client::{AgentClient, AgentStream};
+use russh::keys::{self, HashAlg};
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tracing::{debug, info};
+
+/// `SSH_AGENTC_SIGN_REQUEST` from draft-miller-ssh-agent.
+const SSH_AGENTC_SIGN_REQUEST: u8 = 13;
+/// Upper bound for a single agent message; real requests are a few KB.
+const MAX_AGENT_MESSAGE_LEN: usize = 256 * 1024;
+
+/// Default OpenSSH for Windows agent pipe, used when `SSH_AUTH_SOCK` is not set.
+#[cfg(windows)]
+const OPENSSH_AGENT_PIPE: &str = r"\\.\pipe\openssh-ssh-agent";
+
+pub type LocalAgent = AgentClient<Box<dyn AgentStream + Send + Unpin + 'static>>;
+
+/// Connects to the user's SSH agent: `SSH_AUTH_SOCK` on Unix; on Windows the OpenSSH
+/// agent pipe (or `SSH_AUTH_SOCK` if it names a pipe), falling back to Pageant.
+#[cfg(unix)]
+pub async fn connect_local_agent() -> Result<LocalAgent, String> {
+    AgentClient::connect_env()
+        .await
+        .map(AgentClient::dynamic)
+        .map_err(|e| format!("SSH agent unavailable (SSH_AUTH_SOCK): {}", e))
+}
+
+#[cfg(windows)]
+pub async fn connect_local_agent() -> Result<LocalAgent, String> {
+    let pipe = agent_pipe_path();
+    match AgentClient::connect_named_pipe(&pipe).await {
+        Ok(client) => Ok(client.dynamic()),
+        Err(pipe_error) => AgentClient::connect_pageant()
+            .await
+            .map(AgentClient::dynamic)
+            .map_err(|pageant_error| {
+                format!(
+                    "SSH agent unavailable ({}: {}; Pageant: {})",
+                    pipe, pipe_error, pageant_error
+                )
+            }),
+    }
+}
+
+#[cfg(windows)]
+fn agent_pipe_path() -> String {
+    std::env::var("SSH_AUTH_SOCK")
+        .ok()
+        .filter(|value| value.starts_with(r"\\.\pipe\"))
+        .unwrap_or_else(|| OPENSSH_AGENT_PIPE.to_string())
+}
+
+/// Raw byte stream to the local agent, used when proxying forwarded agent channels.
+pub trait AgentIo: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> AgentIo for T {}
+
+#[cfg(unix)]
+async fn open_agent_stream() -> Result<Box<dyn AgentIo>, String> {
+    let socket =
+        std::env::var("SSH_AUTH_SOCK").map_err(|_| "SSH_AUTH_SOCK is not set".to_string())?;
+    let stream = tokio::net::UnixStream::connect(&socket)
+        .await
+        .map_err(|e| format!("Failed to connect to SSH agent at {}: {}", socket, e))?;
+    Ok(Box::new(stream))
+}
+
+#[cfg(windows)]
+async fn open_agent_stream() -> Result<Box<dyn AgentIo>, String> {
+    let pipe = agent_pipe_path();
+    let stream = tokio::net::windows::named_pipe::ClientOptions::new()
+        .open(&pipe)
+        .map_err(|e| format!("Failed to connect to SSH agent at {}: {}", pipe, e))?;
+    Ok(Box::new(stream))
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentSignRequestEvent {
+    pub session_id: Option<String>,
+    pub host: String,
+    pub fingerprint: Option<String>,
+}
+
+/// Relays a forwarded `auth-agent@openssh.com` channel to the local agent. The agent
+/// protocol is strictly request/response, so messages are relayed one at a time and
+/// every sign request is announced with the fingerprint of the key being used.
+pub async fn proxy_forwarded_agent<S>(
+    mut remote: S,
+    session_id: Option<String>,
+    host: String,
+) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut local = open_agent_stream().await?;
+
+    loop {
+        let Some(request) = read_agent_message(&mut remote).await? else {
+            debug!("[SSH] Forwarded agent channel from {} closed", host);
+            return Ok(());
+        };
+
+        if request.first() == Some(&SSH_AGENTC_SIGN_REQUEST) {
+            let fingerprint = sign_request_fingerprint(&request);
+            info!(
+                "[SSH] Forwarded agent signing request from {} ({})",
+                host,
+                fingerprint.as_deref().unwrap_or("unknown key")
+            );
+            SSHClient::emit_app_event(
+                "agent-sign-request",
+                AgentSignRequestEvent {
+                    session_id: session_id.clone(),
+                    host: host.clone(),
+                    fingerprint,
+                },
+            );
+        }
+
+        write_agent_message(&mut local, &request).await?;
+        let response = read_agent_message(&mut local)
+            .await?
+            .ok_or_else(|| "Local SSH agent closed the connection".to_string())?;
+        write_agent_message(&mut remote, &response).await?;
+    }
+}
+
+/// Reads one length-prefixed agent message; `None` on a clean EOF between messages.
+async fn read_agent_message<R>(reader: &mut R) -> Result<Option<Vec<u8>>, String>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(format!("Failed to read agent message: {}", e)),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len == 0 || len > MAX_AGENT_MESSAGE_LEN {
+        return Err(format!("Invalid agent message length {}", len));
+    }
+    let mut body = vec![0u8; len];
+    reader
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| format!("Failed to read agent message: {}", e))?;
+    Ok(Some(body))
+}
+
+async fn write_agent_message<W>(writer: &mut W, body: &[u8]) -> Result<(), String>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut frame = Vec::with_capacity(body.len() + 4);
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(body);
+    writer
+        .write_all(&frame)
+        .await
+        .map_err(|e| format!("Failed to write agent message: {}", e))?;
+    writer
+        .flush()
+        .await
+        .map_err(|e| format!("Failed to write agent message: {}", e))
+}
+
+/// SHA256 fingerprint of the key blob in a sign request (`byte type, string key_blob, ...`).
+fn sign_request_fingerprint(message: &[u8]) -> Option<String> {
+    let len_bytes: [u8; 4] = message.get(1..5)?.try_into().ok()?;
+    let len = u32::from_be_bytes(len_bytes) as usize;
+    let blob = message.get(5..5usize.checked_add(len)?)?;
+    keys::PublicKey::from_bytes(blob)
+        .ok()
+        .map(|key| key.fingerprint(HashAlg::Sha256).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::prelude::*;
+
+    const KEY_A: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIE0CBQkr2bYE6028la0GZPbFTZGAriPQK5z+/Bncxcxf";
+
+    fn sign_request(blob: &[u8]) -> Vec<u8> {
+        let mut message = vec![SSH_AGENTC_SIGN_REQUEST];
+        message.extend_from_slice(&(blob.len() as u32).to_be_bytes());
+        message.extend_from_slice(blob);
+        message.extend_from_slice(&4u32.to_be_bytes());
+        message.extend_from_slice(b"data");
+        message.extend_from_slice(&0u32.to_be_bytes());
+        message
+    }
+
+    #[test]
+    fn extracts_fingerprint_from_sign_request() {
+        let blob = BASE64_STANDARD.decode(KEY_A).unwrap();
+        let expected = keys::parse_public_key_base64(KEY_A)
+            .unwrap()
+            .fingerprint(HashAlg::Sha256)
+            .to_string();
+
+        assert_eq!(
+            sign_request_fingerprint(&sign_request(&blob)),
+            Some(expected)
+        );
+        assert_eq!(
+            sign_request_fingerprint(&[SSH_AGENTC_SIGN_REQUEST, 0, 0]),
+            None
+        );
+        assert_eq!(
+            sign_request_fingerprint(&[SSH_AGENTC_SIGN_REQUEST, 0, 0, 1, 0, 1]),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn agent_messages_round_trip_and_report_clean_eof() {
+        let (mut writer, mut reader) = tokio::io::duplex(64);
+        write_agent_message(&mut writer, &[11]).await.unwrap();
+        drop(writer);
+
+        assert_eq!(read_agent_message(&mut reader).await, Ok(Some(vec![11])));
+        assert_eq!(read_agent_message(&mut reader).await, Ok(None));
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_agent_messages() {
+        let (mut writer, mut reader) = tokio::io::duplex(64);
+        writer
+            .write_all(&((MAX_AGENT_MESSAGE_LEN as u32) + 1).to_be_bytes())
+            .await
+            .unwrap();
+
+        assert!(read_agent_message(&mut reader).await.is_err());
+    }
+}
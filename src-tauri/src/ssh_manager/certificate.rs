@@ -0,0 +1,131 @@
+use chrono::{DateTime, Utc};
+use russh::keys::{Certificate, HashAlg, PrivateKey};
+use serde::Serialize;
+
+/// What the UI shows for an OpenSSH certificate (`ssh-keygen -L`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateInfo {
+    pub key_id: String,
+    pub serial: u64,
+    pub cert_type: String,
+    pub algorithm: String,
+    pub principals: Vec<String>,
+    /// RFC 3339; `None` when the certificate has no lower bound.
+    pub valid_after: Option<String>,
+    /// RFC 3339; `None` when the certificate never expires.
+    pub valid_before: Option<String>,
+    pub expired: bool,
+    pub not_yet_valid: bool,
+    pub ca_fingerprint: String,
+}
+
+/// Parses a certificate in `id_ed25519-cert.pub` format.
+pub fn parse_certificate(content: &str) -> Result<Certificate, String> {
+    Certificate::from_openssh(content.trim())
+        .map_err(|e| format!("Failed to parse SSH certificate: {}", e))
+}
+
+pub fn certificate_info(cert: &Certificate, now: u64) -> CertificateInfo {
+    let valid_after = cert.valid_after();
+    let valid_before = cert.valid_before();
+    CertificateInfo {
+        key_id: cert.key_id().to_string(),
+        serial: cert.serial(),
+        cert_type: if cert.cert_type().is_host() {
+            "host".to_string()
+        } else {
+            "user".to_string()
+        },
+        algorithm: cert.algorithm().as_str().to_string(),
+        principals: cert.valid_principals().to_vec(),
+        valid_after: (valid_after > 0)
+            .then(|| format_timestamp(valid_after))
+            .flatten(),
+        valid_before: (valid_before != u64::MAX)
+            .then(|| format_timestamp(valid_before))
+            .flatten(),
+        expired: now >= valid_before,
+        not_yet_valid: now < valid_after,
+        ca_fingerprint: cert
+            .signature_key()
+            .fingerprint(HashAlg::Sha256)
+            .to_string(),
+    }
+}
+
+/// Checks that `cert` can be presented with `key` right now; the error says why not.
+pub fn check_usable(cert: &Certificate, key: &PrivateKey, now: u64) -> Result<(), String> {
+    if cert.public_key() != key.public_key().key_data() {
+        return Err("SSH certificate does not match the private key".to_string());
+    }
+    let info = certificate_info(cert, now);
+    if info.expired {
+        return Err(format!(
+            "SSH certificate {} expired at {}",
+            info.key_id,
+            info.valid_before.unwrap_or_default()
+        ));
+    }
+    if info.not_yet_valid {
+        return Err(format!(
+            "SSH certificate {} is not valid until {}",
+            info.key_id,
+            info.valid_after.unwrap_or_default()
+        ));
+    }
+    Ok(())
+}
+
+pub fn unix_now() -> u64 {
+    Utc::now().timestamp().max(0) as u64
+}
+
+fn format_timestamp(seconds: u64) -> Option<String> {
+    let seconds = i64::try_from(seconds).ok()?;
+    DateTime::<Utc>::from_timestamp(seconds, 0).map(|time| time.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `ssh-keygen -s ca -I deploy@resh -n deploy,root -V 20200101000000:20300101000000 -z 42`
+    const USER_CERT: &str = "ssh-ed25519-cert-v01@openssh.com AAAAIHNzaC1lZDI1NTE5LWNlcnQtdjAxQG9wZW5zc2guY29tAAAAINpPHD3zE4wFKpg1Kt6+4+6H1ukeFmI392GuB6ZQ7vg8AAAAILcgV3q4jH3d9jyndteFWokDD27XZ6DuFo/al3IqAUxeAAAAAAAAACoAAAABAAAAC2RlcGxveUByZXNoAAAAEgAAAAZkZXBsb3kAAAAEcm9vdAAAAABeC+EAAAAAAHDb2IAAAAAAAAAAggAAABVwZXJtaXQtWDExLWZvcndhcmRpbmcAAAAAAAAAF3Blcm1pdC1hZ2VudC1mb3J3YXJkaW5nAAAAAAAAABZwZXJtaXQtcG9ydC1mb3J3YXJkaW5nAAAAAAAAAApwZXJtaXQtcHR5AAAAAAAAAA5wZXJtaXQtdXNlci1yYwAAAAAAAAAAAAAAMwAAAAtzc2gtZWQyNTUxOQAAACAmxTFe9QFA3dRJI5vzAquEFe3cHZleu9PDAKMaPAkhpgAAAFMAAAALc3NoLWVkMjU1MTkAAABAGk056nX9EW0ByE5izIv8JU23+6a/R0T+0y73mYEkHKAgABHy4pjWw3l0bKD0Hj3nQlGJ0TwgT0PnPkXmPs3kBg== user";
+    const VALID_AFTER: u64 = 1_577_836_800;
+    const VALID_BEFORE: u64 = 1_893_456_000;
+
+    #[test]
+    fn reports_certificate_metadata() {
+        let cert = parse_certificate(USER_CERT).expect("certificate should parse");
+        let info = certificate_info(&cert, VALID_AFTER + 1);
+
+        assert_eq!(info.key_id, "deploy@resh");
+        assert_eq!(info.serial, 42);
+        assert_eq!(info.cert_type, "user");
+        assert_eq!(info.principals, vec!["deploy", "root"]);
+        assert_eq!(
+            info.valid_after.as_deref(),
+            Some("2020-01-01T00:00:00+00:00")
+        );
+        assert_eq!(
+            info.valid_before.as_deref(),
+            Some("2030-01-01T00:00:00+00:00")
+        );
+        assert_eq!(
+            info.ca_fingerprint,
+            "SHA256:6ca20mna3wj5lDy/SiKFVIQ/nXmYomjIl/IVAXNzasQ"
+        );
+        assert!(!info.expired);
+        assert!(!info.not_yet_valid);
+    }
+
+    #[test]
+    fn flags_validity_window_edges() {
+        let cert = parse_certificate(USER_CERT).expect("certificate should parse");
+
+        assert!(certificate_info(&cert, VALID_AFTER - 1).not_yet_valid);
+        assert!(certificate_info(&cert, VALID_BEFORE).expired);
+        assert!(parse_certificate("ssh-ed25519 AAAA not-a-cert").is_err());
+    }
+}
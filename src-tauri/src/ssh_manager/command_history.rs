@@ -0,0 +1,408 @@
+//! Commands run on each server, kept in the `command_history` table.
+//!
+//! Rows come from shell integration, where every command that finishes with an OSC 133 `D` mark
+//! is stored, and from commands sent through the command recorder, whose completion marker
+//! carries the exit status on shells without integration. Commands typed with a leading space
+//! are skipped, as shells do with `HISTCONTROL=ignorespace`.
+//!
+//! Each row keeps the saved server it ran on and the host, so sessions opened without a saved
+//! server are still searchable. `command_history_fts` is an external-content FTS5 index over the
+//! command line and working directory, kept in step by triggers.
+
+use crate::db::DatabaseManager;
+use crate::ssh_manager::shell_integration::CommandRecord;
+use rusqlite::{params, params_from_iter, Connection};
+use serde::Serialize;
+
+/// Oldest rows beyond this are deleted as new ones arrive.
+const MAX_ROWS: i64 = 50_000;
+pub const DEFAULT_LIMIT: u32 = 50;
+pub const MAX_LIMIT: u32 = 500;
+
+pub fn create_tables(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS command_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            server_id TEXT,
+            host TEXT NOT NULL,
+            session_id TEXT NOT NULL,
+            command TEXT NOT NULL,
+            cwd TEXT,
+            exit_code INTEGER,
+            started_at TEXT NOT NULL,
+            finished_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_command_history_server
+            ON command_history(server_id, started_at);
+        CREATE INDEX IF NOT EXISTS idx_command_history_host
+            ON command_history(host, started_at);
+        CREATE VIRTUAL TABLE IF NOT EXISTS command_history_fts USING fts5(
+            command, cwd, content='command_history', content_rowid='id'
+        );
+        CREATE TRIGGER IF NOT EXISTS command_history_fts_insert
+        AFTER INSERT ON command_history BEGIN
+            INSERT INTO command_history_fts(rowid, command, cwd)
+            VALUES (new.id, new.command, new.cwd);
+        END;
+        CREATE TRIGGER IF NOT EXISTS command_history_fts_delete
+        AFTER DELETE ON command_history BEGIN
+            INSERT INTO command_history_fts(command_history_fts, rowid, command, cwd)
+            VALUES ('delete', old.id, old.command, old.cwd);
+        END;",
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandHistoryEntry {
+    pub id: i64,
+    pub server_id: Option<String>,
+    pub host: String,
+    pub session_id: String,
+    pub command: String,
+    pub cwd: Option<String>,
+    pub exit_code: Option<i32>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// Which server a query covers: the saved server when there is one, otherwise the host.
+/// Neither means every server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryScope {
+    pub server_id: Option<String>,
+    pub host: Option<String>,
+}
+
+impl HistoryScope {
+    fn filter(&self) -> (&'static str, Option<&str>) {
+        match (self.server_id.as_deref(), self.host.as_deref()) {
+            (Some(server_id), _) => ("h.server_id = ?", Some(server_id)),
+            (None, Some(host)) => ("h.host = ?", Some(host)),
+            (None, None) => ("1 = 1", None),
+        }
+    }
+}
+
+/// Where a connection's finished commands are stored.
+#[derive(Clone)]
+pub struct HistorySink {
+    db: DatabaseManager,
+    server_id: Option<String>,
+    host: String,
+}
+
+impl HistorySink {
+    pub fn new(db: DatabaseManager, server_id: Option<String>, host: String) -> Self {
+        Self {
+            db,
+            server_id,
+            host,
+        }
+    }
+
+    /// Store a finished command without holding up the output it arrived with.
+    pub fn record(&self, session_id: &str, record: CommandRecord) {
+        let Self {
+            db,
+            server_id,
+            host,
+        } = self.clone();
+        let session_id = session_id.to_string();
+        tokio::spawn(async move {
+            let result = db
+                .run_blocking(move |conn| {
+                    insert(conn, server_id.as_deref(), &host, &session_id, &record)
+                })
+                .await;
+            if let Err(e) = result {
+                tracing::warn!("Failed to store command history: {}", e);
+            }
+        });
+    }
+}
+
+pub fn insert(
+    conn: &Connection,
+    server_id: Option<&str>,
+    host: &str,
+    session_id: &str,
+    record: &CommandRecord,
+) -> Result<(), String> {
+    if record.command.trim().is_empty() || record.leading_space {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO command_history
+            (server_id, host, session_id, command, cwd, exit_code, started_at, finished_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            server_id,
+            host,
+            session_id,
+            record.command,
+            record.cwd,
+            record.exit_code,
+            record.started_at,
+            record.finished_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM command_history WHERE id <= last_insert_rowid() - ?1",
+        params![MAX_ROWS],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Deletes the stored commands in `scope`, every server's when it is empty. Returns how many
+/// were deleted.
+pub fn clear(conn: &Connection, scope: &HistoryScope) -> Result<usize, String> {
+    let (filter, value) = scope.filter();
+    let sql = format!(
+        "DELETE FROM command_history
+         WHERE id IN (SELECT h.id FROM command_history h WHERE {})",
+        filter
+    );
+    conn.execute(&sql, params_from_iter(value))
+        .map_err(|e| e.to_string())
+}
+
+/// Most recent commands in `scope`, newest first.
+pub fn recent(
+    conn: &Connection,
+    scope: &HistoryScope,
+    limit: u32,
+) -> Result<Vec<CommandHistoryEntry>, String> {
+    let (filter, value) = scope.filter();
+    let sql = format!(
+        "SELECT h.id, h.server_id, h.host, h.session_id, h.command, h.cwd, h.exit_code,
+                h.started_at, h.finished_at
+         FROM command_history h
+         WHERE {}
+         ORDER BY h.id DESC
+         LIMIT {}",
+        filter,
+        limit.min(MAX_LIMIT)
+    );
+    query_entries(conn, &sql, value.into_iter().collect())
+}
+
+/// Full-text search in `scope`, best match first. Every word must match, as a prefix.
+pub fn search(
+    conn: &Connection,
+    query: &str,
+    scope: &HistoryScope,
+    limit: u32,
+) -> Result<Vec<CommandHistoryEntry>, String> {
+    let Some(match_query) = fts_query(query) else {
+        return Ok(vec![]);
+    };
+    let (filter, value) = scope.filter();
+    let sql = format!(
+        "SELECT h.id, h.server_id, h.host, h.session_id, h.command, h.cwd, h.exit_code,
+                h.started_at, h.finished_at
+         FROM command_history_fts
+         JOIN command_history h ON h.id = command_history_fts.rowid
+         WHERE command_history_fts MATCH ? AND {}
+         ORDER BY bm25(command_history_fts), h.id DESC
+         LIMIT {}",
+        filter,
+        limit.min(MAX_LIMIT)
+    );
+    let mut values = vec![match_query.as_str()];
+    values.extend(value);
+    query_entries(conn, &sql, values)
+}
+
+fn query_entries(
+    conn: &Connection,
+    sql: &str,
+    values: Vec<&str>,
+) -> Result<Vec<CommandHistoryEntry>, String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params_from_iter(values), |row| {
+            Ok(CommandHistoryEntry {
+                id: row.get(0)?,
+                server_id: row.get(1)?,
+                host: row.get(2)?,
+                session_id: row.get(3)?,
+                command: row.get(4)?,
+                cwd: row.get(5)?,
+                exit_code: row.get(6)?,
+                started_at: row.get(7)?,
+                finished_at: row.get(8)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())
+}
+
+/// Quote each word so FTS5 syntax in user input is matched literally, and allow prefixes.
+fn fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Compact lines for the AI tool result.
+pub fn format_for_ai(entries: &[CommandHistoryEntry]) -> String {
+    if entries.is_empty() {
+        return "No commands found in the history for this server.".to_string();
+    }
+    entries
+        .iter()
+        .map(|entry| {
+            let exit = entry
+                .exit_code
+                .map_or_else(|| "exit ?".to_string(), |code| format!("exit {}", code));
+            format!(
+                "[{}] ({}) {} $ {}",
+                entry.started_at,
+                exit,
+                entry.cwd.as_deref().unwrap_or("?"),
+                entry.command
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(command: &str, cwd: &str, exit_code: i32) -> CommandRecord {
+        CommandRecord {
+            id: 1,
+            command: command.to_string(),
+            cwd: Some(cwd.to_string()),
+            prompt_start: 0,
+            output_start: 0,
+            output_end: Some(0),
+            exit_code: Some(exit_code),
+            started_at: "2026-10-16T10:00:00+00:00".to_string(),
+            finished_at: Some("2026-10-16T10:00:01+00:00".to_string()),
+            leading_space: false,
+        }
+    }
+
+    #[test]
+    fn searches_and_lists_per_server() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let web = Some("web");
+        insert(
+            &conn,
+            web,
+            "10.0.0.1",
+            "s1",
+            &record("systemctl restart nginx", "/etc/nginx", 0),
+        )
+        .unwrap();
+        insert(
+            &conn,
+            web,
+            "10.0.0.1",
+            "s1",
+            &record("tail -f /var/log/syslog", "/", 0),
+        )
+        .unwrap();
+        insert(
+            &conn,
+            None,
+            "10.0.0.9",
+            "s2",
+            &record("nginx -t", "/root", 1),
+        )
+        .unwrap();
+        insert(&conn, web, "10.0.0.1", "s1", &record("   ", "/", 0)).unwrap();
+
+        let web_scope = HistoryScope {
+            server_id: Some("web".into()),
+            host: None,
+        };
+        let recent_web = recent(&conn, &web_scope, 10).unwrap();
+        assert_eq!(recent_web.len(), 2);
+        assert_eq!(recent_web[0].command, "tail -f /var/log/syslog");
+
+        let hits = search(&conn, "ngin", &web_scope, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].cwd.as_deref(), Some("/etc/nginx"));
+
+        let everywhere = search(&conn, "nginx", &HistoryScope::default(), 10).unwrap();
+        assert_eq!(everywhere.len(), 2);
+        let by_host = HistoryScope {
+            server_id: None,
+            host: Some("10.0.0.9".into()),
+        };
+        assert_eq!(recent(&conn, &by_host, 10).unwrap()[0].exit_code, Some(1));
+
+        // FTS syntax in the query is taken literally: `OR` is a word that must match too.
+        assert!(search(&conn, "nginx OR tail", &web_scope, 10)
+            .unwrap()
+            .is_empty());
+        assert!(search(&conn, "\"nginx", &web_scope, 10).is_ok());
+        assert!(search(&conn, "   ", &web_scope, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn deleted_rows_leave_the_index() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        insert(&conn, None, "h", "s", &record("uptime", "/", 0)).unwrap();
+        conn.execute("DELETE FROM command_history", []).unwrap();
+        assert!(search(&conn, "uptime", &HistoryScope::default(), 10)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn skips_space_prefixed_commands_and_clears_per_server() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let secret = CommandRecord {
+            leading_space: true,
+            ..record("export TOKEN=hunter2", "/", 0)
+        };
+        insert(&conn, Some("web"), "10.0.0.1", "s1", &secret).unwrap();
+        insert(
+            &conn,
+            Some("web"),
+            "10.0.0.1",
+            "s1",
+            &record("uptime", "/", 0),
+        )
+        .unwrap();
+        insert(
+            &conn,
+            Some("db"),
+            "10.0.0.2",
+            "s2",
+            &record("uptime", "/", 0),
+        )
+        .unwrap();
+
+        let web = HistoryScope {
+            server_id: Some("web".into()),
+            host: None,
+        };
+        assert!(search(&conn, "TOKEN", &HistoryScope::default(), 10)
+            .unwrap()
+            .is_empty());
+        assert_eq!(clear(&conn, &web).unwrap(), 1);
+        assert!(recent(&conn, &web, 10).unwrap().is_empty());
+        assert_eq!(
+            search(&conn, "uptime", &HistoryScope::default(), 10)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(clear(&conn, &HistoryScope::default()).unwrap(), 1);
+    }
+}
@@ -0,0 +1,764 @@
+use crate::config::types::PortForward;
+use crate::ssh_manager::ssh::SSHClient;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use russh::client::Msg;
+use russh::Channel;
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+const DEFAULT_LOCAL_BIND_HOST: &str = "127.0.0.1";
+const DEFAULT_REMOTE_BIND_HOST: &str = "localhost";
+const FORWARD_TARGET_HOST: &str = "localhost";
+/// Pause after a failed `accept()` so descriptor exhaustion (EMFILE) doesn't spin the listener.
+pub(crate) const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ForwardState {
+    /// A remote forward that was requested from the server but not yet answered.
+    Pending,
+    Active,
+    Error,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortForwardStatus {
+    pub key: String,
+    pub direction: String,
+    pub bind: String,
+    pub target: String,
+    pub state: ForwardState,
+    pub error: Option<String>,
+    pub active_connections: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortForwardError {
+    pub key: String,
+    pub message: String,
+}
+
+struct ForwardEntry {
+    spec: PortForward,
+    state: ForwardState,
+    error: Option<String>,
+    /// Port the server actually bound for a remote forward (differs when `remote` is 0).
+    bound_remote_port: Option<u32>,
+    active_connections: Arc<AtomicUsize>,
+    cancel: CancellationToken,
+    task: Option<JoinHandle<()>>,
+}
+
+impl ForwardEntry {
+    fn new(spec: PortForward) -> Self {
+        Self {
+            spec,
+            state: ForwardState::Active,
+            error: None,
+            bound_remote_port: None,
+            active_connections: Arc::new(AtomicUsize::new(0)),
+            cancel: CancellationToken::new(),
+            task: None,
+        }
+    }
+
+    /// Registers a remote forward before `tcpip-forward` is sent, so channels the server opens
+    /// right after accepting the request already find their entry.
+    fn pending(spec: PortForward) -> Self {
+        Self {
+            state: ForwardState::Pending,
+            ..Self::new(spec)
+        }
+    }
+
+    fn key(&self) -> String {
+        forward_key(&self.spec)
+    }
+
+    fn bind_host(&self) -> &str {
+        self.spec.bind_host().unwrap_or(if self.spec.is_remote() {
+            DEFAULT_REMOTE_BIND_HOST
+        } else {
+            DEFAULT_LOCAL_BIND_HOST
+        })
+    }
+
+    fn status(&self) -> PortForwardStatus {
+        let (direction, bind, target) = if self.spec.is_remote() {
+            let port = self.bound_remote_port.unwrap_or(self.spec.remote as u32);
+            (
+                "remote",
+                format!("{}:{}", self.bind_host(), port),
+                format!("{}:{}", FORWARD_TARGET_HOST, self.spec.local),
+            )
+        } else {
+            (
+                "local",
+                format!("{}:{}", self.bind_host(), self.spec.local),
+                format!("{}:{}", FORWARD_TARGET_HOST, self.spec.remote),
+            )
+        };
+        PortForwardStatus {
+            key: self.key(),
+            direction: direction.to_string(),
+            bind,
+            target,
+            state: self.state,
+            error: self.error.clone(),
+            active_connections: self.active_connections.load(Ordering::Relaxed),
+        }
+    }
+
+    fn shutdown(&mut self) {
+        self.cancel.cancel();
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+lazy_static! {
+    /// Forwards are owned by the terminal session rather than a single SSH handle:
+    /// each accepted connection looks up the session's current handle, so local
+    /// listeners keep their ports across reconnects.
+    static ref SESSION_FORWARDS: DashMap<String, Vec<ForwardEntry>> = DashMap::new();
+}
+
+fn forward_key(spec: &PortForward) -> String {
+    if spec.is_remote() {
+        format!(
+            "R:{}:{}:{}",
+            spec.bind_host().unwrap_or(DEFAULT_REMOTE_BIND_HOST),
+            spec.remote,
+            spec.local
+        )
+    } else {
+        format!(
+            "L:{}:{}:{}",
+            spec.bind_host().unwrap_or(DEFAULT_LOCAL_BIND_HOST),
+            spec.local,
+            spec.remote
+        )
+    }
+}
+
+/// Picks the active remote forward a `forwarded-tcpip` channel belongs to. Servers may
+/// report the bind address differently from how it was requested (`localhost` vs
+/// `127.0.0.1`), so the port decides and the address only breaks ties. A pending forward
+/// matches its requested port; one that asked for port 0 is only used when nothing else fits.
+fn match_remote_forward<'a>(
+    entries: &'a [ForwardEntry],
+    connected_address: &str,
+    connected_port: u32,
+) -> Option<&'a ForwardEntry> {
+    let remote = || entries.iter().filter(|entry| entry.spec.is_remote());
+    let mut candidates: Vec<&ForwardEntry> = remote()
+        .filter(|entry| match entry.state {
+            ForwardState::Active => entry.bound_remote_port == Some(connected_port),
+            ForwardState::Pending => entry.spec.remote as u32 == connected_port,
+            _ => false,
+        })
+        .collect();
+    if candidates.is_empty() {
+        candidates = remote()
+            .filter(|entry| entry.state == ForwardState::Pending && entry.spec.remote == 0)
+            .collect();
+    }
+    candidates
+        .iter()
+        .find(|entry| entry.bind_host() == connected_address)
+        .or_else(|| candidates.first())
+        .copied()
+}
+
+fn emit_status(session_id: &str, status: PortForwardStatus) {
+    SSHClient::emit_app_event(&format!("port-forward-status:{}", session_id), status);
+}
+
+fn emit_error(session_id: &str, key: String, message: String) {
+    SSHClient::emit_app_event(
+        &format!("port-forward-error:{}", session_id),
+        PortForwardError { key, message },
+    );
+}
+
+pub struct PortForwardManager;
+
+impl PortForwardManager {
+    /// Starts every configured forward for a freshly connected session.
+    pub async fn start_session(session_id: &str, forwards: &[PortForward]) {
+        if forwards.is_empty() {
+            return;
+        }
+
+        let mut entries = Vec::with_capacity(forwards.len());
+        for spec in forwards {
+            entries.push(if spec.is_remote() {
+                ForwardEntry::pending(spec.clone())
+            } else {
+                Self::start_local(session_id, spec.clone()).await
+            });
+        }
+
+        if let Some((_, previous)) = SESSION_FORWARDS.remove(session_id) {
+            Self::stop_entries(session_id, previous);
+        }
+        SESSION_FORWARDS.insert(session_id.to_string(), entries);
+
+        for (index, spec) in forwards.iter().enumerate() {
+            if spec.is_remote() {
+                Self::request_remote(session_id, index, spec.clone()).await;
+            }
+        }
+
+        for status in Self::session_status(session_id) {
+            emit_status(session_id, status);
+        }
+    }
+
+    /// Called after a successful reconnect. Local listeners survive the reconnect, so
+    /// only those that failed to bind are retried; remote forwards lived on the old
+    /// server connection and are always requested again.
+    pub async fn refresh_session(session_id: &str) {
+        let restart: Vec<(usize, PortForward)> = match SESSION_FORWARDS.get(session_id) {
+            Some(entries) => entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| entry.spec.is_remote() || entry.state == ForwardState::Error)
+                .map(|(index, entry)| (index, entry.spec.clone()))
+                .collect(),
+            None => return,
+        };
+
+        for (index, spec) in restart {
+            let remote = spec.is_remote();
+            let entry = if remote {
+                ForwardEntry::pending(spec.clone())
+            } else {
+                Self::start_local(session_id, spec.clone()).await
+            };
+            let orphan = match SESSION_FORWARDS.get_mut(session_id) {
+                Some(mut entries) => match entries.get_mut(index) {
+                    Some(slot) => {
+                        let mut previous = std::mem::replace(slot, entry);
+                        previous.shutdown();
+                        None
+                    }
+                    None => Some(entry),
+                },
+                None => Some(entry),
+            };
+            // The session went away while we were binding; release the port again.
+            if let Some(mut orphan) = orphan {
+                orphan.shutdown();
+                continue;
+            }
+            if remote {
+                Self::request_remote(session_id, index, spec).await;
+            }
+        }
+
+        for status in Self::session_status(session_id) {
+            emit_status(session_id, status);
+        }
+    }
+
+    /// Closes local listeners, cancels remote forwards on the server and drops every
+    /// tunnelled connection for a session. Must run before the session is removed so
+    /// the handle is still available for `cancel_tcpip_forward`.
+    pub async fn stop_session(session_id: &str) {
+        let Some((_, entries)) = SESSION_FORWARDS.remove(session_id) else {
+            return;
+        };
+
+        let remote_binds: Vec<(String, u32)> = entries
+            .iter()
+            .filter(|entry| entry.spec.is_remote() && entry.state == ForwardState::Active)
+            .filter_map(|entry| {
+                entry
+                    .bound_remote_port
+                    .map(|port| (entry.bind_host().to_string(), port))
+            })
+            .collect();
+        Self::stop_entries(session_id, entries);
+
+        if remote_binds.is_empty() {
+            return;
+        }
+        if let Some(handle) = SSHClient::get_session_handle(session_id).await {
+            for (address, port) in remote_binds {
+                if let Err(e) = handle.cancel_tcpip_forward(address.clone(), port).await {
+                    debug!(
+                        "[SSH] Failed to cancel remote forward {}:{} for {}: {}",
+                        address, port, session_id, e
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn session_status(session_id: &str) -> Vec<PortForwardStatus> {
+        SESSION_FORWARDS
+            .get(session_id)
+            .map(|entries| entries.iter().map(ForwardEntry::status).collect())
+            .unwrap_or_default()
+    }
+
+    /// Entry point for `ClientHandler::server_channel_open_forwarded_tcpip`: dials the
+    /// local target of the matching remote forward and pipes the channel into it.
+    pub fn accept_forwarded_channel(
+        session_id: &str,
+        channel: Channel<Msg>,
+        connected_address: &str,
+        connected_port: u32,
+        originator: String,
+    ) {
+        let matched = SESSION_FORWARDS.get(session_id).and_then(|entries| {
+            match_remote_forward(&entries, connected_address, connected_port).map(|entry| {
+                (
+                    entry.key(),
+                    entry.spec.local,
+                    entry.active_connections.clone(),
+                    entry.cancel.clone(),
+                )
+            })
+        });
+
+        let Some((key, local_port, active_connections, cancel)) = matched else {
+            warn!(
+                "[SSH] No remote forward registered for {}:{} on {}, dropping channel",
+                connected_address, connected_port, session_id
+            );
+            tokio::spawn(async move {
+                let _ = channel.close().await;
+            });
+            return;
+        };
+
+        let session_id = session_id.to_string();
+        tokio::spawn(async move {
+            active_connections.fetch_add(1, Ordering::Relaxed);
+            let result = tokio::select! {
+                result = forward_remote_connection(channel, local_port, &originator) => result,
+                _ = cancel.cancelled() => Ok(()),
+            };
+            active_connections.fetch_sub(1, Ordering::Relaxed);
+            if let Err(e) = result {
+                warn!(
+                    "[SSH] Remote forward {} for {} failed: {}",
+                    key, session_id, e
+                );
+                emit_error(&session_id, key, e);
+            }
+        });
+    }
+
+    fn stop_entries(session_id: &str, entries: Vec<ForwardEntry>) {
+        for mut entry in entries {
+            entry.shutdown();
+            entry.state = ForwardState::Stopped;
+            entry.error = None;
+            emit_status(session_id, entry.status());
+        }
+    }
+
+    async fn start_local(session_id: &str, spec: PortForward) -> ForwardEntry {
+        let mut entry = ForwardEntry::new(spec);
+        let bind_host = entry.bind_host().to_string();
+        let local_port = entry.spec.local;
+        let remote_port = entry.spec.remote;
+
+        match TcpListener::bind((bind_host.as_str(), local_port)).await {
+            Ok(listener) => {
+                info!(
+                    "[SSH] Local forward {}:{} -> {}:{} active for {}",
+                    bind_host, local_port, FORWARD_TARGET_HOST, remote_port, session_id
+                );
+                entry.task = Some(tokio::spawn(run_local_listener(
+                    session_id.to_string(),
+                    entry.key(),
+                    listener,
+                    remote_port,
+                    entry.active_connections.clone(),
+                )));
+            }
+            Err(e) => {
+                warn!(
+                    "[SSH] Failed to bind local forward {}:{} for {}: {}",
+                    bind_host, local_port, session_id, e
+                );
+                entry.state = ForwardState::Error;
+                entry.error = Some(format!(
+                    "Failed to bind {}:{}: {}",
+                    bind_host, local_port, e
+                ));
+            }
+        }
+
+        entry
+    }
+
+    /// Asks the server for the remote forward registered as pending at `index` and records
+    /// the reply on that entry. A forward granted after the session or entry went away is
+    /// cancelled again.
+    async fn request_remote(session_id: &str, index: usize, spec: PortForward) {
+        let key = forward_key(&spec);
+        let bind_host = spec
+            .bind_host()
+            .unwrap_or(DEFAULT_REMOTE_BIND_HOST)
+            .to_string();
+        let requested_port = spec.remote as u32;
+
+        let Some(handle) = SSHClient::get_session_handle(session_id).await else {
+            Self::finish_remote(
+                session_id,
+                index,
+                &key,
+                Err("Session not found".to_string()),
+            );
+            return;
+        };
+
+        let result = match handle
+            .tcpip_forward(bind_host.clone(), requested_port)
+            .await
+        {
+            Ok(bound_port) => {
+                // Servers only report the port when we asked for port 0.
+                let bound_port = if requested_port == 0 {
+                    bound_port
+                } else {
+                    requested_port
+                };
+                info!(
+                    "[SSH] Remote forward {}:{} -> {}:{} active for {}",
+                    bind_host, bound_port, FORWARD_TARGET_HOST, spec.local, session_id
+                );
+                Ok(bound_port)
+            }
+            Err(e) => {
+                warn!(
+                    "[SSH] Remote forward request {}:{} rejected for {}: {}",
+                    bind_host, requested_port, session_id, e
+                );
+                Err(format!(
+                    "Server rejected remote forward on {}:{}: {}",
+                    bind_host, requested_port, e
+                ))
+            }
+        };
+
+        let granted = result.as_ref().ok().copied();
+        if !Self::finish_remote(session_id, index, &key, result) {
+            if let Some(port) = granted {
+                let _ = handle.cancel_tcpip_forward(bind_host, port).await;
+            }
+        }
+    }
+
+    /// Moves the pending entry at `index` to its final state. Returns false when the entry
+    /// was stopped or replaced in the meantime.
+    fn finish_remote(
+        session_id: &str,
+        index: usize,
+        key: &str,
+        result: Result<u32, String>,
+    ) -> bool {
+        let Some(mut entries) = SESSION_FORWARDS.get_mut(session_id) else {
+            return false;
+        };
+        let Some(entry) = entries
+            .get_mut(index)
+            .filter(|entry| entry.state == ForwardState::Pending && entry.key() == key)
+        else {
+            return false;
+        };
+        match result {
+            Ok(bound_port) => {
+                entry.state = ForwardState::Active;
+                entry.bound_remote_port = Some(bound_port);
+            }
+            Err(message) => {
+                entry.state = ForwardState::Error;
+                entry.error = Some(message);
+            }
+        }
+        true
+    }
+}
+
+async fn run_local_listener(
+    session_id: String,
+    key: String,
+    listener: TcpListener,
+    target_port: u16,
+    active_connections: Arc<AtomicUsize>,
+) {
+    // Connection tasks live in the JoinSet so aborting the listener tears them down too.
+    let mut connections = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (socket, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        warn!("[SSH] Local forward {} accept failed: {}", key, e);
+                        tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                        continue;
+                    }
+                };
+                let session_id = session_id.clone();
+                let key = key.clone();
+                let active_connections = active_connections.clone();
+                connections.spawn(async move {
+                    active_connections.fetch_add(1, Ordering::Relaxed);
+                    let result = forward_local_connection(&session_id, socket, peer, target_port).await;
+                    active_connections.fetch_sub(1, Ordering::Relaxed);
+                    if let Err(e) = result {
+                        warn!("[SSH] Local forward {} for {} failed: {}", key, session_id, e);
+                        emit_error(&session_id, key, e);
+                    }
+                });
+            }
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+        }
+    }
+}
+
+async fn forward_local_connection(
+    session_id: &str,
+    mut socket: TcpStream,
+    peer: SocketAddr,
+    target_port: u16,
+) -> Result<(), String> {
+    let handle = SSHClient::get_session_handle(session_id)
+        .await
+        .ok_or_else(|| "Session not found".to_string())?;
+    let channel = handle
+        .channel_open_direct_tcpip(
+            FORWARD_TARGET_HOST,
+            target_port as u32,
+            peer.ip().to_string(),
+            peer.port() as u32,
+        )
+        .await
+        .map_err(|e| {
+            format!(
+                "Failed to open tunnel to {}:{}: {}",
+                FORWARD_TARGET_HOST, target_port, e
+            )
+        })?;
+
+    let mut stream = channel.into_stream();
+    if let Err(e) = tokio::io::copy_bidirectional(&mut socket, &mut stream).await {
+        debug!("[SSH] Forwarded connection from {} ended: {}", peer, e);
+    }
+    Ok(())
+}
+
+async fn forward_remote_connection(
+    channel: Channel<Msg>,
+    local_port: u16,
+    originator: &str,
+) -> Result<(), String> {
+    let mut socket = match TcpStream::connect((FORWARD_TARGET_HOST, local_port)).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            let _ = channel.close().await;
+            return Err(format!(
+                "Failed to connect to {}:{}: {}",
+                FORWARD_TARGET_HOST, local_port, e
+            ));
+        }
+    };
+
+    let mut stream = channel.into_stream();
+    if let Err(e) = tokio::io::copy_bidirectional(&mut socket, &mut stream).await {
+        debug!(
+            "[SSH] Reverse-forwarded connection from {} ended: {}",
+            originator, e
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(local: u16, remote: u16, bind_host: Option<&str>) -> PortForward {
+        PortForward {
+            local,
+            remote,
+            direction: "local".into(),
+            bind_host: bind_host.map(str::to_string),
+        }
+    }
+
+    fn remote(local: u16, remote: u16, bind_host: Option<&str>) -> PortForward {
+        PortForward {
+            local,
+            remote,
+            direction: "remote".into(),
+            bind_host: bind_host.map(str::to_string),
+        }
+    }
+
+    fn active_remote(spec: PortForward, bound_port: u32) -> ForwardEntry {
+        let mut entry = ForwardEntry::new(spec);
+        entry.bound_remote_port = Some(bound_port);
+        entry
+    }
+
+    #[test]
+    fn local_forward_key_defaults_to_loopback() {
+        assert_eq!(forward_key(&local(8080, 80, None)), "L:127.0.0.1:8080:80");
+        assert_eq!(
+            forward_key(&local(8080, 80, Some("  "))),
+            "L:127.0.0.1:8080:80"
+        );
+        assert_eq!(
+            forward_key(&local(8080, 80, Some("0.0.0.0"))),
+            "L:0.0.0.0:8080:80"
+        );
+    }
+
+    #[test]
+    fn local_forward_status_describes_listener_and_target() {
+        let mut entry = ForwardEntry::new(local(15432, 5432, None));
+        entry.active_connections.fetch_add(2, Ordering::Relaxed);
+
+        let status = entry.status();
+        assert_eq!(status.key, "L:127.0.0.1:15432:5432");
+        assert_eq!(status.direction, "local");
+        assert_eq!(status.bind, "127.0.0.1:15432");
+        assert_eq!(status.target, "localhost:5432");
+        assert_eq!(status.state, ForwardState::Active);
+        assert_eq!(status.active_connections, 2);
+    }
+
+    #[tokio::test]
+    async fn local_forward_reports_bind_failures() {
+        let taken = TcpListener::bind((DEFAULT_LOCAL_BIND_HOST, 0))
+            .await
+            .unwrap();
+        let port = taken.local_addr().unwrap().port();
+
+        let entry = PortForwardManager::start_local("test-session", local(port, 22, None)).await;
+        assert_eq!(entry.state, ForwardState::Error);
+        assert!(entry.task.is_none());
+        assert!(entry
+            .error
+            .as_deref()
+            .unwrap()
+            .starts_with(&format!("Failed to bind 127.0.0.1:{}", port)));
+    }
+
+    #[test]
+    fn remote_forward_status_reports_the_bound_port() {
+        let mut entry = ForwardEntry::new(remote(3000, 0, None));
+        assert_eq!(entry.key(), "R:localhost:0:3000");
+        assert_eq!(entry.status().bind, "localhost:0");
+
+        entry.bound_remote_port = Some(41234);
+        let status = entry.status();
+        assert_eq!(status.direction, "remote");
+        assert_eq!(status.bind, "localhost:41234");
+        assert_eq!(status.target, "localhost:3000");
+    }
+
+    #[test]
+    fn forwarded_channels_match_by_bound_port_then_address() {
+        let mut failed = active_remote(remote(5000, 9000, None), 9000);
+        failed.state = ForwardState::Error;
+        let entries = vec![
+            failed,
+            active_remote(remote(3000, 9000, Some("0.0.0.0")), 9000),
+            active_remote(remote(4000, 9000, None), 9000),
+            active_remote(remote(6000, 0, None), 40000),
+            ForwardEntry::new(local(9000, 9000, None)),
+        ];
+
+        let by_address = match_remote_forward(&entries, "localhost", 9000).unwrap();
+        assert_eq!(by_address.spec.local, 4000);
+        let by_port = match_remote_forward(&entries, "127.0.0.1", 9000).unwrap();
+        assert_eq!(by_port.spec.local, 3000);
+        let dynamic = match_remote_forward(&entries, "localhost", 40000).unwrap();
+        assert_eq!(dynamic.spec.local, 6000);
+        assert!(match_remote_forward(&entries, "localhost", 8000).is_none());
+    }
+
+    #[test]
+    fn forwarded_channels_match_forwards_still_waiting_for_the_reply() {
+        let entries = vec![
+            ForwardEntry::pending(remote(3000, 9000, None)),
+            ForwardEntry::pending(remote(4000, 0, None)),
+            active_remote(remote(5000, 0, None), 40000),
+        ];
+
+        let requested = match_remote_forward(&entries, "localhost", 9000).unwrap();
+        assert_eq!(requested.spec.local, 3000);
+        let bound = match_remote_forward(&entries, "localhost", 40000).unwrap();
+        assert_eq!(bound.spec.local, 5000);
+        let dynamic = match_remote_forward(&entries, "localhost", 41000).unwrap();
+        assert_eq!(dynamic.spec.local, 4000);
+    }
+
+    #[test]
+    fn pending_remote_forwards_record_the_server_reply() {
+        let session_id = "pending-remote-session";
+        let spec = remote(3000, 0, None);
+        let key = forward_key(&spec);
+        SESSION_FORWARDS.insert(
+            session_id.to_string(),
+            vec![
+                ForwardEntry::pending(spec.clone()),
+                ForwardEntry::pending(spec),
+            ],
+        );
+
+        assert!(PortForwardManager::finish_remote(
+            session_id,
+            0,
+            &key,
+            Ok(41234)
+        ));
+        assert!(PortForwardManager::finish_remote(
+            session_id,
+            1,
+            &key,
+            Err("denied".to_string())
+        ));
+        // A second reply for the same slot finds nothing pending.
+        assert!(!PortForwardManager::finish_remote(
+            session_id,
+            0,
+            &key,
+            Ok(1)
+        ));
+        assert!(!PortForwardManager::finish_remote(
+            "missing-session",
+            0,
+            &key,
+            Ok(1)
+        ));
+
+        let statuses = PortForwardManager::session_status(session_id);
+        assert_eq!(statuses[0].state, ForwardState::Active);
+        assert_eq!(statuses[0].bind, "localhost:41234");
+        assert_eq!(statuses[1].state, ForwardState::Error);
+        assert_eq!(statuses[1].error.as_deref(), Some("denied"));
+        SESSION_FORWARDS.remove(session_id);
+    }
+}
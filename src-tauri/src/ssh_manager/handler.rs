@@ -1,23 +1,69 @@
+use crate::ssh_manager::agent;
+use crate::ssh_manager::forwarding::PortForwardManager;
+use crate::ssh_manager::known_hosts::{self, HostKeyVerdict, KnownHostsStore};
 use crate::ssh_manager::ssh::SSHClient;
 use russh::client;
 use russh::keys;
+use serde::Serialize;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio::sync::Mutex;
-use tracing::debug;
+use tracing::{debug, error, info, warn};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostKeyEvent {
+    pub session_id: Option<String>,
+    pub host: String,
+    pub port: u16,
+    pub algorithm: String,
+    pub fingerprint: String,
+    pub known_fingerprint: Option<String>,
+}
+
+/// Filled by the handler when it refuses a server key. russh only reports a generic
+/// "unknown key" error, so connect paths read this slot to surface the real reason.
+#[derive(Debug, Clone, Default)]
+pub struct HostKeyRejection(Arc<std::sync::Mutex<Option<String>>>);
+
+impl HostKeyRejection {
+    fn set(&self, message: String) {
+        if let Ok(mut slot) = self.0.lock() {
+            *slot = Some(message);
+        }
+    }
+
+    pub fn take(&self) -> Option<String> {
+        self.0.lock().ok().and_then(|mut slot| slot.take())
+    }
+
+    /// The rejection message if the handler refused the key, otherwise `fallback`.
+    pub fn explain(&self, fallback: impl std::fmt::Display) -> String {
+        self.take().unwrap_or_else(|| fallback.to_string())
+    }
+}
 
 pub struct ClientHandler {
     pub session_id: Option<String>,
     pub tx: Option<mpsc::UnboundedSender<(String, Vec<u8>)>>,
     pub shell_channel_id: Arc<Mutex<Option<russh::ChannelId>>>,
+    pub host: String,
+    pub port: u16,
+    pub host_key_rejection: HostKeyRejection,
+    /// Only sessions that requested agent forwarding may open agent channels.
+    pub forward_agent: bool,
 }
 
 impl ClientHandler {
-    pub fn new() -> Self {
+    pub fn new(host: &str, port: u16) -> Self {
         Self {
             session_id: None,
             tx: None,
             shell_channel_id: Arc::new(Mutex::new(None)),
+            host: host.to_string(),
+            port,
+            host_key_rejection: HostKeyRejection::default(),
+            forward_agent: false,
         }
     }
 
@@ -25,11 +71,106 @@ impl ClientHandler {
         session_id: String,
         tx: mpsc::UnboundedSender<(String, Vec<u8>)>,
         shell_channel_id: Arc<Mutex<Option<russh::ChannelId>>>,
+        host: &str,
+        port: u16,
     ) -> Self {
         Self {
             session_id: Some(session_id),
             tx: Some(tx),
             shell_channel_id,
+            host: host.to_string(),
+            port,
+            host_key_rejection: HostKeyRejection::default(),
+            forward_agent: false,
+        }
+    }
+
+    /// Tags host key events from an auxiliary connection (e.g. a jump host) with the
+    /// terminal session it belongs to.
+    pub fn for_session(mut self, session_id: &str) -> Self {
+        self.session_id = Some(session_id.to_string());
+        self
+    }
+
+    /// Checks the key against the known_hosts store. Refuses the key when the store is missing;
+    /// the file IO runs on the blocking pool.
+    fn verify_host_key(
+        &self,
+        server_public_key: &keys::PublicKey,
+    ) -> impl std::future::Future<Output = bool> + Send + 'static {
+        let (host, port) = (self.host.clone(), self.port);
+        let rejection = self.host_key_rejection.clone();
+        let key = server_public_key.clone();
+        let mut event = HostKeyEvent {
+            session_id: self.session_id.clone(),
+            host: host.clone(),
+            port,
+            algorithm: key.algorithm().as_str().to_string(),
+            fingerprint: known_hosts::fingerprint(&key),
+            known_fingerprint: None,
+        };
+
+        async move {
+            let Some(store) = KnownHostsStore::global() else {
+                error!(
+                    "[SSH] known_hosts store not initialized, refusing key for {}:{}",
+                    host, port
+                );
+                rejection.set(format!(
+                    "HOST_KEY_UNVERIFIED: Could not verify the host key for {}:{}: \
+                     the known_hosts store is not available",
+                    host, port
+                ));
+                return false;
+            };
+
+            let verdict = {
+                let host = host.clone();
+                tokio::task::spawn_blocking(move || store.verify(&host, port, &key))
+                    .await
+                    .unwrap_or_else(|e| Err(format!("host key check did not finish: {}", e)))
+            };
+
+            match verdict {
+                Ok(HostKeyVerdict::Trusted) => true,
+                Ok(HostKeyVerdict::Learned) => {
+                    info!(
+                        "[SSH] Learned host key for {}:{} ({})",
+                        host, port, event.fingerprint
+                    );
+                    SSHClient::emit_app_event("host-key-learned", event);
+                    true
+                }
+                Ok(HostKeyVerdict::Changed {
+                    line,
+                    known_fingerprint,
+                }) => {
+                    error!(
+                        "[SSH] Host key for {}:{} does not match known_hosts line {} (got {})",
+                        host, port, line, event.fingerprint
+                    );
+                    rejection.set(format!(
+                        "HOST_KEY_CHANGED: The host key for {}:{} has changed (now {}). \
+                         This may indicate a man-in-the-middle attack. Remove the old entry \
+                         (known_hosts line {}) only if the change is expected.",
+                        host, port, event.fingerprint, line
+                    ));
+                    event.known_fingerprint = known_fingerprint;
+                    SSHClient::emit_app_event("host-key-changed", event);
+                    false
+                }
+                Err(e) => {
+                    error!(
+                        "[SSH] Host key verification failed for {}:{}: {}",
+                        host, port, e
+                    );
+                    rejection.set(format!(
+                        "HOST_KEY_UNVERIFIED: Could not verify the host key for {}:{}: {}",
+                        host, port, e
+                    ));
+                    false
+                }
+            }
         }
     }
 }
@@ -39,9 +180,10 @@ impl client::Handler for ClientHandler {
 
     fn check_server_key(
         &mut self,
-        _server_public_key: &keys::PublicKey,
+        server_public_key: &keys::PublicKey,
     ) -> impl std::future::Future<Output = Result<bool, Self::Error>> + Send {
-        async { Ok(true) } // Accept all keys
+        let verify = self.verify_host_key(server_public_key);
+        async move { Ok(verify.await) }
     }
 
     fn data(
@@ -85,6 +227,73 @@ impl client::Handler for ClientHandler {
         }
     }
 
+    fn server_channel_open_forwarded_tcpip(
+        &mut self,
+        channel: russh::Channel<client::Msg>,
+        connected_address: &str,
+        connected_port: u32,
+        originator_address: &str,
+        originator_port: u32,
+        _session: &mut client::Session,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send {
+        match self.session_id.as_deref() {
+            Some(session_id) => {
+                debug!(
+                    "[SSH] Forwarded connection to {}:{} from {}:{} on {}",
+                    connected_address,
+                    connected_port,
+                    originator_address,
+                    originator_port,
+                    session_id
+                );
+                PortForwardManager::accept_forwarded_channel(
+                    session_id,
+                    channel,
+                    connected_address,
+                    connected_port,
+                    format!("{}:{}", originator_address, originator_port),
+                );
+            }
+            None => {
+                warn!(
+                    "[SSH] Unexpected forwarded-tcpip channel for {}:{} without a session",
+                    connected_address, connected_port
+                );
+            }
+        }
+
+        async { Ok(()) }
+    }
+
+    fn server_channel_open_agent_forward(
+        &mut self,
+        channel: russh::Channel<client::Msg>,
+        _session: &mut client::Session,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send {
+        if self.forward_agent {
+            let session_id = self.session_id.clone();
+            let host = self.host.clone();
+            tokio::spawn(async move {
+                if let Err(e) =
+                    agent::proxy_forwarded_agent(channel.into_stream(), session_id, host.clone())
+                        .await
+                {
+                    warn!("[SSH] Forwarded agent channel from {} failed: {}", host, e);
+                }
+            });
+        } else {
+            warn!(
+                "[SSH] Refusing agent channel from {}:{}: agent forwarding not enabled",
+                self.host, self.port
+            );
+            tokio::spawn(async move {
+                let _ = channel.close().await;
+            });
+        }
+
+        async { Ok(()) }
+    }
+
     fn channel_close(
         &mut self,
         channel: russh::ChannelId,
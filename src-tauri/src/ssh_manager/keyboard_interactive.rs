@@ -0,0 +1,239 @@
+use crate::ssh_manager::ssh::SSHClient;
+use dashmap::DashMap;
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use serde::Serialize;
+use sha1::Sha1;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+/// How long a prompt waits for the user; sshd's default `LoginGraceTime` is 120s.
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+const TOTP_STEP_SECONDS: u64 = 30;
+const TOTP_DIGITS: u32 = 6;
+const OTP_PROMPT_HINTS: &[&str] = &[
+    "verification code",
+    "one-time",
+    "one time",
+    "otp",
+    "2fa",
+    "two-factor",
+    "authenticator",
+    "token",
+    "passcode",
+];
+
+lazy_static! {
+    /// Prompts waiting for `answer_keyboard_interactive`, keyed by request id.
+    static ref PENDING_PROMPTS: DashMap<String, oneshot::Sender<Option<Vec<String>>>> =
+        DashMap::new();
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyboardInteractivePrompt {
+    pub prompt: String,
+    pub echo: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyboardInteractiveEvent {
+    pub request_id: String,
+    pub server_id: Option<String>,
+    pub host: String,
+    pub username: String,
+    pub name: String,
+    pub instructions: String,
+    pub prompts: Vec<KeyboardInteractivePrompt>,
+}
+
+/// What the client can answer on its own, before bothering the user.
+#[derive(Debug, Clone, Default)]
+pub struct AutoFill {
+    pub password: Option<String>,
+    pub totp_secret: Option<String>,
+}
+
+impl AutoFill {
+    /// Answers every prompt from stored secrets, or `None` if any prompt needs the user.
+    pub fn answer(&self, prompts: &[KeyboardInteractivePrompt]) -> Option<Vec<String>> {
+        prompts
+            .iter()
+            .map(|prompt| self.answer_prompt(&prompt.prompt))
+            .collect()
+    }
+
+    fn answer_prompt(&self, prompt: &str) -> Option<String> {
+        let prompt = prompt.to_lowercase();
+        if is_otp_prompt(&prompt) {
+            let secret = self.totp_secret.as_deref()?;
+            return match totp_now(secret) {
+                Ok(code) => Some(code),
+                Err(e) => {
+                    warn!("[SSH] Stored TOTP secret is unusable: {}", e);
+                    None
+                }
+            };
+        }
+        if prompt.contains("password") {
+            return self.password.clone();
+        }
+        None
+    }
+}
+
+/// True for rounds that only ask for the account password (e.g. PAM's plain
+/// `Password:`), which the regular password dialog already covers.
+pub fn only_asks_for_password(prompts: &[KeyboardInteractivePrompt]) -> bool {
+    !prompts.is_empty()
+        && prompts.iter().all(|prompt| {
+            let text = prompt.prompt.to_lowercase();
+            text.contains("password") && !is_otp_prompt(&text)
+        })
+}
+
+fn is_otp_prompt(lowercase_prompt: &str) -> bool {
+    OTP_PROMPT_HINTS
+        .iter()
+        .any(|hint| lowercase_prompt.contains(hint))
+}
+
+/// Emits `keyboard-interactive-prompt` and waits for the frontend to answer it.
+pub async fn request_answers(mut event: KeyboardInteractiveEvent) -> Result<Vec<String>, String> {
+    let request_id = uuid::Uuid::new_v4().to_string();
+    event.request_id = request_id.clone();
+    let (tx, rx) = oneshot::channel();
+    PENDING_PROMPTS.insert(request_id.clone(), tx);
+
+    info!(
+        "[SSH] Waiting for {} keyboard-interactive answer(s) for {}@{}",
+        event.prompts.len(),
+        event.username,
+        event.host
+    );
+    SSHClient::emit_app_event("keyboard-interactive-prompt", event);
+
+    let outcome = tokio::time::timeout(PROMPT_TIMEOUT, rx).await;
+    PENDING_PROMPTS.remove(&request_id);
+    match outcome {
+        Ok(Ok(Some(answers))) => Ok(answers),
+        Ok(Ok(None)) | Ok(Err(_)) => {
+            Err("Keyboard-interactive authentication was cancelled".to_string())
+        }
+        Err(_) => Err("Timed out waiting for keyboard-interactive answers".to_string()),
+    }
+}
+
+/// Delivers the user's answers; `None` cancels the prompt.
+pub fn submit_answers(request_id: &str, answers: Option<Vec<String>>) -> Result<(), String> {
+    let (_, sender) = PENDING_PROMPTS
+        .remove(request_id)
+        .ok_or_else(|| "Keyboard-interactive prompt not found or expired".to_string())?;
+    sender
+        .send(answers)
+        .map_err(|_| "Keyboard-interactive prompt is no longer waiting".to_string())
+}
+
+pub fn totp_now(secret: &str) -> Result<String, String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("System clock error: {}", e))?;
+    totp_at(secret, now.as_secs())
+}
+
+/// RFC 6238 TOTP (HMAC-SHA1, 30s step, 6 digits), the default for authenticator apps.
+/// The secret is base32 as shown by those apps; spaces, dashes and padding are ignored.
+pub fn totp_at(secret: &str, unix_time: u64) -> Result<String, String> {
+    let normalized: String = secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '=')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let key = BASE32_NOPAD
+        .decode(normalized.as_bytes())
+        .map_err(|e| format!("Invalid TOTP secret: {}", e))?;
+    if key.is_empty() {
+        return Err("Invalid TOTP secret: empty".to_string());
+    }
+
+    let counter = unix_time / TOTP_STEP_SECONDS;
+    let mut mac =
+        Hmac::<Sha1>::new_from_slice(&key).map_err(|e| format!("Invalid TOTP secret: {}", e))?;
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    Ok(format!(
+        "{:0width$}",
+        binary % 10u32.pow(TOTP_DIGITS),
+        width = TOTP_DIGITS as usize
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 appendix B seed "12345678901234567890", base32 encoded.
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    fn prompt(text: &str) -> KeyboardInteractivePrompt {
+        KeyboardInteractivePrompt {
+            prompt: text.to_string(),
+            echo: false,
+        }
+    }
+
+    #[test]
+    fn totp_matches_rfc_6238_vectors() {
+        assert_eq!(totp_at(RFC_SECRET, 59).unwrap(), "287082");
+        assert_eq!(totp_at(RFC_SECRET, 1_111_111_109).unwrap(), "081804");
+        assert_eq!(totp_at(RFC_SECRET, 2_000_000_000).unwrap(), "279037");
+        assert_eq!(
+            totp_at("gezd gnbv gy3t qojq gezd gnbv gy3t qojq", 59).unwrap(),
+            "287082"
+        );
+        assert!(totp_at("not base32!", 59).is_err());
+    }
+
+    #[test]
+    fn auto_fill_answers_password_and_otp_prompts_only() {
+        let fill = AutoFill {
+            password: Some("secret".to_string()),
+            totp_secret: Some(RFC_SECRET.to_string()),
+        };
+
+        let answers = fill
+            .answer(&[prompt("Password: "), prompt("Verification code: ")])
+            .expect("both prompts should be answered");
+        assert_eq!(answers[0], "secret");
+        assert_eq!(answers[1].len(), 6);
+
+        assert_eq!(fill.answer(&[prompt("Favourite colour? ")]), None);
+        assert_eq!(AutoFill::default().answer(&[prompt("OTP: ")]), None);
+        assert_eq!(fill.answer(&[]), Some(Vec::new()));
+
+        assert!(only_asks_for_password(&[prompt("Password: ")]));
+        assert!(!only_asks_for_password(&[prompt("OTP password: ")]));
+        assert!(!only_asks_for_password(&[]));
+    }
+
+    #[tokio::test]
+    async fn submitted_answers_resolve_the_pending_prompt() {
+        let (tx, rx) = oneshot::channel();
+        PENDING_PROMPTS.insert("request-1".to_string(), tx);
+
+        submit_answers("request-1", Some(vec!["123456".to_string()])).unwrap();
+        assert_eq!(rx.await.unwrap(), Some(vec!["123456".to_string()]));
+        assert!(submit_answers("request-1", None).is_err());
+    }
+}
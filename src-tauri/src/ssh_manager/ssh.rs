@@ -1,11 +1,22 @@
-use crate::config::types::Proxy;
+use crate::config::types::{DynamicForward, PortForward, Proxy};
 use crate::sftp_manager::SftpManager;
+use crate::ssh_manager::agent;
+use crate::ssh_manager::certificate;
+use crate::ssh_manager::command_history::HistoryScope;
+use crate::ssh_manager::forwarding::PortForwardManager;
 use crate::ssh_manager::handler::ClientHandler;
+use crate::ssh_manager::keyboard_interactive::{
+    self, AutoFill, KeyboardInteractiveEvent, KeyboardInteractivePrompt,
+};
+use crate::ssh_manager::proxy_command;
+use crate::ssh_manager::recording;
+use crate::ssh_manager::shell_integration::{CommandRecord, ShellCommandEvent, ShellIntegration};
+use crate::ssh_manager::socks::SocksForwardManager;
 use base64::prelude::*;
 use bytes::Bytes;
 use dashmap::DashMap;
 use lazy_static::lazy_static;
-use russh::client::{self, AuthResult};
+use russh::client::{self, AuthResult, KeyboardInteractiveAuthResponse};
 use russh::keys::{self, PrivateKeyWithHashAlg};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -16,7 +27,7 @@ use tauri::{AppHandle, Emitter};
 use tokio::net::lookup_host;
 use tokio::sync::mpsc;
 use tokio::sync::Mutex;
-use tracing::{error, info, warn};
+use tracing::{debug, error, info, warn};
 
 const COMPLETION_MARKER_PREFIX: &str = "RESH_CMD_DONE_";
 const START_MARKER_PREFIX: &str = "RESH_CMD_START_";
@@ -30,6 +41,8 @@ const SSH_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
 const SSH_KEEPALIVE_MAX: usize = 3;
 const SSH_NODELAY: bool = false;
 const SESSION_MONITOR_INTERVAL: Duration = Duration::from_secs(2);
+/// Display output kept per session for AI reads and command output, in bytes.
+const MAX_BUFFER_SIZE: usize = 100_000;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ConnectParams {
@@ -41,6 +54,29 @@ pub struct ConnectParams {
     pub passphrase: Option<String>,
     pub proxy: Option<Proxy>,
     pub jumphost: Option<JumphostConfig>,
+    /// Ordered jump chain, outermost (reached first) to innermost. When non-empty it
+    /// replaces `jumphost`.
+    #[serde(default)]
+    pub jump_chain: Vec<JumphostConfig>,
+    /// Authenticate with identities from the local SSH agent (auth type "agent").
+    #[serde(default)]
+    pub use_agent: bool,
+    /// Id of the saved `Server` this session was opened from, used to resolve
+    /// per-server options that are not part of the connect form.
+    #[serde(default)]
+    pub server_id: Option<String>,
+    #[serde(default)]
+    pub port_forwards: Vec<PortForward>,
+    #[serde(default)]
+    pub dynamic_forward: Option<DynamicForward>,
+    #[serde(default)]
+    pub forward_agent: bool,
+    /// Base32 TOTP secret used to answer one-time-code prompts automatically.
+    #[serde(default)]
+    pub totp_secret: Option<String>,
+    /// OpenSSH certificate for `private_key`, resolved from the server's authentication.
+    #[serde(default)]
+    pub certificate: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -51,6 +87,70 @@ pub struct JumphostConfig {
     pub password: Option<String>,
     pub private_key: Option<String>,
     pub passphrase: Option<String>,
+    #[serde(default)]
+    pub use_agent: bool,
+    #[serde(default)]
+    pub totp_secret: Option<String>,
+    #[serde(default)]
+    pub certificate: Option<String>,
+    /// Proxy used to reach this hop. Only honoured on the outermost hop; when unset, the
+    /// session's own `proxy` is used to reach it.
+    #[serde(default)]
+    pub proxy: Option<Proxy>,
+}
+
+/// Everything `authenticate_session` needs, shared by the target and jump hosts.
+struct SessionCredentials {
+    username: String,
+    password: Option<String>,
+    private_key: Option<String>,
+    passphrase: Option<String>,
+    certificate: Option<String>,
+    use_agent: bool,
+    totp_secret: Option<String>,
+    /// Shown in keyboard-interactive prompts so the user knows which login is asking.
+    host: String,
+    server_id: Option<String>,
+}
+
+impl ConnectParams {
+    fn jump_hops(&self) -> Vec<&JumphostConfig> {
+        if self.jump_chain.is_empty() {
+            self.jumphost.iter().collect()
+        } else {
+            self.jump_chain.iter().collect()
+        }
+    }
+
+    fn credentials(&self) -> SessionCredentials {
+        SessionCredentials {
+            username: self.username.clone(),
+            password: self.password.clone(),
+            private_key: self.private_key.clone(),
+            passphrase: self.passphrase.clone(),
+            certificate: self.certificate.clone(),
+            use_agent: self.use_agent,
+            totp_secret: self.totp_secret.clone(),
+            host: self.host.clone(),
+            server_id: self.server_id.clone(),
+        }
+    }
+}
+
+impl JumphostConfig {
+    fn credentials(&self) -> SessionCredentials {
+        SessionCredentials {
+            username: self.username.clone(),
+            password: self.password.clone(),
+            private_key: self.private_key.clone(),
+            passphrase: self.passphrase.clone(),
+            certificate: self.certificate.clone(),
+            use_agent: self.use_agent,
+            totp_secret: self.totp_secret.clone(),
+            host: self.host.clone(),
+            server_id: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +162,18 @@ pub struct SystemInfo {
     pub shell: String,
 }
 
+/// What a chunk of terminal output contained.
+#[derive(Debug)]
+pub struct TerminalUpdate {
+    /// The output to display, without completion markers.
+    pub display: String,
+    /// Shell integration commands that started or finished in it.
+    pub events: Vec<ShellCommandEvent>,
+    /// A command sent with `start_command_recording` that finished since the last update, on a
+    /// shell without integration.
+    pub recorded_command: Option<CommandRecord>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SshTransportDiagnostics {
     pub window_size: u32,
@@ -72,7 +184,8 @@ pub struct SshTransportDiagnostics {
 struct SessionData {
     channel: russh::ChannelWriteHalf<russh::client::Msg>,
     session: Arc<russh::client::Handle<ClientHandler>>,
-    jumphost_session: Option<russh::client::Handle<ClientHandler>>,
+    /// Every intermediate hop, outermost first; each one carries the next hop's tunnel.
+    jumphost_sessions: Vec<russh::client::Handle<ClientHandler>>,
     connection_generation: u64,
     config: ConnectParams,
     transport_diagnostics: SshTransportDiagnostics,
@@ -92,6 +205,15 @@ struct SessionData {
     last_completion_check_state: Option<CompletionCheckState>,
     last_completion_probe_recorder_len: usize,
     prompt_match_streak: u8,
+    /// OSC 133/OSC 7 command history, fed with the same output as `terminal_buffer`.
+    shell: ShellIntegration,
+    /// `shell` offset when the current command recording started.
+    recording_shell_offset: u64,
+    /// The recorded command, completed from its completion marker for shells without
+    /// integration.
+    recording_command: Option<CommandRecord>,
+    /// `recording_command` after its marker arrived, until the output is flushed.
+    finished_recording_command: Option<CommandRecord>,
     pub system_info: Option<SystemInfo>,
 }
 
@@ -101,6 +223,7 @@ enum CompletionCheckState {
     Waiting,
     CompletedByMarker,
     CompletedByPrompt,
+    CompletedByShellIntegration,
 }
 
 impl CompletionCheckState {
@@ -110,6 +233,7 @@ impl CompletionCheckState {
             CompletionCheckState::NoRecorder
                 | CompletionCheckState::CompletedByMarker
                 | CompletionCheckState::CompletedByPrompt
+                | CompletionCheckState::CompletedByShellIntegration
         )
     }
 }
@@ -162,6 +286,18 @@ impl SSHClient {
         Arc::new(client_config)
     }
 
+    /// Emits an app-wide event once the app handle is registered; dropped otherwise.
+    pub(crate) fn emit_app_event<S: Serialize + Clone>(event: &str, payload: S) {
+        let Some(app_handle) = APP_HANDLE.get() else {
+            warn!("[SSH] Unable to emit {}: app handle not initialized", event);
+            return;
+        };
+
+        if let Err(e) = app_handle.emit(event, payload) {
+            warn!("[SSH] Failed to emit {} event: {}", event, e);
+        }
+    }
+
     fn emit_connection_closed(session_id: &str) {
         let Some(app_handle) = APP_HANDLE.get() else {
             warn!(
@@ -263,7 +399,7 @@ impl SSHClient {
         let initial_rows = 24;
         let connection_generation = 1;
 
-        let (channel, session, jh_session) = Self::establish_connection(
+        let (channel, session, jh_sessions) = Self::establish_connection(
             session_id.clone(),
             &params,
             tx.clone(),
@@ -272,12 +408,14 @@ impl SSHClient {
         )
         .await?;
 
+        let port_forwards = params.port_forwards.clone();
+        let dynamic_forward = params.dynamic_forward.clone();
         SESSIONS.insert(
             session_id.clone(),
             Arc::new(Mutex::new(SessionData {
                 channel,
                 session: Arc::new(session),
-                jumphost_session: jh_session,
+                jumphost_sessions: jh_sessions,
                 connection_generation,
                 config: params,
                 transport_diagnostics: Self::default_transport_diagnostics(),
@@ -297,11 +435,17 @@ impl SSHClient {
                 last_completion_check_state: None,
                 last_completion_probe_recorder_len: 0,
                 prompt_match_streak: 0,
+                shell: ShellIntegration::default(),
+                recording_shell_offset: 0,
+                recording_command: None,
+                finished_recording_command: None,
                 system_info: None,
             })),
         );
 
         Self::spawn_session_monitor(session_id.clone(), connection_generation);
+        PortForwardManager::start_session(&session_id, &port_forwards).await;
+        SocksForwardManager::start_session(&session_id, dynamic_forward.as_ref()).await;
         Ok(session_id)
     }
 
@@ -315,15 +459,22 @@ impl SSHClient {
         (
             russh::ChannelWriteHalf<russh::client::Msg>,
             russh::client::Handle<ClientHandler>,
-            Option<russh::client::Handle<ClientHandler>>,
+            Vec<russh::client::Handle<ClientHandler>>,
         ),
         String,
     > {
         let config = Self::build_client_config();
 
         let shell_channel_id = Arc::new(Mutex::new(None));
-        let handler =
-            ClientHandler::with_channel(session_id.clone(), tx.clone(), shell_channel_id.clone());
+        let mut handler = ClientHandler::with_channel(
+            session_id.clone(),
+            tx.clone(),
+            shell_channel_id.clone(),
+            &params.host,
+            params.port,
+        );
+        handler.forward_agent = params.forward_agent;
+        let host_key_rejection = handler.host_key_rejection.clone();
 
         info!(
             "[SSH] Connecting to {}:{} as {}",
@@ -341,20 +492,26 @@ impl SSHClient {
         }
 
         // Debug: Log jumphost configuration
-        if let Some(ref j) = params.jumphost {
+        let hops = params.jump_hops();
+        if hops.is_empty() {
+            info!("[SSH] No jumphost configured (jumphost=None)");
+        }
+        for (index, j) in hops.iter().enumerate() {
             info!(
-                "[SSH] Jumphost configured: {}:{} as {}",
-                j.host, j.port, j.username
+                "[SSH] Jumphost {}/{} configured: {}:{} as {}",
+                index + 1,
+                hops.len(),
+                j.host,
+                j.port,
+                j.username
             );
-        } else {
-            info!("[SSH] No jumphost configured (jumphost=None)");
         }
 
-        let mut jh_handle_to_store = None;
+        let mut jump_sessions = Vec::new();
 
         // If jumphost is configured, connection must go through jumphost branch.
-        // Proxy is still used there for connecting to jumphost itself.
-        let proxy_for_direct_target = if params.jumphost.is_some() {
+        // Proxy is still used there for connecting to the first jumphost itself.
+        let proxy_for_direct_target = if !hops.is_empty() {
             None
         } else {
             params.proxy.as_ref()
@@ -431,23 +588,31 @@ impl SSHClient {
                     return Err(format!("HTTP proxy returned error: {}", response_text));
                 }
                 client::connect_stream(config, stream, handler).await
+            } else if p.proxy_type == "command" {
+                let stream =
+                    proxy_command::spawn_for(p, &params.host, params.port, &params.username)?;
+                client::connect_stream(config, stream, handler).await
             } else {
                 return Err(format!("Unsupported proxy type: {}", p.proxy_type));
             }
-        } else if let Some(j) = &params.jumphost {
+        } else if let Some(j) = hops.first().copied() {
             info!(
                 "[SSH] Using jumphost: {}:{} as {}",
                 j.host, j.port, j.username
             );
+            let first_label = Self::hop_label(0, hops.len(), j);
             let _ = tx.send((
                 session_id.clone(),
-                "Connecting to jump host...\r\n".as_bytes().to_vec(),
+                format!("Connecting to {}...\r\n", first_label.to_lowercase())
+                    .as_bytes()
+                    .to_vec(),
             ));
 
-            let jh_handler = ClientHandler::new();
+            let jh_handler = ClientHandler::new(&j.host, j.port).for_session(&session_id);
+            let jh_rejection = jh_handler.host_key_rejection.clone();
 
-            // Connect to jumphost - use proxy if configured, otherwise direct
-            let mut jh_session = if let Some(p) = &params.proxy {
+            // Connect to jumphost - use the hop's or the session's proxy, otherwise direct
+            let mut jh_session = if let Some(p) = j.proxy.as_ref().or(params.proxy.as_ref()) {
                 info!(
                     "[SSH] Connecting to jumphost via {} proxy: {}:{} -> {}:{}",
                     p.proxy_type, p.host, p.port, j.host, j.port
@@ -507,7 +672,8 @@ impl SSHClient {
                         .await
                         .map_err(|e| {
                             error!("[SSH] SSH connection via SOCKS5 failed: {}", e);
-                            format!("Failed to connect to jumphost via SOCKS5: {}", e)
+                            jh_rejection
+                                .explain(format!("Failed to connect to jumphost via SOCKS5: {}", e))
                         })?
                 } else if p.proxy_type == "http" {
                     info!("[SSH] Attempting HTTP proxy connection to jumphost...");
@@ -569,7 +735,21 @@ impl SSHClient {
                         .await
                         .map_err(|e| {
                             error!("[SSH] SSH connection via HTTP proxy failed: {}", e);
-                            format!("Failed to connect to jumphost via HTTP proxy: {}", e)
+                            jh_rejection.explain(format!(
+                                "Failed to connect to jumphost via HTTP proxy: {}",
+                                e
+                            ))
+                        })?
+                } else if p.proxy_type == "command" {
+                    let stream = proxy_command::spawn_for(p, &j.host, j.port, &j.username)?;
+                    client::connect_stream(config.clone(), stream, jh_handler)
+                        .await
+                        .map_err(|e| {
+                            error!("[SSH] SSH connection via proxy command failed: {}", e);
+                            jh_rejection.explain(format!(
+                                "Failed to connect to jumphost via proxy command: {}",
+                                e
+                            ))
                         })?
                 } else {
                     return Err(format!(
@@ -587,7 +767,7 @@ impl SSHClient {
                     .await
                     .map_err(|e| {
                         error!("[SSH] Direct jumphost connection failed: {}", e);
-                        format!("Failed to connect to jumphost: {}", e)
+                        jh_rejection.explain(format!("Failed to connect to jumphost: {}", e))
                     })?
             };
 
@@ -602,19 +782,38 @@ impl SSHClient {
                 if j.password.is_some() { "yes" } else { "no" },
                 if j.private_key.is_some() { "yes" } else { "no" }
             );
-            Self::authenticate_session(
-                &mut jh_session,
-                &j.username,
-                j.password.clone(),
-                j.private_key.clone(),
-                j.passphrase.clone(),
-            )
-            .await
-            .map_err(|e| {
-                error!("[SSH] Jumphost authentication failed: {}", e);
-                format!("Jumphost authentication failed: {}", e)
-            })?;
-            info!("[SSH] Jumphost authentication successful");
+            Self::authenticate_session(&mut jh_session, j.credentials())
+                .await
+                .map_err(|e| {
+                    error!("[SSH] {} authentication failed: {}", first_label, e);
+                    format!("{} authentication failed: {}", first_label, e)
+                })?;
+            info!("[SSH] {} authentication successful", first_label);
+            jump_sessions.push(jh_session);
+
+            for (index, hop) in hops.iter().enumerate().skip(1) {
+                let label = Self::hop_label(index, hops.len(), hop);
+                info!("[SSH] Tunneling to {} as {}", label, hop.username);
+                let _ = tx.send((
+                    session_id.clone(),
+                    format!("Connecting to {}...\r\n", label.to_lowercase())
+                        .as_bytes()
+                        .to_vec(),
+                ));
+                let previous = jump_sessions.last().expect("first hop is connected");
+                let hop_session = Self::connect_through_hop(
+                    config.clone(),
+                    previous,
+                    hop,
+                    &label,
+                    ClientHandler::new(&hop.host, hop.port).for_session(&session_id),
+                    hop.credentials(),
+                )
+                .await
+                .inspect_err(|e| error!("[SSH] {}", e))?;
+                info!("[SSH] {} authentication successful", label);
+                jump_sessions.push(hop_session);
+            }
 
             info!(
                 "[SSH] Opening tunnel to target {}:{}",
@@ -626,7 +825,8 @@ impl SSHClient {
                     .as_bytes()
                     .to_vec(),
             ));
-            let channel = jh_session
+            let last_hop = jump_sessions.last().expect("first hop is connected");
+            let channel = last_hop
                 .channel_open_direct_tcpip(&params.host, params.port as u32, "127.0.0.1", 22222)
                 .await
                 .map_err(|e| {
@@ -635,15 +835,13 @@ impl SSHClient {
                 })?;
             info!("[SSH] Tunnel opened successfully, connecting to target via tunnel...");
 
-            jh_handle_to_store = Some(jh_session);
-
             client::connect_stream(config, channel.into_stream(), handler).await
         } else {
             client::connect(config, (&params.host[..], params.port), handler).await
         }
         .map_err(|e| {
             error!("[SSH] Connection error: {}", e);
-            e.to_string()
+            host_key_rejection.explain(e)
         })?;
 
         let _ = tx.send((
@@ -652,14 +850,7 @@ impl SSHClient {
                 .as_bytes()
                 .to_vec(),
         ));
-        Self::authenticate_session(
-            &mut session,
-            &params.username,
-            params.password.clone(),
-            params.private_key.clone(),
-            params.passphrase.clone(),
-        )
-        .await?;
+        Self::authenticate_session(&mut session, params.credentials()).await?;
 
         info!("[SSH] Authentication successful. Opening channel...");
 
@@ -675,9 +866,15 @@ impl SSHClient {
         }
 
         channel
-            .request_pty(true, "xterm-256color", cols, rows, 0, 0, &[])
+            .request_pty(true, recording::RECORDING_TERM, cols, rows, 0, 0, &[])
             .await
             .map_err(|e| format!("PTY request failed: {}", e))?;
+        if params.forward_agent {
+            // Not fatal: the shell still works, only onward agent use is unavailable.
+            if let Err(e) = channel.agent_forward(true).await {
+                warn!("[SSH] Agent forwarding request failed: {}", e);
+            }
+        }
         channel
             .request_shell(true)
             .await
@@ -686,17 +883,84 @@ impl SSHClient {
         let (read_half, write_half) = channel.split();
         Self::spawn_shell_channel_drain(session_id, read_half);
 
-        Ok((write_half, session, jh_handle_to_store))
+        Ok((write_half, session, jump_sessions))
+    }
+
+    /// "Jumphost" for a single hop, "Jumphost 2/3 (gw.example:22)" inside a chain, so
+    /// progress and errors name the hop involved.
+    fn hop_label(index: usize, total: usize, hop: &JumphostConfig) -> String {
+        if total <= 1 {
+            "Jumphost".to_string()
+        } else {
+            format!(
+                "Jumphost {}/{} ({}:{})",
+                index + 1,
+                total,
+                hop.host,
+                hop.port
+            )
+        }
+    }
+
+    /// Reaches `hop` through a direct-tcpip channel on the previous hop, then verifies
+    /// its host key and authenticates it like any other server.
+    async fn connect_through_hop(
+        config: Arc<client::Config>,
+        previous: &client::Handle<ClientHandler>,
+        hop: &JumphostConfig,
+        label: &str,
+        handler: ClientHandler,
+        credentials: SessionCredentials,
+    ) -> Result<client::Handle<ClientHandler>, String> {
+        let rejection = handler.host_key_rejection.clone();
+        let channel = previous
+            .channel_open_direct_tcpip(&hop.host, hop.port as u32, "127.0.0.1", 22222)
+            .await
+            .map_err(|e| format!("{}: failed to open tunnel: {}", label, e))?;
+        let mut session = client::connect_stream(config, channel.into_stream(), handler)
+            .await
+            .map_err(|e| rejection.explain(format!("{}: failed to connect: {}", label, e)))?;
+        Self::authenticate_session(&mut session, credentials)
+            .await
+            .map_err(|e| format!("{} authentication failed: {}", label, e))?;
+        Ok(session)
+    }
+
+    async fn best_rsa_hash<H: client::Handler>(
+        session: &client::Handle<H>,
+    ) -> Option<keys::HashAlg> {
+        match session.best_supported_rsa_hash().await {
+            Ok(best) => best.flatten(),
+            Err(error) => {
+                warn!(
+                    "[SSH] Failed to query best RSA hash, falling back to default ordering: {}",
+                    error
+                );
+                None
+            }
+        }
     }
 
     async fn authenticate_session<H: client::Handler>(
         session: &mut client::Handle<H>,
-        username: &str,
-        password: Option<String>,
-        private_key: Option<String>,
-        passphrase: Option<String>,
+        credentials: SessionCredentials,
     ) -> Result<(), String> {
+        let SessionCredentials {
+            username,
+            password,
+            private_key,
+            passphrase,
+            certificate,
+            use_agent,
+            totp_secret,
+            host,
+            server_id,
+        } = credentials;
+        let username = username.as_str();
         let mut authenticated = false;
+        let mut agent_error = None;
+        let mut certificate_error = None;
+        let mut interactive_error = None;
 
         if let Some(key_content) = private_key {
             info!(
@@ -710,36 +974,66 @@ impl SSHClient {
                     format!("Failed to decode private key: {}", e)
                 })?;
             let key_pair = Arc::new(key);
-            let hash_alg = match session.best_supported_rsa_hash().await {
-                Ok(best) => best.flatten(),
-                Err(error) => {
-                    warn!(
-                        "[SSH] Failed to query best RSA hash, falling back to default ordering: {}",
-                        error
-                    );
-                    None
+
+            if let Some(cert_content) = certificate.as_deref().filter(|c| !c.trim().is_empty()) {
+                match Self::authenticate_with_certificate(
+                    session,
+                    username,
+                    key_pair.clone(),
+                    cert_content,
+                )
+                .await
+                {
+                    Ok(true) => {
+                        authenticated = true;
+                        info!("[SSH] Certificate authentication successful.");
+                    }
+                    Ok(false) => warn!("[SSH] Certificate rejected for user: '{}'", username),
+                    Err(e) => {
+                        error!("[SSH] Certificate authentication error: {}", e);
+                        certificate_error = Some(e);
+                    }
                 }
-            };
-            let auth_key = PrivateKeyWithHashAlg::new(key_pair, hash_alg);
-            match session.authenticate_publickey(username, auth_key).await {
-                Ok(AuthResult::Success) => {
+            }
+
+            if !authenticated {
+                let hash_alg = Self::best_rsa_hash(session).await;
+                let auth_key = PrivateKeyWithHashAlg::new(key_pair, hash_alg);
+                match session.authenticate_publickey(username, auth_key).await {
+                    Ok(AuthResult::Success) => {
+                        authenticated = true;
+                        info!("[SSH] Publickey authentication successful.");
+                    }
+                    Ok(AuthResult::Failure {
+                        remaining_methods,
+                        partial_success,
+                    }) => warn!(
+                        "[SSH] Publickey authentication rejected. remaining_methods={:?}, partial_success={}",
+                        remaining_methods,
+                        partial_success
+                    ),
+                    Err(e) => error!("[SSH] Publickey authentication error: {}", e),
+                }
+            }
+        }
+
+        if !authenticated && use_agent {
+            info!("[SSH] Attempting agent auth for user: '{}'...", username);
+            match Self::authenticate_with_agent(session, username).await {
+                Ok(true) => {
                     authenticated = true;
-                    info!("[SSH] Publickey authentication successful.");
+                    info!("[SSH] Agent authentication successful.");
+                }
+                Ok(false) => warn!("[SSH] No agent identity was accepted for {}", username),
+                Err(e) => {
+                    error!("[SSH] Agent authentication error: {}", e);
+                    agent_error = Some(e);
                 }
-                Ok(AuthResult::Failure {
-                    remaining_methods,
-                    partial_success,
-                }) => warn!(
-                    "[SSH] Publickey authentication rejected. remaining_methods={:?}, partial_success={}",
-                    remaining_methods,
-                    partial_success
-                ),
-                Err(e) => error!("[SSH] Publickey authentication error: {}", e),
             }
         }
 
         if !authenticated {
-            if let Some(pwd) = password {
+            if let Some(pwd) = password.clone() {
                 info!("[SSH] Attempting password auth for user: '{}'...", username);
                 match session.authenticate_password(username, pwd).await {
                     Ok(AuthResult::Success) => {
@@ -759,11 +1053,63 @@ impl SSHClient {
             }
         }
 
+        if !authenticated {
+            info!(
+                "[SSH] Attempting keyboard-interactive auth for user: '{}'...",
+                username
+            );
+            let auto_fill = AutoFill {
+                password,
+                totp_secret,
+            };
+            let event = KeyboardInteractiveEvent {
+                request_id: String::new(),
+                server_id,
+                host,
+                username: username.to_string(),
+                name: String::new(),
+                instructions: String::new(),
+                prompts: Vec::new(),
+            };
+            match Self::authenticate_keyboard_interactive(session, &auto_fill, event).await {
+                Ok(true) => {
+                    authenticated = true;
+                    info!("[SSH] Keyboard-interactive authentication successful.");
+                }
+                Ok(false) => warn!(
+                    "[SSH] Keyboard-interactive authentication rejected for {}",
+                    username
+                ),
+                Err(e) => {
+                    error!("[SSH] Keyboard-interactive authentication error: {}", e);
+                    interactive_error = Some(e);
+                }
+            }
+        }
+
         if !authenticated {
             error!(
                 "[SSH] All authentication methods failed for user: {}",
                 username
             );
+            if let Some(interactive_error) = interactive_error {
+                return Err(format!(
+                    "Keyboard-interactive authentication failed: {}",
+                    interactive_error
+                ));
+            }
+            if let Some(certificate_error) = certificate_error {
+                return Err(format!(
+                    "AUTH_PASSWORD_REQUIRED: {}. Please enter your password.",
+                    certificate_error
+                ));
+            }
+            if let Some(agent_error) = agent_error {
+                return Err(format!(
+                    "AUTH_PASSWORD_REQUIRED: {}. Please enter your password.",
+                    agent_error
+                ));
+            }
             return Err(
                 "AUTH_PASSWORD_REQUIRED: Authentication failed. Please enter your password."
                     .to_string(),
@@ -774,6 +1120,145 @@ impl SSHClient {
         Ok(())
     }
 
+    /// Presents `cert_content` together with its private key. Unusable certificates
+    /// (expired, not yet valid, or for another key) fail locally with a readable reason
+    /// instead of an opaque server rejection.
+    async fn authenticate_with_certificate<H: client::Handler>(
+        session: &mut client::Handle<H>,
+        username: &str,
+        key_pair: Arc<keys::PrivateKey>,
+        cert_content: &str,
+    ) -> Result<bool, String> {
+        let cert = certificate::parse_certificate(cert_content)?;
+        certificate::check_usable(&cert, &key_pair, certificate::unix_now())?;
+        info!(
+            "[SSH] Attempting certificate auth for user: '{}' (key id: {})",
+            username,
+            cert.key_id()
+        );
+        match session
+            .authenticate_openssh_cert(username, key_pair, cert)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            AuthResult::Success => Ok(true),
+            AuthResult::Failure {
+                remaining_methods, ..
+            } => {
+                debug!(
+                    "[SSH] Certificate rejected. remaining_methods={:?}",
+                    remaining_methods
+                );
+                Ok(false)
+            }
+        }
+    }
+
+    /// Runs the keyboard-interactive exchange. Each round is answered from the stored
+    /// password/TOTP secret when possible, otherwise forwarded to the frontend. A round that
+    /// only asks for an unknown password returns `Ok(false)` so the caller falls back to
+    /// the usual `AUTH_PASSWORD_REQUIRED` dialog.
+    async fn authenticate_keyboard_interactive<H: client::Handler>(
+        session: &mut client::Handle<H>,
+        auto_fill: &AutoFill,
+        event: KeyboardInteractiveEvent,
+    ) -> Result<bool, String> {
+        let mut response = session
+            .authenticate_keyboard_interactive_start(event.username.as_str(), None::<String>)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        loop {
+            match response {
+                KeyboardInteractiveAuthResponse::Success => return Ok(true),
+                KeyboardInteractiveAuthResponse::Failure {
+                    remaining_methods, ..
+                } => {
+                    debug!(
+                        "[SSH] Keyboard-interactive rejected. remaining_methods={:?}",
+                        remaining_methods
+                    );
+                    return Ok(false);
+                }
+                KeyboardInteractiveAuthResponse::InfoRequest {
+                    name,
+                    instructions,
+                    prompts,
+                } => {
+                    let prompts: Vec<KeyboardInteractivePrompt> = prompts
+                        .into_iter()
+                        .map(|p| KeyboardInteractivePrompt {
+                            prompt: p.prompt,
+                            echo: p.echo,
+                        })
+                        .collect();
+                    let answers = match auto_fill.answer(&prompts) {
+                        Some(answers) => answers,
+                        None if auto_fill.password.is_none()
+                            && keyboard_interactive::only_asks_for_password(&prompts) =>
+                        {
+                            return Ok(false);
+                        }
+                        None => {
+                            keyboard_interactive::request_answers(KeyboardInteractiveEvent {
+                                name,
+                                instructions,
+                                prompts,
+                                ..event.clone()
+                            })
+                            .await?
+                        }
+                    };
+                    response = session
+                        .authenticate_keyboard_interactive_respond(answers)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+        }
+    }
+
+    /// Offers every agent identity in turn; the agent performs the signatures, so keys on
+    /// hardware tokens work too. `Ok(false)` means the server rejected all of them.
+    async fn authenticate_with_agent<H: client::Handler>(
+        session: &mut client::Handle<H>,
+        username: &str,
+    ) -> Result<bool, String> {
+        let mut agent = agent::connect_local_agent().await?;
+        let identities = agent
+            .request_identities()
+            .await
+            .map_err(|e| format!("Failed to list SSH agent identities: {}", e))?;
+        if identities.is_empty() {
+            return Err("SSH agent has no identities loaded".to_string());
+        }
+
+        let hash_alg = Self::best_rsa_hash(session).await;
+        for identity in identities {
+            let public_key = identity.public_key().into_owned();
+            info!(
+                "[SSH] Offering agent identity {} ({})",
+                public_key.fingerprint(keys::HashAlg::Sha256),
+                identity.comment()
+            );
+            match session
+                .authenticate_publickey_with(username, public_key, hash_alg, &mut agent)
+                .await
+            {
+                Ok(AuthResult::Success) => return Ok(true),
+                Ok(AuthResult::Failure {
+                    remaining_methods, ..
+                }) => debug!(
+                    "[SSH] Agent identity rejected. remaining_methods={:?}",
+                    remaining_methods
+                ),
+                Err(e) => warn!("[SSH] Agent signing failed: {}", e),
+            }
+        }
+
+        Ok(false)
+    }
+
     pub async fn send_input(session_id: &str, data: &[u8]) -> Result<(), String> {
         let (session, channel_id) = Self::get_session_route(session_id).await?;
         let result = session.data(channel_id, Bytes::copy_from_slice(data)).await;
@@ -829,23 +1314,25 @@ impl SSHClient {
         match Self::establish_connection(session_id.to_string(), &config, tx.clone(), cols, rows)
             .await
         {
-            Ok((channel, session, jh_session)) => {
+            Ok((channel, session, jh_sessions)) => {
                 let next_generation = previous_generation.saturating_add(1);
                 let reconnected = match get_session_arc(session_id) {
                     Some(arc) => {
                         let mut data = arc.lock().await;
                         data.channel = channel;
                         data.session = Arc::new(session);
-                        data.jumphost_session = jh_session;
+                        data.jumphost_sessions = jh_sessions;
                         data.connection_generation = next_generation;
                         data.transport_diagnostics = Self::default_transport_diagnostics();
                         // Reset terminal state for new connection
                         data.terminal_buffer.clear();
+                        data.shell.reset_connection();
                         data.command_recorder = None;
                         data.last_output_len = 0;
                         data.recording_prompt = None;
                         data.recording_start_marker = None;
                         data.recording_completion_marker = None;
+                        data.recording_command = None;
                         data.recent_completion_markers.clear();
                         data.command_finished = false;
                         data.last_completion_check_state = None;
@@ -862,6 +1349,8 @@ impl SSHClient {
 
                 info!("[SSH] Reconnection successful for {}", session_id);
                 Self::spawn_session_monitor(session_id.to_string(), next_generation);
+                PortForwardManager::refresh_session(session_id).await;
+                SocksForwardManager::refresh_session(session_id).await;
                 Ok(())
             }
             Err(e) => {
@@ -886,10 +1375,21 @@ impl SSHClient {
             .window_change(cols, rows, 0, 0)
             .await
             .map_err(|e| format!("Failed to resize: {}", e))?;
+        drop(session_data);
+        recording::record_resize(session_id, cols, rows).await;
         Ok(())
     }
 
+    /// PTY size last requested for the session.
+    pub async fn terminal_size(session_id: &str) -> Result<(u32, u32), String> {
+        let arc = get_session_arc(session_id).ok_or_else(|| "Session not found".to_string())?;
+        let session_data = arc.lock().await;
+        Ok((session_data.cols, session_data.rows))
+    }
+
     pub async fn disconnect(session_id: &str) -> Result<(), String> {
+        PortForwardManager::stop_session(session_id).await;
+        SocksForwardManager::stop_session(session_id);
         SftpManager::remove_session(session_id).await;
         if SESSIONS.remove(session_id).is_some() {
             info!("[SSH] Session {} disconnected and removed.", session_id);
@@ -940,7 +1440,7 @@ impl SSHClient {
     }
 
     /// Start recording output for a single command
-    pub async fn start_command_recording(session_id: &str) -> Result<(), String> {
+    pub async fn start_command_recording(session_id: &str, command: &str) -> Result<(), String> {
         let arc = get_session_arc(session_id).ok_or_else(|| "Session not found".to_string())?;
         let mut session_data = arc.lock().await;
         session_data.command_recorder = Some(String::new());
@@ -969,6 +1469,19 @@ impl SSHClient {
         session_data.last_completion_check_state = None;
         session_data.last_completion_probe_recorder_len = 0;
         session_data.prompt_match_streak = 0;
+        session_data.recording_shell_offset = session_data.shell.offset();
+        session_data.recording_command = Some(CommandRecord {
+            id: 0,
+            command: command.trim().to_string(),
+            cwd: session_data.shell.cwd().map(str::to_string),
+            prompt_start: session_data.recording_shell_offset,
+            output_start: session_data.recording_shell_offset,
+            output_end: None,
+            exit_code: None,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+            leading_space: command.starts_with(' '),
+        });
 
         Ok(())
     }
@@ -985,18 +1498,56 @@ impl SSHClient {
         }
     }
 
-    /// Build a queued done-marker command for completion detection.
-    /// The marker is removed from terminal display output before emitting to frontend.
+    /// Build a queued done-marker command for completion detection. The marker carries the
+    /// command's exit status and is removed from terminal display output before emitting to
+    /// frontend.
     pub async fn get_recording_marker_command(session_id: &str) -> Result<String, String> {
         let arc = get_session_arc(session_id).ok_or_else(|| "Session not found".to_string())?;
         let session_data = arc.lock().await;
         if let Some(marker) = session_data.recording_completion_marker.as_ref() {
-            Ok(format!("printf '\\033]633;{}\\007'\n", marker))
+            let shell = session_data
+                .system_info
+                .as_ref()
+                .map(|info| info.shell.as_str());
+            Ok(format!(
+                "{}\n",
+                Self::completion_marker_command(marker, Self::exit_status_variable(shell))
+            ))
         } else {
             Err("No active command recording marker".to_string())
         }
     }
 
+    /// fish has no `$?`.
+    fn exit_status_variable(shell: Option<&str>) -> &'static str {
+        if shell.is_some_and(|shell| shell.trim_end().ends_with("fish")) {
+            "$status"
+        } else {
+            "$?"
+        }
+    }
+
+    fn completion_marker_command(marker: &str, status_variable: &str) -> String {
+        format!(
+            "printf '\\033]633;{};%s\\007' \"{}\"",
+            marker, status_variable
+        )
+    }
+
+    /// Exit status carried by the completion marker: `None` until the marker arrived, `Some(None)`
+    /// when it carries no readable status.
+    fn marker_exit_code(output: &str, marker: &str) -> Option<Option<i32>> {
+        let osc = format!("\x1b]633;{}", marker);
+        let after = &output[output.find(&osc)? + osc.len()..];
+        let Some(status) = after.strip_prefix(';') else {
+            return Some(None);
+        };
+        let end = status
+            .find(|c: char| c != '-' && !c.is_ascii_digit())
+            .filter(|&end| status[end..].starts_with(['\x07', '\x1b']))?;
+        Some(status[..end].parse().ok())
+    }
+
     /// Extract the last N characters from buffer for prompt suffix comparison
     fn extract_prompt_suffix(buffer: &str, n: usize) -> Option<String> {
         if buffer.is_empty() {
@@ -1043,12 +1594,30 @@ impl SSHClient {
     }
 
     fn strip_completion_marker_artifacts(text: &str, marker: &str) -> String {
-        let osc_bell = format!("\x1b]633;{}\x07", marker);
-        let osc_st = format!("\x1b]633;{}\x1b\\", marker);
+        let osc = format!("\x1b]633;{}", marker);
         let cmd_echo_single = format!("printf '\\033]633;{}\\007'", marker);
         let cmd_echo_double = format!("printf \"\\033]633;{}\\007\"", marker);
-        text.replace(&osc_bell, "")
-            .replace(&osc_st, "")
+        let mut stripped = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(at) = rest.find(&osc) {
+            stripped.push_str(&rest[..at]);
+            let after = &rest[at + osc.len()..];
+            // Completion markers carry `;<exit status>` before the terminator.
+            let status_len = after
+                .find(|c: char| c != ';' && c != '-' && !c.is_ascii_digit())
+                .unwrap_or(after.len());
+            let after = &after[status_len..];
+            rest = after
+                .strip_prefix('\x07')
+                .or_else(|| after.strip_prefix("\x1b\\"))
+                .unwrap_or(after);
+        }
+        stripped.push_str(rest);
+        ["$?", "$status"]
+            .iter()
+            .fold(stripped, |text, variable| {
+                text.replace(&Self::completion_marker_command(marker, variable), "")
+            })
             .replace(&cmd_echo_single, "")
             .replace(&cmd_echo_double, "")
     }
@@ -1063,6 +1632,7 @@ impl SSHClient {
         }
         session_data.recording_start_marker = None;
         session_data.recording_completion_marker = None;
+        session_data.recording_command = None;
         session_data.last_completion_check_state = None;
         session_data.last_completion_probe_recorder_len = 0;
         session_data.prompt_match_streak = 0;
@@ -1105,7 +1675,17 @@ impl SSHClient {
                 .recording_completion_marker
                 .as_ref()
                 .is_some_and(|marker| new_content.contains(marker));
-            if marker_completed {
+            // Shell integration wins over the marker: the shell reported the command finished
+            // (OSC 133 D). Marker commands are stripped from the echo, so the first finished
+            // command with a command line is the recorded one.
+            let shell_finished =
+                Self::shell_completion(&session_data.shell, session_data.recording_shell_offset);
+            if let Some(exit_code) = shell_finished {
+                session_data.prompt_match_streak = 0;
+                session_data.command_finished = true;
+                session_data.last_exit_code = exit_code;
+                CompletionCheckState::CompletedByShellIntegration
+            } else if marker_completed {
                 session_data.prompt_match_streak = 0;
                 CompletionCheckState::CompletedByMarker
             } else if let Some(recorded_prompt) = session_data.recording_prompt.clone() {
@@ -1247,6 +1827,7 @@ impl SSHClient {
                 "Connection lost".to_string()
             })?;
 
+        recording::record_input(session_id, input).await;
         Ok(())
     }
 
@@ -1257,34 +1838,116 @@ impl SSHClient {
         let mut session_data = arc.lock().await;
         if let Some(recorder) = session_data.command_recorder.as_mut() {
             recorder.push_str(data);
+            // Finished here rather than with the display output, which can be flushed after
+            // the caller already stopped the recording.
+            if let Some(record) = Self::take_finished_recording_command(&mut session_data) {
+                session_data.finished_recording_command = Some(record);
+            }
         }
         Ok(())
     }
 
-    /// Update the terminal buffer with new data
-    pub async fn update_terminal_buffer(session_id: &str, data: &str) -> Result<String, String> {
-        const MAX_BUFFER_SIZE: usize = 100_000;
-
+    /// Update the terminal buffer with new data.
+    pub async fn update_terminal_buffer(
+        session_id: &str,
+        data: &str,
+    ) -> Result<TerminalUpdate, String> {
         let arc = get_session_arc(session_id).ok_or_else(|| "Session not found".to_string())?;
         let mut session_data = arc.lock().await;
         let mut display_data = data.to_string();
         for marker in session_data.recent_completion_markers.iter() {
             display_data = Self::strip_completion_marker_artifacts(&display_data, marker);
         }
-        session_data.terminal_buffer.push_str(&display_data);
+        Self::push_terminal_output(&mut session_data.terminal_buffer, &display_data);
+        let events = session_data.shell.feed(&display_data);
+
+        Ok(TerminalUpdate {
+            display: display_data,
+            events,
+            recorded_command: session_data.finished_recording_command.take(),
+        })
+    }
+
+    /// The recorded command once its completion marker arrived. Shells with integration report
+    /// it with their own marks, so it is dropped for them.
+    fn take_finished_recording_command(session_data: &mut SessionData) -> Option<CommandRecord> {
+        let exit_code = Self::marker_exit_code(
+            session_data.command_recorder.as_deref()?,
+            session_data.recording_completion_marker.as_deref()?,
+        )?;
+        let mut record = session_data.recording_command.take()?;
+        if session_data.shell.is_detected() {
+            return None;
+        }
+        record.output_end = Some(session_data.shell.offset());
+        record.exit_code = exit_code;
+        record.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        Some(record)
+    }
 
+    fn push_terminal_output(buffer: &mut String, data: &str) {
+        buffer.push_str(data);
         // 仅在超出阈值时原地 drain 头部，避免分配 + 全量拷贝（最多 100KB）
-        if session_data.terminal_buffer.len() > MAX_BUFFER_SIZE {
-            let mut cut_off = session_data.terminal_buffer.len() - MAX_BUFFER_SIZE;
-            while cut_off < session_data.terminal_buffer.len()
-                && !session_data.terminal_buffer.is_char_boundary(cut_off)
-            {
+        if buffer.len() > MAX_BUFFER_SIZE {
+            let mut cut_off = buffer.len() - MAX_BUFFER_SIZE;
+            while cut_off < buffer.len() && !buffer.is_char_boundary(cut_off) {
                 cut_off += 1;
             }
-            session_data.terminal_buffer.drain(..cut_off);
+            buffer.drain(..cut_off);
         }
+    }
+
+    /// Exit code of the first command shell integration saw finish since `offset`; `Some(None)`
+    /// when it finished without reporting one.
+    fn shell_completion(shell: &ShellIntegration, offset: u64) -> Option<Option<i32>> {
+        shell.finished_since(offset).map(|record| record.exit_code)
+    }
 
-        Ok(display_data)
+    /// Commands seen through shell integration, oldest first, with the running one last.
+    pub async fn get_command_history(session_id: &str) -> Result<Vec<CommandRecord>, String> {
+        let arc = get_session_arc(session_id).ok_or_else(|| "Session not found".to_string())?;
+        let data = arc.lock().await;
+        Ok(data.shell.history())
+    }
+
+    /// Which stored command history belongs to the session's server.
+    pub async fn history_scope(session_id: &str) -> Result<HistoryScope, String> {
+        let arc = get_session_arc(session_id).ok_or_else(|| "Session not found".to_string())?;
+        let data = arc.lock().await;
+        Ok(HistoryScope {
+            server_id: data.config.server_id.clone(),
+            host: Some(data.config.host.clone()),
+        })
+    }
+
+    /// Output of a shell integration command, while it is still in the terminal buffer.
+    pub async fn get_command_output(session_id: &str, command_id: u64) -> Result<String, String> {
+        let arc = get_session_arc(session_id).ok_or_else(|| "Session not found".to_string())?;
+        let data = arc.lock().await;
+        Self::command_output(&data.shell, &data.terminal_buffer, command_id)
+    }
+
+    fn command_output(
+        shell: &ShellIntegration,
+        terminal_buffer: &str,
+        command_id: u64,
+    ) -> Result<String, String> {
+        let record = shell
+            .find(command_id)
+            .ok_or_else(|| "Command not found".to_string())?;
+        // The buffer holds the last `len` bytes of the stream the offsets count.
+        let buffer_start = shell.offset() - terminal_buffer.len() as u64;
+        if record.output_start < buffer_start {
+            return Err("Command output is no longer in the terminal buffer".to_string());
+        }
+        let start = (record.output_start - buffer_start) as usize;
+        let end = record
+            .output_end
+            .map_or(terminal_buffer.len(), |end| (end - buffer_start) as usize);
+        let output = terminal_buffer
+            .get(start..end)
+            .ok_or_else(|| "Command output is no longer in the terminal buffer".to_string())?;
+        Ok(strip_ansi_escapes::strip_str(output))
     }
 
     pub async fn update_system_info(session_id: &str, info: SystemInfo) -> Result<(), String> {
@@ -1300,194 +1963,15 @@ impl SSHClient {
         data.system_info.clone()
     }
 
-    pub async fn gather_system_info(params: ConnectParams) -> Result<SystemInfo, String> {
-        let config = Self::build_client_config();
-        let handler = ClientHandler::new();
-
-        // Keep route selection consistent with establish_connection:
-        // with jumphost configured, never try direct proxy-to-target first.
-        let proxy_for_direct_target = if params.jumphost.is_some() {
-            None
-        } else {
-            params.proxy.as_ref()
-        };
-
-        let mut session = if let Some(p) = proxy_for_direct_target {
-            if p.proxy_type == "socks5" {
-                use tokio_socks::tcp::Socks5Stream;
-                let has_auth = p.username.as_ref().map(|u| !u.is_empty()).unwrap_or(false);
-                let stream = if has_auth {
-                    let u = p.username.as_ref().unwrap();
-                    let p_auth = p.password.as_ref().map(|s| s.as_str()).unwrap_or("");
-                    Socks5Stream::connect_with_password(
-                        (p.host.as_str(), p.port),
-                        (&params.host[..], params.port),
-                        u,
-                        p_auth,
-                    )
-                    .await
-                    .map_err(|e| format!("SOCKS5 proxy error: {}", e))?
-                } else {
-                    Socks5Stream::connect(
-                        (p.host.as_str(), p.port),
-                        (&params.host[..], params.port),
-                    )
-                    .await
-                    .map_err(|e| format!("SOCKS5 proxy error: {}", e))?
-                };
-                client::connect_stream(config, stream, handler).await
-            } else if p.proxy_type == "http" {
-                use tokio::io::{AsyncReadExt, AsyncWriteExt};
-                let mut stream = tokio::net::TcpStream::connect((p.host.as_str(), p.port))
-                    .await
-                    .map_err(|e| format!("Failed to connect to HTTP proxy: {}", e))?;
-
-                let mut auth_header = String::new();
-                if let Some(u) = &p.username {
-                    if !u.is_empty() {
-                        let p_auth = p.password.as_ref().map(|s| s.as_str()).unwrap_or("");
-                        let auth_str = format!("{}:{}", u, p_auth);
-                        let encoded = BASE64_STANDARD.encode(auth_str);
-                        auth_header = format!("\r\nProxy-Authorization: Basic {}", encoded);
-                    }
-                }
-
-                let connect_req = format!(
-                    "CONNECT {}:{} HTTP/1.1\r\nHost: {}:{} {}\r\n\r\n",
-                    params.host, params.port, params.host, params.port, auth_header
-                );
-                stream
-                    .write_all(connect_req.as_bytes())
-                    .await
-                    .map_err(|e| e.to_string())?;
-
-                let mut response = [0u8; 4096];
-                let n = stream
-                    .read(&mut response)
-                    .await
-                    .map_err(|e| e.to_string())?;
-                let response_text = String::from_utf8_lossy(&response[..n]);
-                if !response_text.contains("200 Connection established")
-                    && !response_text.contains("200 OK")
-                {
-                    return Err(format!("HTTP proxy returned error: {}", response_text));
-                }
-                client::connect_stream(config, stream, handler).await
-            } else {
-                return Err(format!("Unsupported proxy type: {}", p.proxy_type));
-            }
-        } else if let Some(j) = &params.jumphost {
-            let jh_handler = ClientHandler::new();
-            let mut jh_session = if let Some(p) = &params.proxy {
-                if p.proxy_type == "socks5" {
-                    use tokio_socks::tcp::Socks5Stream;
-                    let has_auth = p.username.as_ref().map(|u| !u.is_empty()).unwrap_or(false);
-                    let stream = if has_auth {
-                        let u = p.username.as_ref().unwrap();
-                        let p_auth = p.password.as_ref().map(|s| s.as_str()).unwrap_or("");
-                        Socks5Stream::connect_with_password(
-                            (p.host.as_str(), p.port),
-                            (&j.host[..], j.port),
-                            u,
-                            p_auth,
-                        )
-                        .await
-                        .map_err(|e| {
-                            format!("SOCKS5 proxy error when connecting to jumphost: {}", e)
-                        })?
-                    } else {
-                        Socks5Stream::connect((p.host.as_str(), p.port), (&j.host[..], j.port))
-                            .await
-                            .map_err(|e| {
-                                format!("SOCKS5 proxy error when connecting to jumphost: {}", e)
-                            })?
-                    };
-                    client::connect_stream(config.clone(), stream, jh_handler)
-                        .await
-                        .map_err(|e| format!("Failed to connect to jumphost via SOCKS5: {}", e))?
-                } else if p.proxy_type == "http" {
-                    use tokio::io::{AsyncReadExt, AsyncWriteExt};
-                    let mut stream = tokio::net::TcpStream::connect((p.host.as_str(), p.port))
-                        .await
-                        .map_err(|e| format!("Failed to connect to HTTP proxy: {}", e))?;
-
-                    let mut auth_header = String::new();
-                    if let Some(u) = &p.username {
-                        if !u.is_empty() {
-                            let p_auth = p.password.as_ref().map(|s| s.as_str()).unwrap_or("");
-                            let auth_str = format!("{}:{}", u, p_auth);
-                            let encoded = BASE64_STANDARD.encode(auth_str);
-                            auth_header = format!("\r\nProxy-Authorization: Basic {}", encoded);
-                        }
-                    }
-
-                    let connect_req = format!(
-                        "CONNECT {}:{} HTTP/1.1\r\nHost: {}:{} {}\r\n\r\n",
-                        j.host, j.port, j.host, j.port, auth_header
-                    );
-                    stream
-                        .write_all(connect_req.as_bytes())
-                        .await
-                        .map_err(|e| e.to_string())?;
-
-                    let mut response = [0u8; 4096];
-                    let n = stream
-                        .read(&mut response)
-                        .await
-                        .map_err(|e| e.to_string())?;
-                    let response_text = String::from_utf8_lossy(&response[..n]);
-                    if !response_text.contains("200 Connection established")
-                        && !response_text.contains("200 OK")
-                    {
-                        return Err(format!(
-                            "HTTP proxy returned error when connecting to jumphost: {}",
-                            response_text
-                        ));
-                    }
-                    client::connect_stream(config.clone(), stream, jh_handler)
-                        .await
-                        .map_err(|e| {
-                            format!("Failed to connect to jumphost via HTTP proxy: {}", e)
-                        })?
-                } else {
-                    return Err(format!("Unsupported proxy type: {}", p.proxy_type));
-                }
-            } else {
-                client::connect(config.clone(), (j.host.as_str(), j.port), jh_handler)
-                    .await
-                    .map_err(|e| format!("Failed to connect to jumphost: {}", e))?
-            };
-
-            Self::authenticate_session(
-                &mut jh_session,
-                &j.username,
-                j.password.clone(),
-                j.private_key.clone(),
-                j.passphrase.clone(),
-            )
+    /// Probes OS, distro and login shell over the session's existing connection. A second
+    /// login would replay one-time codes and prompt again.
+    pub async fn gather_system_info(
+        session_id: &str,
+        params: &ConnectParams,
+    ) -> Result<SystemInfo, String> {
+        let session = Self::get_session_handle(session_id)
             .await
-            .map_err(|e| format!("Jumphost authentication failed: {}", e))?;
-
-            let channel = jh_session
-                .channel_open_direct_tcpip(&params.host, params.port as u32, "127.0.0.1", 22222)
-                .await
-                .map_err(|e| format!("Failed to open direct-tcpip through jumphost: {}", e))?;
-
-            client::connect_stream(config, channel.into_stream(), handler).await
-        } else {
-            client::connect(config, (&params.host[..], params.port), handler).await
-        }
-        .map_err(|e| e.to_string())?;
-
-        Self::authenticate_session(
-            &mut session,
-            &params.username,
-            params.password.clone(),
-            params.private_key.clone(),
-            params.passphrase.clone(),
-        )
-        .await?;
-
+            .ok_or_else(|| "Session not found".to_string())?;
         let mut channel = session
             .channel_open_session()
             .await
@@ -1524,6 +2008,8 @@ impl SSHClient {
                 }
             }
         }
+        // The probe shares the terminal's connection, so don't leave its channel open.
+        let _ = channel.close().await;
 
         let mut info = SystemInfo {
             os: "Unknown".to_string(),
@@ -1566,3 +2052,139 @@ impl SSHClient {
         Ok(info)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn osc(body: &str) -> String {
+        format!("\x1b]{}\x07", body)
+    }
+
+    /// Feed output the way `update_terminal_buffer` does.
+    fn feed(
+        shell: &mut ShellIntegration,
+        buffer: &mut String,
+        data: &str,
+    ) -> Vec<ShellCommandEvent> {
+        SSHClient::push_terminal_output(buffer, data);
+        shell.feed(data)
+    }
+
+    fn start(shell: &mut ShellIntegration, buffer: &mut String, command: &str) -> u64 {
+        let prompt = format!(
+            "{}$ {}{}\n{}",
+            osc("133;A"),
+            osc("133;B"),
+            command,
+            osc("133;C")
+        );
+        feed(shell, buffer, &prompt)[0].command.id
+    }
+
+    fn run(shell: &mut ShellIntegration, buffer: &mut String, command: &str, output: &str) -> u64 {
+        let id = start(shell, buffer, command);
+        feed(shell, buffer, output);
+        feed(shell, buffer, &osc("133;D;0"));
+        id
+    }
+
+    #[test]
+    fn command_output_follows_the_rolling_buffer_and_reconnects() {
+        let (mut shell, mut buffer) = (ShellIntegration::default(), String::new());
+        let first = run(&mut shell, &mut buffer, "ls", "total 0\n");
+        assert_eq!(
+            SSHClient::command_output(&shell, &buffer, first).unwrap(),
+            "total 0\n"
+        );
+
+        // Multi-byte output drains the buffer past both the first command and its own start.
+        let large = run(
+            &mut shell,
+            &mut buffer,
+            "cat big",
+            &"é".repeat(MAX_BUFFER_SIZE / 2),
+        );
+        let last = run(&mut shell, &mut buffer, "pwd", "/srv\n");
+        assert!(buffer.len() <= MAX_BUFFER_SIZE);
+        assert!(shell.offset() > MAX_BUFFER_SIZE as u64);
+        assert!(SSHClient::command_output(&shell, &buffer, first).is_err());
+        assert!(SSHClient::command_output(&shell, &buffer, large).is_err());
+        assert_eq!(
+            SSHClient::command_output(&shell, &buffer, last).unwrap(),
+            "/srv\n"
+        );
+
+        // A reconnect clears the buffer but keeps the history and the stream offset.
+        let interrupted = start(&mut shell, &mut buffer, "tail -f log");
+        feed(&mut shell, &mut buffer, "line\n");
+        buffer.clear();
+        shell.reset_connection();
+        assert!(SSHClient::command_output(&shell, &buffer, last).is_err());
+        assert!(SSHClient::command_output(&shell, &buffer, interrupted).is_err());
+        let after = run(&mut shell, &mut buffer, "uptime", "up 1 day\n");
+        assert_eq!(
+            SSHClient::command_output(&shell, &buffer, after).unwrap(),
+            "up 1 day\n"
+        );
+        assert!(SSHClient::command_output(&shell, &buffer, after + 1).is_err());
+    }
+
+    #[test]
+    fn buffer_is_cut_at_a_char_boundary() {
+        let mut buffer = "é".to_string();
+        SSHClient::push_terminal_output(&mut buffer, &"a".repeat(MAX_BUFFER_SIZE - 1));
+        assert_eq!(buffer.len(), MAX_BUFFER_SIZE - 1);
+        assert!(buffer.bytes().all(|b| b == b'a'));
+    }
+
+    #[test]
+    fn shell_completion_only_counts_commands_after_the_recording_started() {
+        let (mut shell, mut buffer) = (ShellIntegration::default(), String::new());
+        run(&mut shell, &mut buffer, "true", "");
+        let recording_offset = shell.offset();
+        assert_eq!(SSHClient::shell_completion(&shell, recording_offset), None);
+
+        start(&mut shell, &mut buffer, "false");
+        assert_eq!(SSHClient::shell_completion(&shell, recording_offset), None);
+        feed(&mut shell, &mut buffer, &osc("133;D;1"));
+        assert_eq!(
+            SSHClient::shell_completion(&shell, recording_offset),
+            Some(Some(1))
+        );
+
+        // A shell that only marks prompts finishes the command without an exit code.
+        let recording_offset = shell.offset();
+        start(&mut shell, &mut buffer, "make");
+        feed(&mut shell, &mut buffer, &osc("133;A"));
+        assert_eq!(
+            SSHClient::shell_completion(&shell, recording_offset),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn completion_marker_carries_the_exit_status() {
+        assert_eq!(SSHClient::marker_exit_code("output", "M1"), None);
+        assert_eq!(
+            SSHClient::marker_exit_code(&format!("out{}", osc("633;M1;2")), "M1"),
+            Some(Some(2))
+        );
+        // The status is only trusted once its terminator arrived.
+        assert_eq!(SSHClient::marker_exit_code("out\x1b]633;M1;12", "M1"), None);
+        assert_eq!(
+            SSHClient::marker_exit_code(&osc("633;M1"), "M1"),
+            Some(None)
+        );
+    }
+
+    #[test]
+    fn completion_marker_and_its_echo_are_stripped() {
+        let echo = SSHClient::completion_marker_command("M1", "$status");
+        let recorded = format!("{}\nhello\n{}$ ", echo, osc("633;M1;0"));
+        assert_eq!(
+            SSHClient::strip_completion_marker_artifacts(&recorded, "M1"),
+            "\nhello\n$ "
+        );
+    }
+}
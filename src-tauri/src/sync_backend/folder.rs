@@ -0,0 +1,279 @@
+// Plain local or network folder backend.
+//
+// The version token is the SHA-256 of the file content. Writers serialize through a
+// `<name>.lock` file created with `create_new` (atomic on local disks as well as SMB and NFS
+// shares), re-check the precondition while holding it, then replace the document through a
+// temporary file and rename so readers never observe a partial write.
+
+use super::{blocking, DownloadedResource, SyncBackend, SyncBackendError, UploadCondition};
+use crate::config::sync_protocol::{hex_sha256, sync_account_key};
+use futures::future::BoxFuture;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+const LOCK_WAIT: Duration = Duration::from_secs(10);
+const LOCK_POLL: Duration = Duration::from_millis(100);
+/// A lock older than this was left behind by a crashed writer; a live write takes milliseconds.
+const STALE_LOCK_AGE: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone)]
+pub struct FolderBackend {
+    root: PathBuf,
+}
+
+impl FolderBackend {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn read(&self, name: &str) -> Result<Option<DownloadedResource>, SyncBackendError> {
+        match fs::read(self.root.join(name)) {
+            Ok(content) => {
+                let etag = hex_sha256(&content);
+                Ok(Some(DownloadedResource {
+                    content,
+                    etag: Some(etag),
+                }))
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(io_error("read", name, error)),
+        }
+    }
+
+    fn write(
+        &self,
+        name: &str,
+        content: &[u8],
+        condition: &UploadCondition,
+    ) -> Result<Option<String>, SyncBackendError> {
+        if !self.root.is_dir() {
+            return Err(SyncBackendError::Unavailable(format!(
+                "Sync folder {} does not exist",
+                self.root.display()
+            )));
+        }
+
+        let _lock = FolderLock::acquire(&self.root.join(format!("{name}.lock")))?;
+        let current = self.read(name)?.and_then(|document| document.etag);
+        let condition_holds = match (condition, current.as_deref()) {
+            (UploadCondition::CreateOnly, None) => true,
+            (UploadCondition::IfMatch(expected), Some(current)) => expected == current,
+            _ => false,
+        };
+        if !condition_holds {
+            return Err(SyncBackendError::PreconditionFailed);
+        }
+
+        let temp = self
+            .root
+            .join(format!(".{name}.{}.tmp", uuid::Uuid::new_v4()));
+        let written = File::create(&temp)
+            .and_then(|mut file| {
+                file.write_all(content)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&temp, self.root.join(name)));
+        if let Err(error) = written {
+            let _ = fs::remove_file(&temp);
+            return Err(io_error("write", name, error));
+        }
+        Ok(Some(hex_sha256(content)))
+    }
+}
+
+impl SyncBackend for FolderBackend {
+    fn account_key(&self) -> String {
+        sync_account_key(&format!("file://{}", self.root.display()), "")
+    }
+
+    fn download<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<DownloadedResource>, SyncBackendError>> {
+        let backend = self.clone();
+        let name = name.to_string();
+        Box::pin(blocking(move || backend.read(&name)))
+    }
+
+    fn upload_conditionally<'a>(
+        &'a self,
+        name: &'a str,
+        content: &'a [u8],
+        condition: UploadCondition,
+    ) -> BoxFuture<'a, Result<Option<String>, SyncBackendError>> {
+        let backend = self.clone();
+        let name = name.to_string();
+        let content = content.to_vec();
+        Box::pin(blocking(move || backend.write(&name, &content, &condition)))
+    }
+}
+
+/// Exclusive writer lock, released when dropped. The file holds a random token so a writer
+/// only ever removes the lock it created itself.
+struct FolderLock {
+    path: PathBuf,
+    token: String,
+}
+
+impl FolderLock {
+    fn acquire(path: &Path) -> Result<Self, SyncBackendError> {
+        let deadline = Instant::now() + LOCK_WAIT;
+        let token = format!("{} {}", std::process::id(), uuid::Uuid::new_v4());
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(path) {
+                Ok(mut file) => {
+                    let _ = writeln!(file, "{token}");
+                    return Ok(Self {
+                        path: path.to_path_buf(),
+                        token,
+                    });
+                }
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => {
+                    if lock_is_stale(path) {
+                        reclaim_stale_lock(path);
+                        continue;
+                    }
+                    if Instant::now() >= deadline {
+                        return Err(SyncBackendError::Unavailable(format!(
+                            "Sync folder is locked by another writer ({})",
+                            path.display()
+                        )));
+                    }
+                    std::thread::sleep(LOCK_POLL);
+                }
+                Err(error) => {
+                    return Err(SyncBackendError::Unavailable(format!(
+                        "Could not lock sync folder ({}): {error}",
+                        path.display()
+                    )));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for FolderLock {
+    fn drop(&mut self) {
+        let owned = fs::read_to_string(&self.path)
+            .map(|content| content.trim() == self.token)
+            .unwrap_or(false);
+        if owned {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn lock_is_stale(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age > STALE_LOCK_AGE)
+}
+
+/// Takes a stale lock out of the way atomically. Renaming to a unique name succeeds for only
+/// one writer; if another writer replaced the stale lock with a fresh one between our check
+/// and the rename, the renamed file is fresh and is linked back instead of deleted.
+fn reclaim_stale_lock(path: &Path) {
+    let mut claimed = path.as_os_str().to_owned();
+    claimed.push(format!(".{}.stale", uuid::Uuid::new_v4()));
+    let claimed = PathBuf::from(claimed);
+    if fs::rename(path, &claimed).is_err() {
+        // Someone else reclaimed it first.
+        return;
+    }
+    if lock_is_stale(&claimed) {
+        tracing::warn!("Removed stale sync folder lock {}", path.display());
+    } else if let Err(error) = fs::hard_link(&claimed, path) {
+        tracing::warn!(
+            "Could not restore sync folder lock {} taken over by mistake: {error}",
+            path.display()
+        );
+    }
+    let _ = fs::remove_file(&claimed);
+}
+
+fn io_error(action: &str, name: &str, error: io::Error) -> SyncBackendError {
+    SyncBackendError::Unavailable(format!("Could not {action} {name} in sync folder: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn writes_are_conditional_on_the_content_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = FolderBackend::new(dir.path());
+        assert_eq!(backend.download("sync.json").await.unwrap(), None);
+
+        let first = backend
+            .upload_conditionally("sync.json", b"one", UploadCondition::CreateOnly)
+            .await
+            .unwrap()
+            .expect("version token");
+        assert_eq!(
+            backend
+                .upload_conditionally("sync.json", b"two", UploadCondition::CreateOnly)
+                .await,
+            Err(SyncBackendError::PreconditionFailed)
+        );
+
+        let downloaded = backend.download("sync.json").await.unwrap().unwrap();
+        assert_eq!(downloaded.content, b"one");
+        assert_eq!(downloaded.etag.as_deref(), Some(first.as_str()));
+
+        assert_eq!(
+            backend
+                .upload_conditionally(
+                    "sync.json",
+                    b"two",
+                    UploadCondition::IfMatch("stale".into())
+                )
+                .await,
+            Err(SyncBackendError::PreconditionFailed)
+        );
+        backend
+            .upload_conditionally("sync.json", b"two", UploadCondition::IfMatch(first))
+            .await
+            .unwrap();
+        assert_eq!(fs::read(dir.path().join("sync.json")).unwrap(), b"two");
+        assert!(!dir.path().join("sync.json.lock").exists());
+    }
+
+    #[tokio::test]
+    async fn stale_lock_left_by_a_crashed_writer_is_reclaimed() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = File::create(dir.path().join("sync.json.lock")).unwrap();
+        lock.set_modified(SystemTime::now() - STALE_LOCK_AGE * 2)
+            .unwrap();
+        drop(lock);
+
+        FolderBackend::new(dir.path())
+            .upload_conditionally("sync.json", b"{}", UploadCondition::CreateOnly)
+            .await
+            .unwrap();
+        assert!(!dir.path().join("sync.json.lock").exists());
+    }
+
+    #[test]
+    fn reclaiming_never_removes_a_fresh_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sync.json.lock");
+        fs::write(&path, "other-writer\n").unwrap();
+
+        // Another writer re-created the lock after we judged the old one stale.
+        reclaim_stale_lock(&path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "other-writer\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // A lock we do not own survives our drop.
+        drop(FolderLock {
+            path: path.clone(),
+            token: "ours".into(),
+        });
+        assert!(path.exists());
+    }
+}
@@ -0,0 +1,351 @@
+// Git repository backend.
+//
+// Documents live at the root of one branch. The local cache is a bare repository under the app
+// data dir: reads fetch the branch and use the file's blob id as its version token, writes build
+// a commit on top of the fetched tip without a working tree and push it without `--force`. A
+// rejected push therefore means another device pushed first, which maps to
+// `PreconditionFailed`. Authentication is left to the user's git setup (SSH agent, credential
+// helper), so no secret is stored for this backend.
+
+use super::{blocking, DownloadedResource, SyncBackend, SyncBackendError, UploadCondition};
+use crate::config::sync_protocol::sync_account_key;
+use futures::future::BoxFuture;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+pub const DEFAULT_BRANCH: &str = "main";
+/// Local ref the fetched branch tip is stored under inside the cache repository.
+const TIP_REF: &str = "refs/resh/sync-tip";
+
+#[derive(Debug, Clone)]
+pub struct GitBackend {
+    remote: String,
+    branch: String,
+    repo_dir: PathBuf,
+}
+
+impl GitBackend {
+    pub fn new(remote: &str, branch: &str, cache_root: &Path) -> Self {
+        let branch = match branch.trim() {
+            "" => DEFAULT_BRANCH,
+            branch => branch,
+        };
+        let remote = remote.trim();
+        let repo_dir = cache_root.join(&sync_account_key(remote, branch)[..16]);
+        Self {
+            remote: remote.to_string(),
+            branch: branch.to_string(),
+            repo_dir,
+        }
+    }
+
+    fn branch_ref(&self) -> String {
+        format!("refs/heads/{}", self.branch)
+    }
+
+    fn read(&self, name: &str) -> Result<Option<DownloadedResource>, SyncBackendError> {
+        self.ensure_repo()?;
+        let Some(tip) = self.fetch_tip()? else {
+            return Ok(None);
+        };
+        let Some(blob) = self.blob_id(&tip, name)? else {
+            return Ok(None);
+        };
+        let content = self.git(&["cat-file", "blob", &blob], None, None)?.stdout;
+        Ok(Some(DownloadedResource {
+            content,
+            etag: Some(blob),
+        }))
+    }
+
+    fn write(
+        &self,
+        name: &str,
+        content: &[u8],
+        condition: &UploadCondition,
+    ) -> Result<Option<String>, SyncBackendError> {
+        self.ensure_repo()?;
+        let tip = self.fetch_tip()?;
+        let current = match &tip {
+            Some(tip) => self.blob_id(tip, name)?,
+            None => None,
+        };
+        let condition_holds = match (condition, current.as_deref()) {
+            (UploadCondition::CreateOnly, None) => true,
+            (UploadCondition::IfMatch(expected), Some(current)) => expected == current,
+            _ => false,
+        };
+        if !condition_holds {
+            return Err(SyncBackendError::PreconditionFailed);
+        }
+
+        let blob = stdout_line(self.git(&["hash-object", "-w", "--stdin"], Some(content), None)?);
+        // A private index keeps concurrent writes from sharing staging state.
+        let index = self
+            .repo_dir
+            .join(format!("resh-index-{}", uuid::Uuid::new_v4()));
+        let commit = self.commit_with_index(&index, tip.as_deref(), name, &blob);
+        let _ = std::fs::remove_file(&index);
+        let commit = commit?;
+
+        let refspec = format!("{commit}:{}", self.branch_ref());
+        let output = self.run(&["push", "-q", &self.remote, &refspec], None, None)?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if [
+                "[rejected]",
+                "non-fast-forward",
+                "fetch first",
+                "stale info",
+            ]
+            .iter()
+            .any(|marker| stderr.contains(marker))
+            {
+                return Err(SyncBackendError::PreconditionFailed);
+            }
+            return Err(self.command_error("push", &output));
+        }
+        Ok(Some(blob))
+    }
+
+    fn commit_with_index(
+        &self,
+        index: &Path,
+        parent: Option<&str>,
+        name: &str,
+        blob: &str,
+    ) -> Result<String, SyncBackendError> {
+        match parent {
+            Some(parent) => self.git(&["read-tree", parent], None, Some(index))?,
+            None => self.git(&["read-tree", "--empty"], None, Some(index))?,
+        };
+        let cacheinfo = format!("100644,{blob},{name}");
+        self.git(
+            &["update-index", "--add", "--cacheinfo", &cacheinfo],
+            None,
+            Some(index),
+        )?;
+        let tree = stdout_line(self.git(&["write-tree"], None, Some(index))?);
+
+        let message = format!("Update {name} from Resh");
+        let mut args = vec!["commit-tree", tree.as_str(), "-m", message.as_str()];
+        if let Some(parent) = parent {
+            args.extend(["-p", parent]);
+        }
+        Ok(stdout_line(self.git(&args, None, None)?))
+    }
+
+    fn ensure_repo(&self) -> Result<(), SyncBackendError> {
+        if self.remote.is_empty() || self.remote.starts_with('-') {
+            return Err(SyncBackendError::Unavailable(
+                "Git sync remote must be a repository URL or path".to_string(),
+            ));
+        }
+        if self.repo_dir.join("HEAD").is_file() {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.repo_dir).map_err(|error| {
+            SyncBackendError::Unavailable(format!("Could not create git sync cache: {error}"))
+        })?;
+        let output = git_command()
+            .args(["init", "-q", "--bare"])
+            .arg(&self.repo_dir)
+            .output()
+            .map_err(spawn_error)?;
+        if !output.status.success() {
+            return Err(self.command_error("init", &output));
+        }
+        Ok(())
+    }
+
+    /// Fetch the sync branch and return its tip, or `None` when the branch does not exist yet.
+    fn fetch_tip(&self) -> Result<Option<String>, SyncBackendError> {
+        let refspec = format!("+{}:{TIP_REF}", self.branch_ref());
+        let output = self.run(
+            &["fetch", "-q", "--no-tags", &self.remote, &refspec],
+            None,
+            None,
+        )?;
+        if !output.status.success() {
+            if String::from_utf8_lossy(&output.stderr).contains("couldn't find remote ref") {
+                return Ok(None);
+            }
+            return Err(self.command_error("fetch", &output));
+        }
+        Ok(Some(stdout_line(self.git(
+            &["rev-parse", "--verify", TIP_REF],
+            None,
+            None,
+        )?)))
+    }
+
+    fn blob_id(&self, tip: &str, name: &str) -> Result<Option<String>, SyncBackendError> {
+        let object = format!("{tip}:{name}");
+        let output = self.run(&["rev-parse", "--verify", "-q", &object], None, None)?;
+        Ok(output.status.success().then(|| stdout_line(output)))
+    }
+
+    /// Run git against the cache repository and fail on a non-zero exit.
+    fn git(
+        &self,
+        args: &[&str],
+        stdin: Option<&[u8]>,
+        index: Option<&Path>,
+    ) -> Result<Output, SyncBackendError> {
+        let output = self.run(args, stdin, index)?;
+        if output.status.success() {
+            Ok(output)
+        } else {
+            Err(self.command_error(args[0], &output))
+        }
+    }
+
+    fn run(
+        &self,
+        args: &[&str],
+        stdin: Option<&[u8]>,
+        index: Option<&Path>,
+    ) -> Result<Output, SyncBackendError> {
+        let mut command = git_command();
+        command.arg("--git-dir").arg(&self.repo_dir).args(args);
+        if let Some(index) = index {
+            command.env("GIT_INDEX_FILE", index);
+        }
+        if stdin.is_some() {
+            command.stdin(Stdio::piped());
+        }
+        let mut child = command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(spawn_error)?;
+        if let (Some(input), Some(mut pipe)) = (stdin, child.stdin.take()) {
+            pipe.write_all(input).map_err(|error| {
+                SyncBackendError::Unavailable(format!("Could not write to git: {error}"))
+            })?;
+        }
+        child.wait_with_output().map_err(spawn_error)
+    }
+
+    /// git output can echo the remote URL, which may embed credentials.
+    fn command_error(&self, action: &str, output: &Output) -> SyncBackendError {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = stderr
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("no output")
+            .replace(&self.remote, "<remote>");
+        SyncBackendError::Unavailable(format!("git {action} failed: {}", detail.trim()))
+    }
+}
+
+impl SyncBackend for GitBackend {
+    fn account_key(&self) -> String {
+        sync_account_key(&format!("git+{}#{}", self.remote, self.branch), "")
+    }
+
+    fn download<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<DownloadedResource>, SyncBackendError>> {
+        let backend = self.clone();
+        let name = name.to_string();
+        Box::pin(blocking(move || backend.read(&name)))
+    }
+
+    fn upload_conditionally<'a>(
+        &'a self,
+        name: &'a str,
+        content: &'a [u8],
+        condition: UploadCondition,
+    ) -> BoxFuture<'a, Result<Option<String>, SyncBackendError>> {
+        let backend = self.clone();
+        let name = name.to_string();
+        let content = content.to_vec();
+        Box::pin(blocking(move || backend.write(&name, &content, &condition)))
+    }
+}
+
+fn git_command() -> Command {
+    let mut command = Command::new("git");
+    // Never block on an interactive credential prompt, and keep messages parseable.
+    command
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("LC_ALL", "C")
+        .env("GIT_AUTHOR_NAME", "Resh")
+        .env("GIT_AUTHOR_EMAIL", "resh@localhost")
+        .env("GIT_COMMITTER_NAME", "Resh")
+        .env("GIT_COMMITTER_EMAIL", "resh@localhost")
+        .stdin(Stdio::null());
+    command
+}
+
+fn stdout_line(output: Output) -> String {
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+fn spawn_error(error: std::io::Error) -> SyncBackendError {
+    SyncBackendError::Unavailable(format!("Could not run git (is it installed?): {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn pushes_are_conditional_on_the_fetched_blob() {
+        if Command::new("git").arg("--version").output().is_err() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        assert!(Command::new("git")
+            .args(["init", "-q", "--bare"])
+            .arg(&remote)
+            .status()
+            .unwrap()
+            .success());
+        let remote = remote.to_string_lossy().to_string();
+        let device_a = GitBackend::new(&remote, "", &dir.path().join("a"));
+        let device_b = GitBackend::new(&remote, "", &dir.path().join("b"));
+
+        assert_eq!(device_a.download("sync.json").await.unwrap(), None);
+        let first = device_a
+            .upload_conditionally("sync.json", b"one", UploadCondition::CreateOnly)
+            .await
+            .unwrap()
+            .expect("blob id");
+        assert_eq!(
+            device_b
+                .upload_conditionally("sync.json", b"two", UploadCondition::CreateOnly)
+                .await,
+            Err(SyncBackendError::PreconditionFailed)
+        );
+
+        let seen_by_b = device_b.download("sync.json").await.unwrap().unwrap();
+        assert_eq!(seen_by_b.content, b"one");
+        assert_eq!(seen_by_b.etag.as_deref(), Some(first.as_str()));
+
+        device_b
+            .upload_conditionally("sync.json", b"two", UploadCondition::IfMatch(first.clone()))
+            .await
+            .unwrap();
+        assert_eq!(
+            device_a
+                .upload_conditionally("sync.json", b"three", UploadCondition::IfMatch(first))
+                .await,
+            Err(SyncBackendError::PreconditionFailed)
+        );
+        assert_eq!(
+            device_a
+                .download("sync.json")
+                .await
+                .unwrap()
+                .unwrap()
+                .content,
+            b"two"
+        );
+    }
+}
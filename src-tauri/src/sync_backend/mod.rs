@@ -0,0 +1,119 @@
+// Storage backends for config sync.
+//
+// `SyncManager` only needs two primitives from the remote side: read a named document together
+// with a version token, and write it back only if that token still matches. Every backend maps
+// its own optimistic-concurrency mechanism onto those primitives (HTTP ETags for WebDAV and S3,
+// a content hash checked under a lock file for folders, fast-forward-only pushes for git).
+
+pub mod folder;
+pub mod git;
+pub mod s3;
+
+use crate::config::types::{Proxy, WebDAVSettings};
+use futures::future::BoxFuture;
+use std::fmt;
+use std::path::Path;
+
+pub use folder::FolderBackend;
+pub use git::GitBackend;
+pub use s3::S3Backend;
+
+/// A downloaded document together with the version token observed while reading it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadedResource {
+    pub content: Vec<u8>,
+    pub etag: Option<String>,
+}
+
+/// Write precondition used to prevent a stale sync from silently overwriting the remote file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UploadCondition {
+    /// The file did not exist during GET, so only create it if it is still absent.
+    CreateOnly,
+    /// Replace only the exact entity tag returned by the preceding GET.
+    IfMatch(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncBackendError {
+    /// The remote document was created or replaced after it was read.
+    PreconditionFailed,
+    /// Transport, permission or server failure; the message never contains document content.
+    Unavailable(String),
+}
+
+impl fmt::Display for SyncBackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PreconditionFailed => f.write_str("Remote resource changed concurrently"),
+            Self::Unavailable(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for SyncBackendError {}
+
+/// Conditional get/put over the remote sync location.
+pub trait SyncBackend: Send + Sync {
+    /// Stable identity of the remote location, used to key the local sync baseline.
+    fn account_key(&self) -> String;
+
+    fn download<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<DownloadedResource>, SyncBackendError>>;
+
+    /// Store `content` under `name` if `condition` still holds and return the new version token.
+    fn upload_conditionally<'a>(
+        &'a self,
+        name: &'a str,
+        content: &'a [u8],
+        condition: UploadCondition,
+    ) -> BoxFuture<'a, Result<Option<String>, SyncBackendError>>;
+}
+
+/// Build the backend selected in the sync settings. Secrets in `settings` must already be
+/// revealed; `app_data_dir` holds local working state such as the git checkout.
+pub fn from_settings(
+    settings: &WebDAVSettings,
+    proxy: Option<Proxy>,
+    app_data_dir: &Path,
+) -> Result<Box<dyn SyncBackend>, String> {
+    let location = settings.url.trim();
+    if location.is_empty() {
+        return Err("Sync location is empty".to_string());
+    }
+    Ok(match settings.backend.as_str() {
+        "webdav" | "" => Box::new(crate::webdav::WebDAVClient::new(
+            settings.url.clone(),
+            settings.username.clone(),
+            settings.password.clone(),
+            proxy,
+        )),
+        "s3" => Box::new(S3Backend::new(
+            location,
+            &settings.region,
+            &settings.username,
+            &settings.password,
+            proxy.as_ref(),
+        )?),
+        "folder" => Box::new(FolderBackend::new(location)),
+        "git" => Box::new(GitBackend::new(
+            location,
+            &settings.branch,
+            &app_data_dir.join("sync-git"),
+        )),
+        other => return Err(format!("Unsupported sync backend '{}'", other)),
+    })
+}
+
+/// Run filesystem or subprocess work for a backend off the async runtime.
+async fn blocking<T, F>(work: F) -> Result<T, SyncBackendError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, SyncBackendError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(work).await.map_err(|error| {
+        SyncBackendError::Unavailable(format!("Sync backend task failed: {error}"))
+    })?
+}
@@ -1,4 +1,8 @@
+use crate::config::sync_protocol::sync_account_key;
 use crate::config::types::Proxy;
+pub use crate::sync_backend::{DownloadedResource, UploadCondition};
+use crate::sync_backend::{SyncBackend, SyncBackendError};
+use futures::future::BoxFuture;
 use reqwest::{
     header::{ETAG, IF_MATCH, IF_NONE_MATCH},
     Client, Method, StatusCode,
@@ -13,22 +17,6 @@ pub struct WebDAVClient {
     client: Client,
 }
 
-/// A downloaded WebDAV resource together with the entity tag observed during the GET or PROPFIND.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct DownloadedResource {
-    pub content: Vec<u8>,
-    pub etag: Option<String>,
-}
-
-/// Write precondition used to prevent a stale sync from silently overwriting the remote file.
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum UploadCondition {
-    /// The file did not exist during GET, so only create it if it is still absent.
-    CreateOnly,
-    /// Replace only the exact entity tag returned by the preceding GET.
-    IfMatch(String),
-}
-
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum WebDavError {
     Request,
@@ -48,6 +36,15 @@ impl fmt::Display for WebDavError {
 
 impl Error for WebDavError {}
 
+impl From<WebDavError> for SyncBackendError {
+    fn from(error: WebDavError) -> Self {
+        match error {
+            WebDavError::PreconditionFailed => Self::PreconditionFailed,
+            other => Self::Unavailable(other.to_string()),
+        }
+    }
+}
+
 impl WebDAVClient {
     pub fn new(base_url: String, username: String, password: String, proxy: Option<Proxy>) -> Self {
         let mut client_builder = Client::builder().user_agent("Resh/0.1.0");
@@ -202,6 +199,32 @@ impl WebDAVClient {
     }
 }
 
+impl SyncBackend for WebDAVClient {
+    fn account_key(&self) -> String {
+        sync_account_key(&self.base_url, &self.username)
+    }
+
+    fn download<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<Option<DownloadedResource>, SyncBackendError>> {
+        Box::pin(async move { WebDAVClient::download(self, name).await.map_err(Into::into) })
+    }
+
+    fn upload_conditionally<'a>(
+        &'a self,
+        name: &'a str,
+        content: &'a [u8],
+        condition: UploadCondition,
+    ) -> BoxFuture<'a, Result<Option<String>, SyncBackendError>> {
+        Box::pin(async move {
+            WebDAVClient::upload_conditionally(self, name, content, condition)
+                .await
+                .map_err(Into::into)
+        })
+    }
+}
+
 fn response_etag(headers: &reqwest::header::HeaderMap) -> Option<String> {
     headers
         .get(ETAG)
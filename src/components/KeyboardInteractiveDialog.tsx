@@ -0,0 +1,108 @@
+import React, { useEffect, useState } from "react"
+import { invoke } from "@tauri-apps/api/core"
+import { listen } from "@tauri-apps/api/event"
+import type { KeyboardInteractiveRequest } from "../types"
+import { useTranslation } from "../i18n"
+import { FormModal } from "./FormModal"
+
+const answerRequest = (requestId: string, answers: string[] | null) =>
+  invoke("answer_keyboard_interactive", { requestId, answers })
+
+/**
+ * Asks the user for keyboard-interactive answers the backend could not fill in
+ * from the stored password or TOTP secret (e.g. "Passcode or option" prompts).
+ */
+export const KeyboardInteractiveDialog: React.FC = () => {
+  const { t } = useTranslation()
+  const [queue, setQueue] = useState<KeyboardInteractiveRequest[]>([])
+  const [answers, setAnswers] = useState<string[]>([])
+  const request = queue[0]
+
+  useEffect(() => {
+    let isMounted = true
+
+    const promptListener = listen<KeyboardInteractiveRequest>(
+      "keyboard-interactive-prompt",
+      (event) => {
+        if (isMounted && event.payload?.requestId) {
+          setQueue((prev) => [...prev, event.payload])
+        }
+      },
+    )
+
+    return () => {
+      isMounted = false
+      promptListener.then((unlisten) => unlisten())
+    }
+  }, [])
+
+  useEffect(() => {
+    setAnswers(request ? request.prompts.map(() => "") : [])
+  }, [request])
+
+  if (!request) {
+    return null
+  }
+
+  const finish = () => setQueue((prev) => prev.slice(1))
+
+  const handleSubmit = async () => {
+    try {
+      await answerRequest(request.requestId, answers)
+    } finally {
+      finish()
+    }
+  }
+
+  const handleCancel = () => {
+    void answerRequest(request.requestId, null).catch(() => undefined)
+    finish()
+  }
+
+  return (
+    <FormModal
+      isOpen
+      title={request.name || t.keyboardInteractive.title}
+      onSubmit={handleSubmit}
+      onClose={handleCancel}
+      submitText={t.keyboardInteractive.submit}
+    >
+      <p className="text-sm mb-4 text-zinc-400">
+        {t.keyboardInteractive.description.replace(
+          "{server}",
+          `${request.username}@${request.host}`,
+        )}
+      </p>
+      {request.instructions && (
+        <p className="text-sm mb-4 whitespace-pre-wrap text-[var(--text-secondary)]">
+          {request.instructions}
+        </p>
+      )}
+      {request.prompts.map((prompt, index) => (
+        <div className="mb-4" key={`${request.requestId}-${index}`}>
+          <label
+            htmlFor={`keyboard-interactive-${index}`}
+            className="block text-sm font-medium text-zinc-400 mb-1.5"
+          >
+            {prompt.prompt}
+          </label>
+          <input
+            id={`keyboard-interactive-${index}`}
+            type={prompt.echo ? "text" : "password"}
+            autoFocus={index === 0}
+            autoComplete="off"
+            value={answers[index] ?? ""}
+            onChange={(e) =>
+              setAnswers((prev) =>
+                prev.map((answer, i) =>
+                  i === index ? e.target.value : answer,
+                ),
+              )
+            }
+            className="w-full px-3 py-2 text-sm rounded-md border border-zinc-700/50 outline-none transition-all bg-[var(--bg-primary)] text-[var(--text-primary)] placeholder:text-[var(--text-muted)] focus:border-blue-500 focus:shadow-[0_0_20px_rgba(59,130,246,0.2)]"
+          />
+        </div>
+      ))}
+    </FormModal>
+  )
+}
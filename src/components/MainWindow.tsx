@@ -78,6 +78,7 @@ import {
   selectUpdateDialogOpen,
 } from "../stores/useUpdateStore"
 import { UpdateDialog } from "./UpdateDialog"
+import { KeyboardInteractiveDialog } from "./KeyboardInteractiveDialog"
 import { updateManagerApi } from "../hooks/useUpdateManager"
 import type { SettingsSaveApi } from "./settings/SettingsModal"
 import type {
@@ -2213,11 +2214,13 @@ export const MainWindow: React.FC = () => {
       if (!tab || !isTerminalTab(tab)) {
         return
       }
-      let defaultName = `recording-${tabId}.txt`
+      const extension =
+        config?.general.recordingMode === "asciicast" ? "cast" : "txt"
+      let defaultName = `recording-${tabId}.${extension}`
       if (config) {
         const server = config.servers.find((s) => s.id === tab.serverId)
         if (server) {
-          defaultName = `recording-${server.host.replace(/[^a-z0-9]/gi, "_")}-${new Date().toISOString().replace(/[:.]/g, "-")}.txt`
+          defaultName = `recording-${server.host.replace(/[^a-z0-9]/gi, "_")}-${new Date().toISOString().replace(/[:.]/g, "-")}.${extension}`
         }
       }
 
@@ -3103,6 +3106,8 @@ export const MainWindow: React.FC = () => {
         isOpen={updateDialogOpen}
         onClose={() => updateManagerApi.closeDialog()}
       />
+
+      <KeyboardInteractiveDialog />
     </div>
   )
 }
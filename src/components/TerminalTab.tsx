@@ -455,6 +455,7 @@ export const TerminalTab = React.memo<TerminalTabProps>(
                 passphrase: passphrase || undefined,
                 proxy: proxy || null,
                 jumphost: jumphost || null,
+                server_id: currentServer.id,
               },
             },
           )
@@ -834,6 +835,7 @@ export const TerminalTab = React.memo<TerminalTabProps>(
               sessionId: sessionIdRef.current,
               filePath: path,
               mode,
+              recordInput: config?.general.recordingInput ?? false,
             })
           } catch (err) {
             // Failed to start recording
@@ -870,7 +872,11 @@ export const TerminalTab = React.memo<TerminalTabProps>(
           handleStopRecording as unknown as EventListener,
         )
       }
-    }, [tabId, config?.general.recordingMode])
+    }, [
+      tabId,
+      config?.general.recordingMode,
+      config?.general.recordingInput,
+    ])
 
     // Listen for reconnect event
     useEffect(() => {
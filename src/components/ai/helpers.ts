@@ -18,6 +18,8 @@ export const HIDDEN_TOOL_CALL_NAMES = new Set([
   "get_terminal_output",
   "get_selected_terminal_output",
   "read_file",
+  "search_command_history",
+  "get_session_commands",
 ])
 
 export const COMMAND_EXECUTION_TOOL_NAMES = new Set([
@@ -76,6 +78,8 @@ export const getToolDisplayName = (toolName: string, t: any): string => {
     get_terminal_output: t.ai.tool.getTerminalOutput,
     get_selected_terminal_output: t.ai.tool.getSelectedTerminalOutput,
     read_file: t.ai.tool.readFile,
+    search_command_history: t.ai.tool.searchCommandHistory,
+    get_session_commands: t.ai.tool.getSessionCommands,
     run_in_terminal: t.ai.tool.executeCommand,
     run_in_background: t.ai.tool.executeBackgroundCommand,
     send_interrupt: t.ai.tool.sendInterrupt,
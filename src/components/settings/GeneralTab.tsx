@@ -1,5 +1,5 @@
 import React from "react"
-import { GeneralSettings, NewTabServerSort } from "../../types"
+import { GeneralSettings, NewTabServerSort, RecordingMode } from "../../types"
 import { useTranslation } from "../../i18n"
 import { useConfig } from "../../hooks/useConfig"
 import { CustomSelect } from "../CustomSelect"
@@ -38,7 +38,7 @@ export const GeneralTab: React.FC<GeneralTabProps> = ({
     onGeneralUpdate({ ...general, language })
   }
 
-  const handleRecordingModeChange = (recordingMode: "raw" | "text") => {
+  const handleRecordingModeChange = (recordingMode: RecordingMode) => {
     onGeneralUpdate({ ...general, recordingMode })
   }
 
@@ -160,16 +160,39 @@ export const GeneralTab: React.FC<GeneralTabProps> = ({
             <CustomSelect
               id="recording-mode-select"
               value={general.recordingMode || "raw"}
-              onChange={(val) =>
-                handleRecordingModeChange(val as "raw" | "text")
-              }
+              onChange={(val) => handleRecordingModeChange(val as RecordingMode)}
               options={[
                 { value: "raw", label: t.recordingModes.raw },
                 { value: "text", label: t.recordingModes.text },
+                { value: "asciicast", label: t.recordingModes.asciicast },
               ]}
             />
           </div>
 
+          {general.recordingMode === "asciicast" && (
+            <div className="flex flex-col gap-1.5 mb-4">
+              <label className="flex items-center gap-2 cursor-pointer">
+                <input
+                  type="checkbox"
+                  checked={general.recordingInput ?? false}
+                  onChange={(e) =>
+                    onGeneralUpdate({
+                      ...general,
+                      recordingInput: e.target.checked,
+                    })
+                  }
+                  className="appearance-none -webkit-appearance-none w-[18px] h-[18px] border-[1.5px] border-zinc-700/50 rounded bg-[var(--bg-primary)] cursor-pointer relative transition-all flex-shrink-0 inline-flex items-center justify-center vertical-middle checked:bg-blue-500 checked:border-blue-500 checked:shadow-[0_0_20px_rgba(59,130,246,0.2)] hover:border-blue-500 focus:outline-none focus:shadow-[0_0_0_3px_rgba(59,130,246,0.2)] disabled:opacity-50 disabled:cursor-not-allowed"
+                />
+                <span className="block text-sm font-medium text-zinc-400 mb-0 ">
+                  {t.recordingInput}
+                </span>
+              </label>
+              <p className="text-xs text-[var(--text-muted)] ml-7">
+                {t.recordingInputHint}
+              </p>
+            </div>
+          )}
+
           <div className="flex flex-col gap-1.5 mb-4">
             <label
               htmlFor="max-recent-servers"
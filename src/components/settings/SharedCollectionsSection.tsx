@@ -0,0 +1,265 @@
+import React, { useState } from "react"
+import { Plus, Share2, Trash2 } from "lucide-react"
+import { v4 as uuidv4 } from "uuid"
+import {
+  GeneralSettings,
+  SharedCollection,
+  SyncBackendKind,
+  TriggerSyncResult,
+} from "../../types"
+import { useTranslation } from "../../i18n"
+import { useConfig } from "../../hooks/useConfig"
+import { CustomSelect } from "../CustomSelect"
+
+interface SharedCollectionsSectionProps {
+  general: GeneralSettings
+  onGeneralUpdate: (general: GeneralSettings) => void
+  onShared: (result: TriggerSyncResult) => void
+  onError: (message: string) => void
+}
+
+const inputClassName =
+  "w-full px-3 py-2 text-sm border border-zinc-700/50 rounded-md outline-none transition-all focus:border-blue-500 focus:shadow-[0_0_20px_rgba(59,130,246,0.2)] bg-[var(--bg-primary)] text-[var(--text-primary)] placeholder:text-[var(--text-muted)]"
+
+export const SharedCollectionsSection: React.FC<
+  SharedCollectionsSectionProps
+> = ({ general, onGeneralUpdate, onShared, onError }) => {
+  const { t } = useTranslation()
+  const { config, shareWithCollection } = useConfig()
+  const [shareTargets, setShareTargets] = useState<Record<string, string>>({})
+  const collections = general.sharedCollections ?? []
+
+  // Only synced servers and snippets can move; everything else stays personal.
+  const shareableItems = [
+    ...(config?.servers ?? [])
+      .filter((server) => server.synced)
+      .map((server) => ({
+        value: `server:${server.id}`,
+        label: server.name,
+      })),
+    ...(config?.snippets ?? [])
+      .filter((snippet) => snippet.synced)
+      .map((snippet) => ({
+        value: `snippet:${snippet.id}`,
+        label: snippet.name,
+      })),
+  ]
+
+  const setCollections = (next: SharedCollection[]) => {
+    onGeneralUpdate({ ...general, sharedCollections: next })
+  }
+
+  const updateCollection = (id: string, patch: Partial<SharedCollection>) => {
+    setCollections(
+      collections.map((collection) =>
+        collection.id === id ? { ...collection, ...patch } : collection,
+      ),
+    )
+  }
+
+  const updateSync = (
+    collection: SharedCollection,
+    field: keyof SharedCollection["sync"],
+    value: string | boolean,
+  ) => {
+    updateCollection(collection.id, {
+      sync: { ...collection.sync, [field]: value },
+    })
+  }
+
+  const addCollection = () => {
+    setCollections([
+      ...collections,
+      {
+        id: uuidv4(),
+        name: "",
+        readOnly: true,
+        sync: {
+          backend: "webdav",
+          url: "",
+          username: "",
+          password: "",
+          enabled: true,
+          proxyId: null,
+        },
+      },
+    ])
+  }
+
+  const share = async (collectionId: string) => {
+    const target = shareTargets[collectionId]
+    if (!target) return
+    const [entityType, id] = target.split(":")
+    try {
+      onShared(await shareWithCollection(collectionId, [{ entityType, id }]))
+      setShareTargets((prev) => ({ ...prev, [collectionId]: "" }))
+    } catch (err) {
+      onError(err instanceof Error ? err.message : String(err))
+    }
+  }
+
+  return (
+    <div>
+      <div className="flex items-center justify-between mb-2">
+        <h3 className="text-base font-semibold mb-0">{t.sharedCollections}</h3>
+        <button
+          type="button"
+          onClick={addCollection}
+          className="inline-flex items-center gap-2 px-3 py-1.5 text-sm rounded border border-zinc-700/50 bg-[var(--bg-primary)] text-[var(--text-primary)] hover:brightness-110"
+        >
+          <Plus size={14} />
+          <span>{t.sharedCollectionAdd}</span>
+        </button>
+      </div>
+      <p className="text-xs text-zinc-500 mb-3">{t.sharedCollectionsHint}</p>
+
+      <div className="space-y-4">
+        {collections.map((collection) => {
+          const backend: SyncBackendKind = collection.sync.backend ?? "webdav"
+          const usesHttp = backend === "webdav" || backend === "s3"
+          return (
+            <div
+              key={collection.id}
+              className="space-y-3 rounded-md border border-zinc-700/50 p-3"
+            >
+              <div className="flex items-center gap-3">
+                <input
+                  type="text"
+                  value={collection.name}
+                  onChange={(e) =>
+                    updateCollection(collection.id, { name: e.target.value })
+                  }
+                  placeholder={t.sharedCollectionName}
+                  aria-label={t.sharedCollectionName}
+                  className={inputClassName}
+                />
+                <label className="flex items-center gap-2 text-sm text-zinc-400 whitespace-nowrap cursor-pointer">
+                  <input
+                    type="checkbox"
+                    checked={collection.sync.enabled}
+                    onChange={(e) =>
+                      updateSync(collection, "enabled", e.target.checked)
+                    }
+                  />
+                  {t.common.enableSync}
+                </label>
+                <label className="flex items-center gap-2 text-sm text-zinc-400 whitespace-nowrap cursor-pointer">
+                  <input
+                    type="checkbox"
+                    checked={!!collection.readOnly}
+                    onChange={(e) =>
+                      updateCollection(collection.id, {
+                        readOnly: e.target.checked,
+                      })
+                    }
+                  />
+                  {t.sharedCollectionReadOnly}
+                </label>
+                <button
+                  type="button"
+                  onClick={() =>
+                    setCollections(
+                      collections.filter((c) => c.id !== collection.id),
+                    )
+                  }
+                  title={t.sharedCollectionRemove}
+                  className="text-zinc-400 hover:text-red-400"
+                >
+                  <Trash2 size={14} />
+                </button>
+              </div>
+
+              <div className="grid grid-cols-[10rem_1fr] gap-3">
+                <CustomSelect
+                  value={backend}
+                  onChange={(val) => updateSync(collection, "backend", val)}
+                  options={[
+                    { value: "webdav", label: t.syncBackendWebdav },
+                    { value: "s3", label: t.syncBackendS3 },
+                    { value: "folder", label: t.syncBackendFolder },
+                    { value: "git", label: t.syncBackendGit },
+                  ]}
+                />
+                <input
+                  type="text"
+                  value={collection.sync.url}
+                  onChange={(e) =>
+                    updateSync(collection, "url", e.target.value)
+                  }
+                  aria-label={t.webdavUrl}
+                  className={inputClassName}
+                />
+              </div>
+
+              {usesHttp && (
+                <div className="grid grid-cols-2 gap-3">
+                  <input
+                    type="text"
+                    value={collection.sync.username}
+                    onChange={(e) =>
+                      updateSync(collection, "username", e.target.value)
+                    }
+                    placeholder={
+                      backend === "s3" ? t.syncS3AccessKey : t.username
+                    }
+                    className={inputClassName}
+                  />
+                  <input
+                    type="password"
+                    value={collection.sync.password}
+                    onChange={(e) =>
+                      updateSync(collection, "password", e.target.value)
+                    }
+                    placeholder={
+                      backend === "s3" ? t.syncS3SecretKey : t.password
+                    }
+                    className={inputClassName}
+                  />
+                </div>
+              )}
+
+              <input
+                type="password"
+                value={collection.sync.encryptionPassphrase ?? ""}
+                onChange={(e) =>
+                  updateSync(collection, "encryptionPassphrase", e.target.value)
+                }
+                placeholder={t.webdavEncryptionPassphrase}
+                className={inputClassName}
+              />
+
+              {!collection.readOnly && shareableItems.length > 0 && (
+                <div className="flex items-center gap-3">
+                  <div className="flex-1">
+                    <CustomSelect
+                      value={shareTargets[collection.id] ?? ""}
+                      onChange={(val) =>
+                        setShareTargets((prev) => ({
+                          ...prev,
+                          [collection.id]: val,
+                        }))
+                      }
+                      options={[
+                        { value: "", label: t.sharedCollectionShareItem },
+                        ...shareableItems,
+                      ]}
+                    />
+                  </div>
+                  <button
+                    type="button"
+                    onClick={() => share(collection.id)}
+                    disabled={!shareTargets[collection.id]}
+                    className="inline-flex items-center gap-2 px-3 py-1.5 text-sm rounded border border-zinc-700/50 bg-[var(--bg-primary)] text-[var(--text-primary)] hover:brightness-110 disabled:opacity-50 disabled:cursor-not-allowed"
+                  >
+                    <Share2 size={14} />
+                    <span>{t.sharedCollectionShare}</span>
+                  </button>
+                </div>
+              )}
+            </div>
+          )
+        })}
+      </div>
+    </div>
+  )
+}
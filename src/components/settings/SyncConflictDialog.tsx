@@ -153,6 +153,8 @@ export const SyncConflictDialog: React.FC<SyncConflictDialogProps> = ({
                     </p>
                     <p className="mt-1 mb-0 text-xs text-[var(--text-muted)]">
                       {t.syncConflictEntity}: {conflict.entityType} · {conflict.kind}
+                      {conflict.collection &&
+                        ` · ${t.sharedCollection}: ${conflict.collection}`}
                     </p>
                   </div>
                   {choice && <Check size={16} className="shrink-0 text-blue-400" />}
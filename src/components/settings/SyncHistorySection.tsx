@@ -0,0 +1,186 @@
+import React, { useState } from "react"
+import { History, Loader2, RotateCcw } from "lucide-react"
+import { invoke } from "@tauri-apps/api/core"
+import {
+  SyncRevision,
+  SyncRevisionEntityDiff,
+  TriggerSyncResult,
+} from "../../types"
+import { useTranslation } from "../../i18n"
+import { useConfig } from "../../hooks/useConfig"
+import { ConfirmationModal } from "../ConfirmationModal"
+import { logger } from "../../utils/logger"
+
+interface SyncHistorySectionProps {
+  onRestored: (result: TriggerSyncResult) => void
+  onError: (message: string) => void
+}
+
+export const SyncHistorySection: React.FC<SyncHistorySectionProps> = ({
+  onRestored,
+  onError,
+}) => {
+  const { t } = useTranslation()
+  const { restoreSyncRevision } = useConfig()
+  const [revisions, setRevisions] = useState<SyncRevision[] | null>(null)
+  const [selectedId, setSelectedId] = useState<string | null>(null)
+  const [diff, setDiff] = useState<SyncRevisionEntityDiff[]>([])
+  const [loading, setLoading] = useState(false)
+  const [revisionToRestore, setRevisionToRestore] = useState<string | null>(
+    null,
+  )
+
+  const changeLabel = {
+    added: t.syncHistoryAdded,
+    removed: t.syncHistoryRemoved,
+    modified: t.syncHistoryModified,
+  }
+
+  const loadRevisions = async () => {
+    setLoading(true)
+    try {
+      setRevisions(await invoke<SyncRevision[]>("list_sync_revisions"))
+      setSelectedId(null)
+      setDiff([])
+    } catch (err) {
+      logger.error("[SyncHistory] Failed to list revisions", err)
+      onError(err instanceof Error ? err.message : String(err))
+    } finally {
+      setLoading(false)
+    }
+  }
+
+  const selectRevision = async (revisionId: string) => {
+    const latest = revisions?.[0]
+    if (!latest) return
+    setSelectedId(revisionId)
+    try {
+      setDiff(
+        await invoke<SyncRevisionEntityDiff[]>("diff_sync_revisions", {
+          fromId: revisionId,
+          toId: latest.id,
+        }),
+      )
+    } catch (err) {
+      logger.error("[SyncHistory] Failed to diff revisions", err)
+      onError(err instanceof Error ? err.message : String(err))
+    }
+  }
+
+  const confirmRestore = async () => {
+    const revisionId = revisionToRestore
+    setRevisionToRestore(null)
+    if (!revisionId) return
+    setLoading(true)
+    try {
+      onRestored(await restoreSyncRevision(revisionId))
+      await loadRevisions()
+    } catch (err) {
+      onError(err instanceof Error ? err.message : String(err))
+    } finally {
+      setLoading(false)
+    }
+  }
+
+  return (
+    <div>
+      <div className="flex items-center justify-between mb-3">
+        <h3 className="text-base font-semibold mb-0">{t.syncHistory}</h3>
+        <button
+          type="button"
+          onClick={loadRevisions}
+          disabled={loading}
+          className="inline-flex items-center gap-2 px-3 py-1.5 text-sm rounded border border-zinc-700/50 bg-[var(--bg-primary)] text-[var(--text-primary)] hover:brightness-110 disabled:opacity-50 disabled:cursor-not-allowed"
+        >
+          {loading ? (
+            <Loader2 size={14} className="animate-spin" />
+          ) : (
+            <History size={14} />
+          )}
+          <span>{t.syncHistoryLoad}</span>
+        </button>
+      </div>
+
+      {revisions && revisions.length === 0 && (
+        <p className="text-sm text-zinc-500">{t.syncHistoryEmpty}</p>
+      )}
+
+      {revisions && revisions.length > 0 && (
+        <ul className="space-y-1 max-h-64 overflow-y-auto">
+          {revisions.map((revision) => (
+            <li key={revision.id}>
+              <div
+                className={`flex items-center justify-between gap-3 rounded-md px-3 py-2 text-sm cursor-pointer ${
+                  selectedId === revision.id
+                    ? "bg-blue-500/10 border border-blue-500/30"
+                    : "border border-transparent hover:bg-zinc-700/20"
+                }`}
+                onClick={() => selectRevision(revision.id)}
+              >
+                <span className="truncate">
+                  {new Date(revision.recordedAt).toLocaleString()}
+                </span>
+                <span className="text-xs text-zinc-500 whitespace-nowrap">
+                  {revision.source === "remote"
+                    ? t.syncHistoryRemote
+                    : t.syncHistoryLocal}
+                  {" · "}
+                  {t.syncHistoryEntities.replace(
+                    "{count}",
+                    String(revision.entityCount),
+                  )}
+                </span>
+                <button
+                  type="button"
+                  onClick={(e) => {
+                    e.stopPropagation()
+                    setRevisionToRestore(revision.id)
+                  }}
+                  disabled={loading}
+                  title={t.syncHistoryRestore}
+                  className="inline-flex items-center gap-1 text-xs text-zinc-400 hover:text-[var(--text-primary)] disabled:opacity-50"
+                >
+                  <RotateCcw size={12} />
+                  <span>{t.syncHistoryRestore}</span>
+                </button>
+              </div>
+              {selectedId === revision.id && (
+                <div className="mt-1 mb-2 ml-3 text-xs text-zinc-400">
+                  <p className="mb-1 font-medium">
+                    {t.syncHistoryChangesSinceRevision}
+                  </p>
+                  {diff.length === 0 ? (
+                    <p className="text-zinc-500">{t.syncHistoryNoChanges}</p>
+                  ) : (
+                    <ul className="space-y-0.5">
+                      {diff.map((entry) => (
+                        <li key={`${entry.entityType}:${entry.id}`}>
+                          <span className="text-zinc-500">
+                            {changeLabel[entry.change]}
+                          </span>{" "}
+                          {entry.displayName}{" "}
+                          <span className="text-zinc-500">
+                            ({entry.entityType})
+                          </span>
+                        </li>
+                      ))}
+                    </ul>
+                  )}
+                </div>
+              )}
+            </li>
+          ))}
+        </ul>
+      )}
+
+      <ConfirmationModal
+        isOpen={!!revisionToRestore}
+        title={t.syncHistoryRestore}
+        message={t.syncHistoryRestoreConfirm}
+        onConfirm={confirmRestore}
+        onCancel={() => setRevisionToRestore(null)}
+        type="warning"
+      />
+    </div>
+  )
+}
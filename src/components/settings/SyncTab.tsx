@@ -1,10 +1,22 @@
-import React, { useState } from "react"
+import React, { useEffect, useState } from "react"
 import { RefreshCw, Check, AlertCircle, Loader2 } from "lucide-react"
-import { GeneralSettings, SyncOutcome } from "../../types"
+import { invoke } from "@tauri-apps/api/core"
+import { listen } from "@tauri-apps/api/event"
+import {
+  GeneralSettings,
+  SyncBackendKind,
+  SyncOutcome,
+  SyncSchedulerStatus,
+  SyncedSettingsGroup,
+  TriggerSyncResult,
+} from "../../types"
 import { useTranslation } from "../../i18n"
 import { useConfig } from "../../hooks/useConfig"
 import { CustomSelect } from "../CustomSelect"
+import { ConfirmationModal } from "../ConfirmationModal"
 import { SyncConflictDialog } from "./SyncConflictDialog"
+import { SyncHistorySection } from "./SyncHistorySection"
+import { SharedCollectionsSection } from "./SharedCollectionsSection"
 
 export interface SyncTabProps {
   general: GeneralSettings
@@ -14,7 +26,9 @@ export interface SyncTabProps {
 function syncOutcomeMessage(outcome: SyncOutcome): string {
   switch (outcome.status) {
     case "applied":
-      return ""
+      return (outcome.collectionFailures ?? [])
+        .map((failure) => `${failure.collection}: ${failure.message}`)
+        .join("; ")
     case "conflicts":
       return `${outcome.conflicts.length} sync item(s) need your resolution`
     case "concurrentRemoteChange":
@@ -31,6 +45,7 @@ export const SyncTab: React.FC<SyncTabProps> = ({
   const { t } = useTranslation()
   const {
     triggerSync,
+    migrateSyncEncryption,
     resolveSyncConflicts,
     syncConflictAttempt,
     config,
@@ -40,10 +55,76 @@ export const SyncTab: React.FC<SyncTabProps> = ({
   >("idle")
   const [syncError, setSyncError] = useState<string | null>(null)
   const [isConflictDialogOpen, setIsConflictDialogOpen] = useState(false)
+  const [isEncryptionMigrationOpen, setIsEncryptionMigrationOpen] =
+    useState(false)
+  const [schedulerStatus, setSchedulerStatus] =
+    useState<SyncSchedulerStatus | null>(null)
+  const backend: SyncBackendKind = general.webdav.backend ?? "webdav"
+  const usesHttp = backend === "webdav" || backend === "s3"
+  const locationLabel = {
+    webdav: t.webdavUrl,
+    s3: t.syncS3Url,
+    folder: t.syncFolderPath,
+    git: t.syncGitUrl,
+  }[backend]
+  const locationPlaceholder = {
+    webdav: "https://example.com/webdav",
+    s3: "https://s3.amazonaws.com/bucket/resh",
+    folder: "/mnt/share/resh",
+    git: "git@example.com:me/resh-sync.git",
+  }[backend]
+
+  useEffect(() => {
+    let isMounted = true
+    invoke<SyncSchedulerStatus>("get_sync_scheduler_status")
+      .then((status) => {
+        if (isMounted) setSchedulerStatus(status)
+      })
+      .catch(() => {})
+    const unlisten = listen<SyncSchedulerStatus>(
+      "sync-scheduler-status",
+      (event) => {
+        if (isMounted) setSchedulerStatus(event.payload)
+      },
+    )
+    return () => {
+      isMounted = false
+      unlisten.then((fn) => fn())
+    }
+  }, [])
+
+  const formatTime = (value: string | null) =>
+    value ? new Date(value).toLocaleTimeString() : ""
+
+  const schedulerMessage = (() => {
+    if (!schedulerStatus) return ""
+    switch (schedulerStatus.state) {
+      case "disabled":
+        return ""
+      case "idle":
+        return schedulerStatus.nextSyncAt
+          ? t.syncSchedulerNext.replace(
+              "{time}",
+              formatTime(schedulerStatus.nextSyncAt),
+            )
+          : ""
+      case "pushPending":
+        return t.syncSchedulerPushPending
+      case "syncing":
+        return t.syncSchedulerSyncing
+      case "backoff":
+        return t.syncSchedulerBackoff.replace(
+          "{time}",
+          formatTime(schedulerStatus.nextSyncAt),
+        )
+      case "paused":
+        return t.syncSchedulerPaused
+    }
+  })()
 
   const handleWebDAVUpdate = (
     field: keyof typeof general.webdav,
-    value: string | boolean | null,
+    value: string | number | boolean | null,
   ) => {
     onGeneralUpdate({
       ...general,
@@ -51,28 +132,76 @@ export const SyncTab: React.FC<SyncTabProps> = ({
     })
   }
 
+  const syncedSettings = general.syncedSettings ?? []
+  const settingsGroups: { group: SyncedSettingsGroup; label: string }[] = [
+    { group: "theme", label: t.syncedSettingsTheme },
+    { group: "terminalSettings", label: t.syncedSettingsTerminal },
+    { group: "sftpSettings", label: t.syncedSettingsSftp },
+    { group: "editorRules", label: t.syncedSettingsEditorRules },
+  ]
+
+  const toggleSyncedSettings = (
+    group: SyncedSettingsGroup,
+    enabled: boolean,
+  ) => {
+    onGeneralUpdate({
+      ...general,
+      syncedSettings: enabled
+        ? [...syncedSettings.filter((g) => g !== group), group]
+        : syncedSettings.filter((g) => g !== group),
+    })
+  }
+
+  const showSyncError = (message: string) => {
+    setSyncStatus("error")
+    setSyncError(message)
+    setTimeout(() => setSyncStatus("idle"), 5000)
+  }
+
+  const showSyncResult = (result: TriggerSyncResult) => {
+    if (
+      result.outcome.status === "applied" &&
+      result.outcome.collectionFailures?.length
+    ) {
+      // The personal document was synced; only the listed collections were skipped.
+      showSyncError(syncOutcomeMessage(result.outcome))
+    } else if (result.outcome.status === "applied") {
+      setSyncStatus("success")
+      setTimeout(() => setSyncStatus("idle"), 3000)
+    } else if (result.outcome.status === "conflicts") {
+      setSyncStatus("idle")
+      setIsConflictDialogOpen(true)
+    } else if (
+      result.outcome.status === "failed" &&
+      result.outcome.error.kind === "plaintextRemote"
+    ) {
+      setSyncStatus("idle")
+      setIsEncryptionMigrationOpen(true)
+    } else {
+      showSyncError(syncOutcomeMessage(result.outcome))
+    }
+  }
+
   const handleSync = async () => {
     if (syncStatus === "syncing") return
 
     try {
       setSyncStatus("syncing")
       setSyncError(null)
-      const result = await triggerSync()
-      if (result.outcome.status === "applied") {
-        setSyncStatus("success")
-        setTimeout(() => setSyncStatus("idle"), 3000)
-      } else if (result.outcome.status === "conflicts") {
-        setSyncStatus("idle")
-        setIsConflictDialogOpen(true)
-      } else {
-        setSyncStatus("error")
-        setSyncError(syncOutcomeMessage(result.outcome))
-        setTimeout(() => setSyncStatus("idle"), 5000)
-      }
+      showSyncResult(await triggerSync())
     } catch (err) {
-      setSyncStatus("error")
-      setSyncError(err instanceof Error ? err.message : String(err))
-      setTimeout(() => setSyncStatus("idle"), 5000)
+      showSyncError(err instanceof Error ? err.message : String(err))
+    }
+  }
+
+  const confirmEncryptionMigration = async () => {
+    setIsEncryptionMigrationOpen(false)
+    try {
+      setSyncStatus("syncing")
+      setSyncError(null)
+      showSyncResult(await migrateSyncEncryption())
+    } catch (err) {
+      showSyncError(err instanceof Error ? err.message : String(err))
     }
   }
 
@@ -136,6 +265,17 @@ export const SyncTab: React.FC<SyncTabProps> = ({
           </div>
         )}
 
+        {general.webdav.enabled && schedulerMessage && (
+          <p className="my-3 text-xs text-zinc-500">
+            {schedulerMessage}
+            {schedulerStatus?.lastSuccessAt &&
+              ` · ${t.syncSchedulerLastSuccess.replace(
+                "{time}",
+                formatTime(schedulerStatus.lastSuccessAt),
+              )}`}
+          </p>
+        )}
+
         {syncConflictAttempt && (
           <div className="my-3 flex items-center justify-between gap-3 rounded-md border border-amber-500/30 bg-amber-500/10 p-3 text-sm text-amber-200">
             <div className="flex min-w-0 items-center gap-2">
@@ -162,76 +302,239 @@ export const SyncTab: React.FC<SyncTabProps> = ({
         >
           <div className="flex flex-col gap-1.5 mb-4">
             <label
-              htmlFor="webdav-url"
+              htmlFor="webdav-backend"
               className="block text-sm font-medium text-zinc-400 mb-1.5 "
             >
-              {t.webdavUrl}
+              {t.syncBackend}
             </label>
-            <input
-              id="webdav-url"
-              type="text"
-              value={general.webdav.url}
-              onChange={(e) => handleWebDAVUpdate("url", e.target.value)}
-              className="w-full px-3 py-2 text-sm border border-zinc-700/50 rounded-md outline-none transition-all focus:border-blue-500 focus:shadow-[0_0_20px_rgba(59,130,246,0.2)] disabled:opacity-50 disabled:cursor-not-allowed bg-[var(--bg-primary)] text-[var(--text-primary)] placeholder:text-[var(--text-muted)]"
-              placeholder="https://example.com/webdav"
+            <CustomSelect
+              id="webdav-backend"
+              value={backend}
+              onChange={(val) => handleWebDAVUpdate("backend", val)}
+              options={[
+                { value: "webdav", label: t.syncBackendWebdav },
+                { value: "s3", label: t.syncBackendS3 },
+                { value: "folder", label: t.syncBackendFolder },
+                { value: "git", label: t.syncBackendGit },
+              ]}
             />
           </div>
 
           <div className="flex flex-col gap-1.5 mb-4">
             <label
-              htmlFor="webdav-proxy"
+              htmlFor="webdav-url"
               className="block text-sm font-medium text-zinc-400 mb-1.5 "
             >
-              {t.webdavProxy}
+              {locationLabel}
             </label>
-            <CustomSelect
-              id="webdav-proxy"
-              value={general.webdav.proxyId || ""}
-              onChange={(val) => handleWebDAVUpdate("proxyId", val || null)}
-              options={[
-                { value: "", label: t.common.none },
-                ...(config?.proxies || []).map((proxy) => ({
-                  value: proxy.id,
-                  label: proxy.name,
-                })),
-              ]}
+            <input
+              id="webdav-url"
+              type="text"
+              value={general.webdav.url}
+              onChange={(e) => handleWebDAVUpdate("url", e.target.value)}
+              className="w-full px-3 py-2 text-sm border border-zinc-700/50 rounded-md outline-none transition-all focus:border-blue-500 focus:shadow-[0_0_20px_rgba(59,130,246,0.2)] disabled:opacity-50 disabled:cursor-not-allowed bg-[var(--bg-primary)] text-[var(--text-primary)] placeholder:text-[var(--text-muted)]"
+              placeholder={locationPlaceholder}
             />
+            {backend === "git" && (
+              <p className="text-xs text-zinc-500">{t.syncGitHint}</p>
+            )}
           </div>
 
+          {backend === "s3" && (
+            <div className="flex flex-col gap-1.5 mb-4">
+              <label
+                htmlFor="webdav-region"
+                className="block text-sm font-medium text-zinc-400 mb-1.5 "
+              >
+                {t.syncS3Region}
+              </label>
+              <input
+                id="webdav-region"
+                type="text"
+                value={general.webdav.region ?? ""}
+                onChange={(e) => handleWebDAVUpdate("region", e.target.value)}
+                className="w-full px-3 py-2 text-sm border border-zinc-700/50 rounded-md outline-none transition-all focus:border-blue-500 focus:shadow-[0_0_20px_rgba(59,130,246,0.2)] disabled:opacity-50 disabled:cursor-not-allowed bg-[var(--bg-primary)] text-[var(--text-primary)] placeholder:text-[var(--text-muted)]"
+                placeholder="us-east-1"
+              />
+            </div>
+          )}
+
+          {backend === "git" && (
+            <div className="flex flex-col gap-1.5 mb-4">
+              <label
+                htmlFor="webdav-branch"
+                className="block text-sm font-medium text-zinc-400 mb-1.5 "
+              >
+                {t.syncGitBranch}
+              </label>
+              <input
+                id="webdav-branch"
+                type="text"
+                value={general.webdav.branch ?? ""}
+                onChange={(e) => handleWebDAVUpdate("branch", e.target.value)}
+                className="w-full px-3 py-2 text-sm border border-zinc-700/50 rounded-md outline-none transition-all focus:border-blue-500 focus:shadow-[0_0_20px_rgba(59,130,246,0.2)] disabled:opacity-50 disabled:cursor-not-allowed bg-[var(--bg-primary)] text-[var(--text-primary)] placeholder:text-[var(--text-muted)]"
+                placeholder="main"
+              />
+            </div>
+          )}
+
+          {usesHttp && (
+            <>
+              <div className="flex flex-col gap-1.5 mb-4">
+                <label
+                  htmlFor="webdav-proxy"
+                  className="block text-sm font-medium text-zinc-400 mb-1.5 "
+                >
+                  {t.webdavProxy}
+                </label>
+                <CustomSelect
+                  id="webdav-proxy"
+                  value={general.webdav.proxyId || ""}
+                  onChange={(val) =>
+                    handleWebDAVUpdate("proxyId", val || null)
+                  }
+                  options={[
+                    { value: "", label: t.common.none },
+                    ...(config?.proxies || []).map((proxy) => ({
+                      value: proxy.id,
+                      label: proxy.name,
+                    })),
+                  ]}
+                />
+              </div>
+
+              <div className="flex flex-col gap-1.5 mb-4">
+                <label
+                  htmlFor="webdav-username"
+                  className="block text-sm font-medium text-zinc-400 mb-1.5 "
+                >
+                  {backend === "s3" ? t.syncS3AccessKey : t.username}
+                </label>
+                <input
+                  id="webdav-username"
+                  type="text"
+                  value={general.webdav.username}
+                  onChange={(e) =>
+                    handleWebDAVUpdate("username", e.target.value)
+                  }
+                  className="w-full px-3 py-2 text-sm border border-zinc-700/50 rounded-md outline-none transition-all focus:border-blue-500 focus:shadow-[0_0_20px_rgba(59,130,246,0.2)] disabled:opacity-50 disabled:cursor-not-allowed bg-[var(--bg-primary)] text-[var(--text-primary)] placeholder:text-[var(--text-muted)]"
+                />
+              </div>
+
+              <div className="flex flex-col gap-1.5 mb-4">
+                <label
+                  htmlFor="webdav-password"
+                  className="block text-sm font-medium text-zinc-400 mb-1.5 "
+                >
+                  {backend === "s3" ? t.syncS3SecretKey : t.password}
+                </label>
+                <input
+                  id="webdav-password"
+                  type="password"
+                  value={general.webdav.password}
+                  onChange={(e) =>
+                    handleWebDAVUpdate("password", e.target.value)
+                  }
+                  className="w-full px-3 py-2 text-sm border border-zinc-700/50 rounded-md outline-none transition-all focus:border-blue-500 focus:shadow-[0_0_20px_rgba(59,130,246,0.2)] disabled:opacity-50 disabled:cursor-not-allowed bg-[var(--bg-primary)] text-[var(--text-primary)] placeholder:text-[var(--text-muted)]"
+                />
+              </div>
+            </>
+          )}
+
           <div className="flex flex-col gap-1.5 mb-4">
             <label
-              htmlFor="webdav-username"
+              htmlFor="webdav-encryption-passphrase"
               className="block text-sm font-medium text-zinc-400 mb-1.5 "
             >
-              {t.username}
+              {t.webdavEncryptionPassphrase}
             </label>
             <input
-              id="webdav-username"
-              type="text"
-              value={general.webdav.username}
-              onChange={(e) => handleWebDAVUpdate("username", e.target.value)}
+              id="webdav-encryption-passphrase"
+              type="password"
+              value={general.webdav.encryptionPassphrase ?? ""}
+              onChange={(e) =>
+                handleWebDAVUpdate("encryptionPassphrase", e.target.value)
+              }
               className="w-full px-3 py-2 text-sm border border-zinc-700/50 rounded-md outline-none transition-all focus:border-blue-500 focus:shadow-[0_0_20px_rgba(59,130,246,0.2)] disabled:opacity-50 disabled:cursor-not-allowed bg-[var(--bg-primary)] text-[var(--text-primary)] placeholder:text-[var(--text-muted)]"
             />
+            <p className="text-xs text-zinc-500">
+              {t.webdavEncryptionPassphraseHint}
+            </p>
           </div>
 
           <div className="flex flex-col gap-1.5 mb-4">
             <label
-              htmlFor="webdav-password"
+              htmlFor="webdav-pull-interval"
               className="block text-sm font-medium text-zinc-400 mb-1.5 "
             >
-              {t.password}
+              {t.syncPullInterval}
             </label>
             <input
-              id="webdav-password"
-              type="password"
-              value={general.webdav.password}
-              onChange={(e) => handleWebDAVUpdate("password", e.target.value)}
+              id="webdav-pull-interval"
+              type="number"
+              min={0}
+              value={general.webdav.pullIntervalMinutes ?? 15}
+              onChange={(e) =>
+                handleWebDAVUpdate(
+                  "pullIntervalMinutes",
+                  Math.max(0, parseInt(e.target.value, 10) || 0),
+                )
+              }
               className="w-full px-3 py-2 text-sm border border-zinc-700/50 rounded-md outline-none transition-all focus:border-blue-500 focus:shadow-[0_0_20px_rgba(59,130,246,0.2)] disabled:opacity-50 disabled:cursor-not-allowed bg-[var(--bg-primary)] text-[var(--text-primary)] placeholder:text-[var(--text-muted)]"
             />
+            <p className="text-xs text-zinc-500">{t.syncPullIntervalHint}</p>
+          </div>
+
+          <div className="flex flex-col gap-1.5 mb-4">
+            <span className="block text-sm font-medium text-zinc-400 mb-1.5 ">
+              {t.syncedSettings}
+            </span>
+            <div className="flex flex-wrap gap-4">
+              {settingsGroups.map(({ group, label }) => (
+                <label
+                  key={group}
+                  className="flex items-center gap-2 text-sm text-zinc-400 cursor-pointer"
+                >
+                  <input
+                    type="checkbox"
+                    checked={syncedSettings.includes(group)}
+                    onChange={(e) =>
+                      toggleSyncedSettings(group, e.target.checked)
+                    }
+                  />
+                  {label}
+                </label>
+              ))}
+            </div>
+            <p className="text-xs text-zinc-500">{t.syncedSettingsHint}</p>
           </div>
         </div>
       </div>
 
+      {general.webdav.enabled && general.webdav.url && (
+        <SharedCollectionsSection
+          general={general}
+          onGeneralUpdate={onGeneralUpdate}
+          onShared={showSyncResult}
+          onError={showSyncError}
+        />
+      )}
+
+      {general.webdav.enabled && general.webdav.url && (
+        <SyncHistorySection
+          onRestored={showSyncResult}
+          onError={showSyncError}
+        />
+      )}
+
+      <ConfirmationModal
+        isOpen={isEncryptionMigrationOpen}
+        title={t.webdavEncryptionPassphrase}
+        message={t.syncEncryptPlainRemoteConfirm}
+        onConfirm={confirmEncryptionMigration}
+        onCancel={() => setIsEncryptionMigrationOpen(false)}
+        type="warning"
+      />
       <SyncConflictDialog
         isOpen={isConflictDialogOpen}
         attempt={syncConflictAttempt}
@@ -2,6 +2,16 @@ import { useEffect, useState } from "react"
 import { listen } from "@tauri-apps/api/event"
 import type { IDisposable, IMarker, Terminal } from "xterm"
 
+/** Command started or finished, from OSC 133 shell integration in the backend. */
+interface ShellCommandEvent {
+  phase: "start" | "end"
+  command: {
+    id: number
+    command: string
+    exitCode: number | null
+  }
+}
+
 interface CommandBlock {
   id: number
   color: string
@@ -75,6 +85,9 @@ export const useCommandBlockBar = (
     let frameId: number | null = null
     let disposed = false
     let commandBlockUnlisten: (() => void) | null = null
+    let shellCommandUnlisten: (() => void) | null = null
+    // Once the shell reports its commands, Enter presses and AI events no longer open blocks.
+    let shellIntegration = false
 
     const commitState = (next: CommandBlockBarState) => {
       setState((current) => (sameState(current, next) ? current : next))
@@ -170,6 +183,7 @@ export const useCommandBlockBar = (
 
     disposables.push(
       terminal.onData((data) => {
+        if (shellIntegration) return
         if (terminal.buffer.active.type === "alternate") return
 
         let changed = false
@@ -197,6 +211,7 @@ export const useCommandBlockBar = (
 
     if (sessionId) {
       void listen<string>(`terminal-command-block:${sessionId}`, (event) => {
+        if (shellIntegration) return
         if (terminal.buffer.active.type === "alternate") return
 
         if (event.payload === "start") {
@@ -214,6 +229,26 @@ export const useCommandBlockBar = (
           commandBlockUnlisten = unlisten
         }
       })
+
+      void listen<ShellCommandEvent>(
+        `terminal-shell-command:${sessionId}`,
+        (event) => {
+          shellIntegration = true
+          // Wait until the output written before the mark is parsed, so markers land on it.
+          terminal.write("", () => {
+            if (disposed || terminal.buffer.active.type === "alternate") return
+            closeCurrent()
+            if (event.payload.phase === "start") openNew()
+            scheduleUpdate()
+          })
+        },
+      ).then((unlisten) => {
+        if (disposed) {
+          unlisten()
+        } else {
+          shellCommandUnlisten = unlisten
+        }
+      })
     }
 
     scheduleUpdate()
@@ -228,6 +263,7 @@ export const useCommandBlockBar = (
         disposable.dispose()
       }
       commandBlockUnlisten?.()
+      shellCommandUnlisten?.()
 
       const snapshot = blocks.slice()
       blocks.length = 0
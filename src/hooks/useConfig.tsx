@@ -12,6 +12,7 @@ import { listen } from "@tauri-apps/api/event"
 import {
   Config,
   SyncConflictAttempt,
+  SyncEntityRef,
   SyncOutcome,
   SyncResolution,
   TriggerSyncResult,
@@ -27,10 +28,16 @@ interface ConfigContextType {
   saveConfig: (config: Config) => Promise<void>
   recordServerConnection: (serverId: string) => Promise<void>
   triggerSync: () => Promise<TriggerSyncResult>
+  migrateSyncEncryption: () => Promise<TriggerSyncResult>
   resolveSyncConflicts: (
     attemptToken: string,
     resolutions: SyncResolution[],
   ) => Promise<TriggerSyncResult>
+  restoreSyncRevision: (revisionId: string) => Promise<TriggerSyncResult>
+  shareWithCollection: (
+    collectionId: string,
+    entities: SyncEntityRef[],
+  ) => Promise<TriggerSyncResult>
   /**
    * 同步取出当前 Provider 内最新的 Config 引用。
    * 用于 useCallback 闭包：避免依赖里漏写 `config` 时拿到 stale 快照，
@@ -171,6 +178,18 @@ export const ConfigProvider: React.FC<{ children: ReactNode }> = ({
     }
   }, [applySyncResult])
 
+  const migrateSyncEncryption = useCallback(async () => {
+    try {
+      logger.info("[ConfigProvider] Encrypting plain remote sync document...")
+      const result = await invoke<TriggerSyncResult>("migrate_sync_encryption")
+      applySyncResult(result)
+      return result
+    } catch (err) {
+      logger.error("[ConfigProvider] Sync encryption migration failed", err)
+      throw err
+    }
+  }, [applySyncResult])
+
   const resolveSyncConflicts = useCallback(
     async (attemptToken: string, resolutions: SyncResolution[]) => {
       try {
@@ -189,6 +208,42 @@ export const ConfigProvider: React.FC<{ children: ReactNode }> = ({
     [applySyncResult],
   )
 
+  const restoreSyncRevision = useCallback(
+    async (revisionId: string) => {
+      try {
+        logger.info("[ConfigProvider] Restoring sync revision", revisionId)
+        const result = await invoke<TriggerSyncResult>(
+          "restore_sync_revision",
+          { revisionId },
+        )
+        applySyncResult(result)
+        return result
+      } catch (err) {
+        logger.error("[ConfigProvider] Failed to restore sync revision", err)
+        throw err
+      }
+    },
+    [applySyncResult],
+  )
+
+  const shareWithCollection = useCallback(
+    async (collectionId: string, entities: SyncEntityRef[]) => {
+      try {
+        logger.info("[ConfigProvider] Sharing with collection", collectionId)
+        const result = await invoke<TriggerSyncResult>(
+          "share_with_collection",
+          { collectionId, entities },
+        )
+        applySyncResult(result)
+        return result
+      } catch (err) {
+        logger.error("[ConfigProvider] Failed to share with collection", err)
+        throw err
+      }
+    },
+    [applySyncResult],
+  )
+
   useEffect(() => {
     loadConfig()
   }, [loadConfig])
@@ -238,7 +293,10 @@ export const ConfigProvider: React.FC<{ children: ReactNode }> = ({
         saveConfig,
         recordServerConnection,
         triggerSync,
+        migrateSyncEncryption,
         resolveSyncConflicts,
+        restoreSyncRevision,
+        shareWithCollection,
         getLatestConfig,
       }}
     >
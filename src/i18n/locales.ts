@@ -31,6 +31,41 @@ export const locales = {
     webdav: "WebDAV Sync",
     webdavUrl: "WebDAV URL",
     webdavProxy: "Proxy Server",
+    syncBackend: "Storage Backend",
+    syncBackendWebdav: "WebDAV",
+    syncBackendS3: "S3-compatible bucket",
+    syncBackendFolder: "Local or network folder",
+    syncBackendGit: "Git repository",
+    syncS3Url: "Bucket URL",
+    syncS3AccessKey: "Access Key ID",
+    syncS3SecretKey: "Secret Access Key",
+    syncS3Region: "Region",
+    syncFolderPath: "Folder Path",
+    syncGitUrl: "Repository URL",
+    syncGitBranch: "Branch",
+    syncGitHint:
+      "Uses your existing git credentials (SSH agent or credential helper).",
+    webdavEncryptionPassphrase: "Encryption Passphrase",
+    webdavEncryptionPassphraseHint:
+      "Encrypts sync data before upload. Use the same passphrase on every device; leave empty to upload unencrypted.",
+    syncEncryptPlainRemoteConfirm:
+      "The remote sync data is not encrypted although a passphrase is set. Only continue if you just set the passphrase: the remote data is merged and uploaded encrypted. Otherwise someone may have replaced it.",
+    syncPullInterval: "Background Sync Interval (minutes)",
+    syncPullIntervalHint:
+      "Pulls remote changes on this interval; 0 turns periodic pulls off. Local edits are pushed a few seconds after saving.",
+    syncedSettings: "Synced Settings",
+    syncedSettingsHint:
+      "Share these settings with your other devices. The SFTP download path always stays on this device.",
+    syncedSettingsTheme: "Theme",
+    syncedSettingsTerminal: "Terminal",
+    syncedSettingsSftp: "SFTP transfers",
+    syncedSettingsEditorRules: "Editor rules",
+    syncSchedulerNext: "Next sync at {time}",
+    syncSchedulerPushPending: "Pushing local changes shortly",
+    syncSchedulerSyncing: "Background sync running",
+    syncSchedulerBackoff: "Sync location unreachable; retrying at {time}",
+    syncSchedulerPaused: "Paused while an update restart is prepared",
+    syncSchedulerLastSuccess: "Last synced {time}",
     syncNow: "Sync Now",
     syncing: "Syncing...",
     syncSuccess: "Sync successful",
@@ -51,13 +86,41 @@ export const locales = {
     syncConflictDeleted: "Deleted",
     syncConflictsRefreshRequired:
       "This conflict attempt is no longer current. Refresh synchronization and try again.",
+    syncHistory: "Sync History",
+    syncHistoryLoad: "Show history",
+    syncHistoryEmpty: "No revisions have been recorded yet.",
+    syncHistoryRemote: "Downloaded",
+    syncHistoryLocal: "Uploaded",
+    syncHistoryEntities: "{count} item(s)",
+    syncHistoryChangesSinceRevision: "Changes since this revision",
+    syncHistoryNoChanges: "Identical to the latest revision.",
+    syncHistoryAdded: "Added",
+    syncHistoryRemoved: "Removed",
+    syncHistoryModified: "Modified",
+    syncHistoryRestore: "Restore",
+    syncHistoryRestoreConfirm:
+      "Restore synced items to this revision? The rollback is uploaded as a new sync.",
+    sharedCollections: "Shared Collections",
+    sharedCollectionsHint:
+      "Team sync locations merged next to your personal config. Items of a read-only collection cannot be edited here.",
+    sharedCollection: "Collection",
+    sharedCollectionAdd: "Add collection",
+    sharedCollectionName: "Name",
+    sharedCollectionReadOnly: "Read-only",
+    sharedCollectionRemove: "Remove collection",
+    sharedCollectionShareItem: "Move a synced item into this collection",
+    sharedCollectionShare: "Move",
     username: "Username",
     password: "Password",
     recordingMode: "Recording Mode",
     recordingModes: {
       raw: "Raw (Includes ANSI codes)",
       text: "Text (Strip ANSI codes)",
+      asciicast: "asciicast v2 (Replayable with timing)",
     },
+    recordingInput: "Record keystrokes",
+    recordingInputHint:
+      "Adds typed input to asciicast recordings. Passwords typed at prompts are recorded too.",
     confirmations: "Confirmations",
     confirmCloseTab: "Confirm before closing tabs",
     confirmExitApp: "Confirm before exiting application",
@@ -309,6 +372,11 @@ export const locales = {
       privateKeyPlaceholder: "Paste your private key here...",
       rememberCredentials: "Remember authentication information",
     },
+    keyboardInteractive: {
+      title: "Additional Authentication",
+      description: "{server} asks for more information to sign in:",
+      submit: "Continue",
+    },
     welcome: {
       title: "Welcome to Resh",
       subtitle: "A professional, high-performance SSH client.",
@@ -569,6 +637,8 @@ export const locales = {
         getTerminalOutput: "Read Terminal Output",
         getSelectedTerminalOutput: "Read Selected Terminal Output",
         readFile: "Read Remote File",
+        searchCommandHistory: "Search Command History",
+        getSessionCommands: "Read Terminal Commands",
         sendInterrupt: "Send Interrupt (Ctrl+C)",
         sendTerminalInput: "Send Terminal Input",
         sftpDownload: "SFTP Download",
@@ -758,6 +828,38 @@ export const locales = {
     webdav: "WebDAV 同步",
     webdavUrl: "WebDAV 地址",
     webdavProxy: "代理服务器",
+    syncBackend: "存储后端",
+    syncBackendWebdav: "WebDAV",
+    syncBackendS3: "S3 兼容存储桶",
+    syncBackendFolder: "本地或网络文件夹",
+    syncBackendGit: "Git 仓库",
+    syncS3Url: "存储桶地址",
+    syncS3AccessKey: "Access Key ID",
+    syncS3SecretKey: "Secret Access Key",
+    syncS3Region: "区域",
+    syncFolderPath: "文件夹路径",
+    syncGitUrl: "仓库地址",
+    syncGitBranch: "分支",
+    syncGitHint: "使用现有的 git 凭据（SSH agent 或凭据助手）。",
+    webdavEncryptionPassphrase: "加密口令",
+    webdavEncryptionPassphraseHint:
+      "上传前加密同步数据。所有设备需使用相同口令；留空则不加密上传。",
+    syncEncryptPlainRemoteConfirm:
+      "已设置加密口令，但远端同步数据未加密。仅当你刚设置口令时才继续：远端数据将被合并并加密上传。否则可能有人替换了远端数据。",
+    syncPullInterval: "后台同步间隔（分钟）",
+    syncPullIntervalHint: "按此间隔拉取远程变更；设为 0 关闭定时拉取。本地修改会在保存几秒后推送。",
+    syncedSettings: "同步的设置",
+    syncedSettingsHint: "在你的其他设备间共享这些设置。SFTP 下载路径始终只保存在本机。",
+    syncedSettingsTheme: "主题",
+    syncedSettingsTerminal: "终端",
+    syncedSettingsSftp: "SFTP 传输",
+    syncedSettingsEditorRules: "编辑器规则",
+    syncSchedulerNext: "下次同步：{time}",
+    syncSchedulerPushPending: "即将推送本地修改",
+    syncSchedulerSyncing: "后台同步中",
+    syncSchedulerBackoff: "无法连接同步位置，将于 {time} 重试",
+    syncSchedulerPaused: "正在准备更新重启，已暂停",
+    syncSchedulerLastSuccess: "上次同步：{time}",
     syncNow: "立即同步",
     syncing: "同步中...",
     syncSuccess: "同步成功",
@@ -777,13 +879,38 @@ export const locales = {
     syncConflictUseRemote: "采用远端",
     syncConflictDeleted: "已删除",
     syncConflictsRefreshRequired: "当前冲突已过期，请刷新同步后重试。",
+    syncHistory: "同步历史",
+    syncHistoryLoad: "显示历史",
+    syncHistoryEmpty: "尚未记录任何版本。",
+    syncHistoryRemote: "已下载",
+    syncHistoryLocal: "已上传",
+    syncHistoryEntities: "{count} 项",
+    syncHistoryChangesSinceRevision: "此版本之后的变更",
+    syncHistoryNoChanges: "与最新版本相同。",
+    syncHistoryAdded: "新增",
+    syncHistoryRemoved: "删除",
+    syncHistoryModified: "修改",
+    syncHistoryRestore: "恢复",
+    syncHistoryRestoreConfirm: "将同步项恢复到此版本？回滚会作为一次新的同步上传。",
+    sharedCollections: "共享集合",
+    sharedCollectionsHint: "与个人配置并行合并的团队同步位置。只读集合中的项目无法在本地编辑。",
+    sharedCollection: "集合",
+    sharedCollectionAdd: "添加集合",
+    sharedCollectionName: "名称",
+    sharedCollectionReadOnly: "只读",
+    sharedCollectionRemove: "移除集合",
+    sharedCollectionShareItem: "将同步项移动到此集合",
+    sharedCollectionShare: "移动",
     username: "用户名",
     password: "密码",
     recordingMode: "录制模式",
     recordingModes: {
       raw: "原始数据 (包含颜色代码)",
       text: "纯文本 (去除颜色代码)",
+      asciicast: "asciicast v2 (可按时间回放)",
     },
+    recordingInput: "录制键盘输入",
+    recordingInputHint: "在 asciicast 录制中加入键入的内容。提示符下输入的密码也会被录制。",
     confirmations: "确认操作",
     confirmCloseTab: "关闭标签页前确认",
     confirmExitApp: "退出应用前确认",
@@ -1028,6 +1155,11 @@ export const locales = {
       privateKeyPlaceholder: "在此粘贴您的私钥...",
       rememberCredentials: "记住认证信息",
     },
+    keyboardInteractive: {
+      title: "附加身份验证",
+      description: "{server} 需要更多信息才能登录：",
+      submit: "继续",
+    },
     welcome: {
       title: "欢迎使用 Resh",
       subtitle: "一款专业、高性能的 SSH 客户端。",
@@ -1276,6 +1408,8 @@ export const locales = {
         getTerminalOutput: "读取终端输出",
         getSelectedTerminalOutput: "读取终端选中文本",
         readFile: "读取远程文件",
+        searchCommandHistory: "搜索命令历史",
+        getSessionCommands: "读取终端命令",
         sendInterrupt: "发送中断 (Ctrl+C)",
         sendTerminalInput: "发送终端输入",
         sftpDownload: "SFTP 下载",
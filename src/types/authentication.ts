@@ -1,10 +1,41 @@
 export interface Authentication {
   id: string
   name: string
-  type: "key" | "password"
+  type: "key" | "password" | "agent"
   keyContent?: string
   passphrase?: string
   password?: string
+  totpSecret?: string
+  certificate?: string
   synced: boolean
   updatedAt: string
 }
+
+export interface CertificateInfo {
+  keyId: string
+  serial: number
+  certType: "user" | "host"
+  algorithm: string
+  principals: string[]
+  validAfter?: string | null
+  validBefore?: string | null
+  expired: boolean
+  notYetValid: boolean
+  caFingerprint: string
+}
+
+export interface KeyboardInteractivePrompt {
+  prompt: string
+  echo: boolean
+}
+
+/** Payload of the `keyboard-interactive-prompt` event. */
+export interface KeyboardInteractiveRequest {
+  requestId: string
+  serverId?: string | null
+  host: string
+  username: string
+  name: string
+  instructions: string
+  prompts: KeyboardInteractivePrompt[]
+}
@@ -37,3 +37,44 @@ export interface ManualAuthCredentials {
   passphrase?: string
   rememberMe?: boolean
 }
+
+export interface ImportDuplicate {
+  name: string
+  existingId: string
+  existingName: string
+}
+
+export interface SkippedField {
+  session: string
+  field: string
+  reason: string
+}
+
+export type ImportFormat = "putty" | "mobaxterm" | "termius"
+
+export interface ImportReport {
+  source: string
+  dryRun: boolean
+  servers: Server[]
+  authentications: Authentication[]
+  proxies: ProxyConfig[]
+  duplicates: ImportDuplicate[]
+  skipped: SkippedField[]
+  warnings: string[]
+}
+
+export interface ExportedKeyFile {
+  path: string
+}
+
+export interface SshConfigExport {
+  content: string
+  keyFiles: ExportedKeyFile[]
+  warnings: string[]
+}
+
+export interface VaultStatus {
+  enabled: boolean
+  unlocked: boolean
+  autoLockMinutes: number
+}
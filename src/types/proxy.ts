@@ -1,12 +1,13 @@
 export interface ProxyConfig {
   id: string
   name: string
-  type: "http" | "socks5"
+  type: "http" | "socks5" | "command"
   host: string
   port: number
   username?: string
   password?: string
   ignoreSslErrors: boolean
+  command?: string
   synced: boolean
   updatedAt: string
 }
@@ -9,17 +9,47 @@ export interface Server {
   proxyId: string | null
   jumphostId: string | null
   portForwards: PortForward[]
+  dynamicForward?: DynamicForward | null
+  forwardAgent?: boolean
   keepAlive: number
   autoExecCommands: string[]
   snippets?: import("./snippet").Snippet[]
   sftpFavoritePaths?: string[]
   additionalPrompt?: string | null
+  /** Record every session automatically; overrides a policy for the server's group. */
+  recordingPolicy?: RecordingPolicy | null
   synced: boolean
   createdAt?: string
   updatedAt: string
 }
 
+export interface RecordingPolicy {
+  enabled: boolean
+  format: import("./settings").RecordingMode
+  recordInput?: boolean
+  /** Empty means the `recordings` folder in the app data directory. */
+  directory?: string
+  /** Without extension; supports {server}, {host}, {user}, {group}, {date}, {time} and {session}. */
+  fileName?: string
+  /** Finished recordings to keep; 0 keeps all. */
+  keepCount?: number
+  /** Days to keep recordings; 0 keeps them forever. */
+  keepDays?: number
+  compress?: boolean
+}
+
+export interface GroupRecordingPolicy extends RecordingPolicy {
+  group: string
+}
+
+export interface DynamicForward {
+  bindHost?: string | null
+  port: number
+}
+
 export interface PortForward {
   local: number
   remote: number
+  direction?: "local" | "remote"
+  bindHost?: string | null
 }
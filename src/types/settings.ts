@@ -9,12 +9,29 @@ export interface TerminalSettings {
   renderer?: TerminalRenderer
 }
 
+export type SyncBackendKind = "webdav" | "s3" | "folder" | "git"
+
 export interface WebDAVSettings {
+  backend?: SyncBackendKind
   url: string
   username: string
   password: string
   enabled: boolean
   proxyId?: string | null
+  encryptionPassphrase?: string
+  region?: string
+  branch?: string
+  /** Minutes between background pulls; 0 turns them off. */
+  pullIntervalMinutes?: number
+}
+
+/** A team sync location whose entities are merged next to the personal config. */
+export interface SharedCollection {
+  id: string
+  name: string
+  /** Mirror the collection without uploading; its entities cannot be edited locally. */
+  readOnly?: boolean
+  sync: WebDAVSettings
 }
 
 export interface EditorRule {
@@ -46,11 +63,18 @@ export interface SftpSettings {
   enableLargeFileStriping: boolean
 }
 
+/** Settings groups that can be opted into the personal sync document. */
+export type SyncedSettingsGroup =
+  | "theme"
+  | "terminalSettings"
+  | "sftpSettings"
+  | "editorRules"
+
 export type Theme = "light" | "dark" | "orange" | "green" | "system"
 export type Language = "en" | "zh-CN"
 export type AIMode = "ask" | "agent"
 export type AIThinkingLevel = "off" | "low" | "medium" | "high" | "max"
-export type RecordingMode = "raw" | "text"
+export type RecordingMode = "raw" | "text" | "asciicast"
 export type TabWidthMode = "adaptive" | "fixed"
 export type TerminalRightClickMode = "contextMenu" | "selectionCopyPaste"
 export type NewTabServerSort =
@@ -72,6 +96,8 @@ export interface GeneralSettings {
   language: Language
   terminal: TerminalSettings
   webdav: WebDAVSettings
+  sharedCollections?: SharedCollection[]
+  syncedSettings?: SyncedSettingsGroup[]
   confirmCloseTab: boolean
   confirmExitApp: boolean
   debugEnabled: boolean
@@ -86,6 +112,8 @@ export interface GeneralSettings {
   maxRecentServers: number
   recentServerIds: string[]
   recordingMode: RecordingMode
+  recordingInput?: boolean
+  groupRecordingPolicies?: import("./server").GroupRecordingPolicy[]
   tabWidthMode: TabWidthMode
   tabFixedWidth: number
   terminalRightClickMode: TerminalRightClickMode
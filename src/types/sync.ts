@@ -16,6 +16,8 @@ export interface SyncConflict {
   local: SyncEntitySummary
   remote: SyncEntitySummary
   resolutionToken: string
+  /** Name of the shared collection the conflict belongs to; absent for the personal config. */
+  collection?: string
 }
 
 export interface SyncConflictAttempt {
@@ -23,6 +25,11 @@ export interface SyncConflictAttempt {
   attemptToken: string
 }
 
+export interface SyncEntityRef {
+  entityType: string
+  id: string
+}
+
 export interface SyncResolution {
   entityType: string
   id: string
@@ -30,8 +37,17 @@ export interface SyncResolution {
   resolutionToken: string
 }
 
+export interface CollectionSyncFailure {
+  collection: string
+  message: string
+}
+
 export type SyncOutcome =
-  | { status: "applied"; changedEntityCount: number }
+  | {
+      status: "applied"
+      changedEntityCount: number
+      collectionFailures?: CollectionSyncFailure[]
+    }
   | {
       status: "conflicts"
       conflicts: SyncConflict[]
@@ -50,3 +66,38 @@ export interface TriggerSyncResult {
   config: Config | null
   outcome: SyncOutcome
 }
+
+export type SyncSchedulerState =
+  | "disabled"
+  | "idle"
+  | "pushPending"
+  | "syncing"
+  | "backoff"
+  | "paused"
+
+/** Payload of the `sync-scheduler-status` event. */
+export interface SyncSchedulerStatus {
+  state: SyncSchedulerState
+  nextSyncAt: string | null
+  lastSuccessAt: string | null
+  lastError: string | null
+  consecutiveFailures: number
+}
+
+export type SyncRevisionSource = "remote" | "local"
+
+export interface SyncRevision {
+  id: string
+  recordedAt: string
+  source: SyncRevisionSource
+  entityCount: number
+}
+
+export interface SyncRevisionEntityDiff {
+  entityType: string
+  id: string
+  displayName: string
+  change: "added" | "removed" | "modified"
+  before: SyncEntitySummary
+  after: SyncEntitySummary
+}